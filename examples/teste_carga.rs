@@ -17,16 +17,18 @@ struct Cfg {
     concurrency: usize,
     timeout: Duration,
     warmup: u64,
+    http2: bool,
 }
 
 #[tokio::main]
 async fn main() {
     let cfg = Cfg {
-        url: std::env::var("URL").unwrap_or_else(|_| "http://127.0.0.1:3000/api/posts".into()),
+        url: std::env::var("URL").unwrap_or_else(|_| "http://127.0.0.1:3000/api/v1/posts".into()),
         total: std::env::var("N").ok().and_then(|s| s.parse().ok()).unwrap_or(200_000),
         concurrency: std::env::var("C").ok().and_then(|s| s.parse().ok()).unwrap_or(1_500),
         timeout: Duration::from_secs(10),
         warmup: std::env::var("WARMUP").ok().and_then(|s| s.parse().ok()).unwrap_or(20_000),
+        http2: std::env::var("HTTP2").is_ok_and(|v| v == "1"),
     };
 
     println!("🚀 Iniciando teste de carga...");
@@ -35,14 +37,31 @@ async fn main() {
     println!("⚡ Concorrência: {}", cfg.concurrency);
 
     // --- cliente HTTP com ajustes de pool/keep-alive ---
-    let client = Client::builder()
-        .http1_only() // alinhado ao seu servidor Axum HTTP/1.1
+    let mut builder = Client::builder()
         .pool_max_idle_per_host(cfg.concurrency.max(64)) // evita gargalo de conexões ociosas
         .pool_idle_timeout(Duration::from_secs(30))
         .tcp_nodelay(true)
-        .timeout(cfg.timeout)
-        .build()
-        .expect("client");
+        .timeout(cfg.timeout);
+
+    // HTTP2=1: h2 via ALPN em URLs https, ou h2c (prior knowledge) em http
+    builder = match (cfg.http2, cfg.url.starts_with("https://")) {
+        (false, _) => builder.http1_only(),
+        (true, false) => builder.http2_prior_knowledge(),
+        (true, true) => builder,
+    };
+
+    // TLS_CA: certificado (PEM) a confiar, ex.: o autoassinado de desenvolvimento
+    if let Ok(caminho) = std::env::var("TLS_CA") {
+        let pem = std::fs::read(&caminho).expect("TLS_CA");
+        builder = builder.add_root_certificate(reqwest::Certificate::from_pem(&pem).expect("certificado PEM"));
+    }
+
+    let client = builder.build().expect("client");
+
+    match client.get(&cfg.url).send().await {
+        Ok(resp) => println!("🔌 Protocolo negociado: {:?}", resp.version()),
+        Err(e) => println!("⚠️  Falha na requisição inicial: {e}"),
+    }
 
     // --- warmup (opcional, mas reduz spikes iniciais) ---
     if cfg.warmup > 0 {
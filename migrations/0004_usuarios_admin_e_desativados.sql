@@ -0,0 +1,4 @@
+-- Administradores (criados pelo blogctl ou pelo seed) e contas desativadas,
+-- que deixam de conseguir fazer login
+ALTER TABLE users ADD COLUMN IF NOT EXISTS is_admin BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS disabled_at TIMESTAMP WITH TIME ZONE;
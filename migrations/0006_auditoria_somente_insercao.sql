@@ -0,0 +1,19 @@
+-- O registro de auditoria só recebe eventos novos: alterar ou apagar é erro,
+-- inclusive fora da aplicação
+CREATE OR REPLACE FUNCTION audit_events_somente_insercao() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS audit_events_sem_alteracao ON audit_events;
+CREATE TRIGGER audit_events_sem_alteracao
+    BEFORE UPDATE OR DELETE ON audit_events
+    FOR EACH ROW EXECUTE FUNCTION audit_events_somente_insercao();
+
+DROP TRIGGER IF EXISTS audit_events_sem_truncate ON audit_events;
+CREATE TRIGGER audit_events_sem_truncate
+    BEFORE TRUNCATE ON audit_events
+    FOR EACH STATEMENT EXECUTE FUNCTION audit_events_somente_insercao();
+
+CREATE INDEX IF NOT EXISTS idx_audit_events_action ON audit_events (action, created_at);
@@ -0,0 +1,4 @@
+-- Ids dos eventos do feed de posts (GET /api/v1/posts/stream). Vêm do banco
+-- para serem os mesmos em todas as instâncias que recebem o NOTIFY, e assim o
+-- Last-Event-ID de um cliente vale em qualquer uma delas
+CREATE SEQUENCE IF NOT EXISTS post_events_id_seq;
@@ -0,0 +1,13 @@
+-- O login busca o usuário por lower(email); o índice único acelera a busca e garante
+-- uma conta por email, independente de maiúsculas.
+-- Contas antigas cujo email só difere em maiúsculas de outra mais antiga ficam com o
+-- email prefixado pelo id, para que um administrador resolva o conflito.
+UPDATE users SET email = 'duplicado-' || id || '.' || email
+WHERE EXISTS (
+    SELECT 1 FROM users AS anterior
+    WHERE lower(anterior.email) = lower(users.email) AND anterior.id < users.id
+);
+
+UPDATE users SET email = lower(email) WHERE email <> lower(email);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_email_lower ON users (lower(email));
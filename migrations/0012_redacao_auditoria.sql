@@ -0,0 +1,23 @@
+-- A única alteração aceita no registro de auditoria é a redação dos dados
+-- pessoais de uma conta anonimizada (veja RepositorioUsuarios::anonimizar):
+-- IP e User-Agent apagados e email, hash do email e nome de usuário tirados do
+-- diff. Qualquer outra mudança continua sendo erro.
+CREATE OR REPLACE FUNCTION audit_events_somente_insercao() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'UPDATE'
+        AND NEW.id = OLD.id
+        AND NEW.actor_id IS NOT DISTINCT FROM OLD.actor_id
+        AND NEW.action = OLD.action
+        AND NEW.target_type IS NOT DISTINCT FROM OLD.target_type
+        AND NEW.target_id IS NOT DISTINCT FROM OLD.target_id
+        AND NEW.created_at = OLD.created_at
+        AND NEW.ip IS NULL
+        AND NEW.user_agent IS NULL
+        AND NEW.diff = OLD.diff - ARRAY['email', 'email_hash', 'username']
+    THEN
+        RETURN NEW;
+    END IF;
+
+    RAISE EXCEPTION 'audit_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,3 @@
+-- Data da última alteração, usada no Last-Modified e na concorrência otimista do PUT
+ALTER TABLE posts ADD COLUMN updated_at TEXT NOT NULL DEFAULT '';
+UPDATE posts SET updated_at = created_at WHERE updated_at = '';
@@ -0,0 +1,4 @@
+-- Administradores (criados pelo blogctl ou pelo seed) e contas desativadas,
+-- que deixam de conseguir fazer login
+ALTER TABLE users ADD COLUMN is_admin INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN disabled_at TEXT;
@@ -0,0 +1,20 @@
+-- Exclusão de contas a pedido do usuário: depois do prazo de carência a conta
+-- é anonimizada (os posts ficam, sem os dados pessoais do autor)
+ALTER TABLE users ADD COLUMN deletion_scheduled_at TEXT;
+ALTER TABLE users ADD COLUMN anonymized_at TEXT;
+
+-- Registro de auditoria. Sem chave estrangeira: o evento sobrevive à conta
+CREATE TABLE IF NOT EXISTS audit_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    actor_id INTEGER,
+    action TEXT NOT NULL,
+    target_type TEXT,
+    target_id TEXT,
+    ip TEXT,
+    user_agent TEXT,
+    diff TEXT NOT NULL DEFAULT '{}',
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_events_created_at ON audit_events (created_at);
+CREATE INDEX IF NOT EXISTS idx_audit_events_actor_id ON audit_events (actor_id, created_at);
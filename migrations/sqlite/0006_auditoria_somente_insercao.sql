@@ -0,0 +1,15 @@
+-- O registro de auditoria só recebe eventos novos: alterar ou apagar é erro,
+-- inclusive fora da aplicação
+CREATE TRIGGER IF NOT EXISTS audit_events_sem_alteracao
+BEFORE UPDATE ON audit_events
+BEGIN
+    SELECT RAISE(ABORT, 'audit_events is append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_events_sem_exclusao
+BEFORE DELETE ON audit_events
+BEGIN
+    SELECT RAISE(ABORT, 'audit_events is append-only');
+END;
+
+CREATE INDEX IF NOT EXISTS idx_audit_events_action ON audit_events (action, created_at);
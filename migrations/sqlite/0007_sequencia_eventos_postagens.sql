@@ -0,0 +1,4 @@
+-- Sem equivalente no SQLite: sem NOTIFY, o feed de posts só atende a própria
+-- instância e numera os eventos em memória. A migração existe para manter a
+-- mesma numeração de versões do PostgreSQL
+SELECT 1;
@@ -0,0 +1,22 @@
+-- A única alteração aceita no registro de auditoria é a redação dos dados
+-- pessoais de uma conta anonimizada (veja RepositorioUsuarios::anonimizar):
+-- IP e User-Agent apagados e email, hash do email e nome de usuário tirados do
+-- diff. Qualquer outra mudança continua sendo erro.
+DROP TRIGGER IF EXISTS audit_events_sem_alteracao;
+
+CREATE TRIGGER audit_events_sem_alteracao
+BEFORE UPDATE ON audit_events
+WHEN NOT (
+    NEW.id = OLD.id
+    AND NEW.actor_id IS OLD.actor_id
+    AND NEW.action = OLD.action
+    AND NEW.target_type IS OLD.target_type
+    AND NEW.target_id IS OLD.target_id
+    AND NEW.created_at = OLD.created_at
+    AND NEW.ip IS NULL
+    AND NEW.user_agent IS NULL
+    AND NEW.diff = json_remove(OLD.diff, '$.email', '$.email_hash', '$.username')
+)
+BEGIN
+    SELECT RAISE(ABORT, 'audit_events is append-only');
+END;
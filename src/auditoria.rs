@@ -0,0 +1,288 @@
+//! Registro de auditoria (`audit_events`): quem fez o quê, sobre qual
+//! registro e de onde. Os eventos só são acrescentados, nunca alterados; o
+//! banco recusa `UPDATE` e `DELETE` na tabela. A única exceção é a redação
+//! feita ao anonimizar uma conta: o gatilho aceita apagar `ip` e `user_agent`
+//! e tirar `email`, `email_hash` e `username` do `diff`, sem tocar no resto.
+//!
+//! Dados pessoais não entram no `diff`: o cadastro aponta para o usuário pelo
+//! alvo, e as falhas de login guardam só [`hash_email`].
+//!
+//! `diff` guarda as mudanças campo a campo como `{"campo": [antes, depois]}`
+//! (veja [`diferencas`]); eventos sem mudança de dados levam o contexto, como
+//! o motivo de uma falha de login.
+
+use axum::{
+    extract::{ConnectInfo, FromRef, FromRequestParts},
+    http::{header::USER_AGENT, request::Parts, HeaderMap},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use ipnet::IpNet;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sha2::Sha256;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use crate::{configuracao::Configuracao, validacao::normalizar_email};
+
+/// Nomes das ações gravadas em `audit_events.action`.
+pub mod acoes {
+    pub const LOGIN_SUCESSO: &str = "auth.login_succeeded";
+    pub const LOGIN_FALHA: &str = "auth.login_failed";
+
+    pub const CONTA_REGISTRADA: &str = "account.registered";
+    pub const SENHA_ALTERADA: &str = "account.password_changed";
+    pub const EXCLUSAO_SOLICITADA: &str = "account.deletion_requested";
+    pub const EXCLUSAO_CANCELADA: &str = "account.deletion_canceled";
+    pub const CONTA_ANONIMIZADA: &str = "account.anonymized";
+
+    pub const POSTAGEM_CRIADA: &str = "post.created";
+    pub const POSTAGEM_ATUALIZADA: &str = "post.updated";
+    pub const POSTAGEM_APAGADA: &str = "post.deleted";
+
+    pub const WEBHOOK_CRIADO: &str = "webhook.created";
+    pub const WEBHOOK_APAGADO: &str = "webhook.deleted";
+
+    pub const ADMIN_USUARIO_CRIADO: &str = "admin.user_created";
+    pub const ADMIN_PAPEL_ALTERADO: &str = "admin.role_changed";
+    pub const ADMIN_USUARIO_DESATIVADO: &str = "admin.user_disabled";
+    pub const ADMIN_USUARIO_REATIVADO: &str = "admin.user_enabled";
+    pub const ADMIN_TOKEN_EMITIDO: &str = "admin.token_issued";
+    pub const ADMIN_POSTAGEM_REMOVIDA: &str = "admin.post_removed";
+    pub const ADMIN_DADOS_EXPORTADOS: &str = "admin.data_exported";
+    pub const ADMIN_DADOS_IMPORTADOS: &str = "admin.data_imported";
+    pub const ADMIN_TAREFA_REENFILEIRADA: &str = "admin.job_retried";
+}
+
+/// Tipos de registro em `audit_events.target_type`.
+pub mod alvos {
+    pub const USUARIO: &str = "user";
+    pub const POSTAGEM: &str = "post";
+    pub const WEBHOOK: &str = "webhook";
+    pub const TAREFA: &str = "job";
+}
+
+/// Motivos de `auth.login_failed`, em `diff.reason`.
+pub mod motivos {
+    pub const EMAIL_DESCONHECIDO: &str = "unknown_email";
+    pub const SENHA_INCORRETA: &str = "wrong_password";
+    pub const CONTA_DESATIVADA: &str = "account_disabled";
+}
+
+/// Um evento a registrar. `id_ator` vazio indica o próprio sistema (tarefas
+/// de fundo, `blogctl`) ou alguém ainda não identificado (login que falhou).
+#[derive(Debug, Clone)]
+pub struct NovoEvento {
+    pub id_ator: Option<i32>,
+    pub acao: &'static str,
+    pub tipo_alvo: Option<&'static str>,
+    pub id_alvo: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub diff: Value,
+}
+
+impl NovoEvento {
+    pub fn new(acao: &'static str) -> Self {
+        NovoEvento {
+            id_ator: None,
+            acao,
+            tipo_alvo: None,
+            id_alvo: None,
+            ip: None,
+            user_agent: None,
+            diff: Value::Object(Default::default()),
+        }
+    }
+
+    pub fn ator(mut self, id_usuario: i32) -> Self {
+        self.id_ator = Some(id_usuario);
+        self
+    }
+
+    pub fn alvo(mut self, tipo: &'static str, id: impl ToString) -> Self {
+        self.tipo_alvo = Some(tipo);
+        self.id_alvo = Some(id.to_string());
+        self
+    }
+
+    pub fn origem(mut self, origem: &Origem) -> Self {
+        self.ip = Some(origem.ip.clone());
+        self.user_agent = origem.user_agent.clone();
+        self
+    }
+
+    pub fn diff(mut self, diff: Value) -> Self {
+        self.diff = diff;
+        self
+    }
+}
+
+/// De onde veio a requisição: o IP do cliente e o `User-Agent`.
+///
+/// O IP é o da conexão. Só quando ela vem de um proxy listado em
+/// [`Configuracao::proxies_confiaveis`] o `X-Forwarded-For` é lido, da direita
+/// para a esquerda, e o cliente é o primeiro endereço que não é de um proxy
+/// confiável; o que vem antes dele foi escrito pelo próprio cliente e não vale.
+#[derive(Debug, Clone)]
+pub struct Origem {
+    pub ip: String,
+    pub user_agent: Option<String>,
+}
+
+impl<S> FromRequestParts<S> for Origem
+where
+    S: Send + Sync,
+    Arc<Configuracao>: FromRef<S>,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(partes: &mut Parts, estado: &S) -> Result<Self, Self::Rejection> {
+        let ConnectInfo(endereco) = ConnectInfo::<SocketAddr>::from_request_parts(partes, estado)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let configuracao = Arc::<Configuracao>::from_ref(estado);
+
+        let ip = ip_do_cliente(endereco.ip(), &partes.headers, &configuracao.proxies_confiaveis);
+
+        let user_agent = partes
+            .headers
+            .get(USER_AGENT)
+            .and_then(|valor| valor.to_str().ok())
+            .map(|valor| valor.to_string());
+
+        Ok(Origem { ip: ip.to_string(), user_agent })
+    }
+}
+
+fn ip_do_cliente(conexao: IpAddr, cabecalhos: &HeaderMap, proxies_confiaveis: &[IpNet]) -> IpAddr {
+    let confiavel = |ip: &IpAddr| proxies_confiaveis.iter().any(|rede| rede.contains(ip));
+    if !confiavel(&conexao) {
+        return conexao;
+    }
+
+    // Cada proxy acrescenta à direita quem se conectou a ele; vários cabeçalhos valem como um só
+    let saltos: Vec<&str> = cabecalhos
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|valor| valor.to_str().ok())
+        .flat_map(|valor| valor.split(','))
+        .map(str::trim)
+        .collect();
+
+    let mut cliente = conexao;
+    for salto in saltos.into_iter().rev() {
+        // Um valor que não é IP não foi escrito por um proxy confiável: para no último válido
+        let Ok(ip) = salto.parse::<IpAddr>() else {
+            break;
+        };
+        cliente = ip;
+        if !confiavel(&ip) {
+            break;
+        }
+    }
+    cliente
+}
+
+/// HMAC-SHA256 do email normalizado, em hexadecimal, com a chave informada.
+/// Permite agrupar as falhas de login de um mesmo email sem gravá-lo.
+pub fn hash_email(chave: &str, email: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(chave.as_bytes()).expect("o HMAC aceita chaves de qualquer tamanho");
+    mac.update(normalizar_email(email).as_bytes());
+    mac.finalize().into_bytes().iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Os campos que mudaram entre dois objetos, como `{"campo": [antes, depois]}`.
+/// `Value::Null` de um dos lados representa um registro criado ou apagado:
+/// todos os campos do outro lado entram no resultado.
+pub fn diferencas(antes: &Value, depois: &Value) -> Value {
+    let vazio = Map::new();
+    let campos_antes = antes.as_object().unwrap_or(&vazio);
+    let campos_depois = depois.as_object().unwrap_or(&vazio);
+
+    let mut diff = Map::new();
+    for campo in campos_antes.keys().chain(campos_depois.keys()) {
+        let anterior = campos_antes.get(campo).unwrap_or(&Value::Null);
+        let atual = campos_depois.get(campo).unwrap_or(&Value::Null);
+        if anterior != atual && !diff.contains_key(campo) {
+            diff.insert(campo.clone(), Value::Array(vec![anterior.clone(), atual.clone()]));
+        }
+    }
+    Value::Object(diff)
+}
+
+/// Filtros da consulta ao registro (`GET /admin/audit`). Os eventos vêm dos
+/// mais novos para os mais antigos; `before_id` pagina a partir do último id visto.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FiltroEventos {
+    #[serde(rename = "actor_id")]
+    pub id_ator: Option<i32>,
+    #[serde(rename = "action")]
+    pub acao: Option<String>,
+    #[serde(rename = "since")]
+    pub desde: Option<DateTime<Utc>>,
+    #[serde(rename = "until")]
+    pub ate: Option<DateTime<Utc>>,
+    #[serde(rename = "before_id")]
+    pub antes_do_id: Option<i64>,
+    #[serde(rename = "limit")]
+    pub limite: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn encaminhado(valores: &[&str]) -> HeaderMap {
+        let mut cabecalhos = HeaderMap::new();
+        for valor in valores {
+            cabecalhos.append("x-forwarded-for", valor.parse().unwrap());
+        }
+        cabecalhos
+    }
+
+    #[test]
+    fn x_forwarded_for_so_vale_vindo_de_proxy_confiavel() {
+        let proxies: Vec<IpNet> = vec!["10.0.0.0/8".parse().unwrap()];
+        let ip = |conexao: &str, valores: &[&str]| ip_do_cliente(conexao.parse().unwrap(), &encaminhado(valores), &proxies).to_string();
+
+        // Direto do cliente: o cabeçalho é ignorado
+        assert_eq!(ip("203.0.113.7", &["1.2.3.4"]), "203.0.113.7");
+        // Pelo proxy: o salto mais à direita que não é um proxy confiável
+        assert_eq!(ip("10.0.0.2", &["1.2.3.4, 198.51.100.9, 10.0.0.5"]), "198.51.100.9");
+        assert_eq!(ip("10.0.0.2", &["1.2.3.4", "198.51.100.9"]), "198.51.100.9");
+        // Só proxies: o mais distante deles
+        assert_eq!(ip("10.0.0.2", &["10.0.0.7, 10.0.0.5"]), "10.0.0.7");
+        // Sem cabeçalho ou com lixo, o último endereço válido
+        assert_eq!(ip("10.0.0.2", &[]), "10.0.0.2");
+        assert_eq!(ip("10.0.0.2", &["nao-e-ip, 10.0.0.5"]), "10.0.0.5");
+    }
+
+    #[test]
+    fn diferencas_trazem_so_os_campos_alterados() {
+        let antes = json!({ "title": "Antigo", "content": "Texto" });
+        let depois = json!({ "title": "Novo", "content": "Texto" });
+
+        assert_eq!(diferencas(&antes, &depois), json!({ "title": ["Antigo", "Novo"] }));
+        assert_eq!(diferencas(&antes, &antes), json!({}));
+    }
+
+    #[test]
+    fn diferencas_de_criacao_e_exclusao() {
+        let postagem = json!({ "title": "Post", "content": "Texto" });
+
+        assert_eq!(
+            diferencas(&Value::Null, &postagem),
+            json!({ "title": [null, "Post"], "content": [null, "Texto"] })
+        );
+        assert_eq!(
+            diferencas(&postagem, &Value::Null),
+            json!({ "title": ["Post", null], "content": ["Texto", null] })
+        );
+    }
+}
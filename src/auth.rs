@@ -1,52 +1,54 @@
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+use crate::metricas::medir_bcrypt;
 
-const JWT_SECRET: &str = "your-secret-key-change-in-production"; // Em produção, use variável de ambiente
+pub const DURACAO_TOKEN_SEGUNDOS: i64 = 3600 * 24; // 24 horas
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Credenciais {
     pub id_usuario: i32,
     pub email: String,
+    pub id_sessao: Uuid,
     pub exp: usize,
 }
 
-pub fn criar_token(id_usuario: i32, email: String) -> Result<String, jsonwebtoken::errors::Error> {
+pub fn criar_token(segredo: &str, id_usuario: i32, email: String, id_sessao: Uuid) -> Result<String, jsonwebtoken::errors::Error> {
     let expiracao = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs() as usize
-        + 3600 * 24; // 24 horas
+        + DURACAO_TOKEN_SEGUNDOS as usize;
 
     let credenciais = Credenciais {
         id_usuario,
         email,
+        id_sessao,
         exp: expiracao,
     };
 
-    let secreto = std::env::var("JWT_SECRET").unwrap_or_else(|_| JWT_SECRET.to_string());
     encode(
         &Header::default(),
         &credenciais,
-        &EncodingKey::from_secret(secreto.as_ref()),
+        &EncodingKey::from_secret(segredo.as_ref()),
     )
 }
 
-pub fn verificar_token(token: &str) -> Result<Credenciais, jsonwebtoken::errors::Error> {
-    let secreto = std::env::var("JWT_SECRET").unwrap_or_else(|_| JWT_SECRET.to_string());
+pub fn verificar_token(segredo: &str, token: &str) -> Result<Credenciais, jsonwebtoken::errors::Error> {
     let dados_token = decode::<Credenciais>(
         token,
-        &DecodingKey::from_secret(secreto.as_ref()),
+        &DecodingKey::from_secret(segredo.as_ref()),
         &Validation::default(),
     )?;
     Ok(dados_token.claims)
 }
 
 pub fn hash_senha(senha: &str) -> Result<String, bcrypt::BcryptError> {
-    bcrypt::hash(senha, bcrypt::DEFAULT_COST)
+    medir_bcrypt("hash", || bcrypt::hash(senha, bcrypt::DEFAULT_COST))
 }
 
 pub fn verificar_senha(senha: &str, hash: &str) -> Result<bool, bcrypt::BcryptError> {
-    bcrypt::verify(senha, hash)
+    medir_bcrypt("verify", || bcrypt::verify(senha, hash))
 }
 
@@ -0,0 +1,591 @@
+//! `blogctl`: administração do blog pela linha de comando, sobre o mesmo banco
+//! (`DATABASE_URL`) e as mesmas regras da API.
+//!
+//! ```text
+//! cargo run --bin blogctl -- usuarios criar ana ana@example.com --admin
+//! cargo run --bin blogctl -- tokens emitir admin@example.com
+//! cargo run --bin blogctl -- seed gerar --usuarios 500 --posts 20000 --semente 7
+//! cargo run --bin blogctl -- exportar --saida backup.jsonl
+//! ```
+
+use clap::{Parser, Subcommand};
+use hello_rust::{
+    auditoria::{acoes, alvos, diferencas, NovoEvento, Origem},
+    auth::{criar_token, hash_senha},
+    cache::{Cache, Chave},
+    configuracao::Configuracao,
+    db::{self, Banco},
+    erros::ErrosValidacao,
+    models::{CriarUsuarioRequisicao, RespostaPostagem, RespostaUsuario, Usuario},
+    notificacoes::{tipos, Notificacoes},
+    politica_senha::politica,
+    privacidade,
+    repositorios::Repositorios,
+    seed::{self, Fixture, Gerador, Idioma},
+    transferencia::{self, Cabecalho},
+    transmissao::{TipoEvento, Transmissao},
+    validacao::{normalizar_email, Validar},
+    webhooks::{self, eventos},
+};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use tokio::io::{AsyncBufRead, AsyncWrite, BufReader, BufWriter};
+use tracing_subscriber::EnvFilter;
+use uuid::Uuid;
+
+type Resultado<T = ()> = Result<T, Box<dyn std::error::Error>>;
+
+// Sessões abertas pelo `tokens emitir` e eventos de auditoria aparecem com esta origem
+const USER_AGENT_CLI: &str = "blogctl";
+const IP_CLI: &str = "127.0.0.1";
+
+// Atende à política de senhas, para que os usuários gerados também sirvam no login pela API
+const SENHA_GERADOS: &str = "Carga#Blog2024";
+
+#[derive(Parser)]
+#[command(name = "blogctl", version, about = "Administração do blog (usuários, posts, migrações e seed)")]
+struct Cli {
+    #[command(subcommand)]
+    comando: Comando,
+}
+
+#[derive(Subcommand)]
+enum Comando {
+    /// Gerenciar contas de usuário
+    #[command(subcommand)]
+    Usuarios(ComandoUsuarios),
+    /// Emitir tokens JWT para testes (ex.: examples/teste_carga_auth.rs)
+    #[command(subcommand)]
+    Tokens(ComandoTokens),
+    /// Listar e moderar posts
+    #[command(subcommand)]
+    Posts(ComandoPosts),
+    /// Aplicar as migrações pendentes
+    Migrar,
+    /// Dados iniciais: fixtures nomeadas (YAML/JSON) e geração em massa
+    #[command(subcommand)]
+    Seed(ComandoSeed),
+    /// Exportar usuários, posts e sessões em JSON Lines
+    Exportar {
+        /// Arquivo de destino (padrão: stdout)
+        #[arg(long)]
+        saida: Option<PathBuf>,
+    },
+    /// Importar um arquivo gerado por `exportar` (ou por GET /admin/export)
+    Importar {
+        /// Caminho do arquivo, ou - para ler do stdin
+        arquivo: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ComandoUsuarios {
+    Listar,
+    /// Criar uma conta (sem --senha, uma senha aleatória é gerada e exibida)
+    Criar {
+        nome_usuario: String,
+        email: String,
+        #[arg(long, env = "BLOGCTL_SENHA", hide_env_values = true)]
+        senha: Option<String>,
+        /// Conceder privilégios de administrador
+        #[arg(long)]
+        admin: bool,
+    },
+    /// Conceder (ou, com --revogar, retirar) privilégios de administrador
+    Admin {
+        usuario: String,
+        #[arg(long)]
+        revogar: bool,
+    },
+    /// Impedir o login e revogar as sessões abertas
+    Desativar { usuario: String },
+    Reativar { usuario: String },
+    /// Anonimizar a conta na hora, sem a carência do DELETE /profile (os posts ficam)
+    Deletar {
+        usuario: String,
+        /// Confirma a exclusão (sem ela nada é alterado)
+        #[arg(long)]
+        sim: bool,
+    },
+    /// Trocar a senha e revogar as sessões abertas
+    RedefinirSenha {
+        usuario: String,
+        #[arg(long, env = "BLOGCTL_SENHA", hide_env_values = true)]
+        senha: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ComandoTokens {
+    /// Abrir uma sessão para o usuário e imprimir o token (válido por 24h)
+    Emitir { usuario: String },
+}
+
+#[derive(Subcommand)]
+enum ComandoPosts {
+    Listar {
+        /// Só os posts deste usuário
+        #[arg(long)]
+        usuario: Option<String>,
+        #[arg(long, default_value_t = 20)]
+        limite: i64,
+    },
+    /// Apagar um post de qualquer usuário
+    Deletar { id: i32 },
+}
+
+#[derive(Subcommand)]
+enum ComandoSeed {
+    /// Listar as fixtures de FIXTURES_DIR (padrão: fixtures/)
+    Listar,
+    /// Aplicar uma fixture pelo nome ou pelo caminho de um .yaml/.json
+    Fixture {
+        #[arg(default_value = seed::FIXTURE_PADRAO)]
+        nome: String,
+    },
+    /// Gerar usuários e posts fictícios (a mesma semente gera os mesmos dados)
+    Gerar {
+        #[arg(long, default_value_t = 100)]
+        usuarios: usize,
+        #[arg(long, default_value_t = 1000)]
+        posts: usize,
+        #[arg(long, default_value_t = 42)]
+        semente: u64,
+        /// pt ou en
+        #[arg(long, default_value = "pt")]
+        idioma: Idioma,
+        /// Senha de todos os usuários gerados
+        #[arg(long, default_value = SENHA_GERADOS)]
+        senha: String,
+        /// Gravar a fixture neste arquivo (.yaml ou .json) em vez de aplicá-la
+        #[arg(long)]
+        saida: Option<PathBuf>,
+    },
+}
+
+#[tokio::main]
+async fn main() {
+    // Logs só com RUST_LOG; a saída normal do comando vai para o stdout
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")))
+        .with_writer(std::io::stderr)
+        .init();
+
+    let cli = Cli::parse();
+
+    if let Err(erro) = executar(cli.comando).await {
+        eprintln!("erro: {erro}");
+        std::process::exit(1);
+    }
+}
+
+async fn executar(comando: Comando) -> Resultado {
+    let banco = db::conectar()?;
+
+    // Só o próprio comando de migração (e os de seed que não tocam no banco) roda num banco desatualizado
+    let sem_banco = matches!(
+        comando,
+        Comando::Seed(ComandoSeed::Listar | ComandoSeed::Gerar { saida: Some(_), .. })
+    );
+    if !matches!(comando, Comando::Migrar) && !sem_banco {
+        verificar_migracoes(&banco).await?;
+    }
+    let repositorios = banco.repositorios();
+
+    let resultado = match comando {
+        Comando::Usuarios(comando) => usuarios(&repositorios, comando).await,
+        Comando::Tokens(ComandoTokens::Emitir { usuario }) => emitir_token(&repositorios, &usuario).await,
+        Comando::Posts(comando) => posts(&banco, &repositorios, comando).await,
+        Comando::Migrar => migrar(&banco).await,
+        Comando::Seed(comando) => semear(&repositorios, comando).await,
+        Comando::Exportar { saida } => exportar(&banco, &repositorios, saida).await,
+        Comando::Importar { arquivo } => importar(&banco, &repositorios, &arquivo).await,
+    };
+
+    banco.fechar().await;
+    resultado
+}
+
+async fn verificar_migracoes(banco: &Banco) -> Resultado {
+    let aplicada = banco.versao_aplicada().await?;
+    if aplicada != Some(banco.versao_esperada()) {
+        return Err(format!(
+            "o banco está na migração {}, mas a esperada é {}; rode `blogctl migrar`",
+            aplicada.map_or("nenhuma".to_string(), |versao| versao.to_string()),
+            banco.versao_esperada()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+async fn migrar(banco: &Banco) -> Resultado {
+    let antes = banco.versao_aplicada().await?;
+    banco.executar_migracoes().await?;
+    let depois = banco.versao_aplicada().await?;
+
+    if antes == depois {
+        println!("Nenhuma migração pendente (versão {})", depois.unwrap_or_default());
+    } else {
+        println!("Migrações aplicadas até a versão {}", depois.unwrap_or_default());
+    }
+    Ok(())
+}
+
+async fn usuarios(repositorios: &Repositorios, comando: ComandoUsuarios) -> Resultado {
+    match comando {
+        ComandoUsuarios::Listar => {
+            let usuarios = repositorios.usuarios.listar().await?;
+            println!("{:>5}  {:<20} {:<32} {:<8} {:<10} CRIADO EM", "ID", "USUÁRIO", "EMAIL", "ADMIN", "SITUAÇÃO");
+            for usuario in usuarios {
+                println!(
+                    "{:>5}  {:<20} {:<32} {:<8} {:<10} {}",
+                    usuario.id,
+                    usuario.nome_usuario,
+                    usuario.email,
+                    if usuario.admin { "sim" } else { "não" },
+                    if usuario.desativado_em.is_some() { "desativado" } else { "ativo" },
+                    usuario.criado_em.format("%Y-%m-%d %H:%M"),
+                );
+            }
+        }
+        ComandoUsuarios::Criar { nome_usuario, email, senha, admin } => {
+            let (senha, gerada) = senha_ou_aleatoria(senha);
+
+            // As mesmas regras do POST /auth/register
+            let mut requisicao = CriarUsuarioRequisicao { nome_usuario, email, senha };
+            requisicao.normalizar();
+            let mut erros = ErrosValidacao::default();
+            requisicao.validar(&mut erros);
+            requisicao.validar_assincrono(&mut erros).await;
+            if !erros.is_empty() {
+                return Err(erros.into());
+            }
+
+            if repositorios
+                .usuarios
+                .buscar_conflito(&requisicao.email, &requisicao.nome_usuario)
+                .await?
+                .is_some()
+            {
+                return Err("email ou nome de usuário já cadastrado".into());
+            }
+
+            let usuario = repositorios
+                .usuarios
+                .criar(&requisicao.nome_usuario, &requisicao.email, &hash_senha(&requisicao.senha)?)
+                .await?;
+            if admin {
+                repositorios.usuarios.definir_admin(usuario.id, true).await?;
+            }
+
+            let dados = json!({ "is_admin": admin });
+            let evento = NovoEvento::new(acoes::ADMIN_USUARIO_CRIADO)
+                .alvo(alvos::USUARIO, usuario.id)
+                .diff(diferencas(&Value::Null, &dados));
+            auditar(repositorios, evento).await?;
+            webhooks::publicar(repositorios, eventos::USUARIO_REGISTRADO, json!(RespostaUsuario::from(usuario.clone())))
+                .await?;
+
+            println!(
+                "Usuário {} criado (id {}{})",
+                usuario.nome_usuario,
+                usuario.id,
+                if admin { ", administrador" } else { "" }
+            );
+            if gerada {
+                println!("Senha: {}", requisicao.senha);
+            }
+        }
+        ComandoUsuarios::Admin { usuario, revogar } => {
+            let usuario = buscar_usuario(repositorios, &usuario).await?;
+            repositorios.usuarios.definir_admin(usuario.id, !revogar).await?;
+            let evento = NovoEvento::new(acoes::ADMIN_PAPEL_ALTERADO)
+                .alvo(alvos::USUARIO, usuario.id)
+                .diff(diferencas(&json!({ "is_admin": usuario.admin }), &json!({ "is_admin": !revogar })));
+            auditar(repositorios, evento).await?;
+            if revogar {
+                println!("{} não é mais administrador", usuario.nome_usuario);
+            } else {
+                println!("{} agora é administrador", usuario.nome_usuario);
+            }
+        }
+        ComandoUsuarios::Desativar { usuario } => {
+            let usuario = buscar_usuario(repositorios, &usuario).await?;
+            repositorios.usuarios.definir_desativado(usuario.id, true).await?;
+            let revogadas = repositorios.sessoes.revogar_todas(usuario.id).await?;
+            let evento = NovoEvento::new(acoes::ADMIN_USUARIO_DESATIVADO)
+                .alvo(alvos::USUARIO, usuario.id)
+                .diff(json!({ "revoked_sessions": revogadas }));
+            auditar(repositorios, evento).await?;
+            println!("Usuário {} desativado ({revogadas} sessões revogadas)", usuario.nome_usuario);
+        }
+        ComandoUsuarios::Reativar { usuario } => {
+            let usuario = buscar_usuario(repositorios, &usuario).await?;
+            if usuario.anonimizado_em.is_some() {
+                return Err(format!("a conta {} foi excluída e não pode ser reativada", usuario.id).into());
+            }
+            repositorios.usuarios.definir_desativado(usuario.id, false).await?;
+            auditar(repositorios, NovoEvento::new(acoes::ADMIN_USUARIO_REATIVADO).alvo(alvos::USUARIO, usuario.id)).await?;
+            println!("Usuário {} reativado", usuario.nome_usuario);
+        }
+        ComandoUsuarios::Deletar { usuario, sim } => {
+            let usuario = buscar_usuario(repositorios, &usuario).await?;
+            if usuario.anonimizado_em.is_some() {
+                return Err(format!("a conta {} já foi excluída", usuario.id).into());
+            }
+            if !sim {
+                return Err(format!(
+                    "isso apaga os dados pessoais de {} (os posts ficam, sem autor identificável); \
+                     repita com --sim para confirmar",
+                    usuario.nome_usuario
+                )
+                .into());
+            }
+
+            privacidade::anonimizar(repositorios, usuario.id, privacidade::MOTIVO_ADMINISTRADOR).await?;
+            println!("Conta de {} excluída (agora usuario_removido_{})", usuario.nome_usuario, usuario.id);
+        }
+        ComandoUsuarios::RedefinirSenha { usuario, senha } => {
+            let usuario = buscar_usuario(repositorios, &usuario).await?;
+            if usuario.anonimizado_em.is_some() {
+                return Err(format!("a conta {} foi excluída e não pode receber uma senha", usuario.id).into());
+            }
+            let (senha, gerada) = senha_ou_aleatoria(senha);
+
+            let violacoes = politica().validar(&senha, &[&usuario.nome_usuario, &usuario.email]).await;
+            if !violacoes.is_empty() {
+                let mut erros = ErrosValidacao::default();
+                for violacao in violacoes {
+                    erros.adicionar("password", violacao);
+                }
+                return Err(erros.into());
+            }
+
+            repositorios.usuarios.atualizar_senha(usuario.id, &hash_senha(&senha)?).await?;
+            let revogadas = repositorios.sessoes.revogar_todas(usuario.id).await?;
+            let evento = NovoEvento::new(acoes::SENHA_ALTERADA)
+                .alvo(alvos::USUARIO, usuario.id)
+                .diff(json!({ "revoked_sessions": revogadas }));
+            auditar(repositorios, evento).await?;
+            println!("Senha de {} redefinida ({revogadas} sessões revogadas)", usuario.nome_usuario);
+            if gerada {
+                println!("Senha: {senha}");
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn emitir_token(repositorios: &Repositorios, usuario: &str) -> Resultado {
+    let usuario = buscar_usuario(repositorios, usuario).await?;
+    if usuario.desativado_em.is_some() {
+        return Err(format!("a conta de {} está desativada", usuario.nome_usuario).into());
+    }
+
+    // Uma sessão de verdade: o token passa pelo middleware_auth e pode ser revogado
+    let id_sessao = repositorios
+        .sessoes
+        .criar(usuario.id, Some(USER_AGENT_CLI.to_string()), IP_CLI.to_string())
+        .await?;
+    let token = criar_token(&Configuracao::do_ambiente().segredo_jwt, usuario.id, usuario.email, id_sessao)?;
+
+    let evento = NovoEvento::new(acoes::ADMIN_TOKEN_EMITIDO)
+        .alvo(alvos::USUARIO, usuario.id)
+        .diff(json!({ "session_id": id_sessao }));
+    auditar(repositorios, evento).await?;
+
+    println!("{token}");
+    Ok(())
+}
+
+async fn posts(banco: &Banco, repositorios: &Repositorios, comando: ComandoPosts) -> Resultado {
+    match comando {
+        ComandoPosts::Listar { usuario, limite } => {
+            let postagens = match usuario {
+                Some(usuario) => {
+                    let usuario = buscar_usuario(repositorios, &usuario).await?;
+                    let mut postagens = repositorios.postagens.listar_do_usuario(usuario.id).await?;
+                    postagens.truncate(limite.max(0) as usize);
+                    postagens
+                }
+                None => repositorios.postagens.listar_recentes(limite).await?,
+            };
+
+            println!("{:>5}  {:>7}  {:<16} TÍTULO", "ID", "USUÁRIO", "CRIADO EM");
+            for postagem in postagens {
+                println!(
+                    "{:>5}  {:>7}  {:<16} {}",
+                    postagem.id,
+                    postagem.id_usuario,
+                    postagem.criado_em.format("%Y-%m-%d %H:%M"),
+                    postagem.titulo
+                );
+            }
+        }
+        ComandoPosts::Deletar { id } => {
+            // Lida antes de apagar, para o diff guardar o que foi removido
+            let Some(postagem) = repositorios.postagens.buscar(id).await? else {
+                return Err(format!("post {id} não encontrado").into());
+            };
+            if !repositorios.postagens.remover(id).await? {
+                return Err(format!("post {id} não encontrado").into());
+            }
+
+            let removida = json!({ "user_id": postagem.id_usuario, "title": postagem.titulo, "content": postagem.conteudo });
+            let evento = NovoEvento::new(acoes::ADMIN_POSTAGEM_REMOVIDA)
+                .alvo(alvos::POSTAGEM, id)
+                .diff(diferencas(&removida, &Value::Null));
+            auditar(repositorios, evento).await?;
+            invalidar_cache(&[Chave::Postagem(id), Chave::PostagensPublicas]).await;
+            // No PostgreSQL, o NOTIFY chega aos clientes do feed de todas as instâncias
+            Transmissao::new(banco, repositorios.clone()).publicar(TipoEvento::Apagada, id).await;
+            // ...e o aviso ao autor, às conexões dele
+            let aviso = json!({ "post_id": id, "title": postagem.titulo });
+            Notificacoes::new(banco, repositorios.clone())
+                .notificar(postagem.id_usuario, tipos::POSTAGEM_REMOVIDA, aviso)
+                .await?;
+            // Os webhooks saem pelo servidor, que envia as entregas enfileiradas
+            webhooks::publicar(repositorios, eventos::POSTAGEM_APAGADA, json!(RespostaPostagem::from(postagem))).await?;
+            println!("Post {id} apagado");
+        }
+    }
+    Ok(())
+}
+
+async fn semear(repositorios: &Repositorios, comando: ComandoSeed) -> Resultado {
+    match comando {
+        ComandoSeed::Listar => {
+            for nome in seed::fixtures_disponiveis() {
+                println!("{nome}");
+            }
+        }
+        ComandoSeed::Fixture { nome } => {
+            let fixture = Fixture::carregar(&nome)?;
+            let credenciais: Vec<(String, String)> = fixture
+                .usuarios
+                .iter()
+                .map(|usuario| (usuario.email.clone(), usuario.senha.clone()))
+                .collect();
+
+            let resumo = seed::aplicar(repositorios, fixture).await?;
+            invalidar_cache_do_seed(&resumo).await;
+            println!("Fixture {nome} aplicada ({resumo})");
+            if !credenciais.is_empty() {
+                println!("Credenciais:");
+                for (email, senha) in credenciais {
+                    println!("  {email} / {senha}");
+                }
+            }
+        }
+        ComandoSeed::Gerar { usuarios, posts, semente, idioma, senha, saida } => {
+            let fixture = Gerador::new(semente, idioma).gerar(usuarios, posts, &senha);
+
+            if let Some(saida) = saida {
+                fixture.salvar(&saida)?;
+                println!("Fixture com {usuarios} usuários e {} posts gravada em {}", fixture.postagens.len(), saida.display());
+                return Ok(());
+            }
+
+            let resumo = seed::aplicar(repositorios, fixture).await?;
+            invalidar_cache_do_seed(&resumo).await;
+            println!("Dados gerados com a semente {semente} ({resumo})");
+            println!("Senha de todos os usuários gerados: {senha}");
+        }
+    }
+    Ok(())
+}
+
+async fn exportar(banco: &Banco, repositorios: &Repositorios, saida: Option<PathBuf>) -> Resultado {
+    let cabecalho = Cabecalho::do_banco(banco).await?;
+    let evento = NovoEvento::new(acoes::ADMIN_DADOS_EXPORTADOS).diff(json!({ "schema_version": cabecalho.schema_version }));
+    auditar(repositorios, evento).await?;
+
+    let destino: Box<dyn AsyncWrite + Unpin + Send> = match &saida {
+        Some(caminho) => Box::new(tokio::fs::File::create(caminho).await?),
+        None => Box::new(tokio::io::stdout()),
+    };
+
+    let manifesto = transferencia::exportar(repositorios, cabecalho, BufWriter::new(destino)).await?;
+    // Com a saída no stdout, o resumo vai para o stderr para não misturar com o arquivo
+    let resumo = manifesto
+        .tables
+        .iter()
+        .map(|(tabela, resumo)| format!("{tabela}: {}", resumo.count))
+        .collect::<Vec<_>>()
+        .join(", ");
+    match saida {
+        Some(caminho) => println!("Exportação gravada em {} ({resumo})", caminho.display()),
+        None => eprintln!("Exportação concluída ({resumo})"),
+    }
+    Ok(())
+}
+
+async fn importar(banco: &Banco, repositorios: &Repositorios, arquivo: &str) -> Resultado {
+    let origem: Box<dyn AsyncBufRead + Unpin + Send> = if arquivo == "-" {
+        Box::new(BufReader::new(tokio::io::stdin()))
+    } else {
+        Box::new(BufReader::new(tokio::fs::File::open(arquivo).await?))
+    };
+
+    let resumo = transferencia::importar(repositorios, origem).await?;
+    if resumo.source_schema_version != banco.versao_esperada() {
+        println!(
+            "Aviso: o arquivo veio de um banco na migração {}; este está na {}",
+            resumo.source_schema_version,
+            banco.versao_esperada()
+        );
+    }
+    if !resumo.ids_postagens.is_empty() {
+        let mut chaves: Vec<Chave> = resumo.ids_postagens.iter().map(|id| Chave::Postagem(*id)).collect();
+        chaves.push(Chave::PostagensPublicas);
+        invalidar_cache(&chaves).await;
+    }
+    auditar(repositorios, NovoEvento::new(acoes::ADMIN_DADOS_IMPORTADOS).diff(json!(&resumo))).await?;
+    println!("Importação concluída ({resumo})");
+    Ok(())
+}
+
+async fn invalidar_cache_do_seed(resumo: &seed::Resumo) {
+    if resumo.postagens_criadas + resumo.postagens_atualizadas == 0 {
+        return;
+    }
+    let mut chaves: Vec<Chave> = resumo.ids_atualizados.iter().map(|id| Chave::Postagem(*id)).collect();
+    chaves.push(Chave::PostagensPublicas);
+    invalidar_cache(&chaves).await;
+}
+
+/// Registra o evento com a origem da linha de comando (sem ator: quem roda o
+/// `blogctl` tem acesso direto ao banco).
+async fn auditar(repositorios: &Repositorios, evento: NovoEvento) -> Resultado {
+    let origem = Origem { ip: IP_CLI.to_string(), user_agent: Some(USER_AGENT_CLI.to_string()) };
+    repositorios.auditoria.registrar(&evento.origem(&origem)).await?;
+    Ok(())
+}
+
+/// Aceita o id, o email ou o nome de usuário.
+async fn buscar_usuario(repositorios: &Repositorios, usuario: &str) -> Resultado<Usuario> {
+    let encontrado = if let Ok(id) = usuario.parse::<i32>() {
+        repositorios.usuarios.buscar_por_id(id).await?
+    } else if usuario.contains('@') {
+        repositorios.usuarios.buscar_por_email(&normalizar_email(usuario)).await?
+    } else {
+        repositorios.usuarios.buscar_por_nome_usuario(usuario).await?
+    };
+
+    encontrado.ok_or_else(|| format!("usuário {usuario} não encontrado").into())
+}
+
+/// Senha informada ou, sem ela, 24 dígitos hexadecimais de um UUID v4 (92 bits aleatórios).
+fn senha_ou_aleatoria(senha: Option<String>) -> (String, bool) {
+    match senha {
+        Some(senha) => (senha, false),
+        None => (Uuid::new_v4().simple().to_string()[..24].to_string(), true),
+    }
+}
+
+/// Com `CACHE_URL` apontando para o Redis do servidor, a remoção aparece na hora;
+/// com o cache em memória, só quando a entrada expirar (`CACHE_TTL_SEGUNDOS`).
+async fn invalidar_cache(chaves: &[Chave]) {
+    Cache::do_ambiente().await.invalidar(chaves).await;
+}
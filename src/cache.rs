@@ -0,0 +1,355 @@
+use axum::http::StatusCode;
+use futures::future::BoxFuture;
+use lru::LruCache;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::HashMap,
+    future::Future,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::OnceCell;
+
+use crate::{configuracao::variavel_ou, erros::erro_interno, metricas};
+
+/// Armazenamento por trás do [`Cache`]. Falhas do armazenamento não chegam ao
+/// cliente: uma leitura que falha conta como ausência e uma escrita é descartada.
+pub trait Armazenamento: Send + Sync {
+    fn obter<'a>(&'a self, chave: &'a str) -> BoxFuture<'a, Option<Vec<u8>>>;
+    fn gravar<'a>(&'a self, chave: &'a str, valor: Vec<u8>, ttl: Duration) -> BoxFuture<'a, ()>;
+    fn remover<'a>(&'a self, chaves: &'a [String]) -> BoxFuture<'a, ()>;
+}
+
+/// Entradas em cache. Cada variante vira um rótulo `namespace` nas métricas.
+#[derive(Debug, Clone, Copy)]
+pub enum Chave {
+    PostagensPublicas,
+    Postagem(i32),
+}
+
+impl Chave {
+    fn namespace(&self) -> &'static str {
+        match self {
+            Chave::PostagensPublicas => "public_posts",
+            Chave::Postagem(_) => "post",
+        }
+    }
+
+    fn texto(&self) -> String {
+        match self {
+            Chave::PostagensPublicas => "blog:posts:publicos".to_string(),
+            Chave::Postagem(id) => format!("blog:posts:{id}"),
+        }
+    }
+}
+
+type Carregamento = Arc<OnceCell<Result<Vec<u8>, StatusCode>>>;
+
+/// Cache de leitura (read-through) compartilhado pelos handlers via `Extension`.
+///
+/// Requisições simultâneas pela mesma chave ausente esperam um único
+/// carregamento, em vez de irem todas ao banco.
+#[derive(Clone)]
+pub struct Cache {
+    armazenamento: Arc<dyn Armazenamento>,
+    ttl: Duration,
+    em_andamento: Arc<Mutex<HashMap<String, Carregamento>>>,
+    // Incrementada a cada invalidação; um carregamento que começou antes dela
+    // pode ter lido dados velhos e não deve sobreviver no cache
+    geracao: Arc<AtomicU64>,
+}
+
+impl Cache {
+    pub fn new(armazenamento: impl Armazenamento + 'static, ttl: Duration) -> Self {
+        Cache {
+            armazenamento: Arc::new(armazenamento),
+            ttl,
+            em_andamento: Arc::default(),
+            geracao: Arc::default(),
+        }
+    }
+
+    /// Cache configurado pelo ambiente:
+    /// - `CACHE_URL`: `memoria` (padrão) ou `redis://...` (requer a feature `redis`)
+    /// - `CACHE_TTL_SEGUNDOS`: validade das entradas (padrão: `30`)
+    /// - `CACHE_CAPACIDADE`: máximo de entradas do cache em memória (padrão: `1000`)
+    pub async fn do_ambiente() -> Self {
+        let ttl = Duration::from_secs(variavel_ou("CACHE_TTL_SEGUNDOS", 30));
+        let url = std::env::var("CACHE_URL").unwrap_or_else(|_| "memoria".to_string());
+
+        if url.starts_with("redis://") || url.starts_with("rediss://") {
+            #[cfg(feature = "redis")]
+            match CacheRedis::conectar(&url).await {
+                Ok(redis) => {
+                    tracing::info!(ttl_segundos = ttl.as_secs(), "Cache no Redis");
+                    return Cache::new(redis, ttl);
+                }
+                Err(erro) => tracing::warn!(erro = %erro, "falha ao conectar ao Redis; usando cache em memória"),
+            }
+
+            #[cfg(not(feature = "redis"))]
+            tracing::warn!("CACHE_URL aponta para o Redis, mas a feature `redis` não foi compilada; usando cache em memória");
+        } else if url != "memoria" {
+            tracing::warn!(url, "CACHE_URL desconhecida; usando cache em memória");
+        }
+
+        let capacidade = variavel_ou("CACHE_CAPACIDADE", 1000);
+        tracing::info!(capacidade, ttl_segundos = ttl.as_secs(), "Cache em memória");
+        Cache::new(CacheMemoria::new(capacidade), ttl)
+    }
+
+    /// Devolve o valor em cache ou executa `carregar`, guardando o resultado.
+    /// Erros (como `404`) não são guardados, mas são repassados a quem esperava
+    /// pelo mesmo carregamento.
+    pub async fn obter_ou_carregar<T, F, Fut>(&self, chave: Chave, carregar: F) -> Result<T, StatusCode>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, StatusCode>>,
+    {
+        let texto = chave.texto();
+
+        if let Some(bytes) = self.armazenamento.obter(&texto).await {
+            metricas::registrar_cache(chave.namespace(), "hit");
+            return serde_json::from_slice(&bytes).map_err(erro_interno);
+        }
+
+        let (carregamento, primeiro) = {
+            let mut em_andamento = self.em_andamento.lock().unwrap();
+            match em_andamento.get(&texto) {
+                Some(carregamento) => (carregamento.clone(), false),
+                None => {
+                    let carregamento = Carregamento::default();
+                    em_andamento.insert(texto.clone(), carregamento.clone());
+                    (carregamento, true)
+                }
+            }
+        };
+        metricas::registrar_cache(chave.namespace(), if primeiro { "miss" } else { "coalesced" });
+
+        let resultado = carregamento
+            .get_or_init(|| async {
+                let geracao = self.geracao.load(Ordering::Acquire);
+                let bytes = serde_json::to_vec(&carregar().await?).map_err(erro_interno)?;
+
+                self.armazenamento.gravar(&texto, bytes.clone(), self.ttl).await;
+                if self.geracao.load(Ordering::Acquire) != geracao {
+                    self.armazenamento.remover(std::slice::from_ref(&texto)).await;
+                }
+
+                Ok(bytes)
+            })
+            .await
+            .clone();
+
+        {
+            let mut em_andamento = self.em_andamento.lock().unwrap();
+            if em_andamento.get(&texto).is_some_and(|atual| Arc::ptr_eq(atual, &carregamento)) {
+                em_andamento.remove(&texto);
+            }
+        }
+
+        serde_json::from_slice(&resultado?).map_err(erro_interno)
+    }
+
+    /// Remove as chaves do cache depois de uma escrita no banco.
+    pub async fn invalidar(&self, chaves: &[Chave]) {
+        let textos: Vec<String> = chaves.iter().map(Chave::texto).collect();
+
+        self.geracao.fetch_add(1, Ordering::AcqRel);
+        {
+            // Carregamentos em andamento já não servem para quem chegar depois
+            let mut em_andamento = self.em_andamento.lock().unwrap();
+            for texto in &textos {
+                em_andamento.remove(texto);
+            }
+        }
+
+        self.armazenamento.remover(&textos).await;
+    }
+}
+
+/// LRU em memória com validade por entrada; cada instância tem o seu.
+pub struct CacheMemoria {
+    entradas: Mutex<LruCache<String, (Instant, Vec<u8>)>>,
+}
+
+impl CacheMemoria {
+    pub fn new(capacidade: usize) -> Self {
+        let capacidade = NonZeroUsize::new(capacidade).unwrap_or(NonZeroUsize::MIN);
+        CacheMemoria {
+            entradas: Mutex::new(LruCache::new(capacidade)),
+        }
+    }
+}
+
+impl Armazenamento for CacheMemoria {
+    fn obter<'a>(&'a self, chave: &'a str) -> BoxFuture<'a, Option<Vec<u8>>> {
+        let mut entradas = self.entradas.lock().unwrap();
+        let valor = match entradas.get(chave) {
+            Some((expira_em, _)) if *expira_em <= Instant::now() => {
+                entradas.pop(chave);
+                None
+            }
+            Some((_, valor)) => Some(valor.clone()),
+            None => None,
+        };
+
+        Box::pin(async move { valor })
+    }
+
+    fn gravar<'a>(&'a self, chave: &'a str, valor: Vec<u8>, ttl: Duration) -> BoxFuture<'a, ()> {
+        self.entradas
+            .lock()
+            .unwrap()
+            .put(chave.to_string(), (Instant::now() + ttl, valor));
+
+        Box::pin(async {})
+    }
+
+    fn remover<'a>(&'a self, chaves: &'a [String]) -> BoxFuture<'a, ()> {
+        let mut entradas = self.entradas.lock().unwrap();
+        for chave in chaves {
+            entradas.pop(chave);
+        }
+
+        Box::pin(async {})
+    }
+}
+
+/// Cache no Redis, compartilhado entre instâncias. A conexão é refeita
+/// automaticamente; enquanto o Redis estiver fora, tudo é lido do banco.
+#[cfg(feature = "redis")]
+pub struct CacheRedis {
+    conexao: redis::aio::ConnectionManager,
+}
+
+#[cfg(feature = "redis")]
+impl CacheRedis {
+    pub async fn conectar(url: &str) -> redis::RedisResult<Self> {
+        // Um Redis lento não pode segurar as requisições: depois do limite, vale o banco
+        let configuracao = redis::aio::ConnectionManagerConfig::new()
+            .set_number_of_retries(1)
+            .set_connection_timeout(Duration::from_secs(2))
+            .set_response_timeout(Duration::from_millis(500));
+
+        let cliente = redis::Client::open(url)?;
+        Ok(CacheRedis {
+            conexao: cliente.get_connection_manager_with_config(configuracao).await?,
+        })
+    }
+}
+
+#[cfg(feature = "redis")]
+impl Armazenamento for CacheRedis {
+    fn obter<'a>(&'a self, chave: &'a str) -> BoxFuture<'a, Option<Vec<u8>>> {
+        use redis::AsyncCommands;
+
+        let mut conexao = self.conexao.clone();
+        Box::pin(async move {
+            conexao
+                .get::<_, Option<Vec<u8>>>(chave)
+                .await
+                .inspect_err(|erro| tracing::warn!(erro = %erro, "falha ao ler do Redis"))
+                .ok()
+                .flatten()
+        })
+    }
+
+    fn gravar<'a>(&'a self, chave: &'a str, valor: Vec<u8>, ttl: Duration) -> BoxFuture<'a, ()> {
+        use redis::AsyncCommands;
+
+        let mut conexao = self.conexao.clone();
+        Box::pin(async move {
+            let ttl_ms = ttl.as_millis().max(1) as u64;
+            if let Err(erro) = conexao.pset_ex::<_, _, ()>(chave, valor, ttl_ms).await {
+                tracing::warn!(erro = %erro, "falha ao gravar no Redis");
+            }
+        })
+    }
+
+    fn remover<'a>(&'a self, chaves: &'a [String]) -> BoxFuture<'a, ()> {
+        use redis::AsyncCommands;
+
+        let mut conexao = self.conexao.clone();
+        Box::pin(async move {
+            if let Err(erro) = conexao.del::<_, ()>(chaves).await {
+                tracing::warn!(erro = %erro, "falha ao invalidar chaves no Redis");
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    fn cache() -> Cache {
+        Cache::new(CacheMemoria::new(10), Duration::from_secs(60))
+    }
+
+    #[tokio::test]
+    async fn carregamentos_simultaneos_vao_uma_vez_ao_banco() {
+        let cache = cache();
+        let consultas = AtomicUsize::new(0);
+
+        let carregar = || async {
+            consultas.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok::<_, StatusCode>(vec![1, 2, 3])
+        };
+
+        let resultados = futures::future::join_all(
+            (0..10).map(|_| cache.obter_ou_carregar(Chave::PostagensPublicas, carregar)),
+        )
+        .await;
+
+        assert!(resultados.iter().all(|resultado| resultado == &Ok(vec![1, 2, 3])));
+        assert_eq!(consultas.load(Ordering::SeqCst), 1);
+
+        // Já em cache: nenhuma consulta nova
+        let _ = cache.obter_ou_carregar(Chave::PostagensPublicas, carregar).await;
+        assert_eq!(consultas.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn invalidacao_remove_apenas_as_chaves_informadas() {
+        let cache = cache();
+        let _ = cache.obter_ou_carregar(Chave::Postagem(1), || async { Ok(String::from("v1")) }).await;
+        let _ = cache.obter_ou_carregar(Chave::Postagem(2), || async { Ok(String::from("v1")) }).await;
+
+        cache.invalidar(&[Chave::Postagem(1)]).await;
+
+        let postagem_1 = cache.obter_ou_carregar(Chave::Postagem(1), || async { Ok(String::from("v2")) }).await;
+        let postagem_2 = cache.obter_ou_carregar(Chave::Postagem(2), || async { Ok(String::from("v2")) }).await;
+        assert_eq!(postagem_1, Ok("v2".to_string()));
+        assert_eq!(postagem_2, Ok("v1".to_string()));
+    }
+
+    #[tokio::test]
+    async fn erros_nao_sao_guardados() {
+        let cache = cache();
+
+        let ausente = cache
+            .obter_ou_carregar::<String, _, _>(Chave::Postagem(1), || async { Err(StatusCode::NOT_FOUND) })
+            .await;
+        assert_eq!(ausente, Err(StatusCode::NOT_FOUND));
+
+        let criada = cache.obter_ou_carregar(Chave::Postagem(1), || async { Ok(String::from("nova")) }).await;
+        assert_eq!(criada, Ok("nova".to_string()));
+    }
+
+    #[tokio::test]
+    async fn entrada_expirada_e_recarregada() {
+        let cache = Cache::new(CacheMemoria::new(10), Duration::ZERO);
+        let _ = cache.obter_ou_carregar(Chave::Postagem(1), || async { Ok(String::from("v1")) }).await;
+
+        let recarregada = cache.obter_ou_carregar(Chave::Postagem(1), || async { Ok(String::from("v2")) }).await;
+        assert_eq!(recarregada, Ok("v2".to_string()));
+    }
+}
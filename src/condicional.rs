@@ -0,0 +1,264 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{
+        header::{
+            CACHE_CONTROL, CONTENT_ENCODING, CONTENT_TYPE, ETAG, IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+            LAST_MODIFIED,
+        },
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use std::borrow::Cow;
+
+use crate::erros::erro_interno;
+
+const FORMATO_DATA_HTTP: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+// Codificações que a camada de compressão pode aplicar, usadas como sufixo do ETag
+const CODIFICACOES: [&str; 4] = ["gzip", "br", "zstd", "deflate"];
+
+/// Corpo JSON serializado junto com seu `ETag` forte (hash dos bytes).
+pub struct Representacao {
+    corpo: Vec<u8>,
+    pub etag: String,
+    modificado_em: Option<DateTime<Utc>>,
+}
+
+impl Representacao {
+    pub fn nova<T: Serialize>(valor: &T) -> Result<Self, StatusCode> {
+        let corpo = serde_json::to_vec(valor).map_err(erro_interno)?;
+        let hash: String = Sha1::digest(&corpo).iter().map(|byte| format!("{byte:02x}")).collect();
+
+        Ok(Representacao {
+            corpo,
+            etag: format!("\"{hash}\""),
+            modificado_em: None,
+        })
+    }
+
+    /// Adiciona o `Last-Modified`, habilitando `If-Modified-Since`.
+    pub fn modificada_em(mut self, modificado_em: DateTime<Utc>) -> Self {
+        self.modificado_em = Some(modificado_em);
+        self
+    }
+
+    /// `304` quando o cliente já tem esta versão (`If-None-Match` tem prioridade
+    /// sobre `If-Modified-Since`); caso contrário, `200` com o corpo.
+    pub fn responder(self, cabecalhos: &HeaderMap) -> Response {
+        if self.nao_modificada(cabecalhos) {
+            return self.montar(StatusCode::NOT_MODIFIED, Body::empty());
+        }
+
+        self.resposta()
+    }
+
+    /// `200` com o corpo e os validadores, sem avaliar condições.
+    pub fn resposta(mut self) -> Response {
+        let corpo = Body::from(std::mem::take(&mut self.corpo));
+        self.montar(StatusCode::OK, corpo)
+    }
+
+    fn nao_modificada(&self, cabecalhos: &HeaderMap) -> bool {
+        if let Some(if_none_match) = cabecalhos.get(IF_NONE_MATCH) {
+            return corresponde(if_none_match, &self.etag, Comparacao::Fraca);
+        }
+
+        let desde = cabecalhos
+            .get(IF_MODIFIED_SINCE)
+            .and_then(|valor| valor.to_str().ok())
+            .and_then(|valor| DateTime::parse_from_rfc2822(valor).ok());
+
+        match (desde, self.modificado_em) {
+            // O Last-Modified tem resolução de segundos
+            (Some(desde), Some(modificado_em)) => modificado_em.timestamp() <= desde.timestamp(),
+            _ => false,
+        }
+    }
+
+    fn montar(self, status: StatusCode, corpo: Body) -> Response {
+        let mut resposta = Response::builder()
+            .status(status)
+            .header(ETAG, &self.etag)
+            // Guardar, mas sempre revalidar: a revalidação é barata com o ETag
+            .header(CACHE_CONTROL, "no-cache");
+
+        if status == StatusCode::OK {
+            resposta = resposta.header(CONTENT_TYPE, "application/json");
+        }
+        if let Some(modificado_em) = self.modificado_em {
+            resposta = resposta.header(LAST_MODIFIED, modificado_em.format(FORMATO_DATA_HTTP).to_string());
+        }
+
+        resposta.body(corpo).expect("cabeçalhos válidos")
+    }
+}
+
+/// `If-Match` do `PUT`: sem o cabeçalho, a alteração segue normalmente;
+/// com ele, só quando o cliente editou a versão atual (senão `412`).
+pub fn verificar_if_match(cabecalhos: &HeaderMap, etag_atual: &str) -> Result<(), StatusCode> {
+    match cabecalhos.get(IF_MATCH) {
+        Some(if_match) if !corresponde(if_match, etag_atual, Comparacao::Forte) => {
+            Err(StatusCode::PRECONDITION_FAILED)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Middleware, por fora da compressão: o `ETag` forte identifica os bytes
+/// enviados, então cada codificação é outra representação e ganha o sufixo
+/// dela (`"<hash>-gzip"`). Como todas trazem o mesmo JSON, `If-None-Match` e
+/// `If-Match` comparam sem o sufixo. O `304` não é comprimido, mas repete o
+/// validador que o cliente tem guardado.
+pub async fn etag_por_codificacao(requisicao: Request, proximo: Next) -> Response {
+    let if_none_match = requisicao.headers().get(IF_NONE_MATCH).cloned();
+    let mut resposta = proximo.run(requisicao).await;
+
+    let Some(etag) = resposta
+        .headers()
+        .get(ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .filter(|etag| etag.starts_with('"'))
+    else {
+        return resposta;
+    };
+
+    let codificacao = resposta
+        .headers()
+        .get(CONTENT_ENCODING)
+        .and_then(|codificacao| codificacao.to_str().ok())
+        .filter(|codificacao| *codificacao != "identity");
+
+    let etag_codificado = match codificacao {
+        Some(codificacao) => Some(format!("{}-{codificacao}\"", etag.trim_end_matches('"'))),
+        None if resposta.status() == StatusCode::NOT_MODIFIED => if_none_match
+            .as_ref()
+            .and_then(|valor| valor.to_str().ok())
+            .and_then(|valor| {
+                valor
+                    .split(',')
+                    .map(|candidato| candidato.trim().trim_start_matches("W/"))
+                    .find(|candidato| *candidato != etag && sem_codificacao(candidato) == etag)
+                    .map(str::to_string)
+            }),
+        None => None,
+    };
+
+    if let Some(valor) = etag_codificado.and_then(|etag| HeaderValue::from_str(&etag).ok()) {
+        resposta.headers_mut().insert(ETAG, valor);
+    }
+    resposta
+}
+
+/// O ETag sem o sufixo da codificação: `"<hash>-gzip"` vira `"<hash>"`.
+fn sem_codificacao(etag: &str) -> Cow<'_, str> {
+    match etag.strip_suffix('"').and_then(|etag| etag.rsplit_once('-')) {
+        Some((base, codificacao)) if CODIFICACOES.contains(&codificacao) => Cow::Owned(format!("{base}\"")),
+        _ => Cow::Borrowed(etag),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Comparacao {
+    /// `If-Match` exige que o ETag informado seja forte e idêntico
+    Forte,
+    /// `If-None-Match` ignora o prefixo `W/`
+    Fraca,
+}
+
+fn corresponde(valor: &HeaderValue, etag: &str, comparacao: Comparacao) -> bool {
+    let Ok(valor) = valor.to_str() else {
+        return false;
+    };
+
+    valor.split(',').map(str::trim).any(|candidato| match comparacao {
+        _ if candidato == "*" => true,
+        Comparacao::Forte => sem_codificacao(candidato) == etag,
+        Comparacao::Fraca => sem_codificacao(candidato.trim_start_matches("W/")) == etag,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn cabecalhos(nome: axum::http::HeaderName, valor: &str) -> HeaderMap {
+        let mut cabecalhos = HeaderMap::new();
+        cabecalhos.insert(nome, HeaderValue::from_str(valor).unwrap());
+        cabecalhos
+    }
+
+    fn representacao() -> Representacao {
+        Representacao::nova(&serde_json::json!({ "id": 1 }))
+            .unwrap()
+            .modificada_em(Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap())
+    }
+
+    #[test]
+    fn etag_igual_responde_304() {
+        let etag = representacao().etag;
+
+        for valor in [etag.clone(), format!("W/{etag}"), format!("\"outro\", {etag}"), "*".to_string()] {
+            let resposta = representacao().responder(&cabecalhos(IF_NONE_MATCH, &valor));
+            assert_eq!(resposta.status(), StatusCode::NOT_MODIFIED, "If-None-Match: {valor}");
+        }
+
+        let resposta = representacao().responder(&cabecalhos(IF_NONE_MATCH, "\"outro\""));
+        assert_eq!(resposta.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn if_modified_since_compara_em_segundos() {
+        let nao_modificada = representacao().responder(&cabecalhos(IF_MODIFIED_SINCE, "Sun, 18 Oct 2026 12:00:00 GMT"));
+        assert_eq!(nao_modificada.status(), StatusCode::NOT_MODIFIED);
+
+        let modificada = representacao().responder(&cabecalhos(IF_MODIFIED_SINCE, "Sun, 18 Oct 2026 11:59:59 GMT"));
+        assert_eq!(modificada.status(), StatusCode::OK);
+        assert_eq!(modificada.headers()[LAST_MODIFIED], "Sun, 18 Oct 2026 12:00:00 GMT");
+    }
+
+    #[test]
+    fn if_none_match_tem_prioridade_sobre_if_modified_since() {
+        let mut condicoes = cabecalhos(IF_NONE_MATCH, "\"outro\"");
+        condicoes.insert(IF_MODIFIED_SINCE, HeaderValue::from_static("Sun, 18 Oct 2026 12:00:00 GMT"));
+
+        assert_eq!(representacao().responder(&condicoes).status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn etag_de_outra_codificacao_corresponde_ao_mesmo_json() {
+        let etag = representacao().etag;
+        let gzip = format!("{}-gzip\"", etag.trim_end_matches('"'));
+
+        let resposta = representacao().responder(&cabecalhos(IF_NONE_MATCH, &gzip));
+        assert_eq!(resposta.status(), StatusCode::NOT_MODIFIED);
+        assert!(verificar_if_match(&cabecalhos(IF_MATCH, &gzip), &etag).is_ok());
+        assert_eq!(
+            verificar_if_match(&cabecalhos(IF_MATCH, &format!("{}-outro\"", etag.trim_end_matches('"'))), &etag),
+            Err(StatusCode::PRECONDITION_FAILED)
+        );
+    }
+
+    #[test]
+    fn if_match_exige_etag_forte_identico() {
+        let etag = representacao().etag;
+
+        assert!(verificar_if_match(&HeaderMap::new(), &etag).is_ok());
+        assert!(verificar_if_match(&cabecalhos(IF_MATCH, &etag), &etag).is_ok());
+        assert!(verificar_if_match(&cabecalhos(IF_MATCH, "*"), &etag).is_ok());
+        assert_eq!(
+            verificar_if_match(&cabecalhos(IF_MATCH, &format!("W/{etag}")), &etag),
+            Err(StatusCode::PRECONDITION_FAILED)
+        );
+        assert_eq!(
+            verificar_if_match(&cabecalhos(IF_MATCH, "\"antigo\""), &etag),
+            Err(StatusCode::PRECONDITION_FAILED)
+        );
+    }
+}
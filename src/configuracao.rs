@@ -0,0 +1,68 @@
+use ipnet::IpNet;
+use std::net::IpAddr;
+
+const SEGREDO_JWT_PADRAO: &str = "your-secret-key-change-in-production"; // Em produção, use JWT_SECRET
+
+// Tamanho máximo padrão do arquivo enviado ao /admin/import (IMPORTACAO_LIMITE_MB)
+const LIMITE_IMPORTACAO_MB: usize = 256;
+
+/// Configuração própria de cada instância da aplicação. Duas aplicações
+/// montadas no mesmo processo podem, por exemplo, assinar tokens com segredos diferentes.
+#[derive(Debug, Clone)]
+pub struct Configuracao {
+    /// Segredo HMAC usado para assinar e verificar os tokens JWT
+    pub segredo_jwt: String,
+    /// Proxies reversos cujo `X-Forwarded-For` é aceito; sem nenhum, o IP do
+    /// cliente é sempre o da conexão (veja [`Origem`](crate::auditoria::Origem))
+    pub proxies_confiaveis: Vec<IpNet>,
+    /// Tamanho máximo, em bytes, do arquivo enviado ao `POST /admin/import`
+    pub limite_importacao: usize,
+}
+
+impl Configuracao {
+    /// `JWT_SECRET`, com o segredo de desenvolvimento como padrão,
+    /// `PROXIES_CONFIAVEIS`, uma lista de IPs ou redes separados por vírgula
+    /// (ex.: `10.0.0.0/8,192.168.1.10`), e `IMPORTACAO_LIMITE_MB`.
+    pub fn do_ambiente() -> Self {
+        Configuracao {
+            segredo_jwt: variavel_ou("JWT_SECRET", SEGREDO_JWT_PADRAO.to_string()),
+            proxies_confiaveis: redes(&std::env::var("PROXIES_CONFIAVEIS").unwrap_or_default()),
+            limite_importacao: variavel_ou("IMPORTACAO_LIMITE_MB", LIMITE_IMPORTACAO_MB) * 1024 * 1024,
+        }
+    }
+}
+
+impl Default for Configuracao {
+    /// O segredo de desenvolvimento, sem proxies confiáveis e com o limite de importação padrão.
+    fn default() -> Self {
+        Configuracao {
+            segredo_jwt: SEGREDO_JWT_PADRAO.to_string(),
+            proxies_confiaveis: Vec::new(),
+            limite_importacao: LIMITE_IMPORTACAO_MB * 1024 * 1024,
+        }
+    }
+}
+
+/// IPs ou redes em CIDR separados por vírgula; um IP sozinho vale como `/32` ou `/128`.
+fn redes(lista: &str) -> Vec<IpNet> {
+    lista
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .filter_map(|item| {
+            let rede = item.parse::<IpNet>().or_else(|_| item.parse::<IpAddr>().map(IpNet::from));
+            if rede.is_err() {
+                tracing::warn!(item, "entrada inválida em PROXIES_CONFIAVEIS; ignorada");
+            }
+            rede.ok()
+        })
+        .collect()
+}
+
+/// Lê uma variável de ambiente, usando `padrao` quando ausente ou inválida.
+pub fn variavel_ou<T: std::str::FromStr>(nome: &str, padrao: T) -> T {
+    std::env::var(nome)
+        .ok()
+        .and_then(|valor| valor.parse().ok())
+        .unwrap_or(padrao)
+}
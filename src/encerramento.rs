@@ -0,0 +1,124 @@
+use std::{collections::HashMap, future::Future, time::Duration};
+use tokio::task::{self, JoinSet};
+use tokio_util::sync::CancellationToken;
+
+use crate::{configuracao::variavel_ou, saude::Prontidao};
+
+/// Tempos do encerramento gracioso, lidos do ambiente:
+/// - `ENCERRAMENTO_ESPERA_SEGUNDOS`: quanto tempo a instância segue aceitando conexões
+///   depois de a prontidão falhar, para o balanceador tirá-la de rotação (padrão: `0`)
+/// - `ENCERRAMENTO_PRAZO_SEGUNDOS`: limite para concluir as requisições em andamento
+///   e as tarefas de fundo (padrão: `30`)
+pub struct ConfiguracaoEncerramento {
+    pub espera: Duration,
+    pub prazo: Duration,
+}
+
+impl ConfiguracaoEncerramento {
+    pub fn do_ambiente() -> Self {
+        ConfiguracaoEncerramento {
+            espera: Duration::from_secs(variavel_ou("ENCERRAMENTO_ESPERA_SEGUNDOS", 0)),
+            prazo: Duration::from_secs(variavel_ou("ENCERRAMENTO_PRAZO_SEGUNDOS", 30)),
+        }
+    }
+}
+
+/// Aguarda SIGTERM ou SIGINT, marca a instância como não pronta e, após a
+/// espera configurada, cancela `parar_de_aceitar`.
+pub async fn aguardar_sinal(
+    configuracao: &ConfiguracaoEncerramento,
+    prontidao: &Prontidao,
+    parar_de_aceitar: CancellationToken,
+) {
+    let sinal = sinal_recebido().await;
+    tracing::info!(sinal, "Encerrando: prontidão desativada");
+    prontidao.marcar_encerrando();
+
+    if !configuracao.espera.is_zero() {
+        tokio::time::sleep(configuracao.espera).await;
+    }
+
+    tracing::info!(
+        prazo_segundos = configuracao.prazo.as_secs(),
+        "Parando de aceitar conexões e aguardando as requisições em andamento"
+    );
+    parar_de_aceitar.cancel();
+}
+
+/// Execuções de fundo que reservaram algo no banco (uma tarefa da fila, uma
+/// entrega de webhook), cada uma com a sua reserva. No encerramento,
+/// [`EmAndamento::encerrar`] devolve as reservas das que tiveram de ser
+/// canceladas, para quem as fez liberá-las.
+pub struct EmAndamento<R> {
+    execucoes: JoinSet<()>,
+    reservas: HashMap<task::Id, R>,
+}
+
+impl<R> Default for EmAndamento<R> {
+    fn default() -> Self {
+        EmAndamento { execucoes: JoinSet::new(), reservas: HashMap::new() }
+    }
+}
+
+impl<R> EmAndamento<R> {
+    pub fn len(&self) -> usize {
+        self.execucoes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.execucoes.is_empty()
+    }
+
+    pub fn iniciar<F>(&mut self, reserva: R, execucao: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let id = self.execucoes.spawn(execucao).id();
+        self.reservas.insert(id, reserva);
+    }
+
+    /// Espera uma execução terminar e devolve a reserva dela; `None` se não há nenhuma.
+    pub async fn proxima(&mut self) -> Option<R> {
+        let id = match self.execucoes.join_next_with_id().await? {
+            Ok((id, ())) => id,
+            Err(erro) => erro.id(),
+        };
+        self.reservas.remove(&id)
+    }
+
+    /// Espera as execuções por até `prazo`, cancela as que não terminaram e
+    /// devolve as reservas delas. Só volta depois que as canceladas pararam,
+    /// de modo que nenhuma grava o resultado depois de a reserva ser liberada.
+    pub async fn encerrar(mut self, prazo: Duration) -> Vec<R> {
+        let _ = tokio::time::timeout(prazo, async {
+            while self.proxima().await.is_some() {}
+        })
+        .await;
+
+        self.execucoes.abort_all();
+        while let Some(resultado) = self.execucoes.join_next_with_id().await {
+            // Terminou entre o fim do prazo e o cancelamento
+            if let Ok((id, ())) = resultado {
+                self.reservas.remove(&id);
+            }
+        }
+        self.reservas.into_values().collect()
+    }
+}
+
+#[cfg(unix)]
+async fn sinal_recebido() -> &'static str {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sigterm = signal(SignalKind::terminate()).expect("falha ao registrar o handler de SIGTERM");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => "SIGINT",
+        _ = sigterm.recv() => "SIGTERM",
+    }
+}
+
+#[cfg(not(unix))]
+async fn sinal_recebido() -> &'static str {
+    tokio::signal::ctrl_c().await.expect("falha ao registrar o handler de Ctrl+C");
+    "Ctrl+C"
+}
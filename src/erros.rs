@@ -0,0 +1,100 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use std::collections::BTreeMap;
+
+/// Erros de validação agrupados por campo da requisição.
+#[derive(Debug, Default)]
+pub struct ErrosValidacao {
+    campos: BTreeMap<&'static str, Vec<String>>,
+}
+
+impl ErrosValidacao {
+    pub fn adicionar(&mut self, campo: &'static str, mensagem: impl Into<String>) {
+        self.campos.entry(campo).or_default().push(mensagem.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.campos.is_empty()
+    }
+
+    /// As mensagens por campo, como vão no `fields` das respostas de erro.
+    pub fn campos(&self) -> &BTreeMap<&'static str, Vec<String>> {
+        &self.campos
+    }
+}
+
+// Usado fora das rotas, como no blogctl: uma linha por mensagem
+impl std::fmt::Display for ErrosValidacao {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mensagens = self
+            .campos
+            .iter()
+            .flat_map(|(campo, mensagens)| mensagens.iter().map(move |mensagem| format!("{campo}: {mensagem}")));
+
+        for (indice, mensagem) in mensagens.enumerate() {
+            if indice > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{mensagem}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ErrosValidacao {}
+
+impl IntoResponse for ErrosValidacao {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(serde_json::json!({
+                "error": "Dados inválidos",
+                "fields": self.campos,
+            })),
+        )
+            .into_response()
+    }
+}
+
+/// Erro retornado pelos handlers que, além de códigos HTTP simples,
+/// precisam devolver detalhes ao cliente.
+#[derive(Debug)]
+pub enum ErroApi {
+    Status(StatusCode),
+    /// Corpo da requisição ilegível (JSON malformado, campos ausentes, tipo de conteúdo errado)
+    Requisicao(StatusCode, String),
+    Validacao(ErrosValidacao),
+}
+
+impl From<StatusCode> for ErroApi {
+    fn from(status: StatusCode) -> Self {
+        ErroApi::Status(status)
+    }
+}
+
+impl From<ErrosValidacao> for ErroApi {
+    fn from(erros: ErrosValidacao) -> Self {
+        ErroApi::Validacao(erros)
+    }
+}
+
+impl IntoResponse for ErroApi {
+    fn into_response(self) -> Response {
+        match self {
+            ErroApi::Status(status) => status.into_response(),
+            ErroApi::Requisicao(status, mensagem) => {
+                (status, Json(serde_json::json!({ "error": mensagem }))).into_response()
+            }
+            ErroApi::Validacao(erros) => erros.into_response(),
+        }
+    }
+}
+
+/// Registra a causa de uma falha inesperada e responde apenas `500` ao cliente.
+/// Usado nos `map_err` em vez de descartar o erro original.
+pub fn erro_interno<E: std::fmt::Display>(erro: E) -> StatusCode {
+    tracing::error!(erro = %erro, "erro interno");
+    StatusCode::INTERNAL_SERVER_ERROR
+}
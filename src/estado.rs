@@ -0,0 +1,74 @@
+use axum::extract::FromRef;
+use std::sync::Arc;
+
+use crate::{
+    configuracao::Configuracao, db::Banco, notificacoes::Notificacoes, repositorios::Repositorios, saude::Prontidao,
+    transmissao::Transmissao,
+};
+
+/// Estado das rotas. Cada handler extrai só a parte de que precisa:
+/// `State<Repositorios>` para os dados, `State<Banco>` para saúde e métricas,
+/// `State<Arc<Configuracao>>` para o segredo dos tokens, `State<Transmissao>`
+/// para o feed de posts em tempo real, `State<Notificacoes>` para as
+/// notificações dos usuários, `State<Prontidao>` para as sondas de saúde.
+#[derive(Clone)]
+pub struct Estado {
+    pub banco: Banco,
+    pub repositorios: Repositorios,
+    pub configuracao: Arc<Configuracao>,
+    pub transmissao: Transmissao,
+    pub notificacoes: Notificacoes,
+    pub prontidao: Prontidao,
+}
+
+impl Estado {
+    pub fn new(banco: Banco, configuracao: Configuracao) -> Self {
+        let repositorios = banco.repositorios();
+        let transmissao = Transmissao::new(&banco, repositorios.clone());
+        let notificacoes = Notificacoes::new(&banco, repositorios.clone());
+        Estado {
+            banco,
+            repositorios,
+            configuracao: Arc::new(configuracao),
+            transmissao,
+            notificacoes,
+            prontidao: Prontidao::inicializada(),
+        }
+    }
+}
+
+impl FromRef<Estado> for Banco {
+    fn from_ref(estado: &Estado) -> Self {
+        estado.banco.clone()
+    }
+}
+
+impl FromRef<Estado> for Repositorios {
+    fn from_ref(estado: &Estado) -> Self {
+        estado.repositorios.clone()
+    }
+}
+
+impl FromRef<Estado> for Arc<Configuracao> {
+    fn from_ref(estado: &Estado) -> Self {
+        estado.configuracao.clone()
+    }
+}
+
+impl FromRef<Estado> for Transmissao {
+    fn from_ref(estado: &Estado) -> Self {
+        estado.transmissao.clone()
+    }
+}
+
+impl FromRef<Estado> for Notificacoes {
+    fn from_ref(estado: &Estado) -> Self {
+        estado.notificacoes.clone()
+    }
+}
+
+impl FromRef<Estado> for Prontidao {
+    fn from_ref(estado: &Estado) -> Self {
+        estado.prontidao.clone()
+    }
+}
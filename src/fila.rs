@@ -0,0 +1,428 @@
+//! Fila de tarefas em segundo plano, gravada na tabela `jobs`: o trabalho
+//! demorado sai da requisição e sobrevive a reinícios da instância.
+//!
+//! Cada tipo de tarefa é uma struct serializável que implementa [`TipoTarefa`];
+//! ela vira o `payload` da tarefa. Quem enfileira usa [`enfileirar`] (basta ter
+//! os `Repositorios`); quem executa é a [`Fila`], com os tipos registrados na
+//! inicialização, e só reserva tarefas dos tipos que conhece.
+//!
+//! Os trabalhadores de todas as instâncias reservam tarefas com
+//! `FOR UPDATE SKIP LOCKED`, sem executar a mesma duas vezes. Uma falha reagenda
+//! a tarefa com espera exponencial até o máximo de tentativas do tipo; esgotadas
+//! (ou num erro definitivo), ela fica `dead` até um administrador pedir outra
+//! tentativa em `POST /api/v1/admin/jobs/{id}/retry`. Se a instância cair no meio
+//! da execução, a reserva expira e outra instância retoma a tarefa; num
+//! encerramento normal, as tarefas que não terminam no prazo voltam à fila na hora.
+//!
+//! Tarefas agendadas ([`Fila::agendar`]) seguem uma expressão cron; cada
+//! ocorrência é enfileirada com a chave `{kind}@{horário}`, de modo que várias
+//! instâncias verificando a mesma agenda criam uma tarefa só. Ocorrências
+//! perdidas enquanto nenhuma instância estava no ar não são recuperadas.
+
+use chrono::{DateTime, Duration, Utc};
+use futures::future::{self, BoxFuture};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, fmt, sync::Arc, time::Instant};
+use tokio_util::{sync::CancellationToken, task::AbortOnDropHandle};
+
+use crate::{
+    configuracao::variavel_ou,
+    encerramento::EmAndamento,
+    metricas,
+    models::Tarefa,
+    repositorios::{Repositorios, Resultado},
+};
+
+pub mod cron;
+
+pub use cron::Cron;
+
+/// Valores de `jobs.status`.
+pub mod situacoes {
+    pub const PENDENTE: &str = "pending";
+    pub const EM_EXECUCAO: &str = "running";
+    pub const CONCLUIDA: &str = "succeeded";
+    /// Tentativas esgotadas ou erro definitivo; só volta à fila pelo administrador
+    pub const MORTA: &str = "dead";
+}
+
+pub const MAXIMO_TENTATIVAS_PADRAO: i32 = 5;
+const ESPERA_INICIAL: Duration = Duration::seconds(15);
+const ESPERA_MAXIMA: Duration = Duration::hours(1);
+
+const PRAZO_EXECUCAO: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+// Maior que o prazo de execução: uma tarefa só é retomada quando quem a
+// reservou certamente desistiu dela
+const DURACAO_RESERVA: Duration = Duration::minutes(10);
+const INTERVALO_VERIFICACAO: std::time::Duration = std::time::Duration::from_secs(2);
+
+const TRABALHADORES_PADRAO: usize = 4;
+
+/// Tarefas concluídas ficam esse tempo para consulta antes de a limpeza apagá-las.
+const RETENCAO_CONCLUIDAS: Duration = Duration::days(7);
+
+/// Configuração da fila nesta instância, lida do ambiente:
+/// - `FILA_TRABALHADORES`: tarefas executadas ao mesmo tempo (padrão: `4`);
+///   com `0`, a instância só enfileira, inclusive as agendadas
+pub struct ConfiguracaoFila {
+    pub trabalhadores: usize,
+}
+
+impl ConfiguracaoFila {
+    pub fn do_ambiente() -> Self {
+        ConfiguracaoFila {
+            trabalhadores: variavel_ou("FILA_TRABALHADORES", TRABALHADORES_PADRAO),
+        }
+    }
+}
+
+/// Um tipo de tarefa: os campos da struct são o `payload` e `executar` é o trabalho.
+pub trait TipoTarefa: Serialize + DeserializeOwned + Send + 'static {
+    /// Gravado em `jobs.kind`; não deve mudar enquanto houver tarefas dele na fila.
+    const TIPO: &'static str;
+
+    const MAXIMO_TENTATIVAS: i32 = MAXIMO_TENTATIVAS_PADRAO;
+
+    /// Pode rodar mais de uma vez para a mesma tarefa (depois de uma falha ou
+    /// de uma reserva expirada), então deve ser idempotente.
+    fn executar(self, repositorios: Repositorios) -> BoxFuture<'static, Result<(), ErroTarefa>>;
+}
+
+/// Falha na execução de uma tarefa.
+#[derive(Debug)]
+pub enum ErroTarefa {
+    /// Tentada de novo, com espera, até o máximo de tentativas
+    Temporario(String),
+    /// Não adianta tentar de novo: a tarefa vai direto para `dead`
+    Definitivo(String),
+}
+
+impl fmt::Display for ErroTarefa {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErroTarefa::Temporario(mensagem) | ErroTarefa::Definitivo(mensagem) => f.write_str(mensagem),
+        }
+    }
+}
+
+// Permite `?` com os erros do banco e afins, tratados como temporários
+impl<E: std::error::Error> From<E> for ErroTarefa {
+    fn from(erro: E) -> Self {
+        ErroTarefa::Temporario(erro.to_string())
+    }
+}
+
+/// Uma tarefa a enfileirar.
+#[derive(Debug, Clone)]
+pub struct NovaTarefa {
+    pub tipo: &'static str,
+    pub payload: Value,
+    pub executar_em: DateTime<Utc>,
+    pub maximo_tentativas: i32,
+    /// Única entre todas as tarefas: enfileirar de novo com a mesma chave não faz nada
+    pub chave: Option<String>,
+}
+
+impl NovaTarefa {
+    pub fn new<T: TipoTarefa>(tarefa: &T, executar_em: DateTime<Utc>) -> Self {
+        NovaTarefa {
+            tipo: T::TIPO,
+            payload: serde_json::to_value(tarefa).expect("tarefa serializável em JSON"),
+            executar_em,
+            maximo_tentativas: T::MAXIMO_TENTATIVAS,
+            chave: None,
+        }
+    }
+}
+
+/// Resultado de uma execução, gravado na tarefa.
+#[derive(Debug, Clone)]
+pub struct ResultadoExecucao {
+    /// A tentativa executada: se a tarefa já estiver em outra, o resultado é descartado
+    pub tentativa: i32,
+    pub concluida_em: DateTime<Utc>,
+    /// Vazio quando a tarefa terminou sem erro
+    pub erro: Option<String>,
+    /// Quando tentar de novo, depois de uma falha; `None` encerra a tarefa
+    pub proxima_tentativa: Option<DateTime<Utc>>,
+}
+
+impl ResultadoExecucao {
+    /// A situação em que a tarefa fica.
+    pub fn situacao(&self) -> &'static str {
+        match (&self.erro, self.proxima_tentativa) {
+            (None, _) => situacoes::CONCLUIDA,
+            (Some(_), Some(_)) => situacoes::PENDENTE,
+            (Some(_), None) => situacoes::MORTA,
+        }
+    }
+}
+
+/// Filtros de `GET /admin/jobs`. Mais novas primeiro; `before_id` pagina a
+/// partir do último id visto.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FiltroTarefas {
+    pub status: Option<String>,
+    #[serde(rename = "kind")]
+    pub tipo: Option<String>,
+    #[serde(rename = "before_id")]
+    pub antes_do_id: Option<i64>,
+    #[serde(rename = "limit")]
+    pub limite: Option<i64>,
+}
+
+/// Enfileira a tarefa para executar assim que houver um trabalhador livre.
+pub async fn enfileirar<T: TipoTarefa>(repositorios: &Repositorios, tarefa: &T) -> Resultado<Tarefa> {
+    enfileirar_em(repositorios, tarefa, Utc::now()).await
+}
+
+/// Enfileira a tarefa para executar a partir de `quando`.
+pub async fn enfileirar_em<T: TipoTarefa>(repositorios: &Repositorios, tarefa: &T, quando: DateTime<Utc>) -> Resultado<Tarefa> {
+    // Sem chave não há conflito, então a tarefa sempre é criada
+    repositorios
+        .tarefas
+        .enfileirar(&NovaTarefa::new(tarefa, quando))
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)
+}
+
+/// Espera antes da tentativa seguinte à `tentativas`-ésima: 15s, 30s, 1min... até 1h.
+pub fn espera(tentativas: i32) -> Duration {
+    let expoente = tentativas.saturating_sub(1).clamp(0, 16) as u32;
+    (ESPERA_INICIAL * 2i32.pow(expoente)).min(ESPERA_MAXIMA)
+}
+
+type Executor = Arc<dyn Fn(Value, Repositorios) -> BoxFuture<'static, Result<(), ErroTarefa>> + Send + Sync>;
+
+struct Agendamento {
+    cron: Cron,
+    tarefa: NovaTarefa,
+}
+
+/// Os tipos de tarefa que esta instância executa e as tarefas agendadas.
+pub struct Fila {
+    repositorios: Repositorios,
+    executores: HashMap<&'static str, Executor>,
+    agendamentos: Vec<Agendamento>,
+}
+
+impl Fila {
+    pub fn new(repositorios: Repositorios) -> Self {
+        Fila {
+            repositorios,
+            executores: HashMap::new(),
+            agendamentos: Vec::new(),
+        }
+    }
+
+    /// Executa as tarefas do tipo `T` enfileiradas por qualquer instância.
+    pub fn registrar<T: TipoTarefa>(mut self) -> Self {
+        let executor: Executor = Arc::new(|payload, repositorios| match serde_json::from_value::<T>(payload) {
+            Ok(tarefa) => tarefa.executar(repositorios),
+            Err(erro) => Box::pin(future::ready(Err(ErroTarefa::Definitivo(format!("payload inválido: {erro}"))))),
+        });
+        self.executores.insert(T::TIPO, executor);
+        self
+    }
+
+    /// Registra `T` e enfileira `tarefa` a cada ocorrência de `expressao` (cron, em UTC).
+    ///
+    /// # Panics
+    ///
+    /// Se a expressão for inválida ou nunca ocorrer: as agendas são fixas no código.
+    pub fn agendar<T: TipoTarefa>(mut self, expressao: &str, tarefa: T) -> Self {
+        let cron: Cron = expressao.parse().unwrap_or_else(|erro| panic!("agenda de {}: {erro}", T::TIPO));
+        assert!(cron.proxima(Utc::now()).is_some(), "agenda de {}: `{cron}` nunca ocorre", T::TIPO);
+
+        self.agendamentos.push(Agendamento { cron, tarefa: NovaTarefa::new(&tarefa, Utc::now()) });
+        self.registrar::<T>()
+    }
+
+    /// Enfileira as tarefas agendadas com alguma ocorrência em `(desde, ate]`,
+    /// uma por agenda, para o horário da última; devolve quantas criou.
+    pub async fn enfileirar_agendadas(&self, desde: DateTime<Utc>, ate: DateTime<Utc>) -> Resultado<usize> {
+        let mut criadas = 0;
+        for Agendamento { cron, tarefa } in self.agendamentos.iter() {
+            let Some(mut ocorrencia) = cron.proxima(desde).filter(|ocorrencia| *ocorrencia <= ate) else {
+                continue;
+            };
+            while let Some(seguinte) = cron.proxima(ocorrencia).filter(|seguinte| *seguinte <= ate) {
+                ocorrencia = seguinte;
+            }
+
+            let tarefa = NovaTarefa {
+                executar_em: ocorrencia,
+                chave: Some(format!("{}@{}", tarefa.tipo, ocorrencia.to_rfc3339())),
+                ..tarefa.clone()
+            };
+            if self.repositorios.tarefas.enfileirar(&tarefa).await?.is_some() {
+                criadas += 1;
+            }
+        }
+        Ok(criadas)
+    }
+
+    /// Reserva e executa, em paralelo, até `limite` tarefas vencidas em `agora`;
+    /// devolve quantas executou.
+    pub async fn processar(&self, agora: DateTime<Utc>, limite: usize) -> Resultado<usize> {
+        let reservadas = self.reservar(agora, limite).await?;
+        let total = reservadas.len();
+        future::join_all(reservadas.into_iter().map(|tarefa| self.executar_tarefa(tarefa, agora))).await;
+        Ok(total)
+    }
+
+    async fn reservar(&self, agora: DateTime<Utc>, limite: usize) -> Resultado<Vec<Tarefa>> {
+        let tipos: Vec<&'static str> = self.executores.keys().copied().collect();
+        if tipos.is_empty() || limite == 0 {
+            return Ok(Vec::new());
+        }
+        self.repositorios
+            .tarefas
+            .reservar(&tipos, agora, agora + DURACAO_RESERVA, limite as i64)
+            .await
+    }
+
+    async fn executar_tarefa(&self, tarefa: Tarefa, agora: DateTime<Utc>) {
+        // Só são reservadas tarefas dos tipos registrados
+        let (tipo, executor) = self.executores.get_key_value(tarefa.tipo.as_str()).expect("tipo registrado");
+        let (tipo, executor) = (*tipo, executor.clone());
+        let repositorios = self.repositorios.clone();
+        let payload = tarefa.payload.0.clone();
+
+        // Numa task à parte, o pânico de uma tarefa vira uma falha como as outras;
+        // cancelada esta execução (no encerramento), a task vai junto
+        let inicio = Instant::now();
+        let execucao = AbortOnDropHandle::new(tokio::spawn(async move {
+            tokio::time::timeout(PRAZO_EXECUCAO, executor(payload, repositorios)).await
+        }));
+        let erro = match execucao.await {
+            Ok(Ok(Ok(()))) => None,
+            Ok(Ok(Err(erro))) => Some(erro),
+            Ok(Err(_)) => Some(ErroTarefa::Temporario("prazo de execução esgotado".to_string())),
+            Err(_) => Some(ErroTarefa::Temporario("a tarefa entrou em pânico".to_string())),
+        };
+        let duracao = inicio.elapsed();
+
+        let proxima_tentativa = match &erro {
+            Some(ErroTarefa::Temporario(_)) if tarefa.tentativas < tarefa.maximo_tentativas => {
+                Some(agora + espera(tarefa.tentativas))
+            }
+            _ => None,
+        };
+        let resultado = ResultadoExecucao {
+            tentativa: tarefa.tentativas,
+            concluida_em: Utc::now(),
+            erro: erro.map(|erro| erro.to_string()),
+            proxima_tentativa,
+        };
+
+        if let Some(erro) = &resultado.erro {
+            tracing::warn!(
+                id_tarefa = tarefa.id,
+                tipo = tarefa.tipo,
+                tentativa = tarefa.tentativas,
+                situacao = resultado.situacao(),
+                erro = %erro,
+                "falha ao executar tarefa"
+            );
+        }
+        metricas::registrar_tarefa(tipo, resultado.situacao(), duracao);
+
+        match self.repositorios.tarefas.concluir(tarefa.id, &resultado).await {
+            Ok(true) => {}
+            Ok(false) => tracing::warn!(id_tarefa = tarefa.id, "tarefa retomada por outra instância; resultado descartado"),
+            Err(erro) => tracing::warn!(id_tarefa = tarefa.id, erro = %erro, "falha ao gravar o resultado da tarefa"),
+        }
+    }
+
+    /// Tarefa de fundo: enfileira as agendadas e mantém até `trabalhadores`
+    /// tarefas em execução, reservando mais assim que uma termina. Só deve
+    /// começar depois das migrações. Cancelado `parar`, não reserva mais nada e
+    /// espera as tarefas em andamento por até `prazo`; as que não terminarem
+    /// são canceladas e voltam a `pending`, sem contar a tentativa.
+    pub async fn executar(self, trabalhadores: usize, parar: CancellationToken, prazo: std::time::Duration) {
+        let fila = Arc::new(self);
+        let mut em_execucao = EmAndamento::default();
+        let mut intervalo = tokio::time::interval(INTERVALO_VERIFICACAO);
+        let mut verificada_ate = Utc::now();
+
+        loop {
+            tokio::select! {
+                biased;
+                _ = parar.cancelled() => break,
+                _ = intervalo.tick() => {
+                    let agora = Utc::now();
+                    match fila.enfileirar_agendadas(verificada_ate, agora).await {
+                        Ok(_) => verificada_ate = agora,
+                        Err(erro) => tracing::warn!(erro = %erro, "falha ao enfileirar as tarefas agendadas"),
+                    }
+                }
+                Some(_) = em_execucao.proxima() => {}
+            }
+
+            let agora = Utc::now();
+            match fila.reservar(agora, trabalhadores.saturating_sub(em_execucao.len())).await {
+                Ok(tarefas) => {
+                    for tarefa in tarefas {
+                        let fila = fila.clone();
+                        let reserva = (tarefa.id, tarefa.tentativas);
+                        em_execucao.iniciar(reserva, async move { fila.executar_tarefa(tarefa, agora).await });
+                    }
+                }
+                Err(erro) => tracing::warn!(erro = %erro, "falha ao reservar tarefas"),
+            }
+        }
+
+        tracing::info!(em_execucao = em_execucao.len(), "Fila de tarefas encerrando");
+        for (id, tentativa) in em_execucao.encerrar(prazo).await {
+            match fila.repositorios.tarefas.liberar(id, tentativa).await {
+                Ok(true) => tracing::warn!(id_tarefa = id, "tarefa interrompida no encerramento; devolvida à fila"),
+                Ok(false) => {}
+                Err(erro) => tracing::warn!(id_tarefa = id, erro = %erro, "falha ao devolver a tarefa interrompida à fila"),
+            }
+        }
+    }
+}
+
+/// Apaga as tarefas concluídas com sucesso há mais de `RETENCAO_CONCLUIDAS`.
+/// As mortas ficam, para o administrador investigar.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LimpezaTarefas;
+
+impl TipoTarefa for LimpezaTarefas {
+    const TIPO: &'static str = "jobs.cleanup";
+
+    fn executar(self, repositorios: Repositorios) -> BoxFuture<'static, Result<(), ErroTarefa>> {
+        Box::pin(async move {
+            let apagadas = repositorios.tarefas.apagar_concluidas(Utc::now() - RETENCAO_CONCLUIDAS).await?;
+            tracing::info!(apagadas, "Tarefas concluídas antigas apagadas");
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn espera_dobra_ate_o_limite() {
+        assert_eq!(espera(1), Duration::seconds(15));
+        assert_eq!(espera(2), Duration::seconds(30));
+        assert_eq!(espera(3), Duration::seconds(60));
+        assert_eq!(espera(40), ESPERA_MAXIMA);
+    }
+
+    #[test]
+    fn situacao_depende_do_erro_e_da_proxima_tentativa() {
+        let mut resultado = ResultadoExecucao { tentativa: 1, concluida_em: Utc::now(), erro: None, proxima_tentativa: None };
+        assert_eq!(resultado.situacao(), situacoes::CONCLUIDA);
+
+        resultado.erro = Some("falhou".to_string());
+        assert_eq!(resultado.situacao(), situacoes::MORTA);
+
+        resultado.proxima_tentativa = Some(Utc::now());
+        assert_eq!(resultado.situacao(), situacoes::PENDENTE);
+    }
+}
@@ -0,0 +1,195 @@
+//! Expressões cron de cinco campos (`minuto hora dia mês dia-da-semana`), em UTC.
+//!
+//! Cada campo aceita `*`, números, intervalos (`1-5`), listas (`1,15`) e passos
+//! (`*/10`, `0-30/5`); o dia da semana vai de `0` (domingo) a `6`, com `7`
+//! também valendo domingo. Como no cron tradicional, quando dia do mês e dia da
+//! semana são restritos, basta um deles coincidir.
+
+use chrono::{DateTime, Datelike, Duration, DurationRound, NaiveDate, Timelike, Utc};
+use std::{fmt, str::FromStr};
+
+// Uma expressão que não ocorre em cinco anos (como `0 0 30 2 *`) não ocorre nunca
+const HORIZONTE_DIAS: i64 = 5 * 366;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    expressao: String,
+    minutos: u64,
+    horas: u64,
+    dias: u64,
+    meses: u64,
+    dias_semana: u64,
+    dia_restrito: bool,
+    dia_semana_restrito: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErroCron(String);
+
+impl fmt::Display for ErroCron {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expressão cron inválida: {}", self.0)
+    }
+}
+
+impl std::error::Error for ErroCron {}
+
+impl FromStr for Cron {
+    type Err = ErroCron;
+
+    fn from_str(expressao: &str) -> Result<Self, Self::Err> {
+        let campos: Vec<&str> = expressao.split_whitespace().collect();
+        let [minutos, horas, dias, meses, dias_semana] = campos[..] else {
+            return Err(ErroCron(format!("esperados 5 campos, encontrados {}", campos.len())));
+        };
+
+        // O domingo aparece como 0 e como 7
+        let mut semana = campo(dias_semana, 0, 7)?;
+        if semana & (1 << 7) != 0 {
+            semana = (semana | 1) & !(1 << 7);
+        }
+
+        Ok(Cron {
+            expressao: campos.join(" "),
+            minutos: campo(minutos, 0, 59)?,
+            horas: campo(horas, 0, 23)?,
+            dias: campo(dias, 1, 31)?,
+            meses: campo(meses, 1, 12)?,
+            dias_semana: semana,
+            dia_restrito: !dias.starts_with('*'),
+            dia_semana_restrito: !dias_semana.starts_with('*'),
+        })
+    }
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expressao)
+    }
+}
+
+impl Cron {
+    /// A primeira ocorrência depois de `depois` (exclusive); `None` se a expressão nunca ocorre.
+    pub fn proxima(&self, depois: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut instante = depois.duration_trunc(Duration::minutes(1)).ok()? + Duration::minutes(1);
+        let limite = instante + Duration::days(HORIZONTE_DIAS);
+
+        while instante <= limite {
+            if !contem(self.meses, instante.month()) {
+                let (ano, mes) = match instante.month() {
+                    12 => (instante.year() + 1, 1),
+                    mes => (instante.year(), mes + 1),
+                };
+                instante = meia_noite(NaiveDate::from_ymd_opt(ano, mes, 1)?);
+            } else if !self.dia_coincide(instante) {
+                instante = meia_noite(instante.date_naive().succ_opt()?);
+            } else if !contem(self.horas, instante.hour()) {
+                instante = instante.duration_trunc(Duration::hours(1)).ok()? + Duration::hours(1);
+            } else if !contem(self.minutos, instante.minute()) {
+                instante += Duration::minutes(1);
+            } else {
+                return Some(instante);
+            }
+        }
+        None
+    }
+
+    fn dia_coincide(&self, instante: DateTime<Utc>) -> bool {
+        let dia = contem(self.dias, instante.day());
+        let dia_semana = contem(self.dias_semana, instante.weekday().num_days_from_sunday());
+
+        if self.dia_restrito && self.dia_semana_restrito {
+            dia || dia_semana
+        } else {
+            dia && dia_semana
+        }
+    }
+}
+
+fn contem(conjunto: u64, valor: u32) -> bool {
+    conjunto & (1 << valor) != 0
+}
+
+fn meia_noite(data: NaiveDate) -> DateTime<Utc> {
+    data.and_hms_opt(0, 0, 0).expect("meia-noite existe").and_utc()
+}
+
+/// Os valores do campo, como bits de um `u64`.
+fn campo(texto: &str, minimo: u32, maximo: u32) -> Result<u64, ErroCron> {
+    let numero = |valor: &str| -> Result<u32, ErroCron> {
+        match valor.parse::<u32>() {
+            Ok(numero) if (minimo..=maximo).contains(&numero) => Ok(numero),
+            _ => Err(ErroCron(format!("`{valor}` fora de {minimo}-{maximo} em `{texto}`"))),
+        }
+    };
+
+    let mut conjunto = 0;
+    for parte in texto.split(',') {
+        let (intervalo, passo) = match parte.split_once('/') {
+            Some((intervalo, passo)) => match passo.parse::<u32>() {
+                Ok(passo) if passo > 0 => (intervalo, Some(passo)),
+                _ => return Err(ErroCron(format!("passo inválido em `{texto}`"))),
+            },
+            None => (parte, None),
+        };
+
+        let (inicio, fim) = match intervalo.split_once('-') {
+            _ if intervalo == "*" => (minimo, maximo),
+            Some((inicio, fim)) => (numero(inicio)?, numero(fim)?),
+            // `5/15`: de 5 até o fim do campo, de 15 em 15
+            None if passo.is_some() => (numero(intervalo)?, maximo),
+            None => {
+                let valor = numero(intervalo)?;
+                (valor, valor)
+            }
+        };
+        if inicio > fim {
+            return Err(ErroCron(format!("intervalo decrescente em `{texto}`")));
+        }
+
+        for valor in (inicio..=fim).step_by(passo.unwrap_or(1) as usize) {
+            conjunto |= 1 << valor;
+        }
+    }
+    Ok(conjunto)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(texto: &str) -> DateTime<Utc> {
+        texto.parse().unwrap()
+    }
+
+    fn proxima(expressao: &str, depois: &str) -> Option<DateTime<Utc>> {
+        expressao.parse::<Cron>().unwrap().proxima(data(depois))
+    }
+
+    #[test]
+    fn proxima_ocorrencia_e_sempre_depois_do_instante() {
+        assert_eq!(proxima("*/15 * * * *", "2026-10-18T10:07:30Z"), Some(data("2026-10-18T10:15:00Z")));
+        assert_eq!(proxima("*/15 * * * *", "2026-10-18T10:15:00Z"), Some(data("2026-10-18T10:30:00Z")));
+        assert_eq!(proxima("30 3 * * *", "2026-10-18T03:30:00Z"), Some(data("2026-10-19T03:30:00Z")));
+        assert_eq!(proxima("0 0 1 1 *", "2026-10-18T10:00:00Z"), Some(data("2027-01-01T00:00:00Z")));
+        assert_eq!(proxima("5/20 8-9 * * *", "2026-10-18T08:50:00Z"), Some(data("2026-10-18T09:05:00Z")));
+    }
+
+    #[test]
+    fn dia_da_semana_e_dia_do_mes() {
+        // 2026-10-16 é uma sexta-feira
+        assert_eq!(proxima("0 12 * * 1-5", "2026-10-16T13:00:00Z"), Some(data("2026-10-19T12:00:00Z")));
+        assert_eq!(proxima("0 0 * * 7", "2026-10-16T13:00:00Z"), Some(data("2026-10-18T00:00:00Z")));
+        // Restritos os dois, basta um coincidir: o dia 20 vem antes da próxima sexta
+        assert_eq!(proxima("0 0 20 * 5", "2026-10-17T00:00:00Z"), Some(data("2026-10-20T00:00:00Z")));
+        assert_eq!(proxima("0 0 30 2 *", "2026-10-17T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn expressoes_invalidas_sao_recusadas() {
+        for expressao in ["* * * *", "60 * * * *", "*/0 * * * *", "5-1 * * * *", "* * 0 * *", "* * * 13 *", "a * * * *"] {
+            assert!(expressao.parse::<Cron>().is_err(), "{expressao}");
+        }
+        assert_eq!("0  3 * * *".parse::<Cron>().unwrap().to_string(), "0 3 * * *");
+    }
+}
@@ -0,0 +1,476 @@
+//! Endpoint GraphQL (`POST /api/v1/graphql`), ao lado da API REST: usuários,
+//! posts e as relações entre eles numa só requisição, com paginação por cursor.
+//!
+//! As consultas são públicas, como `GET /api/v1/posts` e `GET /api/v1/users`;
+//! as mutações espelham as rotas de posts e exigem o mesmo JWT, no
+//! `Authorization`. Os campos de relação usam os [`carregadores`] em lote, e
+//! cada consulta passa pelos limites de profundidade e de complexidade antes
+//! de tocar no banco. `GET /api/v1/graphql` serve o GraphiQL.
+
+pub mod carregadores;
+
+use async_graphql::{
+    connection::{Connection, Edge},
+    dataloader::DataLoader,
+    parser::{parse_query, types::Selection},
+    Context, EmptySubscription, Error, ErrorExtensions, InputObject, Object, OutputType, Result, Schema, Variables,
+};
+use axum::{
+    extract::{Extension, State},
+    http::{HeaderMap, StatusCode},
+    response::{Html, Json},
+};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::sync::OnceLock;
+use tracing::instrument;
+
+use crate::{
+    auditoria::Origem,
+    cache::Cache,
+    erros::{erro_interno, ErrosValidacao},
+    estado::Estado,
+    handlers::EscritaPostagens,
+    middleware,
+    models::{CriarPostagemRequisicao, Postagem, Usuario},
+    repositorios::Repositorios,
+    transmissao::Transmissao,
+    validacao::Validar,
+};
+use carregadores::{ContagemPostagens, PaginaDoUsuario, PostagensDosUsuarios, UsuariosPorId};
+
+pub type Esquema = Schema<Consulta, Mutacao, EmptySubscription>;
+
+const TAMANHO_PAGINA_PADRAO: i32 = 20;
+const TAMANHO_PAGINA_MAXIMO: i32 = 100;
+
+// Comporta posts → autor → posts do autor; mais que isso é melhor pedir à parte
+const PROFUNDIDADE_MAXIMA: usize = 10;
+// Cada campo custa 1 e as listas multiplicam o custo dos filhos pelo tamanho
+// da página: 20 usuários com os 20 últimos posts de cada ainda passam
+const COMPLEXIDADE_MAXIMA: usize = 2000;
+// A consulta de introspecção do GraphiQL aninha `ofType` até uns 15 níveis
+const PROFUNDIDADE_MAXIMA_INTROSPECCAO: usize = 20;
+
+static ESQUEMA: OnceLock<Esquema> = OnceLock::new();
+static ESQUEMA_INTROSPECCAO: OnceLock<Esquema> = OnceLock::new();
+
+/// O esquema usado nas consultas, com os limites de profundidade e complexidade.
+pub fn esquema() -> &'static Esquema {
+    ESQUEMA.get_or_init(|| {
+        Schema::build(Consulta, Mutacao, EmptySubscription)
+            .limit_depth(PROFUNDIDADE_MAXIMA)
+            .limit_complexity(COMPLEXIDADE_MAXIMA)
+            .finish()
+    })
+}
+
+/// O mesmo esquema, mas só para introspecção: aceita a profundidade das
+/// consultas de ferramentas como o GraphiQL e recusa qualquer campo de dados.
+fn esquema_introspeccao() -> &'static Esquema {
+    ESQUEMA_INTROSPECCAO.get_or_init(|| {
+        Schema::build(Consulta, Mutacao, EmptySubscription)
+            .introspection_only()
+            .limit_depth(PROFUNDIDADE_MAXIMA_INTROSPECCAO)
+            .finish()
+    })
+}
+
+/// Corpo do `POST /api/v1/graphql`, no formato do GraphQL sobre HTTP.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RequisicaoGraphql {
+    pub query: String,
+    #[serde(default, rename = "operationName")]
+    pub nome_operacao: Option<String>,
+    #[serde(default, rename = "variables")]
+    pub variaveis: Option<serde_json::Value>,
+}
+
+#[instrument(skip_all)]
+pub async fn executar(
+    State(estado): State<Estado>,
+    Extension(cache): Extension<Cache>,
+    origem: Origem,
+    cabecalhos: HeaderMap,
+    Json(requisicao): Json<RequisicaoGraphql>,
+) -> Result<Json<async_graphql::Response>, StatusCode> {
+    // Sem token a requisição é anônima e só as mutações são recusadas;
+    // um token inválido é `401`, como nas rotas REST
+    let credenciais = middleware::autenticacao_opcional(&estado, &cabecalhos).await?;
+
+    let esquema = if somente_introspeccao(&requisicao.query) {
+        esquema_introspeccao()
+    } else {
+        esquema()
+    };
+
+    let mut pedido = async_graphql::Request::new(requisicao.query);
+    if let Some(nome_operacao) = requisicao.nome_operacao {
+        pedido = pedido.operation_name(nome_operacao);
+    }
+    if let Some(variaveis) = requisicao.variaveis {
+        pedido = pedido.variables(Variables::from_json(variaveis));
+    }
+
+    let repositorios = estado.repositorios;
+    let pedido = pedido
+        .data(DataLoader::new(UsuariosPorId(repositorios.clone()), tokio::spawn))
+        .data(DataLoader::new(ContagemPostagens(repositorios.clone()), tokio::spawn))
+        .data(DataLoader::new(PostagensDosUsuarios(repositorios.clone()), tokio::spawn))
+        .data(Contexto {
+            repositorios,
+            cache,
+            transmissao: estado.transmissao,
+            origem,
+            id_usuario: credenciais.map(|credenciais| credenciais.id_usuario),
+        });
+
+    Ok(Json(esquema.execute(pedido).await))
+}
+
+pub async fn pagina_graphiql() -> Html<&'static str> {
+    Html(include_str!("../static/graphiql.html"))
+}
+
+/// Consultas que só pedem campos de introspecção (`__schema`, `__type`) na raiz.
+/// Na dúvida (fragmentos na raiz, consulta ilegível), não é introspecção.
+fn somente_introspeccao(consulta: &str) -> bool {
+    let Ok(documento) = parse_query(consulta) else {
+        return false;
+    };
+
+    documento.operations.iter().all(|(_, operacao)| {
+        operacao.node.selection_set.node.items.iter().all(|selecao| match &selecao.node {
+            Selection::Field(campo) => campo.node.name.node.starts_with("__"),
+            _ => false,
+        })
+    })
+}
+
+/// O que os resolvers recebem de cada requisição, além dos carregadores.
+struct Contexto {
+    repositorios: Repositorios,
+    cache: Cache,
+    transmissao: Transmissao,
+    origem: Origem,
+    id_usuario: Option<i32>,
+}
+
+impl Contexto {
+    fn escrita(&self) -> Result<EscritaPostagens<'_>> {
+        let id_usuario = self
+            .id_usuario
+            .ok_or_else(|| erro("Autenticação necessária: envie o token em Authorization", "UNAUTHENTICATED"))?;
+
+        Ok(EscritaPostagens::new(&self.repositorios, &self.cache, &self.transmissao, &self.origem, id_usuario))
+    }
+}
+
+/// Erro com o código em `extensions.code`, para o cliente não depender da mensagem.
+fn erro(mensagem: impl Into<String>, codigo: &'static str) -> Error {
+    Error::new(mensagem).extend_with(|_, extensoes| extensoes.set("code", codigo))
+}
+
+/// Os códigos HTTP devolvidos pela [`EscritaPostagens`], como erros do GraphQL.
+fn erro_status(status: StatusCode) -> Error {
+    match status {
+        StatusCode::NOT_FOUND => erro("Post não encontrado ou de outro usuário", "NOT_FOUND"),
+        _ => erro("Erro interno", "INTERNAL_SERVER_ERROR"),
+    }
+}
+
+/// Registra a causa, como o `erro_interno` das rotas, e devolve só um erro genérico.
+fn falha<E: std::fmt::Display>(causa: E) -> Error {
+    erro_status(erro_interno(causa))
+}
+
+/// `first` e `after` de uma conexão: o tamanho da página e o id do cursor.
+fn pagina(first: Option<i32>, after: Option<String>) -> Result<(i64, Option<i32>)> {
+    let tamanho = first.unwrap_or(TAMANHO_PAGINA_PADRAO);
+    if !(0..=TAMANHO_PAGINA_MAXIMO).contains(&tamanho) {
+        return Err(erro(format!("`first` deve estar entre 0 e {TAMANHO_PAGINA_MAXIMO}"), "BAD_USER_INPUT"));
+    }
+
+    let cursor = after
+        .map(|after| after.parse::<i32>().map_err(|_| erro("Cursor `after` inválido", "BAD_USER_INPUT")))
+        .transpose()?;
+    Ok((i64::from(tamanho), cursor))
+}
+
+/// Custo de uma conexão no limite de complexidade: o dos filhos, uma vez por item da página.
+fn custo_pagina(first: Option<i32>, custo_filhos: usize) -> usize {
+    first.unwrap_or(TAMANHO_PAGINA_PADRAO).clamp(0, TAMANHO_PAGINA_MAXIMO) as usize * custo_filhos
+}
+
+/// Monta a conexão com os itens buscados, até `tamanho + 1`: o excedente só
+/// indica que existe uma próxima página.
+fn conexao<T: OutputType>(mut itens: Vec<T>, tamanho: i64, cursor: Option<i32>, id: impl Fn(&T) -> i32) -> Connection<i32, T> {
+    let tem_proxima = itens.len() as i64 > tamanho;
+    itens.truncate(tamanho as usize);
+
+    let mut conexao = Connection::new(cursor.is_some(), tem_proxima);
+    conexao.edges.extend(itens.into_iter().map(|item| Edge::new(id(&item), item)));
+    conexao
+}
+
+pub struct Consulta;
+
+#[Object]
+impl Consulta {
+    /// O usuário autenticado; `null` sem token.
+    #[graphql(name = "me")]
+    async fn eu(&self, ctx: &Context<'_>) -> Result<Option<UsuarioGraphql>> {
+        match ctx.data_unchecked::<Contexto>().id_usuario {
+            Some(id_usuario) => self.usuario(ctx, id_usuario).await,
+            None => Ok(None),
+        }
+    }
+
+    #[graphql(name = "user")]
+    async fn usuario(&self, ctx: &Context<'_>, id: i32) -> Result<Option<UsuarioGraphql>> {
+        let usuario = ctx.data_unchecked::<DataLoader<UsuariosPorId>>().load_one(id).await.map_err(falha)?;
+        Ok(usuario.map(UsuarioGraphql))
+    }
+
+    /// Usuários em ordem de cadastro.
+    #[graphql(name = "users", complexity = "custo_pagina(first, child_complexity)")]
+    async fn usuarios(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<Connection<i32, UsuarioGraphql>> {
+        let (tamanho, cursor) = pagina(first, after)?;
+        let usuarios = ctx
+            .data_unchecked::<Contexto>()
+            .repositorios
+            .usuarios
+            .listar_pagina(cursor.unwrap_or(0), tamanho + 1)
+            .await
+            .map_err(falha)?;
+
+        Ok(conexao(usuarios.into_iter().map(UsuarioGraphql).collect(), tamanho, cursor, |usuario| usuario.0.id))
+    }
+
+    #[graphql(name = "post")]
+    async fn postagem(&self, ctx: &Context<'_>, id: i32) -> Result<Option<PostagemGraphql>> {
+        let postagem = ctx.data_unchecked::<Contexto>().repositorios.postagens.buscar(id).await.map_err(falha)?;
+        Ok(postagem.map(PostagemGraphql))
+    }
+
+    /// Posts de todos os usuários, dos mais novos para os mais antigos.
+    #[graphql(name = "posts", complexity = "custo_pagina(first, child_complexity)")]
+    async fn postagens(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<Connection<i32, PostagemGraphql>> {
+        let (tamanho, cursor) = pagina(first, after)?;
+        let postagens = ctx
+            .data_unchecked::<Contexto>()
+            .repositorios
+            .postagens
+            .listar_anteriores(cursor, tamanho + 1)
+            .await
+            .map_err(falha)?;
+
+        Ok(conexao(postagens.into_iter().map(PostagemGraphql).collect(), tamanho, cursor, |postagem| postagem.0.id))
+    }
+}
+
+/// Título e conteúdo de um post, com as mesmas regras do REST.
+#[derive(InputObject)]
+#[graphql(name = "PostInput")]
+struct EntradaPostagem {
+    #[graphql(name = "title")]
+    titulo: String,
+    #[graphql(name = "content")]
+    conteudo: String,
+}
+
+impl EntradaPostagem {
+    /// Normaliza e valida como o `JsonValidado`; as mensagens por campo vão em `extensions.fields`.
+    fn validar(self) -> Result<CriarPostagemRequisicao> {
+        let mut requisicao = CriarPostagemRequisicao { titulo: self.titulo, conteudo: self.conteudo };
+        requisicao.normalizar();
+
+        let mut erros = ErrosValidacao::default();
+        requisicao.validar(&mut erros);
+        if erros.is_empty() {
+            return Ok(requisicao);
+        }
+
+        let campos = async_graphql::to_value(erros.campos()).unwrap_or_default();
+        Err(Error::new("Dados inválidos").extend_with(|_, extensoes| {
+            extensoes.set("code", "BAD_USER_INPUT");
+            extensoes.set("fields", campos);
+        }))
+    }
+}
+
+pub struct Mutacao;
+
+#[Object]
+impl Mutacao {
+    /// Cria um post do usuário autenticado, como `POST /api/v1/posts`.
+    #[graphql(name = "createPost")]
+    async fn criar_postagem(&self, ctx: &Context<'_>, input: EntradaPostagem) -> Result<PostagemGraphql> {
+        let escrita = ctx.data_unchecked::<Contexto>().escrita()?;
+        let requisicao = input.validar()?;
+
+        escrita.criar(&requisicao).await.map(PostagemGraphql).map_err(erro_status)
+    }
+
+    /// Edita um post do usuário autenticado, como `PUT /api/v1/posts/{id}`.
+    #[graphql(name = "updatePost")]
+    async fn atualizar_postagem(&self, ctx: &Context<'_>, id: i32, input: EntradaPostagem) -> Result<PostagemGraphql> {
+        let escrita = ctx.data_unchecked::<Contexto>().escrita()?;
+        let requisicao = input.validar()?;
+
+        let atual = escrita
+            .repositorios
+            .postagens
+            .buscar_do_usuario(id, escrita.id_usuario)
+            .await
+            .map_err(falha)?
+            .ok_or_else(|| erro_status(StatusCode::NOT_FOUND))?;
+
+        escrita.atualizar(atual, &requisicao, None).await.map(PostagemGraphql).map_err(erro_status)
+    }
+
+    /// Apaga um post do usuário autenticado, como `DELETE /api/v1/posts/{id}`; devolve o id.
+    #[graphql(name = "deletePost")]
+    async fn deletar_postagem(&self, ctx: &Context<'_>, id: i32) -> Result<i32> {
+        let escrita = ctx.data_unchecked::<Contexto>().escrita()?;
+
+        let postagem = escrita
+            .repositorios
+            .postagens
+            .buscar_do_usuario(id, escrita.id_usuario)
+            .await
+            .map_err(falha)?
+            .ok_or_else(|| erro_status(StatusCode::NOT_FOUND))?;
+
+        escrita.deletar(postagem).await.map_err(erro_status)?;
+        Ok(id)
+    }
+}
+
+pub struct UsuarioGraphql(Usuario);
+
+#[Object(name = "User")]
+impl UsuarioGraphql {
+    async fn id(&self) -> i32 {
+        self.0.id
+    }
+
+    #[graphql(name = "username")]
+    async fn nome_usuario(&self) -> &str {
+        &self.0.nome_usuario
+    }
+
+    async fn email(&self) -> &str {
+        &self.0.email
+    }
+
+    #[graphql(name = "createdAt")]
+    async fn criado_em(&self) -> DateTime<Utc> {
+        self.0.criado_em
+    }
+
+    #[graphql(name = "postCount")]
+    async fn quantidade_postagens(&self, ctx: &Context<'_>) -> Result<i64> {
+        let quantidade = ctx.data_unchecked::<DataLoader<ContagemPostagens>>().load_one(self.0.id).await.map_err(falha)?;
+        Ok(quantidade.unwrap_or(0))
+    }
+
+    /// Posts do usuário, dos mais novos para os mais antigos.
+    #[graphql(name = "posts", complexity = "custo_pagina(first, child_complexity)")]
+    async fn postagens(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<Connection<i32, PostagemGraphql>> {
+        let (tamanho, cursor) = pagina(first, after)?;
+        let pagina = PaginaDoUsuario { id_usuario: self.0.id, antes_do_id: cursor, limite: tamanho + 1 };
+        let postagens = ctx
+            .data_unchecked::<DataLoader<PostagensDosUsuarios>>()
+            .load_one(pagina)
+            .await
+            .map_err(falha)?
+            .unwrap_or_default();
+
+        Ok(conexao(postagens.into_iter().map(PostagemGraphql).collect(), tamanho, cursor, |postagem| postagem.0.id))
+    }
+}
+
+pub struct PostagemGraphql(Postagem);
+
+#[Object(name = "Post")]
+impl PostagemGraphql {
+    async fn id(&self) -> i32 {
+        self.0.id
+    }
+
+    #[graphql(name = "title")]
+    async fn titulo(&self) -> &str {
+        &self.0.titulo
+    }
+
+    #[graphql(name = "content")]
+    async fn conteudo(&self) -> &str {
+        &self.0.conteudo
+    }
+
+    #[graphql(name = "authorId")]
+    async fn id_usuario(&self) -> i32 {
+        self.0.id_usuario
+    }
+
+    #[graphql(name = "createdAt")]
+    async fn criado_em(&self) -> DateTime<Utc> {
+        self.0.criado_em
+    }
+
+    #[graphql(name = "updatedAt")]
+    async fn atualizado_em(&self) -> DateTime<Utc> {
+        self.0.atualizado_em
+    }
+
+    #[graphql(name = "author")]
+    async fn autor(&self, ctx: &Context<'_>) -> Result<UsuarioGraphql> {
+        let autor = ctx
+            .data_unchecked::<DataLoader<UsuariosPorId>>()
+            .load_one(self.0.id_usuario)
+            .await
+            .map_err(falha)?
+            .ok_or_else(|| falha(format!("autor {} do post {} não encontrado", self.0.id_usuario, self.0.id)))?;
+
+        Ok(UsuarioGraphql(autor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn introspeccao_e_reconhecida_so_na_raiz() {
+        assert!(somente_introspeccao("{ __schema { types { name } } }"));
+        assert!(somente_introspeccao("query IntrospectionQuery { __schema { queryType { name } } __type(name: \"Post\") { name } }"));
+        assert!(!somente_introspeccao("{ __schema { types { name } } posts { edges { node { id } } } }"));
+        assert!(!somente_introspeccao("{ ...Raiz } fragment Raiz on Query { __schema { types { name } } }"));
+        assert!(!somente_introspeccao("{ __schema"));
+    }
+
+    #[test]
+    fn esquema_expoe_os_tipos_com_nomes_em_ingles() {
+        let sdl = esquema().sdl();
+        for trecho in ["type Post {", "type User {", "postCount: Int!", "author: User!", "type PostConnection {", "input PostInput {", "createPost(input: PostInput!): Post!"] {
+            assert!(sdl.contains(trecho), "sem `{trecho}` no esquema:\n{sdl}");
+        }
+    }
+}
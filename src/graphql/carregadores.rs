@@ -0,0 +1,76 @@
+//! Carregadores em lote (DataLoader) do GraphQL. Os campos resolvidos para
+//! cada item de uma lista (o autor de cada post, a contagem e os posts de cada
+//! usuário) são juntados e buscados numa consulta só por campo, em vez de uma
+//! por item. Cada requisição tem os seus, então o que foi carregado não vaza
+//! de uma requisição para outra.
+
+use async_graphql::dataloader::Loader;
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    models::{Postagem, Usuario},
+    repositorios::Repositorios,
+};
+
+/// O DataLoader entrega o mesmo erro a todos os campos do lote, e `sqlx::Error` não é `Clone`.
+pub type ErroCarregamento = Arc<sqlx::Error>;
+
+pub struct UsuariosPorId(pub Repositorios);
+
+impl Loader<i32> for UsuariosPorId {
+    type Value = Usuario;
+    type Error = ErroCarregamento;
+
+    async fn load(&self, ids: &[i32]) -> Result<HashMap<i32, Usuario>, Self::Error> {
+        let usuarios = self.0.usuarios.buscar_varios(ids).await?;
+        Ok(usuarios.into_iter().map(|usuario| (usuario.id, usuario)).collect())
+    }
+}
+
+/// Quantidade de posts por id de usuário; quem não tem posts fica de fora.
+pub struct ContagemPostagens(pub Repositorios);
+
+impl Loader<i32> for ContagemPostagens {
+    type Value = i64;
+    type Error = ErroCarregamento;
+
+    async fn load(&self, ids_usuarios: &[i32]) -> Result<HashMap<i32, i64>, Self::Error> {
+        let contagens = self.0.postagens.contar_dos_usuarios(ids_usuarios).await?;
+        Ok(contagens.into_iter().collect())
+    }
+}
+
+/// Uma página dos posts de um usuário: até `limite` posts com id menor que `antes_do_id`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PaginaDoUsuario {
+    pub id_usuario: i32,
+    pub antes_do_id: Option<i32>,
+    pub limite: i64,
+}
+
+pub struct PostagensDosUsuarios(pub Repositorios);
+
+impl Loader<PaginaDoUsuario> for PostagensDosUsuarios {
+    type Value = Vec<Postagem>;
+    type Error = ErroCarregamento;
+
+    async fn load(&self, paginas: &[PaginaDoUsuario]) -> Result<HashMap<PaginaDoUsuario, Vec<Postagem>>, Self::Error> {
+        // Uma consulta para cada combinação de cursor e tamanho; numa lista de
+        // usuários, todos costumam pedir a mesma
+        let mut grupos: HashMap<(Option<i32>, i64), Vec<i32>> = HashMap::new();
+        for pagina in paginas {
+            grupos.entry((pagina.antes_do_id, pagina.limite)).or_default().push(pagina.id_usuario);
+        }
+
+        let mut resultado: HashMap<PaginaDoUsuario, Vec<Postagem>> =
+            paginas.iter().map(|pagina| (pagina.clone(), Vec::new())).collect();
+        for ((antes_do_id, limite), ids_usuarios) in grupos {
+            let postagens = self.0.postagens.listar_anteriores_dos_usuarios(&ids_usuarios, antes_do_id, limite).await?;
+            for postagem in postagens {
+                let pagina = PaginaDoUsuario { id_usuario: postagem.id_usuario, antes_do_id, limite };
+                resultado.entry(pagina).or_default().push(postagem);
+            }
+        }
+        Ok(resultado)
+    }
+}
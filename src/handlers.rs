@@ -1,85 +1,203 @@
 use axum::{
-    extract::{Path, State, Extension},
-    http::StatusCode,
-    response::{Json, Html},
+    body::Body,
+    extract::{ws::WebSocketUpgrade, Path, Query, State, Extension},
+    http::{header::{CONTENT_DISPOSITION, CONTENT_TYPE, IF_MATCH}, HeaderMap, StatusCode},
+    response::{sse::{KeepAlive, Sse}, IntoResponse, Json, Html, Response},
 };
+use futures::{Stream, StreamExt};
+use chrono::{DateTime, SubsecRound, Utc};
+use serde_json::{json, Value};
+use std::{convert::Infallible, sync::Arc, time::Duration};
+use tokio_util::io::ReaderStream;
+use tracing::{instrument, Instrument};
+use uuid::Uuid;
 use crate::{
-    auth::{criar_token, hash_senha, verificar_senha},
-    db::DbPool,
+    auditoria::{self, acoes, alvos, motivos, FiltroEventos, NovoEvento, Origem},
+    cache::{Cache, Chave},
+    auth::{criar_token, hash_senha, verificar_senha, DURACAO_TOKEN_SEGUNDOS},
+    condicional::{verificar_if_match, Representacao},
+    configuracao::Configuracao,
+    db::Banco,
+    erros::{erro_interno, ErroApi, ErrosValidacao},
+    fila::FiltroTarefas,
+    metricas,
+    middleware::PROTOCOLO_WEBSOCKET_BEARER,
     models::*,
+    notificacoes::{self, FiltroNotificacoes, Notificacoes},
+    privacidade::{self, DadosPessoais},
+    repositorios::{violacao_unicidade, Efeitos, Repositorios},
+    transferencia::{self, Cabecalho, ErroTransferencia, ResumoImportacao},
+    transmissao::{TipoEvento, Transmissao},
+    validacao::JsonValidado,
+    webhooks::{self, eventos, FiltroEntregas},
 };
 
+// Quantidade de postagens na listagem pública
+const LIMITE_POSTAGENS_PUBLICAS: i64 = 10;
+
+const TAMANHO_BUFFER_EXPORTACAO: usize = 64 * 1024;
+
+// Comentário enviado no feed de posts quando não há eventos, para proxies
+// não fecharem a conexão ociosa
+const INTERVALO_HEARTBEAT: Duration = Duration::from_secs(15);
+
+// Eventos por página no /admin/audit
+const LIMITE_PADRAO_AUDITORIA: i64 = 100;
+const LIMITE_MAXIMO_AUDITORIA: i64 = 1000;
+
+// Notificações por página no /notifications
+const LIMITE_PADRAO_NOTIFICACOES: i64 = 50;
+const LIMITE_MAXIMO_NOTIFICACOES: i64 = 200;
+
+// Entregas por página no /webhooks/{id}/deliveries
+const LIMITE_PADRAO_ENTREGAS: i64 = 50;
+const LIMITE_MAXIMO_ENTREGAS: i64 = 200;
+
+// Tarefas por página no /admin/jobs
+const LIMITE_PADRAO_TAREFAS: i64 = 50;
+const LIMITE_MAXIMO_TAREFAS: i64 = 500;
+
 // ========== Rotas Públicas ==========
 
 pub async fn pagina_home() -> Html<&'static str> {
     Html(include_str!("../static/home.html"))
 }
 
-pub async fn verificar_saude() -> Json<serde_json::Value> {
-    Json(serde_json::json!({
-        "status": "ok",
-        "message": "API está funcionando"
-    }))
-}
-
+#[instrument(skip_all)]
 pub async fn registrar(
-    State(pool): State<DbPool>,
-    Json(requisicao): Json<CriarUsuarioRequisicao>,
+    State(repositorios): State<Repositorios>,
+    origem: Origem,
+    JsonValidado(requisicao): JsonValidado<CriarUsuarioRequisicao>,
 ) -> Result<Json<RespostaUsuario>, StatusCode> {
-    // Verificar se email já existe
-    let existente = sqlx::query_as::<_, Usuario>(
-        "SELECT * FROM users WHERE email = $1 OR username = $1"
-    )
-    .bind(&requisicao.email)
-    .fetch_optional(&pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // Verificar se email ou nome de usuário já existem
+    let existente = repositorios
+        .usuarios
+        .buscar_conflito(&requisicao.email, &requisicao.nome_usuario)
+        .await
+        .map_err(erro_interno)?;
 
     if existente.is_some() {
         return Err(StatusCode::CONFLICT);
     }
 
     let hash_senha = hash_senha(&requisicao.senha)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let usuario = sqlx::query_as::<_, Usuario>(
-        r#"
-        INSERT INTO users (username, email, password_hash)
-        VALUES ($1, $2, $3)
-        RETURNING *
-        "#,
-    )
-    .bind(&requisicao.nome_usuario)
-    .bind(&requisicao.email)
-    .bind(&hash_senha)
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .map_err(erro_interno)?;
+
+    // Nome e email ficam só em `users`; o alvo basta para chegar neles
+    let efeitos = |usuario: &Usuario| Efeitos {
+        auditoria: NovoEvento::new(acoes::CONTA_REGISTRADA)
+            .ator(usuario.id)
+            .alvo(alvos::USUARIO, usuario.id)
+            .origem(&origem),
+        webhook: webhooks::evento(eventos::USUARIO_REGISTRADO, json!(RespostaUsuario::from(usuario.clone()))),
+    };
+    let usuario = match repositorios
+        .usuarios
+        .criar_com_efeitos(&requisicao.nome_usuario, &requisicao.email, &hash_senha, &efeitos)
+        .await
+    {
+        Ok(usuario) => usuario,
+        // Outro cadastro com o mesmo email ou nome passou pela verificação ao mesmo tempo
+        Err(erro) if violacao_unicidade(&erro) => return Err(StatusCode::CONFLICT),
+        Err(erro) => return Err(erro_interno(erro)),
+    };
 
     Ok(Json(RespostaUsuario::from(usuario)))
 }
 
+#[instrument(skip_all, fields(id_usuario = tracing::field::Empty))]
 pub async fn login(
-    State(pool): State<DbPool>,
-    Json(requisicao): Json<LoginRequisicao>,
+    State(repositorios): State<Repositorios>,
+    State(configuracao): State<Arc<Configuracao>>,
+    State(notificacoes): State<Notificacoes>,
+    origem: Origem,
+    JsonValidado(requisicao): JsonValidado<LoginRequisicao>,
 ) -> Result<Json<RespostaLogin>, StatusCode> {
-    let usuario = sqlx::query_as::<_, Usuario>(
-        "SELECT * FROM users WHERE email = $1"
-    )
-    .bind(&requisicao.email)
-    .fetch_optional(&pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-    .ok_or(StatusCode::UNAUTHORIZED)?;
+    // A tentativa fica registrada com o hash do email informado, mesmo sem conta
+    let email_hash = auditoria::hash_email(&configuracao.segredo_jwt, &requisicao.email);
+    let falha = |motivo: &str| {
+        NovoEvento::new(acoes::LOGIN_FALHA)
+            .origem(&origem)
+            .diff(json!({ "email_hash": email_hash, "reason": motivo }))
+    };
+
+    let Some(usuario) = repositorios
+        .usuarios
+        .buscar_por_email(&requisicao.email)
+        .await
+        .map_err(erro_interno)?
+    else {
+        metricas::registrar_login(false);
+        let evento = falha(motivos::EMAIL_DESCONHECIDO);
+        repositorios.auditoria.registrar(&evento).await.map_err(erro_interno)?;
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    tracing::Span::current().record("id_usuario", usuario.id);
+
+    // Conta anonimizada não tem senha válida ('!' nem é um hash bcrypt): responde
+    // como um email desconhecido, sem chegar à verificação
+    if usuario.anonimizado_em.is_some() {
+        metricas::registrar_login(false);
+        let evento = falha(motivos::EMAIL_DESCONHECIDO);
+        repositorios.auditoria.registrar(&evento).await.map_err(erro_interno)?;
+        return Err(StatusCode::UNAUTHORIZED);
+    }
 
     if !verificar_senha(&requisicao.senha, &usuario.hash_senha)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(erro_interno)?
     {
+        metricas::registrar_login(false);
+        let evento = falha(motivos::SENHA_INCORRETA).alvo(alvos::USUARIO, usuario.id);
+        repositorios.auditoria.registrar(&evento).await.map_err(erro_interno)?;
         return Err(StatusCode::UNAUTHORIZED);
     }
 
-    let token = criar_token(usuario.id, usuario.email.clone())
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // Só depois da senha: quem não a conhece não descobre que a conta existe
+    if usuario.desativado_em.is_some() {
+        metricas::registrar_login(false);
+        let evento = falha(motivos::CONTA_DESATIVADA).alvo(alvos::USUARIO, usuario.id);
+        repositorios.auditoria.registrar(&evento).await.map_err(erro_interno)?;
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    // Um acesso de IP e user agent nunca usados pela conta vira notificação,
+    // exceto o primeiro login, quando não há com o que comparar
+    let sessoes_anteriores = repositorios
+        .sessoes
+        .listar_do_usuario(usuario.id)
+        .await
+        .map_err(erro_interno)?;
+    let dispositivo_novo = !sessoes_anteriores.is_empty()
+        && !sessoes_anteriores
+            .iter()
+            .any(|sessao| sessao.ip.as_ref() == Some(&origem.ip) && sessao.user_agent == origem.user_agent);
+
+    // Cada login abre uma sessão própria, que pode ser revogada individualmente
+    let id_sessao = repositorios
+        .sessoes
+        .criar(usuario.id, origem.user_agent.clone(), origem.ip.clone())
+        .await
+        .map_err(erro_interno)?;
+
+    let token = criar_token(&configuracao.segredo_jwt, usuario.id, usuario.email.clone(), id_sessao)
+        .map_err(erro_interno)?;
+
+    metricas::registrar_login(true);
+    let evento = NovoEvento::new(acoes::LOGIN_SUCESSO)
+        .ator(usuario.id)
+        .alvo(alvos::USUARIO, usuario.id)
+        .origem(&origem)
+        .diff(json!({ "session_id": id_sessao }));
+    repositorios.auditoria.registrar(&evento).await.map_err(erro_interno)?;
+
+    if dispositivo_novo {
+        let dados = json!({ "session_id": id_sessao, "ip": origem.ip, "user_agent": origem.user_agent });
+        notificacoes
+            .notificar(usuario.id, notificacoes::tipos::NOVO_LOGIN, dados)
+            .await
+            .map_err(erro_interno)?;
+    }
 
     Ok(Json(RespostaLogin {
         token,
@@ -87,150 +205,861 @@ pub async fn login(
     }))
 }
 
+#[instrument(skip_all)]
 pub async fn obter_postagens_publicas(
-    State(pool): State<DbPool>,
-) -> Result<Json<Vec<RespostaPostagem>>, StatusCode> {
-    let postagens = sqlx::query_as::<_, Postagem>(
-        "SELECT * FROM posts ORDER BY created_at DESC LIMIT 10"
-    )
-    .fetch_all(&pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    State(repositorios): State<Repositorios>,
+    Extension(cache): Extension<Cache>,
+    cabecalhos: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let resposta_postagens: Vec<RespostaPostagem> = cache
+        .obter_ou_carregar(Chave::PostagensPublicas, || async {
+            let postagens = repositorios
+                .postagens
+                .listar_recentes(LIMITE_POSTAGENS_PUBLICAS)
+                .await
+                .map_err(erro_interno)?;
 
-    let resposta_postagens: Vec<RespostaPostagem> = postagens.into_iter().map(RespostaPostagem::from).collect();
-    Ok(Json(resposta_postagens))
+            Ok(postagens.into_iter().map(RespostaPostagem::from).collect())
+        })
+        .await?;
+
+    // Sem Last-Modified: apagar um post muda a lista sem mudar o maior updated_at
+    Ok(Representacao::nova(&resposta_postagens)?.responder(&cabecalhos))
+}
+
+#[instrument(skip_all)]
+pub async fn transmitir_postagens(
+    State(transmissao): State<Transmissao>,
+    cabecalhos: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<axum::response::sse::Event, Infallible>>>, StatusCode> {
+    // Enviado pelo EventSource ao reconectar
+    let ultimo_id = cabecalhos
+        .get("last-event-id")
+        .and_then(|valor| valor.to_str().ok())
+        .and_then(|valor| valor.trim().parse().ok());
+
+    let mensagens = transmissao.assinar(ultimo_id).await.map_err(|erro| {
+        tracing::warn!(erro = %erro, "falha ao assinar o feed de posts");
+        StatusCode::SERVICE_UNAVAILABLE
+    })?;
+
+    Ok(Sse::new(mensagens.map(|mensagem| Ok(mensagem.evento_sse())))
+        .keep_alive(KeepAlive::new().interval(INTERVALO_HEARTBEAT).text("heartbeat")))
 }
 
 // ========== Rotas Protegidas ==========
 
+#[instrument(skip_all, fields(id_usuario = id_usuario))]
 pub async fn obter_perfil(
     Extension(id_usuario): Extension<i32>,
-    State(pool): State<DbPool>,
-) -> Result<Json<RespostaUsuario>, StatusCode> {
-    let usuario = sqlx::query_as::<_, Usuario>(
-        "SELECT * FROM users WHERE id = $1"
+    State(repositorios): State<Repositorios>,
+) -> Result<Json<RespostaPerfil>, StatusCode> {
+    let usuario = repositorios
+        .usuarios
+        .buscar_por_id(id_usuario)
+        .await
+        .map_err(erro_interno)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(RespostaPerfil::from(usuario)))
+}
+
+#[instrument(skip_all, fields(id_usuario = id_usuario))]
+pub async fn exportar_perfil(
+    Extension(id_usuario): Extension<i32>,
+    State(repositorios): State<Repositorios>,
+) -> Result<Response, StatusCode> {
+    let dados = DadosPessoais::coletar(&repositorios, id_usuario)
+        .await
+        .map_err(erro_interno)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let disposicao = format!("attachment; filename=\"{}\"", dados.nome_arquivo());
+    let arquivo = tokio::task::spawn_blocking(move || dados.compactar())
+        .await
+        .map_err(erro_interno)?
+        .map_err(erro_interno)?;
+
+    Ok((
+        [(CONTENT_TYPE, "application/zip".to_string()), (CONTENT_DISPOSITION, disposicao)],
+        arquivo,
     )
-    .bind(id_usuario)
-    .fetch_optional(&pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-    .ok_or(StatusCode::NOT_FOUND)?;
+        .into_response())
+}
 
-    Ok(Json(RespostaUsuario::from(usuario)))
+#[instrument(skip_all, fields(id_usuario = id_usuario))]
+pub async fn excluir_perfil(
+    Extension(id_usuario): Extension<i32>,
+    State(repositorios): State<Repositorios>,
+    origem: Origem,
+    JsonValidado(requisicao): JsonValidado<ExcluirContaRequisicao>,
+) -> Result<(StatusCode, Json<RespostaExclusaoConta>), StatusCode> {
+    let usuario = repositorios
+        .usuarios
+        .buscar_por_id(id_usuario)
+        .await
+        .map_err(erro_interno)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if !verificar_senha(&requisicao.senha, &usuario.hash_senha).map_err(erro_interno)? {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    // Repetir o pedido mantém a data já agendada
+    if let Some(exclusao_agendada_em) = usuario.exclusao_agendada_em {
+        return Ok((StatusCode::ACCEPTED, Json(RespostaExclusaoConta { exclusao_agendada_em })));
+    }
+
+    // Em microssegundos, como o PostgreSQL guarda: a data respondida é a mesma lida depois
+    let exclusao_agendada_em = (chrono::Utc::now() + privacidade::carencia()).trunc_subsecs(6);
+    repositorios
+        .usuarios
+        .agendar_exclusao(id_usuario, Some(exclusao_agendada_em))
+        .await
+        .map_err(erro_interno)?;
+
+    let evento = NovoEvento::new(acoes::EXCLUSAO_SOLICITADA)
+        .ator(id_usuario)
+        .alvo(alvos::USUARIO, id_usuario)
+        .origem(&origem)
+        .diff(json!({ "deletion_scheduled_at": [null, exclusao_agendada_em] }));
+    repositorios.auditoria.registrar(&evento).await.map_err(erro_interno)?;
+
+    tracing::info!(%exclusao_agendada_em, "Exclusão de conta agendada");
+    Ok((StatusCode::ACCEPTED, Json(RespostaExclusaoConta { exclusao_agendada_em })))
+}
+
+#[instrument(skip_all, fields(id_usuario = id_usuario))]
+pub async fn cancelar_exclusao_perfil(
+    Extension(id_usuario): Extension<i32>,
+    State(repositorios): State<Repositorios>,
+    origem: Origem,
+) -> Result<StatusCode, StatusCode> {
+    let usuario = repositorios
+        .usuarios
+        .buscar_por_id(id_usuario)
+        .await
+        .map_err(erro_interno)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let Some(exclusao_agendada_em) = usuario.exclusao_agendada_em else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    repositorios
+        .usuarios
+        .agendar_exclusao(id_usuario, None)
+        .await
+        .map_err(erro_interno)?;
+
+    let evento = NovoEvento::new(acoes::EXCLUSAO_CANCELADA)
+        .ator(id_usuario)
+        .alvo(alvos::USUARIO, id_usuario)
+        .origem(&origem)
+        .diff(json!({ "deletion_scheduled_at": [exclusao_agendada_em, null] }));
+    repositorios.auditoria.registrar(&evento).await.map_err(erro_interno)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[instrument(skip_all, fields(id_usuario = id_usuario))]
+pub async fn obter_sessoes(
+    Extension(id_usuario): Extension<i32>,
+    Extension(id_sessao): Extension<Uuid>,
+    State(repositorios): State<Repositorios>,
+) -> Result<Json<Vec<RespostaSessao>>, StatusCode> {
+    // Sessões mais antigas que a validade do token já não podem ser usadas
+    let limite = chrono::Utc::now() - chrono::Duration::seconds(DURACAO_TOKEN_SEGUNDOS);
+
+    let sessoes = repositorios
+        .sessoes
+        .listar_ativas(id_usuario, limite)
+        .await
+        .map_err(erro_interno)?;
+
+    let resposta_sessoes: Vec<RespostaSessao> = sessoes
+        .into_iter()
+        .map(|sessao| RespostaSessao::de_sessao(sessao, id_sessao))
+        .collect();
+    Ok(Json(resposta_sessoes))
+}
+
+#[instrument(skip_all, fields(id_usuario = id_usuario, id_sessao = %id_sessao))]
+pub async fn revogar_sessao(
+    Extension(id_usuario): Extension<i32>,
+    Path(id_sessao): Path<Uuid>,
+    State(repositorios): State<Repositorios>,
+) -> Result<StatusCode, StatusCode> {
+    let revogada = repositorios
+        .sessoes
+        .revogar(id_sessao, id_usuario)
+        .await
+        .map_err(erro_interno)?;
+
+    if !revogada {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[instrument(skip_all, fields(id_usuario = id_usuario))]
+pub async fn obter_notificacoes(
+    Extension(id_usuario): Extension<i32>,
+    State(repositorios): State<Repositorios>,
+    Query(filtro): Query<FiltroNotificacoes>,
+) -> Result<Json<Vec<RespostaNotificacao>>, StatusCode> {
+    let limite = filtro
+        .limite
+        .unwrap_or(LIMITE_PADRAO_NOTIFICACOES)
+        .clamp(1, LIMITE_MAXIMO_NOTIFICACOES);
+
+    let notificacoes = repositorios
+        .notificacoes
+        .listar(id_usuario, filtro.nao_lidas, filtro.antes_do_id, limite)
+        .await
+        .map_err(erro_interno)?;
+
+    Ok(Json(notificacoes.into_iter().map(RespostaNotificacao::from).collect()))
+}
+
+#[instrument(skip_all, fields(id_usuario = id_usuario))]
+pub async fn conectar_notificacoes(
+    Extension(id_usuario): Extension<i32>,
+    Extension(id_sessao): Extension<Uuid>,
+    State(repositorios): State<Repositorios>,
+    State(notificacoes): State<Notificacoes>,
+    websocket: WebSocketUpgrade,
+) -> Result<Response, StatusCode> {
+    // Antes do upgrade, enquanto uma falha ainda pode virar uma resposta HTTP.
+    // Assinada antes da contagem: uma notificação criada no meio entra nas duas
+    let assinatura = notificacoes.assinar(id_usuario).await.map_err(|erro| {
+        tracing::warn!(erro = %erro, "não foi possível escutar as notificações");
+        StatusCode::SERVICE_UNAVAILABLE
+    })?;
+    let nao_lidas = repositorios
+        .notificacoes
+        .contar_nao_lidas(id_usuario)
+        .await
+        .map_err(erro_interno)?;
+
+    Ok(websocket
+        .protocols([PROTOCOLO_WEBSOCKET_BEARER])
+        .on_upgrade(move |socket| async move { notificacoes.atender(socket, assinatura, id_sessao, nao_lidas).await }))
+}
+
+#[instrument(skip_all, fields(id_usuario = id_usuario, id_notificacao = id_notificacao))]
+pub async fn marcar_notificacao_lida(
+    Extension(id_usuario): Extension<i32>,
+    Path(id_notificacao): Path<i64>,
+    State(repositorios): State<Repositorios>,
+) -> Result<StatusCode, StatusCode> {
+    let marcada = repositorios
+        .notificacoes
+        .marcar_lida(id_notificacao, id_usuario)
+        .await
+        .map_err(erro_interno)?;
+
+    if !marcada {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[instrument(skip_all, fields(id_usuario = id_usuario))]
+pub async fn marcar_todas_notificacoes_lidas(
+    Extension(id_usuario): Extension<i32>,
+    State(repositorios): State<Repositorios>,
+) -> Result<Json<RespostaNotificacoesLidas>, StatusCode> {
+    let marcadas = repositorios
+        .notificacoes
+        .marcar_todas_lidas(id_usuario)
+        .await
+        .map_err(erro_interno)?;
+
+    Ok(Json(RespostaNotificacoesLidas { marcadas }))
+}
+
+#[instrument(skip_all, fields(id_usuario = id_usuario))]
+pub async fn criar_webhook(
+    Extension(id_usuario): Extension<i32>,
+    State(repositorios): State<Repositorios>,
+    origem: Origem,
+    JsonValidado(requisicao): JsonValidado<CriarWebhookRequisicao>,
+) -> Result<(StatusCode, Json<RespostaWebhookCriado>), ErroApi> {
+    // Regras que dependem de quem cadastra; as demais ficam no Validar
+    let admin = repositorios
+        .usuarios
+        .buscar_por_id(id_usuario)
+        .await
+        .map_err(erro_interno)?
+        .is_some_and(|usuario| usuario.admin);
+    if !admin {
+        let mut erros = ErrosValidacao::default();
+        if !webhooks::destino_permitido(&requisicao.url) {
+            erros.adicionar("url", "Endereços locais e de redes privadas são reservados a administradores");
+        }
+        for evento in requisicao.eventos.iter().filter(|evento| eventos::so_admins(evento)) {
+            erros.adicionar("events", format!("Só administradores assinam {evento}"));
+        }
+        if !erros.is_empty() {
+            return Err(erros.into());
+        }
+    }
+
+    let webhook = repositorios
+        .webhooks
+        .criar(id_usuario, &requisicao.url, &requisicao.eventos, &webhooks::gerar_segredo())
+        .await
+        .map_err(erro_interno)?;
+
+    // O segredo não entra no registro de auditoria
+    let evento = NovoEvento::new(acoes::WEBHOOK_CRIADO)
+        .ator(id_usuario)
+        .alvo(alvos::WEBHOOK, webhook.id)
+        .origem(&origem)
+        .diff(auditoria::diferencas(
+            &Value::Null,
+            &json!({ "url": webhook.url, "events": webhook.eventos.0 }),
+        ));
+    repositorios.auditoria.registrar(&evento).await.map_err(erro_interno)?;
+
+    let segredo = webhook.segredo.clone();
+    Ok((
+        StatusCode::CREATED,
+        Json(RespostaWebhookCriado { webhook: RespostaWebhook::from(webhook), segredo }),
+    ))
+}
+
+#[instrument(skip_all, fields(id_usuario = id_usuario))]
+pub async fn obter_webhooks(
+    Extension(id_usuario): Extension<i32>,
+    State(repositorios): State<Repositorios>,
+) -> Result<Json<Vec<RespostaWebhook>>, StatusCode> {
+    let webhooks = repositorios
+        .webhooks
+        .listar_do_usuario(id_usuario)
+        .await
+        .map_err(erro_interno)?;
+
+    Ok(Json(webhooks.into_iter().map(RespostaWebhook::from).collect()))
+}
+
+#[instrument(skip_all, fields(id_usuario = id_usuario, id_webhook = id_webhook))]
+pub async fn deletar_webhook(
+    Extension(id_usuario): Extension<i32>,
+    Path(id_webhook): Path<i32>,
+    State(repositorios): State<Repositorios>,
+    origem: Origem,
+) -> Result<StatusCode, StatusCode> {
+    let webhook = webhook_do_usuario(&repositorios, id_webhook, id_usuario).await?;
+
+    let deletado = repositorios
+        .webhooks
+        .deletar(id_webhook, id_usuario)
+        .await
+        .map_err(erro_interno)?;
+
+    if !deletado {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let evento = NovoEvento::new(acoes::WEBHOOK_APAGADO)
+        .ator(id_usuario)
+        .alvo(alvos::WEBHOOK, id_webhook)
+        .origem(&origem)
+        .diff(auditoria::diferencas(
+            &json!({ "url": webhook.url, "events": webhook.eventos.0 }),
+            &Value::Null,
+        ));
+    repositorios.auditoria.registrar(&evento).await.map_err(erro_interno)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[instrument(skip_all, fields(id_usuario = id_usuario, id_webhook = id_webhook))]
+pub async fn obter_entregas_webhook(
+    Extension(id_usuario): Extension<i32>,
+    Path(id_webhook): Path<i32>,
+    State(repositorios): State<Repositorios>,
+    Query(filtro): Query<FiltroEntregas>,
+) -> Result<Json<Vec<RespostaEntregaWebhook>>, StatusCode> {
+    webhook_do_usuario(&repositorios, id_webhook, id_usuario).await?;
+    let limite = filtro
+        .limite
+        .unwrap_or(LIMITE_PADRAO_ENTREGAS)
+        .clamp(1, LIMITE_MAXIMO_ENTREGAS);
+
+    let entregas = repositorios
+        .webhooks
+        .listar_entregas(id_webhook, filtro.antes_do_id, limite)
+        .await
+        .map_err(erro_interno)?;
+
+    Ok(Json(entregas.into_iter().map(RespostaEntregaWebhook::from).collect()))
+}
+
+#[instrument(skip_all, fields(id_usuario = id_usuario, id_webhook = id_webhook, id_entrega = id_entrega))]
+pub async fn obter_entrega_webhook(
+    Extension(id_usuario): Extension<i32>,
+    Path((id_webhook, id_entrega)): Path<(i32, i64)>,
+    State(repositorios): State<Repositorios>,
+) -> Result<Json<RespostaDetalheEntrega>, StatusCode> {
+    webhook_do_usuario(&repositorios, id_webhook, id_usuario).await?;
+
+    let entrega = repositorios
+        .webhooks
+        .buscar_entrega(id_entrega, id_webhook)
+        .await
+        .map_err(erro_interno)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let tentativas = repositorios
+        .webhooks
+        .listar_tentativas(id_entrega)
+        .await
+        .map_err(erro_interno)?;
+
+    Ok(Json(RespostaDetalheEntrega {
+        payload: entrega.payload.0.clone(),
+        entrega: RespostaEntregaWebhook::from(entrega),
+        historico: tentativas.into_iter().map(RespostaTentativaEntrega::from).collect(),
+    }))
+}
+
+#[instrument(skip_all, fields(id_usuario = id_usuario, id_webhook = id_webhook, id_entrega = id_entrega))]
+pub async fn reenviar_entrega_webhook(
+    Extension(id_usuario): Extension<i32>,
+    Path((id_webhook, id_entrega)): Path<(i32, i64)>,
+    State(repositorios): State<Repositorios>,
+) -> Result<(StatusCode, Json<RespostaEntregaWebhook>), StatusCode> {
+    webhook_do_usuario(&repositorios, id_webhook, id_usuario).await?;
+
+    let entrega = repositorios
+        .webhooks
+        .reenviar(id_entrega, id_webhook)
+        .await
+        .map_err(erro_interno)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok((StatusCode::ACCEPTED, Json(RespostaEntregaWebhook::from(entrega))))
 }
 
+#[instrument(skip_all, fields(id_usuario = id_usuario))]
 pub async fn obter_minhas_postagens(
     Extension(id_usuario): Extension<i32>,
-    State(pool): State<DbPool>,
-) -> Result<Json<Vec<RespostaPostagem>>, StatusCode> {
-    let postagens = sqlx::query_as::<_, Postagem>(
-        "SELECT * FROM posts WHERE user_id = $1 ORDER BY created_at DESC"
-    )
-    .bind(id_usuario)
-    .fetch_all(&pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    State(repositorios): State<Repositorios>,
+    cabecalhos: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let postagens = repositorios
+        .postagens
+        .listar_do_usuario(id_usuario)
+        .await
+        .map_err(erro_interno)?;
 
     let resposta_postagens: Vec<RespostaPostagem> = postagens.into_iter().map(RespostaPostagem::from).collect();
-    Ok(Json(resposta_postagens))
+    Ok(Representacao::nova(&resposta_postagens)?.responder(&cabecalhos))
 }
 
+#[instrument(skip_all, fields(id_usuario = id_usuario))]
 pub async fn criar_postagem(
     Extension(id_usuario): Extension<i32>,
-    State(pool): State<DbPool>,
-    Json(requisicao): Json<CriarPostagemRequisicao>,
+    State(repositorios): State<Repositorios>,
+    Extension(cache): Extension<Cache>,
+    State(transmissao): State<Transmissao>,
+    origem: Origem,
+    JsonValidado(requisicao): JsonValidado<CriarPostagemRequisicao>,
 ) -> Result<Json<RespostaPostagem>, StatusCode> {
-    let postagem = sqlx::query_as::<_, Postagem>(
-        r#"
-        INSERT INTO posts (title, content, user_id)
-        VALUES ($1, $2, $3)
-        RETURNING *
-        "#,
-    )
-    .bind(&requisicao.titulo)
-    .bind(&requisicao.conteudo)
-    .bind(id_usuario)
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let escrita = EscritaPostagens::new(&repositorios, &cache, &transmissao, &origem, id_usuario);
+    let postagem = escrita.criar(&requisicao).await?;
 
     Ok(Json(RespostaPostagem::from(postagem)))
 }
 
+#[instrument(skip_all, fields(id_postagem = id_postagem))]
 pub async fn obter_postagem(
     Path(id_postagem): Path<i32>,
-    State(pool): State<DbPool>,
-) -> Result<Json<RespostaPostagem>, StatusCode> {
-    let postagem = sqlx::query_as::<_, Postagem>(
-        "SELECT * FROM posts WHERE id = $1"
-    )
-    .bind(id_postagem)
-    .fetch_optional(&pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-    .ok_or(StatusCode::NOT_FOUND)?;
+    State(repositorios): State<Repositorios>,
+    Extension(cache): Extension<Cache>,
+    cabecalhos: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let postagem: RespostaPostagem = cache
+        .obter_ou_carregar(Chave::Postagem(id_postagem), || async {
+            repositorios
+                .postagens
+                .buscar(id_postagem)
+                .await
+                .map_err(erro_interno)?
+                .map(RespostaPostagem::from)
+                .ok_or(StatusCode::NOT_FOUND)
+        })
+        .await?;
 
-    Ok(Json(RespostaPostagem::from(postagem)))
+    let modificado_em = postagem.atualizado_em;
+    Ok(Representacao::nova(&postagem)?
+        .modificada_em(modificado_em)
+        .responder(&cabecalhos))
 }
 
+// Cada extrator é um argumento; agrupá-los esconderia o que o handler usa
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip_all, fields(id_usuario = id_usuario, id_postagem = id_postagem))]
 pub async fn atualizar_postagem(
     Extension(id_usuario): Extension<i32>,
     Path(id_postagem): Path<i32>,
-    State(pool): State<DbPool>,
-    Json(requisicao): Json<CriarPostagemRequisicao>,
-) -> Result<Json<RespostaPostagem>, StatusCode> {
-    // Verificar se o post pertence ao usuário e atualizar
-    let postagem_atualizada = sqlx::query_as::<_, Postagem>(
-        r#"
-        UPDATE posts
-        SET title = $1, content = $2
-        WHERE id = $3 AND user_id = $4
-        RETURNING *
-        "#,
-    )
-    .bind(&requisicao.titulo)
-    .bind(&requisicao.conteudo)
-    .bind(id_postagem)
-    .bind(id_usuario)
-    .fetch_optional(&pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-    .ok_or(StatusCode::NOT_FOUND)?;
+    State(repositorios): State<Repositorios>,
+    Extension(cache): Extension<Cache>,
+    State(transmissao): State<Transmissao>,
+    origem: Origem,
+    cabecalhos: HeaderMap,
+    JsonValidado(requisicao): JsonValidado<CriarPostagemRequisicao>,
+) -> Result<Response, StatusCode> {
+    // A versão atual é a base do diff da auditoria
+    let atual = repositorios
+        .postagens
+        .buscar_do_usuario(id_postagem, id_usuario)
+        .await
+        .map_err(erro_interno)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Com If-Match, a edição só vale para a versão que o cliente leu
+    let versao_lida = if cabecalhos.contains_key(IF_MATCH) {
+        let versao_lida = atual.atualizado_em;
+        verificar_if_match(&cabecalhos, &Representacao::nova(&RespostaPostagem::from(atual.clone()))?.etag)?;
+        Some(versao_lida)
+    } else {
+        None
+    };
 
-    Ok(Json(RespostaPostagem::from(postagem_atualizada)))
+    let escrita = EscritaPostagens::new(&repositorios, &cache, &transmissao, &origem, id_usuario);
+    let postagem_atualizada = escrita.atualizar(atual, &requisicao, versao_lida).await?;
+
+    let modificado_em = postagem_atualizada.atualizado_em;
+    let resposta = RespostaPostagem::from(postagem_atualizada);
+    Ok(Representacao::nova(&resposta)?.modificada_em(modificado_em).resposta())
 }
 
+#[instrument(skip_all, fields(id_usuario = id_usuario, id_postagem = id_postagem))]
 pub async fn deletar_postagem(
     Extension(id_usuario): Extension<i32>,
     Path(id_postagem): Path<i32>,
-    State(pool): State<DbPool>,
+    State(repositorios): State<Repositorios>,
+    Extension(cache): Extension<Cache>,
+    State(transmissao): State<Transmissao>,
+    origem: Origem,
 ) -> Result<StatusCode, StatusCode> {
-    let resultado = sqlx::query(
-        "DELETE FROM posts WHERE id = $1 AND user_id = $2"
-    )
-    .bind(id_postagem)
-    .bind(id_usuario)
-    .execute(&pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // Lida antes de apagar, para o diff guardar o que foi removido
+    let postagem = repositorios
+        .postagens
+        .buscar_do_usuario(id_postagem, id_usuario)
+        .await
+        .map_err(erro_interno)?
+        .ok_or(StatusCode::NOT_FOUND)?;
 
-    if resultado.rows_affected() == 0 {
-        return Err(StatusCode::NOT_FOUND);
-    }
+    let escrita = EscritaPostagens::new(&repositorios, &cache, &transmissao, &origem, id_usuario);
+    escrita.deletar(postagem).await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[instrument(skip_all)]
 pub async fn obter_todos_usuarios(
-    State(pool): State<DbPool>,
+    State(repositorios): State<Repositorios>,
 ) -> Result<Json<Vec<RespostaUsuario>>, StatusCode> {
-    let usuarios = sqlx::query_as::<_, Usuario>(
-        "SELECT * FROM users ORDER BY created_at DESC"
-    )
-    .fetch_all(&pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let usuarios = repositorios
+        .usuarios
+        .listar()
+        .await
+        .map_err(erro_interno)?;
 
     let resposta_usuarios: Vec<RespostaUsuario> = usuarios.into_iter().map(RespostaUsuario::from).collect();
     Ok(Json(resposta_usuarios))
 }
+
+// ========== Rotas de Administração ==========
+
+#[instrument(skip_all, fields(id_usuario = id_usuario))]
+pub async fn exportar_dados(
+    Extension(id_usuario): Extension<i32>,
+    State(banco): State<Banco>,
+    State(repositorios): State<Repositorios>,
+    origem: Origem,
+) -> Result<Response, StatusCode> {
+    let cabecalho = Cabecalho::do_banco(&banco).await.map_err(erro_interno)?;
+
+    let evento = NovoEvento::new(acoes::ADMIN_DADOS_EXPORTADOS)
+        .ator(id_usuario)
+        .origem(&origem)
+        .diff(json!({ "schema_version": cabecalho.schema_version }));
+    repositorios.auditoria.registrar(&evento).await.map_err(erro_interno)?;
+
+    let disposicao = format!(
+        "attachment; filename=\"export-{}.jsonl\"",
+        cabecalho.exported_at.format("%Y%m%dT%H%M%SZ")
+    );
+
+    // O arquivo é gerado enquanto é enviado. Se a exportação falhar no meio,
+    // ele termina sem o manifesto e a importação o recusa.
+    let (escrita, leitura) = tokio::io::duplex(TAMANHO_BUFFER_EXPORTACAO);
+    tokio::spawn(
+        async move {
+            match transferencia::exportar(&repositorios, cabecalho, escrita).await {
+                Ok(manifesto) => tracing::info!(tabelas = ?manifesto.tables, "Exportação concluída"),
+                Err(erro) => tracing::error!(erro = %erro, "exportação interrompida"),
+            }
+        }
+        .in_current_span(),
+    );
+
+    Ok((
+        [(CONTENT_TYPE, "application/x-ndjson".to_string()), (CONTENT_DISPOSITION, disposicao)],
+        Body::from_stream(ReaderStream::new(leitura)),
+    )
+        .into_response())
+}
+
+#[instrument(skip_all, fields(id_usuario = id_usuario))]
+pub async fn importar_dados(
+    Extension(id_usuario): Extension<i32>,
+    State(repositorios): State<Repositorios>,
+    State(configuracao): State<Arc<Configuracao>>,
+    Extension(cache): Extension<Cache>,
+    origem: Origem,
+    corpo: Body,
+) -> Result<Json<ResumoImportacao>, ErroApi> {
+    let bytes = axum::body::to_bytes(corpo, configuracao.limite_importacao)
+        .await
+        .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?;
+
+    let resumo = transferencia::importar(&repositorios, bytes.as_ref())
+        .await
+        .map_err(|erro| match erro {
+            ErroTransferencia::Conflito(_) => ErroApi::Requisicao(StatusCode::CONFLICT, erro.to_string()),
+            ErroTransferencia::Banco(erro) => ErroApi::Status(erro_interno(erro)),
+            _ => ErroApi::Requisicao(StatusCode::UNPROCESSABLE_ENTITY, erro.to_string()),
+        })?;
+
+    let mut chaves: Vec<Chave> = resumo.ids_postagens.iter().map(|id| Chave::Postagem(*id)).collect();
+    chaves.push(Chave::PostagensPublicas);
+    cache.invalidar(&chaves).await;
+
+    let evento = NovoEvento::new(acoes::ADMIN_DADOS_IMPORTADOS)
+        .ator(id_usuario)
+        .origem(&origem)
+        .diff(json!(&resumo));
+    repositorios.auditoria.registrar(&evento).await.map_err(erro_interno)?;
+
+    Ok(Json(resumo))
+}
+
+#[instrument(skip_all, fields(id_usuario = id_usuario))]
+pub async fn obter_eventos_auditoria(
+    Extension(id_usuario): Extension<i32>,
+    State(repositorios): State<Repositorios>,
+    Query(filtro): Query<FiltroEventos>,
+) -> Result<Json<Vec<RespostaEventoAuditoria>>, StatusCode> {
+    let limite = filtro
+        .limite
+        .unwrap_or(LIMITE_PADRAO_AUDITORIA)
+        .clamp(1, LIMITE_MAXIMO_AUDITORIA);
+
+    let eventos = repositorios
+        .auditoria
+        .listar(&filtro, limite)
+        .await
+        .map_err(erro_interno)?;
+
+    Ok(Json(eventos.into_iter().map(RespostaEventoAuditoria::from).collect()))
+}
+
+#[instrument(skip_all)]
+pub async fn obter_tarefas(
+    State(repositorios): State<Repositorios>,
+    Query(filtro): Query<FiltroTarefas>,
+) -> Result<Json<Vec<RespostaTarefa>>, StatusCode> {
+    let limite = filtro
+        .limite
+        .unwrap_or(LIMITE_PADRAO_TAREFAS)
+        .clamp(1, LIMITE_MAXIMO_TAREFAS);
+
+    let tarefas = repositorios
+        .tarefas
+        .listar(&filtro, limite)
+        .await
+        .map_err(erro_interno)?;
+
+    Ok(Json(tarefas.into_iter().map(RespostaTarefa::from).collect()))
+}
+
+#[instrument(skip_all, fields(id_tarefa = id_tarefa))]
+pub async fn obter_tarefa(
+    Path(id_tarefa): Path<i64>,
+    State(repositorios): State<Repositorios>,
+) -> Result<Json<RespostaTarefa>, StatusCode> {
+    let tarefa = repositorios
+        .tarefas
+        .buscar(id_tarefa)
+        .await
+        .map_err(erro_interno)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(RespostaTarefa::from(tarefa)))
+}
+
+#[instrument(skip_all, fields(id_usuario = id_usuario, id_tarefa = id_tarefa))]
+pub async fn tentar_tarefa_de_novo(
+    Extension(id_usuario): Extension<i32>,
+    Path(id_tarefa): Path<i64>,
+    State(repositorios): State<Repositorios>,
+    origem: Origem,
+) -> Result<(StatusCode, Json<RespostaTarefa>), ErroApi> {
+    let Some(tarefa) = repositorios
+        .tarefas
+        .tentar_de_novo(id_tarefa, chrono::Utc::now())
+        .await
+        .map_err(erro_interno)?
+    else {
+        // Não existe, ou não pode voltar à fila
+        let tarefa = repositorios
+            .tarefas
+            .buscar(id_tarefa)
+            .await
+            .map_err(erro_interno)?
+            .ok_or(StatusCode::NOT_FOUND)?;
+        return Err(ErroApi::Requisicao(
+            StatusCode::CONFLICT,
+            format!("Só tarefas pendentes ou mortas voltam à fila (situação atual: {})", tarefa.status),
+        ));
+    };
+
+    let evento = NovoEvento::new(acoes::ADMIN_TAREFA_REENFILEIRADA)
+        .ator(id_usuario)
+        .alvo(alvos::TAREFA, tarefa.id)
+        .origem(&origem)
+        .diff(json!({ "kind": tarefa.tipo, "last_error": tarefa.ultimo_erro }));
+    repositorios.auditoria.registrar(&evento).await.map_err(erro_interno)?;
+
+    Ok((StatusCode::ACCEPTED, Json(RespostaTarefa::from(tarefa))))
+}
+
+// ========== Auxiliares ==========
+
+/// O webhook, se for do usuário; de outro usuário é como se não existisse.
+async fn webhook_do_usuario(repositorios: &Repositorios, id_webhook: i32, id_usuario: i32) -> Result<Webhook, StatusCode> {
+    repositorios
+        .webhooks
+        .buscar_do_usuario(id_webhook, id_usuario)
+        .await
+        .map_err(erro_interno)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Criar, editar e apagar posts, com os efeitos que acompanham cada escrita:
+/// invalidar o cache, publicar no feed em tempo real, registrar na auditoria e
+/// enfileirar os webhooks. Compartilhado pelas rotas REST e pelo GraphQL.
+pub struct EscritaPostagens<'a> {
+    pub repositorios: &'a Repositorios,
+    pub cache: &'a Cache,
+    pub transmissao: &'a Transmissao,
+    pub origem: &'a Origem,
+    pub id_usuario: i32,
+}
+
+impl<'a> EscritaPostagens<'a> {
+    /// Escritas feitas por `id_usuario`, o dono dos posts criados e o único que
+    /// pode editar ou apagar os que receber.
+    pub fn new(repositorios: &'a Repositorios, cache: &'a Cache, transmissao: &'a Transmissao, origem: &'a Origem, id_usuario: i32) -> Self {
+        Self { repositorios, cache, transmissao, origem, id_usuario }
+    }
+
+    pub async fn criar(&self, requisicao: &CriarPostagemRequisicao) -> Result<Postagem, StatusCode> {
+        let efeitos = |postagem: &Postagem| Efeitos {
+            auditoria: NovoEvento::new(acoes::POSTAGEM_CRIADA)
+                .ator(self.id_usuario)
+                .alvo(alvos::POSTAGEM, postagem.id)
+                .origem(self.origem)
+                .diff(auditoria::diferencas(&Value::Null, &campos_auditados(postagem))),
+            webhook: webhooks::evento(eventos::POSTAGEM_CRIADA, json!(RespostaPostagem::from(postagem.clone()))),
+        };
+        let postagem = self
+            .repositorios
+            .postagens
+            .criar_com_efeitos(self.id_usuario, &requisicao.titulo, &requisicao.conteudo, &efeitos)
+            .await
+            .map_err(erro_interno)?;
+
+        self.cache.invalidar(&[Chave::PostagensPublicas]).await;
+        self.transmissao.publicar(TipoEvento::Criada, postagem.id).await;
+
+        Ok(postagem)
+    }
+
+    /// `atual` é a postagem do usuário como foi lida, base do diff da auditoria.
+    /// Com `versao_lida`, a edição só vale se ninguém a editou depois (`412`).
+    pub async fn atualizar(
+        &self,
+        atual: Postagem,
+        requisicao: &CriarPostagemRequisicao,
+        versao_lida: Option<DateTime<Utc>>,
+    ) -> Result<Postagem, StatusCode> {
+        let efeitos = |postagem: &Postagem| Efeitos {
+            auditoria: NovoEvento::new(acoes::POSTAGEM_ATUALIZADA)
+                .ator(self.id_usuario)
+                .alvo(alvos::POSTAGEM, postagem.id)
+                .origem(self.origem)
+                .diff(auditoria::diferencas(&campos_auditados(&atual), &campos_auditados(postagem))),
+            webhook: webhooks::evento(eventos::POSTAGEM_ATUALIZADA, json!(RespostaPostagem::from(postagem.clone()))),
+        };
+
+        // A comparação com updated_at barra outra edição que tenha acontecido depois da leitura
+        let postagem = self
+            .repositorios
+            .postagens
+            .atualizar_com_efeitos(atual.id, self.id_usuario, &requisicao.titulo, &requisicao.conteudo, versao_lida, &efeitos)
+            .await
+            .map_err(erro_interno)?
+            .ok_or(if versao_lida.is_some() {
+                StatusCode::PRECONDITION_FAILED
+            } else {
+                StatusCode::NOT_FOUND
+            })?;
+
+        self.cache.invalidar(&[Chave::Postagem(postagem.id), Chave::PostagensPublicas]).await;
+        self.transmissao.publicar(TipoEvento::Atualizada, postagem.id).await;
+
+        Ok(postagem)
+    }
+
+    /// `postagem` é lida antes de apagar, para a auditoria e os webhooks
+    /// guardarem o que foi removido.
+    pub async fn deletar(&self, postagem: Postagem) -> Result<(), StatusCode> {
+        let efeitos = Efeitos {
+            auditoria: NovoEvento::new(acoes::POSTAGEM_APAGADA)
+                .ator(self.id_usuario)
+                .alvo(alvos::POSTAGEM, postagem.id)
+                .origem(self.origem)
+                .diff(auditoria::diferencas(&campos_auditados(&postagem), &Value::Null)),
+            webhook: webhooks::evento(eventos::POSTAGEM_APAGADA, json!(RespostaPostagem::from(postagem.clone()))),
+        };
+        let deletada = self
+            .repositorios
+            .postagens
+            .deletar_com_efeitos(postagem.id, self.id_usuario, &efeitos)
+            .await
+            .map_err(erro_interno)?;
+
+        if !deletada {
+            return Err(StatusCode::NOT_FOUND);
+        }
+
+        self.cache.invalidar(&[Chave::Postagem(postagem.id), Chave::PostagensPublicas]).await;
+        self.transmissao.publicar(TipoEvento::Apagada, postagem.id).await;
+
+        Ok(())
+    }
+}
+
+/// Os campos de uma postagem comparados nos eventos de auditoria.
+fn campos_auditados(postagem: &Postagem) -> Value {
+    json!({ "title": postagem.titulo, "content": postagem.conteudo })
+}
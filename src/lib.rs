@@ -0,0 +1,211 @@
+//! API REST de blog com Axum. O binário (`main.rs`) cuida do servidor, dos
+//! sinais e das tarefas de fundo; a aplicação em si é montada por
+//! [`ConstrutorAplicacao`], que também serve para embutir a API em outra
+//! aplicação axum:
+//!
+//! ```no_run
+//! use axum::Router;
+//! use hello_rust::{configuracao::Configuracao, db, ConstrutorAplicacao};
+//!
+//! # fn exemplo() -> Result<(), sqlx::Error> {
+//! let banco = db::conectar()?;
+//! let blog = ConstrutorAplicacao::new(banco)
+//!     .configuracao(Configuracao { segredo_jwt: "segredo".into(), ..Default::default() })
+//!     .prefixo("/blog")
+//!     .construir();
+//!
+//! let aplicacao: Router = Router::new().merge(blog);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! As migrações não rodam sozinhas: chame [`db::Banco::executar_migracoes`]
+//! antes de atender requisições. Quem as roda depois de montar a aplicação
+//! informa a [`saude::Prontidao`] ao construtor, para `/health/ready` esperar
+//! por elas.
+
+pub mod auditoria;
+pub mod auth;
+pub mod cache;
+pub mod condicional;
+pub mod configuracao;
+pub mod db;
+pub mod encerramento;
+pub mod erros;
+pub mod estado;
+pub mod fila;
+pub mod graphql;
+pub mod handlers;
+pub mod metricas;
+pub mod middleware;
+pub mod models;
+pub mod notificacoes;
+pub mod openapi;
+pub mod politica_senha;
+pub mod privacidade;
+pub mod repositorios;
+pub mod rotas;
+pub mod saude;
+pub mod seed;
+pub mod telemetria;
+pub mod tls;
+pub mod transferencia;
+pub mod transmissao;
+pub mod validacao;
+pub mod webhooks;
+
+use axum::{middleware::from_fn, Extension, Router};
+use std::time::Duration;
+use tower_http::{
+    compression::CompressionLayer,
+    cors::CorsLayer,
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+};
+
+use crate::{
+    cache::{Cache, CacheMemoria},
+    configuracao::Configuracao,
+    db::Banco,
+    estado::Estado,
+    notificacoes::Notificacoes,
+    repositorios::Repositorios,
+    saude::Prontidao,
+    transmissao::Transmissao,
+};
+
+// Cache usado quando nenhum é informado (o mesmo padrão de CACHE_URL=memoria)
+const CAPACIDADE_CACHE_PADRAO: usize = 1000;
+const TTL_CACHE_PADRAO: Duration = Duration::from_secs(30);
+
+/// Monta o `Router` da aplicação. Só o banco é obrigatório; o resto tem padrão:
+/// configuração lida do ambiente, cache em memória, repositórios do próprio banco,
+/// sem prefixo e com as rotas operacionais (`/health`, `/metrics`, `/docs`, `/home`).
+///
+/// O `Router` devolvido já tem estado, então pode receber outros middlewares
+/// (`.layer(...)`) ou ser combinado com `merge`/`nest` na aplicação que o embute.
+pub struct ConstrutorAplicacao {
+    banco: Banco,
+    configuracao: Option<Configuracao>,
+    cache: Option<Cache>,
+    repositorios: Option<Repositorios>,
+    transmissao: Option<Transmissao>,
+    notificacoes: Option<Notificacoes>,
+    prontidao: Option<Prontidao>,
+    prefixo: Option<String>,
+    rotas_operacionais: bool,
+}
+
+impl ConstrutorAplicacao {
+    pub fn new(banco: Banco) -> Self {
+        ConstrutorAplicacao {
+            banco,
+            configuracao: None,
+            cache: None,
+            repositorios: None,
+            transmissao: None,
+            notificacoes: None,
+            prontidao: None,
+            prefixo: None,
+            rotas_operacionais: true,
+        }
+    }
+
+    pub fn configuracao(mut self, configuracao: Configuracao) -> Self {
+        self.configuracao = Some(configuracao);
+        self
+    }
+
+    /// Cache de leitura dos posts, por exemplo um compartilhado no Redis.
+    pub fn cache(mut self, cache: Cache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Substitui os repositórios derivados do banco (dados em outro lugar, decoradores etc.).
+    pub fn repositorios(mut self, repositorios: Repositorios) -> Self {
+        self.repositorios = Some(repositorios);
+        self
+    }
+
+    /// Feed de posts em tempo real, para quem precisa encerrá-lo (veja [`Transmissao::encerrar`]).
+    pub fn transmissao(mut self, transmissao: Transmissao) -> Self {
+        self.transmissao = Some(transmissao);
+        self
+    }
+
+    /// Notificações dos usuários, para quem precisa criá-las fora das rotas ou
+    /// encerrá-las (veja [`Notificacoes::encerrar`]).
+    pub fn notificacoes(mut self, notificacoes: Notificacoes) -> Self {
+        self.notificacoes = Some(notificacoes);
+        self
+    }
+
+    /// Prontidão acompanhada por `/health/startup` e `/health/ready`. Sem ela,
+    /// a aplicação já nasce inicializada, pois as migrações rodaram antes da
+    /// montagem. Quem prepara o banco depois (como o binário) passa uma
+    /// [`Prontidao::iniciando`], chama `marcar_inicializada` quando as migrações
+    /// terminam e `marcar_encerrando` ao começar o encerramento.
+    pub fn prontidao(mut self, prontidao: Prontidao) -> Self {
+        self.prontidao = Some(prontidao);
+        self
+    }
+
+    /// Serve tudo sob `prefixo` (ex.: `/blog` → `/blog/api/v1/posts`).
+    pub fn prefixo(mut self, prefixo: impl Into<String>) -> Self {
+        self.prefixo = Some(prefixo.into());
+        self
+    }
+
+    /// Sem as rotas da raiz, para quem já expõe saúde e métricas por conta própria.
+    pub fn sem_rotas_operacionais(mut self) -> Self {
+        self.rotas_operacionais = false;
+        self
+    }
+
+    pub fn construir(self) -> Router {
+        let configuracao = self.configuracao.unwrap_or_else(Configuracao::do_ambiente);
+        let cache = self
+            .cache
+            .unwrap_or_else(|| Cache::new(CacheMemoria::new(CAPACIDADE_CACHE_PADRAO), TTL_CACHE_PADRAO));
+
+        let mut estado = Estado::new(self.banco, configuracao);
+        if let Some(repositorios) = self.repositorios {
+            estado.repositorios = repositorios;
+        }
+        estado.transmissao = self
+            .transmissao
+            .unwrap_or_else(|| Transmissao::new(&estado.banco, estado.repositorios.clone()));
+        estado.notificacoes = self
+            .notificacoes
+            .unwrap_or_else(|| Notificacoes::new(&estado.banco, estado.repositorios.clone()));
+        if let Some(prontidao) = self.prontidao {
+            estado.prontidao = prontidao;
+        }
+
+        let rotas = if self.rotas_operacionais {
+            rotas::roteador(estado.clone())
+        } else {
+            rotas::roteador_api(estado.clone())
+        };
+
+        // O X-Request-Id recebido (ou gerado) entra no span da requisição e volta na resposta.
+        // Respostas são comprimidas (zstd, brotli ou gzip) conforme o Accept-Encoding,
+        // e o ETag de cada codificação recebe o sufixo dela.
+        let aplicacao = rotas
+            .layer(from_fn(middleware::middleware_depreciacao))
+            .layer(Extension(cache))
+            .layer(CompressionLayer::new())
+            .layer(from_fn(condicional::etag_por_codificacao))
+            .layer(CorsLayer::permissive())
+            .layer(from_fn(metricas::middleware_metricas))
+            .layer(PropagateRequestIdLayer::x_request_id())
+            .layer(telemetria::camada_trace())
+            .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+            .with_state(estado);
+
+        match self.prefixo.as_deref().map(|prefixo| prefixo.trim_matches('/')) {
+            Some(prefixo) if !prefixo.is_empty() => Router::new().nest(&format!("/{prefixo}"), aplicacao),
+            _ => aplicacao,
+        }
+    }
+}
@@ -1,73 +1,228 @@
-mod auth;
-mod db;
-mod handlers;
-mod middleware;
-mod models;
-
-use axum::{
-    middleware::from_fn,
-    routing::{get, post, put, delete},
-    Router,
+use axum_server::Handle;
+use hello_rust::{
+    cache, configuracao::Configuracao, db, encerramento, fila::{self, ConfiguracaoFila, Fila}, metricas,
+    notificacoes::Notificacoes, politica_senha, privacidade, rotas, saude::Prontidao, seed, telemetria, tls,
+    transmissao::Transmissao, webhooks, ConstrutorAplicacao,
 };
-use tower_http::cors::CorsLayer;
-use tokio::net::TcpListener;
+use std::{net::SocketAddr, time::Duration};
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Inicializar banco de dados
-    let pool = db::inicializar_banco().await?;
-    println!("✅ Banco de dados inicializado");
-    
-    // Popular com dados iniciais
-    db::popular_dados(&pool).await?;
-
-    // Criar rotas públicas
-    let rotas_publicas = Router::new()
-        .route("/home", get(handlers::pagina_home))
-        .route("/health", get(handlers::verificar_saude))
-        .route("/api/auth/register", post(handlers::registrar))
-        .route("/api/auth/login", post(handlers::login))
-        .route("/api/posts", get(handlers::obter_postagens_publicas))
-        .route("/api/posts/{id}", get(handlers::obter_postagem))
-        .route("/api/users", get(handlers::obter_todos_usuarios));
-
-    // Criar rotas protegidas (requerem JWT)
-    let rotas_protegidas = Router::new()
-        .route("/api/profile", get(handlers::obter_perfil))
-        .route("/api/posts/my", get(handlers::obter_minhas_postagens))
-        .route("/api/posts", post(handlers::criar_postagem))
-        .route("/api/posts/{id}", put(handlers::atualizar_postagem))
-        .route("/api/posts/{id}", delete(handlers::deletar_postagem))
-        .layer(from_fn(middleware::middleware_auth));
-
-    // Aplicação principal
-    let aplicacao = Router::new()
-        .merge(rotas_publicas)
-        .merge(rotas_protegidas)
-        .layer(CorsLayer::permissive())
-        .with_state(pool);
-
-    let endereco = "127.0.0.1:3000";
-    let listener = TcpListener::bind(endereco).await?;
-    
-    println!("🚀 Servidor rodando em http://{endereco}");
-    println!("🌐 Interface web disponível em: http://{endereco}/home");
+    // Logs estruturados (LOG_FORMAT, RUST_LOG) e exportação OTLP opcional
+    let telemetria = telemetria::inicializar();
+
+    metricas::inicializar()?;
+
+    // O pool conecta sob demanda; as migrações rodam em segundo plano
+    // enquanto /health/startup responde 503. O banco (PostgreSQL, SQLite ou
+    // memória) vem de DATABASE_URL.
+    let banco = db::conectar()?;
+    tracing::info!(sistema = banco.sistema(), "Banco de dados configurado");
+
+    // /health/startup e /health/ready falham até as migrações terminarem
+    let prontidao = Prontidao::iniciando();
+
+    // SIGTERM/SIGINT: prontidão falha, o listener fecha e as requisições em
+    // andamento têm até `prazo` para terminar
+    let configuracao_encerramento = encerramento::ConfiguracaoEncerramento::do_ambiente();
+    let prazo = configuracao_encerramento.prazo;
+    let parar_de_aceitar = CancellationToken::new();
+
+    // Tarefas de fundo acompanhadas para serem interrompidas no encerramento
+    let tarefas = TaskTracker::new();
+    let parar_tarefas = CancellationToken::new();
+    tarefas.spawn(parar_tarefas.clone().run_until_cancelled_owned(metricas::monitorar_pool(banco.clone())));
+    tarefas.spawn({
+        let (banco, prontidao) = (banco.clone(), prontidao.clone());
+        let (parar_tarefas, parar_de_aceitar) = (parar_tarefas.clone(), parar_de_aceitar.clone());
+        async move {
+            if parar_tarefas.run_until_cancelled(preparar_banco(banco.clone(), &prontidao)).await.is_none() {
+                return;
+            }
+            // Dependem das tabelas e colunas criadas pelas migrações
+            let repositorios = banco.repositorios();
+            // Tipos de tarefa executados por esta instância e as agendas (cron, em UTC)
+            let fila = Fila::new(repositorios.clone())
+                .agendar("*/10 * * * *", privacidade::ExclusoesVencidas)
+                .agendar("30 3 * * *", fila::LimpezaTarefas);
+            let configuracao_fila = ConfiguracaoFila::do_ambiente();
+            tracing::info!(trabalhadores = configuracao_fila.trabalhadores, "Fila de tarefas iniciada");
+            // Param de reservar junto com o listener e terminam o que já
+            // começaram em paralelo com as requisições; o que passar do prazo
+            // volta para a fila
+            tokio::join!(
+                fila.executar(configuracao_fila.trabalhadores, parar_de_aceitar.clone(), prazo),
+                webhooks::executar_entregas(repositorios, parar_de_aceitar, prazo),
+            );
+        }
+    });
+
+    // Carregar a política de senhas (e a lista de senhas vazadas) antes de aceitar requisições
+    let politica_senha = politica_senha::politica();
+    tracing::info!(
+        tamanho_minimo = politica_senha.tamanho_minimo,
+        entropia_minima = politica_senha.entropia_minima,
+        "Política de senhas carregada"
+    );
+
+    // Cache de leitura das rotas públicas de posts (memória ou Redis, via CACHE_URL)
+    let cache = cache::Cache::do_ambiente().await;
+
+    // Feed de posts (SSE) e notificações (WebSocket), guardados aqui para fechar
+    // as conexões no encerramento
+    let transmissao = Transmissao::new(&banco, banco.repositorios());
+    let notificacoes = Notificacoes::new(&banco, banco.repositorios());
+
+    // Rotas e middlewares montados em lib.rs, os mesmos usados nos testes de integração
+    let aplicacao = ConstrutorAplicacao::new(banco.clone())
+        .configuracao(Configuracao::do_ambiente())
+        .cache(cache)
+        .transmissao(transmissao.clone())
+        .notificacoes(notificacoes.clone())
+        .prontidao(prontidao.clone())
+        .construir();
+
+    let endereco = SocketAddr::from(([127, 0, 0, 1], 3000));
+    let listener = std::net::TcpListener::bind(endereco)?;
+    listener.set_nonblocking(true)?;
+
+    // HTTPS quando TLS_CERTIFICADO e TLS_CHAVE estão definidos (veja tls.rs)
+    let tls = match tls::ConfiguracaoTls::do_ambiente() {
+        Some(configuracao_tls) => {
+            let certificado = configuracao_tls.carregar().await?;
+            Some((configuracao_tls, certificado))
+        }
+        None => None,
+    };
+    let esquema = if tls.is_some() { "https" } else { "http" };
+
+    println!("🚀 Servidor rodando em {esquema}://{endereco}");
+    println!("🌐 Interface web disponível em: {esquema}://{endereco}/home");
+    println!("📖 Documentação da API em: {esquema}://{endereco}/docs");
+    println!("📈 Métricas (Prometheus) em: {esquema}://{endereco}/metrics");
+    imprimir_rotas();
+
+    tokio::spawn({
+        let parar_de_aceitar = parar_de_aceitar.clone();
+        async move { encerramento::aguardar_sinal(&configuracao_encerramento, &prontidao, parar_de_aceitar).await }
+    });
+
+    let servidor = Handle::new();
+    let redirecionamento = Handle::new();
+    tokio::spawn({
+        let (servidor, redirecionamento) = (servidor.clone(), redirecionamento.clone());
+        async move {
+            parar_de_aceitar.cancelled().await;
+            // Os fluxos do feed e os WebSockets não terminam sozinhos
+            transmissao.encerrar();
+            notificacoes.encerrar();
+            redirecionamento.graceful_shutdown(Some(prazo));
+            servidor.graceful_shutdown(Some(prazo));
+        }
+    });
+
+    match tls {
+        Some((configuracao_tls, certificado)) => {
+            tarefas.spawn(parar_tarefas.clone().run_until_cancelled_owned(tls::recarregar_ao_mudar(
+                configuracao_tls.clone(),
+                certificado.clone(),
+            )));
+
+            if let Some(endereco_redirecionamento) = configuracao_tls.endereco_redirecionamento {
+                println!("↪️  Redirecionando http://{endereco_redirecionamento} para HTTPS");
+                let roteador = tls::roteador_redirecionamento(endereco.port());
+                tarefas.spawn(async move {
+                    if let Err(erro) = axum_server::bind(endereco_redirecionamento)
+                        .handle(redirecionamento)
+                        .serve(roteador.into_make_service())
+                        .await
+                    {
+                        tracing::error!(erro = %erro, "falha no listener de redirecionamento HTTP");
+                    }
+                });
+            }
+
+            // HTTP/1.1 e HTTP/2 negociados via ALPN
+            axum_server::from_tcp_rustls(listener, certificado)?
+                .handle(servidor)
+                .serve(
+                    aplicacao
+                        .layer(tls::camada_hsts(configuracao_tls.hsts_max_age))
+                        .into_make_service_with_connect_info::<SocketAddr>(),
+                )
+                .await?;
+        }
+        None => {
+            // Aceita HTTP/1.1 e HTTP/2 sem TLS (h2c com prior knowledge)
+            axum_server::from_tcp(listener)?
+                .handle(servidor)
+                .serve(aplicacao.into_make_service_with_connect_info::<SocketAddr>())
+                .await?;
+        }
+    }
+
+    parar_tarefas.cancel();
+    tarefas.close();
+    if tokio::time::timeout(prazo, tarefas.wait()).await.is_err() {
+        tracing::warn!("Tarefas de fundo não terminaram dentro do prazo");
+    }
+
+    banco.fechar().await;
+    tracing::info!("Servidor encerrado");
+
+    telemetria.finalizar();
+    Ok(())
+}
+
+// Tenta até conseguir: enquanto isso a sonda de inicialização segue falhando
+async fn preparar_banco(banco: db::Banco, prontidao: &Prontidao) {
+    const INTERVALO_TENTATIVAS: Duration = Duration::from_secs(5);
+
+    loop {
+        match banco.executar_migracoes().await {
+            Ok(()) => break,
+            Err(erro) => {
+                tracing::error!(erro = %erro, "falha ao aplicar as migrações; tentando novamente");
+                tokio::time::sleep(INTERVALO_TENTATIVAS).await;
+            }
+        }
+    }
+    tracing::info!(versao = banco.versao_esperada(), "Migrações aplicadas");
+
+    // Seed só quando pedido (útil com DATABASE_URL=memoria); o normal é `blogctl seed`
+    if let Ok(nome) = std::env::var("SEED") {
+        let resultado = match seed::Fixture::carregar(&nome) {
+            Ok(fixture) => seed::aplicar(&banco.repositorios(), fixture).await,
+            Err(erro) => Err(erro),
+        };
+        match resultado {
+            Ok(resumo) => tracing::info!(fixture = nome, %resumo, "Seed aplicado"),
+            Err(erro) => tracing::error!(fixture = nome, erro = %erro, "falha ao aplicar o seed"),
+        }
+    }
+
+    prontidao.marcar_inicializada();
+    tracing::info!("Inicialização concluída");
+}
+
+fn imprimir_rotas() {
+    let rotas = rotas::todas_as_rotas();
+
     println!("📚 Rotas públicas:");
-    println!("   GET  /home");
-    println!("   GET  /health");
-    println!("   POST /api/auth/register");
-    println!("   POST /api/auth/login");
-    println!("   GET  /api/posts");
-    println!("   GET  /api/posts/{{id}}");
-    println!("   GET  /api/users");
+    for (caminho, rota) in rotas.iter().filter(|(_, rota)| rota.acesso == rotas::Acesso::Publica) {
+        println!("   {:<6} {caminho}", rota.metodo.as_str());
+    }
     println!("🔒 Rotas protegidas (requerem JWT):");
-    println!("   GET    /api/profile");
-    println!("   GET    /api/posts/my");
-    println!("   POST   /api/posts");
-    println!("   PUT    /api/posts/{{id}}");
-    println!("   DELETE /api/posts/{{id}}");
-    
-    axum::serve(listener, aplicacao).await?;
-    
-    Ok(())
+    for (caminho, rota) in rotas.iter().filter(|(_, rota)| rota.acesso == rotas::Acesso::Protegida) {
+        println!("   {:<6} {caminho}", rota.metodo.as_str());
+    }
+    println!("🛡️  Rotas de administração (requerem JWT de administrador):");
+    for (caminho, rota) in rotas.iter().filter(|(_, rota)| rota.acesso == rotas::Acesso::Admin) {
+        println!("   {:<6} {caminho}", rota.metodo.as_str());
+    }
+    println!(
+        "⚠️  Rotas sem versão ({}/...) estão depreciadas e respondem com o cabeçalho Deprecation",
+        rotas::PREFIXO_LEGADO
+    );
 }
@@ -0,0 +1,283 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header::CONTENT_TYPE, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use metrics::{counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram, Unit};
+use metrics_exporter_prometheus::{BuildError, Matcher, PrometheusBuilder, PrometheusHandle};
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+use crate::db::Banco;
+
+// Os nomes seguem a convenção do Prometheus (inglês, unidade no sufixo),
+// já que são consumidos por painéis e alertas fora deste projeto.
+const REQUISICOES: &str = "http_requests_total";
+const DURACAO_REQUISICAO: &str = "http_request_duration_seconds";
+const REQUISICOES_EM_ANDAMENTO: &str = "http_requests_in_flight";
+const CONEXOES_POOL: &str = "db_pool_connections";
+const CONEXOES_OCIOSAS_POOL: &str = "db_pool_idle_connections";
+const MAXIMO_CONEXOES_POOL: &str = "db_pool_max_connections";
+const ESPERA_POOL: &str = "db_pool_acquire_duration_seconds";
+const FALHAS_POOL: &str = "db_pool_acquire_errors_total";
+const DURACAO_BCRYPT: &str = "bcrypt_duration_seconds";
+const LOGINS: &str = "auth_logins_total";
+const FALHAS_JWT: &str = "auth_jwt_validation_failures_total";
+const CONSULTAS_CACHE: &str = "cache_requests_total";
+const CONEXOES_NOTIFICACOES: &str = "notifications_websocket_connections";
+const CLIENTES_LENTOS_NOTIFICACOES: &str = "notifications_slow_client_disconnects_total";
+const ENTREGAS_WEBHOOKS: &str = "webhook_delivery_attempts_total";
+const EXECUCOES_TAREFAS: &str = "jobs_executed_total";
+const DURACAO_TAREFAS: &str = "job_duration_seconds";
+
+// Rótulo de requisições que não casaram com nenhuma rota: usar a URI
+// crua deixaria qualquer varredura criar séries novas sem limite
+const ROTA_DESCONHECIDA: &str = "unmatched";
+
+// Idem para métodos fora do padrão HTTP, que o cliente pode inventar à vontade
+const METODO_DESCONHECIDO: &str = "OTHER";
+
+const BALDES_SEGUNDOS: &[f64] = &[
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+const INTERVALO_AMOSTRAGEM_POOL: Duration = Duration::from_secs(5);
+
+static EXPORTADOR: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Instala o recorder global do Prometheus; sem ele as métricas são descartadas.
+pub fn inicializar() -> Result<(), BuildError> {
+    let exportador = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), BALDES_SEGUNDOS)?
+        .install_recorder()?;
+    descrever();
+
+    let _ = EXPORTADOR.set(exportador);
+    Ok(())
+}
+
+fn descrever() {
+    describe_counter!(REQUISICOES, "Requisições HTTP atendidas, por método, rota e status");
+    describe_histogram!(DURACAO_REQUISICAO, Unit::Seconds, "Latência das requisições HTTP, por método e rota");
+    describe_gauge!(REQUISICOES_EM_ANDAMENTO, "Requisições HTTP sendo atendidas");
+    describe_gauge!(CONEXOES_POOL, "Conexões abertas no pool do banco");
+    describe_gauge!(CONEXOES_OCIOSAS_POOL, "Conexões ociosas no pool do banco");
+    describe_gauge!(MAXIMO_CONEXOES_POOL, "Limite de conexões do pool do banco");
+    describe_histogram!(ESPERA_POOL, Unit::Seconds, "Tempo de espera para obter uma conexão do pool (amostrado)");
+    describe_counter!(FALHAS_POOL, "Falhas ao obter uma conexão do pool");
+    describe_histogram!(DURACAO_BCRYPT, Unit::Seconds, "Duração das operações de bcrypt, por operação");
+    describe_counter!(LOGINS, "Tentativas de login, por resultado");
+    describe_counter!(FALHAS_JWT, "Tokens rejeitados pelo middleware de autenticação, por motivo");
+    describe_counter!(CONSULTAS_CACHE, "Consultas ao cache, por namespace e resultado (hit, miss, coalesced)");
+    describe_gauge!(CONEXOES_NOTIFICACOES, "Conexões abertas no WebSocket de notificações");
+    describe_counter!(CLIENTES_LENTOS_NOTIFICACOES, "Conexões de notificações encerradas por não acompanharem a entrega");
+    describe_counter!(ENTREGAS_WEBHOOKS, "Envios de webhooks, pela situação em que deixam a entrega (succeeded, pending, failed)");
+    describe_counter!(EXECUCOES_TAREFAS, "Execuções de tarefas em segundo plano, por tipo e situação em que deixam a tarefa (succeeded, pending, dead)");
+    describe_histogram!(DURACAO_TAREFAS, Unit::Seconds, "Duração das execuções de tarefas em segundo plano, por tipo");
+}
+
+/// `GET /metrics`: métricas no formato de texto do Prometheus.
+pub async fn exportar(State(banco): State<Banco>) -> Response {
+    let Some(exportador) = EXPORTADOR.get() else {
+        return StatusCode::SERVICE_UNAVAILABLE.into_response();
+    };
+
+    registrar_pool(&banco);
+    ([(CONTENT_TYPE, "text/plain; version=0.0.4")], exportador.render()).into_response()
+}
+
+/// Conta e cronometra cada requisição pela rota (`/api/v1/posts/{id}`), não pela URI.
+pub async fn middleware_metricas(requisicao: Request, proximo: Next) -> Response {
+    let metodo = rotulo_metodo(requisicao.method());
+    let rota = requisicao
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(ROTA_DESCONHECIDA, MatchedPath::as_str)
+        .to_owned();
+
+    let _em_andamento = EmAndamento::iniciar();
+    let inicio = Instant::now();
+    let resposta = proximo.run(requisicao).await;
+
+    let status = resposta.status().as_u16().to_string();
+    histogram!(DURACAO_REQUISICAO, "method" => metodo, "route" => rota.clone()).record(inicio.elapsed());
+    counter!(REQUISICOES, "method" => metodo, "route" => rota, "status" => status).increment(1);
+
+    resposta
+}
+
+fn rotulo_metodo(metodo: &Method) -> &'static str {
+    match *metodo {
+        Method::GET => "GET",
+        Method::HEAD => "HEAD",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::PATCH => "PATCH",
+        Method::DELETE => "DELETE",
+        Method::OPTIONS => "OPTIONS",
+        Method::CONNECT => "CONNECT",
+        Method::TRACE => "TRACE",
+        _ => METODO_DESCONHECIDO,
+    }
+}
+
+/// Decrementa o gauge mesmo se a requisição for cancelada no meio.
+struct EmAndamento;
+
+impl EmAndamento {
+    fn iniciar() -> Self {
+        gauge!(REQUISICOES_EM_ANDAMENTO).increment(1.0);
+        EmAndamento
+    }
+}
+
+impl Drop for EmAndamento {
+    fn drop(&mut self) {
+        gauge!(REQUISICOES_EM_ANDAMENTO).decrement(1.0);
+    }
+}
+
+fn registrar_pool(banco: &Banco) {
+    let Some(pool) = banco.estatisticas_pool() else {
+        return;
+    };
+
+    gauge!(CONEXOES_POOL).set(pool.tamanho);
+    gauge!(CONEXOES_OCIOSAS_POOL).set(pool.ociosas as f64);
+    gauge!(MAXIMO_CONEXOES_POOL).set(pool.maximo);
+}
+
+/// Tarefa de fundo: o sqlx não expõe o tempo de espera por conexão, então ele é
+/// amostrado obtendo uma conexão do pool a cada `INTERVALO_AMOSTRAGEM_POOL`.
+/// Também executa a manutenção periódica dos histogramas do exportador.
+pub async fn monitorar_pool(banco: Banco) {
+    let mut intervalo = tokio::time::interval(INTERVALO_AMOSTRAGEM_POOL);
+
+    loop {
+        intervalo.tick().await;
+
+        // Com os repositórios em memória não há pool para amostrar
+        if banco.estatisticas_pool().is_some() {
+            let inicio = Instant::now();
+            match banco.obter_conexao().await {
+                Ok(()) => histogram!(ESPERA_POOL).record(inicio.elapsed()),
+                Err(erro) => {
+                    counter!(FALHAS_POOL).increment(1);
+                    tracing::warn!(erro = %erro, "não foi possível obter uma conexão do pool");
+                }
+            }
+            registrar_pool(&banco);
+        }
+
+        if let Some(exportador) = EXPORTADOR.get() {
+            exportador.run_upkeep();
+        }
+    }
+}
+
+/// Cronometra uma operação de bcrypt (`hash` ou `verify`).
+pub fn medir_bcrypt<T>(operacao: &'static str, executar: impl FnOnce() -> T) -> T {
+    let inicio = Instant::now();
+    let resultado = executar();
+    histogram!(DURACAO_BCRYPT, "operation" => operacao).record(inicio.elapsed());
+    resultado
+}
+
+pub fn registrar_login(sucesso: bool) {
+    let resultado = if sucesso { "success" } else { "failure" };
+    counter!(LOGINS, "result" => resultado).increment(1);
+}
+
+pub fn registrar_falha_jwt(motivo: &'static str) {
+    counter!(FALHAS_JWT, "reason" => motivo).increment(1);
+}
+
+/// `coalesced` conta as requisições que esperaram o carregamento de outra.
+pub fn registrar_cache(namespace: &'static str, resultado: &'static str) {
+    counter!(CONSULTAS_CACHE, "namespace" => namespace, "result" => resultado).increment(1);
+}
+
+pub fn registrar_conexao_notificacoes(aberta: bool) {
+    if aberta {
+        gauge!(CONEXOES_NOTIFICACOES).increment(1.0);
+    } else {
+        gauge!(CONEXOES_NOTIFICACOES).decrement(1.0);
+    }
+}
+
+pub fn registrar_cliente_lento_notificacoes() {
+    counter!(CLIENTES_LENTOS_NOTIFICACOES).increment(1);
+}
+
+/// `pending` conta as falhas que ainda serão tentadas de novo.
+pub fn registrar_entrega_webhook(situacao: &'static str) {
+    counter!(ENTREGAS_WEBHOOKS, "result" => situacao).increment(1);
+}
+
+/// Como nas entregas de webhooks, `pending` conta as falhas que ainda serão tentadas de novo.
+pub fn registrar_tarefa(tipo: &'static str, situacao: &'static str, duracao: Duration) {
+    counter!(EXECUCOES_TAREFAS, "kind" => tipo, "result" => situacao).increment(1);
+    histogram!(DURACAO_TAREFAS, "kind" => tipo).record(duracao);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware::from_fn, routing::get, Router};
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn requisicoes_sao_agrupadas_pela_rota() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let exportador = recorder.handle();
+        let _guarda = metrics::set_default_local_recorder(&recorder);
+
+        let api = Router::new().route("/posts/{id}", get(|| async { "ok" }));
+        let aplicacao = Router::new()
+            .nest("/api/v1", api)
+            .layer(from_fn(middleware_metricas));
+
+        for uri in ["/api/v1/posts/1", "/api/v1/posts/2", "/nao-existe"] {
+            let requisicao = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            aplicacao.clone().oneshot(requisicao).await.unwrap();
+        }
+
+        let texto = exportador.render();
+        assert!(
+            texto.contains(r#"http_requests_total{method="GET",route="/api/v1/posts/{id}",status="200"} 2"#),
+            "{texto}"
+        );
+        assert!(
+            texto.contains(r#"http_requests_total{method="GET",route="unmatched",status="404"} 1"#),
+            "{texto}"
+        );
+        assert!(texto.contains("http_requests_in_flight 0"), "{texto}");
+    }
+
+    #[tokio::test]
+    async fn metodos_fora_do_padrao_sao_agrupados() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let exportador = recorder.handle();
+        let _guarda = metrics::set_default_local_recorder(&recorder);
+
+        let aplicacao = Router::new()
+            .route("/posts", get(|| async { "ok" }))
+            .layer(from_fn(middleware_metricas));
+
+        for metodo in ["PURGE", "X-VARREDURA-1", "X-VARREDURA-2"] {
+            let requisicao = Request::builder().method(metodo).uri("/posts").body(Body::empty()).unwrap();
+            aplicacao.clone().oneshot(requisicao).await.unwrap();
+        }
+
+        let texto = exportador.render();
+        assert!(
+            texto.contains(r#"http_requests_total{method="OTHER",route="/posts",status="405"} 3"#),
+            "{texto}"
+        );
+        assert!(!texto.contains("PURGE") && !texto.contains("VARREDURA"), "{texto}");
+    }
+}
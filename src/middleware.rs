@@ -1,29 +1,266 @@
 use axum::{
-    extract::Request,
-    http::{header::AUTHORIZATION, StatusCode},
+    extract::{Extension, Request, State},
+    http::{
+        header::{AUTHORIZATION, LINK, SEC_WEBSOCKET_PROTOCOL},
+        HeaderMap, HeaderName, HeaderValue, StatusCode,
+    },
     middleware::Next,
     response::Response,
 };
-use crate::auth::verificar_token;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use crate::{
+    auth::{verificar_token, Credenciais},
+    erros::erro_interno,
+    estado::Estado,
+    metricas::registrar_falha_jwt,
+};
+
+/// Subprotocolo que leva o token no WebSocket; o servidor o devolve ao aceitar a conexão.
+pub const PROTOCOLO_WEBSOCKET_BEARER: &str = "bearer";
 
-pub async fn middleware_auth(mut requisicao: Request, proximo: Next) -> Result<Response, StatusCode> {
-    let cabecalho_auth = requisicao
+// Evita uma escrita no banco a cada requisição autenticada
+const INTERVALO_ATUALIZACAO_SESSAO_SEGUNDOS: i64 = 60;
+
+pub async fn middleware_auth(
+    State(estado): State<Estado>,
+    mut requisicao: Request,
+    proximo: Next,
+) -> Result<Response, StatusCode> {
+    let token = match requisicao
         .headers()
         .get(AUTHORIZATION)
         .and_then(|cabecalho| cabecalho.to_str().ok())
-        .ok_or(StatusCode::UNAUTHORIZED)?;
-
-    if !cabecalho_auth.starts_with("Bearer ") {
-        return Err(StatusCode::UNAUTHORIZED);
+    {
+        Some(cabecalho_auth) => cabecalho_auth.strip_prefix("Bearer ").ok_or_else(|| {
+            registrar_falha_jwt("malformed_header");
+            StatusCode::UNAUTHORIZED
+        })?,
+        None => token_websocket(requisicao.headers()).ok_or_else(|| {
+            registrar_falha_jwt("missing_token");
+            StatusCode::UNAUTHORIZED
+        })?,
     }
+    .to_string();
 
-    let token = &cabecalho_auth[7..];
-    let credenciais = verificar_token(token).map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let credenciais = validar_token(&estado, &token).await?;
 
     // Adicionar credenciais à extensão da requisição para uso nos handlers
     requisicao.extensions_mut().insert(credenciais.id_usuario);
     requisicao.extensions_mut().insert(credenciais.email.clone());
+    requisicao.extensions_mut().insert(credenciais.id_sessao);
 
     Ok(proximo.run(requisicao).await)
 }
 
+/// Para rotas públicas que também aceitam um token, como o GraphQL: sem
+/// `Authorization` a requisição é anônima (`None`); com ele, o token passa
+/// pelas mesmas verificações do `middleware_auth`.
+pub async fn autenticacao_opcional(estado: &Estado, cabecalhos: &HeaderMap) -> Result<Option<Credenciais>, StatusCode> {
+    let Some(cabecalho_auth) = cabecalhos.get(AUTHORIZATION) else {
+        return Ok(None);
+    };
+
+    let token = cabecalho_auth
+        .to_str()
+        .ok()
+        .and_then(|cabecalho_auth| cabecalho_auth.strip_prefix("Bearer "))
+        .ok_or_else(|| {
+            registrar_falha_jwt("malformed_header");
+            StatusCode::UNAUTHORIZED
+        })?;
+
+    validar_token(estado, token).await.map(Some)
+}
+
+/// Assinatura, validade e sessão (existente e não revogada) do token.
+async fn validar_token(estado: &Estado, token: &str) -> Result<Credenciais, StatusCode> {
+    let credenciais = verificar_token(&estado.configuracao.segredo_jwt, token).map_err(|erro| {
+        tracing::debug!(erro = %erro, "token rejeitado");
+        registrar_falha_jwt(motivo_falha_jwt(&erro));
+        StatusCode::UNAUTHORIZED
+    })?;
+
+    // A sessão precisa existir e não pode ter sido revogada
+    let Some(visto_em) = estado
+        .repositorios
+        .sessoes
+        .visto_em(credenciais.id_sessao, credenciais.id_usuario)
+        .await
+        .map_err(erro_interno)?
+    else {
+        registrar_falha_jwt("session_revoked");
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    if chrono::Utc::now() - visto_em > chrono::Duration::seconds(INTERVALO_ATUALIZACAO_SESSAO_SEGUNDOS) {
+        estado
+            .repositorios
+            .sessoes
+            .registrar_acesso(credenciais.id_sessao)
+            .await
+            .map_err(erro_interno)?;
+    }
+
+    Ok(credenciais)
+}
+
+/// O navegador não deixa o WebSocket enviar `Authorization`; o token vem como
+/// subprotocolo (`Sec-WebSocket-Protocol: bearer, <token>`). Na URL ele
+/// acabaria nos logs de acesso.
+fn token_websocket(cabecalhos: &HeaderMap) -> Option<&str> {
+    let mut protocolos = cabecalhos.get(SEC_WEBSOCKET_PROTOCOL)?.to_str().ok()?.split(',').map(str::trim);
+    match (protocolos.next(), protocolos.next()) {
+        (Some(PROTOCOLO_WEBSOCKET_BEARER), Some(token)) if !token.is_empty() => Some(token),
+        _ => None,
+    }
+}
+
+/// Depois do `middleware_auth`: só deixa passar administradores com a conta ativa.
+pub async fn middleware_admin(
+    State(estado): State<Estado>,
+    Extension(id_usuario): Extension<i32>,
+    requisicao: Request,
+    proximo: Next,
+) -> Result<Response, StatusCode> {
+    let usuario = estado
+        .repositorios
+        .usuarios
+        .buscar_por_id(id_usuario)
+        .await
+        .map_err(erro_interno)?;
+
+    match usuario {
+        Some(usuario) if usuario.admin && usuario.desativado_em.is_none() => Ok(proximo.run(requisicao).await),
+        _ => Err(StatusCode::FORBIDDEN),
+    }
+}
+
+fn motivo_falha_jwt(erro: &jsonwebtoken::errors::Error) -> &'static str {
+    use jsonwebtoken::errors::ErrorKind;
+
+    match erro.kind() {
+        ErrorKind::ExpiredSignature => "expired",
+        ErrorKind::InvalidSignature => "invalid_signature",
+        ErrorKind::ImmatureSignature => "not_yet_valid",
+        _ => "invalid_token",
+    }
+}
+
+// Formato do contrato legado (rotas sem versão e campos em português):
+// depreciado em 2026-10-18, removido após 2027-04-18
+const DEPRECIACAO: &str = "@1792281600";
+const SUNSET: &str = "Sun, 18 Apr 2027 00:00:00 GMT";
+const SUCESSORA: &str = "</api/v1>; rel=\"successor-version\"";
+
+/// Marca, durante uma requisição, que o cliente usou o contrato legado da API.
+#[derive(Clone, Default)]
+pub struct UsoLegado(Arc<AtomicBool>);
+
+impl UsoLegado {
+    pub fn marcar(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    fn marcado(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Adiciona os cabeçalhos `Deprecation`, `Sunset` e `Link` às respostas
+/// de requisições que usaram rotas ou campos legados.
+pub async fn middleware_depreciacao(mut requisicao: Request, proximo: Next) -> Response {
+    let uso_legado = UsoLegado::default();
+    requisicao.extensions_mut().insert(uso_legado.clone());
+
+    let mut resposta = proximo.run(requisicao).await;
+
+    if uso_legado.marcado() {
+        let cabecalhos = resposta.headers_mut();
+        cabecalhos.insert(HeaderName::from_static("deprecation"), HeaderValue::from_static(DEPRECIACAO));
+        cabecalhos.insert(HeaderName::from_static("sunset"), HeaderValue::from_static(SUNSET));
+        cabecalhos.insert(LINK, HeaderValue::from_static(SUCESSORA));
+    }
+
+    resposta
+}
+
+/// Aplicado às rotas sem versão (`/api/...`), mantidas apenas por compatibilidade.
+pub async fn marcar_rota_legada(requisicao: Request, proximo: Next) -> Response {
+    if let Some(uso_legado) = requisicao.extensions().get::<UsoLegado>() {
+        uso_legado.marcar();
+    }
+
+    proximo.run(requisicao).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{models::CriarPostagemRequisicao, validacao::JsonValidado};
+    use axum::{body::Body, middleware::from_fn, routing::post, Router};
+    use tower::ServiceExt;
+
+    async fn eco(JsonValidado(requisicao): JsonValidado<CriarPostagemRequisicao>) -> String {
+        requisicao.titulo
+    }
+
+    fn aplicacao() -> Router {
+        let api = Router::new().route("/posts", post(eco));
+
+        Router::new()
+            .nest("/api/v1", api.clone())
+            .nest("/api", api.layer(from_fn(marcar_rota_legada)))
+            .layer(from_fn(middleware_depreciacao))
+    }
+
+    async fn enviar(caminho: &str, corpo: serde_json::Value) -> Response {
+        let requisicao = Request::post(caminho)
+            .header("content-type", "application/json")
+            .body(Body::from(corpo.to_string()))
+            .unwrap();
+
+        aplicacao().oneshot(requisicao).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn contrato_v1_nao_e_depreciado() {
+        let resposta = enviar("/api/v1/posts", serde_json::json!({ "title": "A", "content": "B" })).await;
+
+        assert_eq!(resposta.status(), StatusCode::OK);
+        assert!(resposta.headers().get("deprecation").is_none());
+    }
+
+    #[tokio::test]
+    async fn campos_legados_sao_aceitos_com_deprecation() {
+        let resposta = enviar("/api/v1/posts", serde_json::json!({ "titulo": "A", "conteudo": "B" })).await;
+
+        assert_eq!(resposta.status(), StatusCode::OK);
+        assert_eq!(resposta.headers()["deprecation"], DEPRECIACAO);
+        assert_eq!(resposta.headers()["sunset"], SUNSET);
+        assert_eq!(resposta.headers()[LINK], SUCESSORA);
+    }
+
+    #[tokio::test]
+    async fn rota_sem_versao_e_depreciada() {
+        let resposta = enviar("/api/posts", serde_json::json!({ "title": "A", "content": "B" })).await;
+
+        assert_eq!(resposta.status(), StatusCode::OK);
+        assert_eq!(resposta.headers()["deprecation"], DEPRECIACAO);
+    }
+
+    #[tokio::test]
+    async fn erros_de_validacao_usam_nomes_em_ingles() {
+        use http_body_util::BodyExt;
+
+        let resposta = enviar("/api/v1/posts", serde_json::json!({ "title": "", "content": "" })).await;
+        assert_eq!(resposta.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let corpo = resposta.into_body().collect().await.unwrap().to_bytes();
+        let corpo: serde_json::Value = serde_json::from_slice(&corpo).unwrap();
+        assert!(corpo["fields"]["title"].is_array());
+        assert!(corpo["fields"]["content"].is_array());
+    }
+}
@@ -1,7 +1,22 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+use crate::{
+    erros::ErrosValidacao,
+    politica_senha::politica,
+    validacao::{normalizar_email, Regra, Validar},
+    webhooks::eventos,
+};
+
+pub const TAMANHO_MAXIMO_NOME_USUARIO: usize = 32;
+pub const TAMANHO_MAXIMO_EMAIL: usize = 254;
+pub const TAMANHO_MAXIMO_TITULO: usize = 255;
+pub const TAMANHO_MAXIMO_CONTEUDO: usize = 50_000;
+pub const TAMANHO_MAXIMO_URL: usize = 2048;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Usuario {
     pub id: i32,
     #[sqlx(rename = "username")]
@@ -11,13 +26,25 @@ pub struct Usuario {
     pub hash_senha: String,
     #[sqlx(rename = "created_at")]
     pub criado_em: chrono::DateTime<chrono::Utc>,
+    #[sqlx(rename = "is_admin")]
+    pub admin: bool,
+    /// Contas desativadas não fazem login e têm as sessões revogadas
+    #[sqlx(rename = "disabled_at")]
+    pub desativado_em: Option<chrono::DateTime<chrono::Utc>>,
+    /// Exclusão pedida pelo usuário: a conta é anonimizada nesta data
+    #[sqlx(rename = "deletion_scheduled_at")]
+    pub exclusao_agendada_em: Option<chrono::DateTime<chrono::Utc>>,
+    #[sqlx(rename = "anonymized_at")]
+    pub anonimizado_em: Option<chrono::DateTime<chrono::Utc>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct RespostaUsuario {
     pub id: i32,
+    #[serde(rename = "username")]
     pub nome_usuario: String,
     pub email: String,
+    #[serde(rename = "created_at")]
     pub criado_em: chrono::DateTime<chrono::Utc>,
 }
 
@@ -32,26 +59,112 @@ impl From<Usuario> for RespostaUsuario {
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// O perfil visto pelo próprio usuário.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RespostaPerfil {
+    #[serde(flatten)]
+    pub usuario: RespostaUsuario,
+    /// Presente quando a exclusão da conta foi pedida e ainda pode ser cancelada
+    #[serde(rename = "deletion_scheduled_at", skip_serializing_if = "Option::is_none")]
+    pub exclusao_agendada_em: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<Usuario> for RespostaPerfil {
+    fn from(usuario: Usuario) -> Self {
+        RespostaPerfil {
+            exclusao_agendada_em: usuario.exclusao_agendada_em,
+            usuario: RespostaUsuario::from(usuario),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ExcluirContaRequisicao {
+    /// A senha atual, para confirmar a exclusão
+    #[serde(rename = "password")]
+    pub senha: String,
+}
+
+impl Validar for ExcluirContaRequisicao {
+    fn validar(&self, erros: &mut ErrosValidacao) {
+        erros.campo("password", &self.senha, &[Regra::Obrigatorio]);
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RespostaExclusaoConta {
+    /// Data em que a conta será anonimizada; até lá, DELETE /profile/deletion cancela o pedido
+    #[serde(rename = "deletion_scheduled_at")]
+    pub exclusao_agendada_em: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct CriarUsuarioRequisicao {
+    #[serde(rename = "username", alias = "nome_usuario")]
     pub nome_usuario: String,
     pub email: String,
+    #[serde(rename = "password", alias = "senha")]
     pub senha: String,
 }
 
-#[derive(Debug, Deserialize)]
+impl Validar for CriarUsuarioRequisicao {
+    const CAMPOS_LEGADOS: &'static [&'static str] = &["nome_usuario", "senha"];
+
+    fn normalizar(&mut self) {
+        self.nome_usuario = self.nome_usuario.trim().to_string();
+        self.email = normalizar_email(&self.email);
+    }
+
+    fn validar(&self, erros: &mut ErrosValidacao) {
+        erros.campo("username", &self.nome_usuario, &[
+            Regra::Obrigatorio,
+            Regra::TamanhoMinimo(3),
+            Regra::TamanhoMaximo(TAMANHO_MAXIMO_NOME_USUARIO),
+            Regra::SemEspacos,
+            Regra::Alfanumerico("_.-"),
+        ]);
+        erros.campo("email", &self.email, &[
+            Regra::Obrigatorio,
+            Regra::TamanhoMaximo(TAMANHO_MAXIMO_EMAIL),
+            Regra::Email,
+        ]);
+    }
+
+    async fn validar_assincrono(&self, erros: &mut ErrosValidacao) {
+        for violacao in politica().validar(&self.senha, &[&self.nome_usuario, &self.email]).await {
+            erros.adicionar("password", violacao);
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct LoginRequisicao {
     pub email: String,
+    #[serde(rename = "password", alias = "senha")]
     pub senha: String,
 }
 
-#[derive(Debug, Serialize)]
+impl Validar for LoginRequisicao {
+    const CAMPOS_LEGADOS: &'static [&'static str] = &["senha"];
+
+    fn normalizar(&mut self) {
+        self.email = normalizar_email(&self.email);
+    }
+
+    fn validar(&self, erros: &mut ErrosValidacao) {
+        erros.campo("email", &self.email, &[Regra::Obrigatorio, Regra::TamanhoMaximo(TAMANHO_MAXIMO_EMAIL)]);
+        erros.campo("password", &self.senha, &[Regra::Obrigatorio]);
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct RespostaLogin {
     pub token: String,
+    #[serde(rename = "user")]
     pub usuario: RespostaUsuario,
 }
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Postagem {
     pub id: i32,
     #[sqlx(rename = "title")]
@@ -62,21 +175,45 @@ pub struct Postagem {
     pub id_usuario: i32,
     #[sqlx(rename = "created_at")]
     pub criado_em: chrono::DateTime<chrono::Utc>,
+    #[sqlx(rename = "updated_at")]
+    pub atualizado_em: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct CriarPostagemRequisicao {
+    #[serde(rename = "title", alias = "titulo")]
     pub titulo: String,
+    #[serde(rename = "content", alias = "conteudo")]
     pub conteudo: String,
 }
 
-#[derive(Debug, Serialize)]
+impl Validar for CriarPostagemRequisicao {
+    const CAMPOS_LEGADOS: &'static [&'static str] = &["titulo", "conteudo"];
+
+    fn normalizar(&mut self) {
+        self.titulo = self.titulo.trim().to_string();
+    }
+
+    fn validar(&self, erros: &mut ErrosValidacao) {
+        erros.campo("title", &self.titulo, &[Regra::Obrigatorio, Regra::TamanhoMaximo(TAMANHO_MAXIMO_TITULO)]);
+        erros.campo("content", &self.conteudo, &[Regra::Obrigatorio, Regra::TamanhoMaximo(TAMANHO_MAXIMO_CONTEUDO)]);
+    }
+}
+
+// Deserialize: guardada no cache no mesmo formato servido aos clientes
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct RespostaPostagem {
     pub id: i32,
+    #[serde(rename = "title")]
     pub titulo: String,
+    #[serde(rename = "content")]
     pub conteudo: String,
+    #[serde(rename = "user_id")]
     pub id_usuario: i32,
+    #[serde(rename = "created_at")]
     pub criado_em: chrono::DateTime<chrono::Utc>,
+    #[serde(rename = "updated_at")]
+    pub atualizado_em: chrono::DateTime<chrono::Utc>,
 }
 
 impl From<Postagem> for RespostaPostagem {
@@ -87,7 +224,584 @@ impl From<Postagem> for RespostaPostagem {
             conteudo: postagem.conteudo,
             id_usuario: postagem.id_usuario,
             criado_em: postagem.criado_em,
+            atualizado_em: postagem.atualizado_em,
         }
     }
 }
 
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Sessao {
+    pub id: Uuid,
+    #[sqlx(rename = "user_id")]
+    pub id_usuario: i32,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    #[sqlx(rename = "created_at")]
+    pub criado_em: chrono::DateTime<chrono::Utc>,
+    #[sqlx(rename = "last_seen_at")]
+    pub visto_em: chrono::DateTime<chrono::Utc>,
+    #[sqlx(rename = "revoked_at")]
+    pub revogada_em: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RespostaSessao {
+    pub id: Uuid,
+    #[serde(rename = "device")]
+    pub dispositivo: Option<String>,
+    pub ip: Option<String>,
+    #[serde(rename = "created_at")]
+    pub criado_em: chrono::DateTime<chrono::Utc>,
+    #[serde(rename = "last_seen_at")]
+    pub visto_em: chrono::DateTime<chrono::Utc>,
+    #[serde(rename = "current")]
+    pub atual: bool,
+}
+
+impl RespostaSessao {
+    pub fn de_sessao(sessao: Sessao, id_sessao_atual: Uuid) -> Self {
+        RespostaSessao {
+            atual: sessao.id == id_sessao_atual,
+            id: sessao.id,
+            dispositivo: sessao.user_agent,
+            ip: sessao.ip,
+            criado_em: sessao.criado_em,
+            visto_em: sessao.visto_em,
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct EventoAuditoria {
+    pub id: i64,
+    #[sqlx(rename = "actor_id")]
+    pub id_ator: Option<i32>,
+    #[sqlx(rename = "action")]
+    pub acao: String,
+    #[sqlx(rename = "target_type")]
+    pub tipo_alvo: Option<String>,
+    #[sqlx(rename = "target_id")]
+    pub id_alvo: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub diff: sqlx::types::Json<serde_json::Value>,
+    #[sqlx(rename = "created_at")]
+    pub criado_em: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RespostaEventoAuditoria {
+    pub id: i64,
+    #[serde(rename = "actor_id")]
+    pub id_ator: Option<i32>,
+    #[serde(rename = "action")]
+    pub acao: String,
+    #[serde(rename = "target_type")]
+    pub tipo_alvo: Option<String>,
+    #[serde(rename = "target_id")]
+    pub id_alvo: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    /// Campos alterados, como `{"campo": [antes, depois]}`, ou o contexto do evento
+    pub diff: serde_json::Value,
+    #[serde(rename = "created_at")]
+    pub criado_em: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<EventoAuditoria> for RespostaEventoAuditoria {
+    fn from(evento: EventoAuditoria) -> Self {
+        RespostaEventoAuditoria {
+            id: evento.id,
+            id_ator: evento.id_ator,
+            acao: evento.acao,
+            tipo_alvo: evento.tipo_alvo,
+            id_alvo: evento.id_alvo,
+            ip: evento.ip,
+            user_agent: evento.user_agent,
+            diff: evento.diff.0,
+            criado_em: evento.criado_em,
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct Notificacao {
+    pub id: i64,
+    #[sqlx(rename = "user_id")]
+    pub id_usuario: i32,
+    #[sqlx(rename = "kind")]
+    pub tipo: String,
+    #[sqlx(rename = "data")]
+    pub dados: sqlx::types::Json<serde_json::Value>,
+    #[sqlx(rename = "created_at")]
+    pub criado_em: chrono::DateTime<chrono::Utc>,
+    #[sqlx(rename = "read_at")]
+    pub lido_em: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RespostaNotificacao {
+    pub id: i64,
+    /// O que aconteceu, como `post.removed`
+    #[serde(rename = "kind")]
+    pub tipo: String,
+    /// Detalhes, conforme o `kind`
+    #[serde(rename = "data")]
+    pub dados: serde_json::Value,
+    #[serde(rename = "created_at")]
+    pub criado_em: chrono::DateTime<chrono::Utc>,
+    /// Vazio enquanto a notificação não for lida
+    #[serde(rename = "read_at")]
+    pub lido_em: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RespostaNotificacoesLidas {
+    /// Quantas notificações ainda não tinham sido lidas
+    #[serde(rename = "updated")]
+    pub marcadas: u64,
+}
+
+impl From<Notificacao> for RespostaNotificacao {
+    fn from(notificacao: Notificacao) -> Self {
+        RespostaNotificacao {
+            id: notificacao.id,
+            tipo: notificacao.tipo,
+            dados: notificacao.dados.0,
+            criado_em: notificacao.criado_em,
+            lido_em: notificacao.lido_em,
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct Webhook {
+    pub id: i32,
+    #[sqlx(rename = "user_id")]
+    pub id_usuario: i32,
+    pub url: String,
+    #[sqlx(rename = "events")]
+    pub eventos: sqlx::types::Json<Vec<String>>,
+    /// Chave do HMAC das entregas; só aparece para o usuário na criação
+    #[sqlx(rename = "secret")]
+    pub segredo: String,
+    #[sqlx(rename = "created_at")]
+    pub criado_em: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CriarWebhookRequisicao {
+    /// Endereço http(s) que recebe os eventos por POST
+    pub url: String,
+    /// Eventos assinados: post.created, post.updated, post.deleted e (só administradores) user.registered
+    #[serde(rename = "events")]
+    pub eventos: Vec<String>,
+}
+
+impl Validar for CriarWebhookRequisicao {
+    fn normalizar(&mut self) {
+        self.url = self.url.trim().to_string();
+        self.eventos.sort();
+        self.eventos.dedup();
+    }
+
+    fn validar(&self, erros: &mut ErrosValidacao) {
+        erros.campo("url", &self.url, &[Regra::Obrigatorio, Regra::TamanhoMaximo(TAMANHO_MAXIMO_URL)]);
+        if !self.url.is_empty() {
+            match reqwest::Url::parse(&self.url) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") && url.host().is_some() => {}
+                _ => erros.adicionar("url", "Use uma URL http ou https"),
+            }
+        }
+
+        if self.eventos.is_empty() {
+            erros.adicionar("events", "Assine pelo menos um evento");
+        }
+        for evento in &self.eventos {
+            if !eventos::TODOS.contains(&evento.as_str()) {
+                erros.adicionar("events", format!("Evento desconhecido: {evento}"));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RespostaWebhook {
+    pub id: i32,
+    pub url: String,
+    #[serde(rename = "events")]
+    pub eventos: Vec<String>,
+    #[serde(rename = "created_at")]
+    pub criado_em: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<Webhook> for RespostaWebhook {
+    fn from(webhook: Webhook) -> Self {
+        RespostaWebhook {
+            id: webhook.id,
+            url: webhook.url,
+            eventos: webhook.eventos.0,
+            criado_em: webhook.criado_em,
+        }
+    }
+}
+
+/// Resposta da criação: a única vez em que o segredo é exibido.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RespostaWebhookCriado {
+    #[serde(flatten)]
+    pub webhook: RespostaWebhook,
+    /// Chave do HMAC-SHA256 de `X-Webhook-Signature`; guarde-a, ela não é exibida de novo
+    #[serde(rename = "secret")]
+    pub segredo: String,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct EntregaWebhook {
+    pub id: i64,
+    #[sqlx(rename = "webhook_id")]
+    pub id_webhook: i32,
+    #[sqlx(rename = "event_id")]
+    pub id_evento: Uuid,
+    #[sqlx(rename = "event")]
+    pub evento: String,
+    pub payload: sqlx::types::Json<serde_json::Value>,
+    /// `pending`, `succeeded` ou `failed` (veja `webhooks::situacoes`)
+    pub status: String,
+    #[sqlx(rename = "attempts")]
+    pub tentativas: i32,
+    #[sqlx(rename = "next_attempt_at")]
+    pub proxima_tentativa_em: chrono::DateTime<chrono::Utc>,
+    #[sqlx(rename = "last_status_code")]
+    pub ultimo_status: Option<i32>,
+    #[sqlx(rename = "last_error")]
+    pub ultimo_erro: Option<String>,
+    #[sqlx(rename = "created_at")]
+    pub criado_em: chrono::DateTime<chrono::Utc>,
+    #[sqlx(rename = "delivered_at")]
+    pub entregue_em: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Entrega reservada para envio, com o destino e a chave da assinatura.
+#[derive(Debug, Clone, FromRow)]
+pub struct EntregaPendente {
+    #[sqlx(flatten)]
+    pub entrega: EntregaWebhook,
+    pub url: String,
+    #[sqlx(rename = "secret")]
+    pub segredo: String,
+    /// O dono do webhook é administrador e pode apontar para a rede interna
+    #[sqlx(rename = "is_admin")]
+    pub dono_admin: bool,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct TentativaEntrega {
+    pub id: i64,
+    #[sqlx(rename = "delivery_id")]
+    pub id_entrega: i64,
+    #[sqlx(rename = "attempted_at")]
+    pub tentada_em: chrono::DateTime<chrono::Utc>,
+    #[sqlx(rename = "status_code")]
+    pub codigo_status: Option<i32>,
+    #[sqlx(rename = "error")]
+    pub erro: Option<String>,
+    #[sqlx(rename = "duration_ms")]
+    pub duracao_ms: i32,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RespostaEntregaWebhook {
+    pub id: i64,
+    /// O mesmo em todas as entregas do evento, inclusive as reenviadas (`X-Webhook-Id`)
+    #[serde(rename = "event_id")]
+    pub id_evento: Uuid,
+    #[serde(rename = "event")]
+    pub evento: String,
+    /// `pending` (aguardando ou entre tentativas), `succeeded` ou `failed` (tentativas esgotadas)
+    pub status: String,
+    #[serde(rename = "attempts")]
+    pub tentativas: i32,
+    /// Próximo envio, enquanto `pending`
+    #[serde(rename = "next_attempt_at")]
+    pub proxima_tentativa_em: Option<chrono::DateTime<chrono::Utc>>,
+    /// Status HTTP da última resposta do endpoint
+    #[serde(rename = "last_status_code")]
+    pub ultimo_status: Option<i32>,
+    #[serde(rename = "last_error")]
+    pub ultimo_erro: Option<String>,
+    #[serde(rename = "created_at")]
+    pub criado_em: chrono::DateTime<chrono::Utc>,
+    #[serde(rename = "delivered_at")]
+    pub entregue_em: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<EntregaWebhook> for RespostaEntregaWebhook {
+    fn from(entrega: EntregaWebhook) -> Self {
+        let pendente = entrega.status == crate::webhooks::situacoes::PENDENTE;
+        RespostaEntregaWebhook {
+            id: entrega.id,
+            id_evento: entrega.id_evento,
+            evento: entrega.evento,
+            status: entrega.status,
+            tentativas: entrega.tentativas,
+            proxima_tentativa_em: pendente.then_some(entrega.proxima_tentativa_em),
+            ultimo_status: entrega.ultimo_status,
+            ultimo_erro: entrega.ultimo_erro,
+            criado_em: entrega.criado_em,
+            entregue_em: entrega.entregue_em,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RespostaTentativaEntrega {
+    #[serde(rename = "attempted_at")]
+    pub tentada_em: chrono::DateTime<chrono::Utc>,
+    #[serde(rename = "status_code")]
+    pub codigo_status: Option<i32>,
+    #[serde(rename = "error")]
+    pub erro: Option<String>,
+    #[serde(rename = "duration_ms")]
+    pub duracao_ms: i32,
+}
+
+impl From<TentativaEntrega> for RespostaTentativaEntrega {
+    fn from(tentativa: TentativaEntrega) -> Self {
+        RespostaTentativaEntrega {
+            tentada_em: tentativa.tentada_em,
+            codigo_status: tentativa.codigo_status,
+            erro: tentativa.erro,
+            duracao_ms: tentativa.duracao_ms,
+        }
+    }
+}
+
+/// Uma entrega com o corpo enviado e o histórico de tentativas.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RespostaDetalheEntrega {
+    #[serde(flatten)]
+    pub entrega: RespostaEntregaWebhook,
+    pub payload: serde_json::Value,
+    #[serde(rename = "attempt_log")]
+    pub historico: Vec<RespostaTentativaEntrega>,
+}
+
+/// Uma tarefa da fila em segundo plano (veja `fila`).
+#[derive(Debug, Clone, FromRow)]
+pub struct Tarefa {
+    pub id: i64,
+    #[sqlx(rename = "kind")]
+    pub tipo: String,
+    pub payload: sqlx::types::Json<serde_json::Value>,
+    /// `pending`, `running`, `succeeded` ou `dead` (veja `fila::situacoes`)
+    pub status: String,
+    #[sqlx(rename = "attempts")]
+    pub tentativas: i32,
+    #[sqlx(rename = "max_attempts")]
+    pub maximo_tentativas: i32,
+    #[sqlx(rename = "run_at")]
+    pub executar_em: chrono::DateTime<chrono::Utc>,
+    #[sqlx(rename = "locked_until")]
+    pub reservada_ate: Option<chrono::DateTime<chrono::Utc>>,
+    #[sqlx(rename = "last_error")]
+    pub ultimo_erro: Option<String>,
+    #[sqlx(rename = "unique_key")]
+    pub chave: Option<String>,
+    #[sqlx(rename = "created_at")]
+    pub criado_em: chrono::DateTime<chrono::Utc>,
+    #[sqlx(rename = "updated_at")]
+    pub atualizado_em: chrono::DateTime<chrono::Utc>,
+    #[sqlx(rename = "finished_at")]
+    pub concluida_em: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RespostaTarefa {
+    pub id: i64,
+    #[serde(rename = "kind")]
+    pub tipo: String,
+    pub payload: serde_json::Value,
+    /// `pending` (aguardando ou entre tentativas), `running`, `succeeded` ou
+    /// `dead` (tentativas esgotadas ou erro definitivo)
+    pub status: String,
+    #[serde(rename = "attempts")]
+    pub tentativas: i32,
+    #[serde(rename = "max_attempts")]
+    pub maximo_tentativas: i32,
+    /// Próxima execução, enquanto `pending`
+    #[serde(rename = "run_at")]
+    pub executar_em: Option<chrono::DateTime<chrono::Utc>>,
+    /// Fim da reserva de quem a executa, enquanto `running`; vencida, outra instância a retoma
+    #[serde(rename = "locked_until")]
+    pub reservada_ate: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(rename = "last_error")]
+    pub ultimo_erro: Option<String>,
+    /// Tarefas agendadas: `{kind}@{horário previsto}`
+    #[serde(rename = "unique_key")]
+    pub chave: Option<String>,
+    #[serde(rename = "created_at")]
+    pub criado_em: chrono::DateTime<chrono::Utc>,
+    #[serde(rename = "updated_at")]
+    pub atualizado_em: chrono::DateTime<chrono::Utc>,
+    #[serde(rename = "finished_at")]
+    pub concluida_em: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<Tarefa> for RespostaTarefa {
+    fn from(tarefa: Tarefa) -> Self {
+        let pendente = tarefa.status == crate::fila::situacoes::PENDENTE;
+        let em_execucao = tarefa.status == crate::fila::situacoes::EM_EXECUCAO;
+        RespostaTarefa {
+            id: tarefa.id,
+            tipo: tarefa.tipo,
+            payload: tarefa.payload.0,
+            status: tarefa.status,
+            tentativas: tarefa.tentativas,
+            maximo_tentativas: tarefa.maximo_tentativas,
+            executar_em: pendente.then_some(tarefa.executar_em),
+            reservada_ate: tarefa.reservada_ate.filter(|_| em_execucao),
+            ultimo_erro: tarefa.ultimo_erro,
+            chave: tarefa.chave,
+            criado_em: tarefa.criado_em,
+            atualizado_em: tarefa.atualizado_em,
+            concluida_em: tarefa.concluida_em,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn data() -> chrono::DateTime<chrono::Utc> {
+        "2026-10-18T12:00:00Z".parse().unwrap()
+    }
+
+    #[test]
+    fn registro_aceita_nomes_em_ingles() {
+        let requisicao: CriarUsuarioRequisicao = serde_json::from_value(json!({
+            "username": "joao",
+            "email": "joao@example.com",
+            "password": "Joao#Rust2024",
+        }))
+        .unwrap();
+
+        assert_eq!(requisicao.nome_usuario, "joao");
+        assert_eq!(requisicao.senha, "Joao#Rust2024");
+    }
+
+    #[test]
+    fn registro_aceita_nomes_legados() {
+        let requisicao: CriarUsuarioRequisicao = serde_json::from_value(json!({
+            "nome_usuario": "joao",
+            "email": "joao@example.com",
+            "senha": "Joao#Rust2024",
+        }))
+        .unwrap();
+
+        assert_eq!(requisicao.nome_usuario, "joao");
+        assert_eq!(requisicao.senha, "Joao#Rust2024");
+    }
+
+    #[test]
+    fn login_aceita_os_dois_formatos() {
+        for corpo in [
+            json!({ "email": "joao@example.com", "password": "x" }),
+            json!({ "email": "joao@example.com", "senha": "x" }),
+        ] {
+            let requisicao: LoginRequisicao = serde_json::from_value(corpo).unwrap();
+            assert_eq!(requisicao.senha, "x");
+        }
+    }
+
+    #[test]
+    fn postagem_aceita_os_dois_formatos() {
+        for corpo in [
+            json!({ "title": "Título", "content": "Conteúdo" }),
+            json!({ "titulo": "Título", "conteudo": "Conteúdo" }),
+        ] {
+            let requisicao: CriarPostagemRequisicao = serde_json::from_value(corpo).unwrap();
+            assert_eq!(requisicao.titulo, "Título");
+            assert_eq!(requisicao.conteudo, "Conteúdo");
+        }
+    }
+
+    #[test]
+    fn resposta_de_login_usa_nomes_em_ingles() {
+        let resposta = RespostaLogin {
+            token: "token".to_string(),
+            usuario: RespostaUsuario {
+                id: 1,
+                nome_usuario: "joao".to_string(),
+                email: "joao@example.com".to_string(),
+                criado_em: data(),
+            },
+        };
+
+        assert_eq!(
+            serde_json::to_value(resposta).unwrap(),
+            json!({
+                "token": "token",
+                "user": {
+                    "id": 1,
+                    "username": "joao",
+                    "email": "joao@example.com",
+                    "created_at": "2026-10-18T12:00:00Z",
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn resposta_de_postagem_usa_nomes_em_ingles() {
+        let resposta = RespostaPostagem {
+            id: 7,
+            titulo: "Título".to_string(),
+            conteudo: "Conteúdo".to_string(),
+            id_usuario: 1,
+            criado_em: data(),
+            atualizado_em: data(),
+        };
+
+        assert_eq!(
+            serde_json::to_value(resposta).unwrap(),
+            json!({
+                "id": 7,
+                "title": "Título",
+                "content": "Conteúdo",
+                "user_id": 1,
+                "created_at": "2026-10-18T12:00:00Z",
+                "updated_at": "2026-10-18T12:00:00Z",
+            })
+        );
+    }
+
+    #[test]
+    fn resposta_de_sessao_usa_nomes_em_ingles() {
+        let id = Uuid::nil();
+        let sessao = Sessao {
+            id,
+            id_usuario: 1,
+            user_agent: Some("curl/8.0".to_string()),
+            ip: Some("127.0.0.1".to_string()),
+            criado_em: data(),
+            visto_em: data(),
+            revogada_em: None,
+        };
+
+        assert_eq!(
+            serde_json::to_value(RespostaSessao::de_sessao(sessao, id)).unwrap(),
+            json!({
+                "id": id,
+                "device": "curl/8.0",
+                "ip": "127.0.0.1",
+                "created_at": "2026-10-18T12:00:00Z",
+                "last_seen_at": "2026-10-18T12:00:00Z",
+                "current": true,
+            })
+        );
+    }
+}
@@ -0,0 +1,368 @@
+//! Notificações por usuário: gravadas em `notifications` (lidas e marcadas
+//! pela API) e entregues na hora a quem está conectado ao WebSocket
+//! `GET /api/v1/notifications/ws`.
+//!
+//! No PostgreSQL a entrega passa por `NOTIFY`, então uma notificação criada em
+//! qualquer processo (outra instância, o `blogctl`) chega às conexões de todas
+//! as instâncias. Nos demais bancos só as conexões da própria instância a recebem;
+//! as outras a encontram na listagem.
+//!
+//! Cada conexão tem uma fila limitada. Um cliente que não a esvazia a tempo é
+//! desconectado, em vez de acumular notificações na memória do servidor; ao
+//! reconectar, busca as que perdeu pela API.
+//!
+//! Mensagens do servidor no WebSocket (texto, JSON):
+//! - `{"type": "hello", "unread": 3}` ao conectar;
+//! - `{"type": "notification", "notification": {...}}` a cada notificação nova.
+
+use axum::extract::ws::{close_code, CloseFrame, Message, Utf8Bytes, WebSocket};
+use futures::{stream::SplitSink, SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::sync::{mpsc, mpsc::error::TrySendError, OnceCell};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::{
+    db::{self, Banco},
+    metricas,
+    models::{Notificacao, RespostaNotificacao},
+    repositorios::Repositorios,
+};
+
+/// Valores de `notifications.kind`.
+pub mod tipos {
+    /// Login de um IP e user agent que o usuário ainda não tinha usado
+    pub const NOVO_LOGIN: &str = "auth.new_login";
+    /// Um administrador removeu um post do usuário
+    pub const POSTAGEM_REMOVIDA: &str = "post.removed";
+}
+
+/// Notificações aguardando envio por conexão.
+const CAPACIDADE_FILA: usize = 64;
+const CANAL: &str = "notifications";
+/// Prazo para o cliente aceitar cada mensagem; quem não lê o socket é desconectado.
+const PRAZO_ENVIO: Duration = Duration::from_secs(10);
+// A cada intervalo: ping (mantém proxies abertos) e checagem da sessão revogada
+const INTERVALO_VERIFICACAO: Duration = Duration::from_secs(30);
+
+/// Filtros de `GET /notifications`. Mais novas primeiro; `before_id` pagina
+/// a partir do último id visto.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FiltroNotificacoes {
+    #[serde(rename = "unread", default)]
+    pub nao_lidas: bool,
+    #[serde(rename = "before_id")]
+    pub antes_do_id: Option<i64>,
+    #[serde(rename = "limit")]
+    pub limite: Option<i64>,
+}
+
+/// O que viaja no `NOTIFY`: só o id da notificação já gravada, que quem entrega
+/// lê do banco, porque o payload do `NOTIFY` é limitado a 8000 bytes.
+#[derive(Debug, Serialize, Deserialize)]
+struct Aviso {
+    id: i64,
+}
+
+/// Criação e entrega das notificações. Clonar é barato: os clones compartilham
+/// as conexões registradas.
+#[derive(Clone)]
+pub struct Notificacoes {
+    interno: Arc<Interno>,
+}
+
+struct Interno {
+    repositorios: Repositorios,
+    postgres: Option<PgPool>,
+    conexoes: Mutex<HashMap<i32, Vec<Conexao>>>,
+    proxima_conexao: AtomicU64,
+    escuta: OnceCell<()>,
+    encerramento: CancellationToken,
+}
+
+struct Conexao {
+    id: u64,
+    fila: mpsc::Sender<Arc<RespostaNotificacao>>,
+}
+
+impl Notificacoes {
+    pub fn new(banco: &Banco, repositorios: Repositorios) -> Self {
+        let postgres = match banco {
+            Banco::Postgres(pool) => Some(pool.clone()),
+            Banco::Sqlite(_) | Banco::Memoria(_) => None,
+        };
+
+        Notificacoes {
+            interno: Arc::new(Interno {
+                repositorios,
+                postgres,
+                conexoes: Mutex::new(HashMap::new()),
+                proxima_conexao: AtomicU64::new(1),
+                escuta: OnceCell::new(),
+                encerramento: CancellationToken::new(),
+            }),
+        }
+    }
+
+    /// Grava a notificação e a entrega às conexões abertas do usuário. Uma falha
+    /// na entrega só é registrada no log: a notificação já está na listagem.
+    pub async fn notificar(&self, id_usuario: i32, tipo: &str, dados: Value) -> Result<Notificacao, sqlx::Error> {
+        let notificacao = self
+            .interno
+            .repositorios
+            .notificacoes
+            .criar(id_usuario, tipo, &dados)
+            .await?;
+        let Some(pool) = &self.interno.postgres else {
+            self.interno.entregar(id_usuario, RespostaNotificacao::from(notificacao.clone()));
+            return Ok(notificacao);
+        };
+
+        let resultado = match serde_json::to_string(&Aviso { id: notificacao.id }) {
+            Ok(payload) => sqlx::query("SELECT pg_notify('notifications_' || current_schema(), $1)")
+                .bind(payload)
+                .execute(pool)
+                .await
+                .map(|_| ()),
+            Err(erro) => Err(sqlx::Error::Encode(Box::new(erro))),
+        };
+        if let Err(erro) = resultado {
+            tracing::warn!(erro = %erro, id_notificacao = notificacao.id, "falha ao publicar a notificação");
+        }
+
+        Ok(notificacao)
+    }
+
+    /// Registra uma conexão do usuário; ela deixa de receber quando a
+    /// [`Assinatura`] é descartada.
+    pub async fn assinar(&self, id_usuario: i32) -> Result<Assinatura, sqlx::Error> {
+        self.garantir_escuta().await?;
+
+        let (fila, receptor) = mpsc::channel(CAPACIDADE_FILA);
+        let id = self.interno.proxima_conexao.fetch_add(1, Ordering::Relaxed);
+        self.interno
+            .conexoes
+            .lock()
+            .unwrap()
+            .entry(id_usuario)
+            .or_default()
+            .push(Conexao { id, fila });
+        metricas::registrar_conexao_notificacoes(true);
+
+        Ok(Assinatura {
+            id,
+            id_usuario,
+            receptor,
+            interno: self.interno.clone(),
+        })
+    }
+
+    /// Avisa as conexões abertas para se encerrarem e termina a escuta do PostgreSQL.
+    pub fn encerrar(&self) {
+        self.interno.encerramento.cancel();
+    }
+
+    /// Atende um WebSocket já aceito até o cliente sair, ficar para trás, ter a
+    /// sessão revogada ou o servidor encerrar. O que o cliente envia é ignorado.
+    pub async fn atender(&self, socket: WebSocket, mut assinatura: Assinatura, id_sessao: Uuid, nao_lidas: i64) {
+        let (mut envio, mut recebimento) = socket.split();
+        let mut verificacao = tokio::time::interval_at(
+            tokio::time::Instant::now() + INTERVALO_VERIFICACAO,
+            INTERVALO_VERIFICACAO,
+        );
+
+        if !enviar(&mut envio, texto(json!({ "type": "hello", "unread": nao_lidas }))).await {
+            return;
+        }
+
+        let fechamento = loop {
+            tokio::select! {
+                _ = self.interno.encerramento.cancelled() => break Some((close_code::AWAY, "server shutting down")),
+                notificacao = assinatura.proxima() => match notificacao {
+                    Some(notificacao) => {
+                        let mensagem = json!({ "type": "notification", "notification": notificacao.as_ref() });
+                        if !enviar(&mut envio, texto(mensagem)).await {
+                            break None;
+                        }
+                    }
+                    None => break Some((close_code::AGAIN, "client too slow")),
+                },
+                mensagem = recebimento.next() => match mensagem {
+                    Some(Ok(Message::Close(_)) | Err(_)) | None => break None,
+                    Some(Ok(_)) => {}
+                },
+                _ = verificacao.tick() => {
+                    let ativa = self.interno.repositorios.sessoes.visto_em(id_sessao, assinatura.id_usuario).await;
+                    if matches!(ativa, Ok(None)) {
+                        break Some((close_code::POLICY, "session revoked"));
+                    }
+                    if !enviar(&mut envio, Message::Ping(Default::default())).await {
+                        break None;
+                    }
+                }
+            }
+        };
+
+        if let Some((codigo, motivo)) = fechamento {
+            let quadro = CloseFrame { code: codigo, reason: Utf8Bytes::from_static(motivo) };
+            enviar(&mut envio, Message::Close(Some(quadro))).await;
+        }
+    }
+
+    /// No PostgreSQL, começa a escutar o canal na primeira conexão.
+    async fn garantir_escuta(&self) -> Result<(), sqlx::Error> {
+        let Some(pool) = &self.interno.postgres else {
+            return Ok(());
+        };
+
+        self.interno
+            .escuta
+            .get_or_try_init(|| async {
+                let ouvinte = db::escutar(pool, CANAL).await?;
+
+                let interno = Arc::downgrade(&self.interno);
+                tokio::spawn(db::repassar_avisos(ouvinte, self.interno.encerramento.clone(), move |payload| {
+                    let interno = interno.upgrade()?;
+                    Some(async move {
+                        match serde_json::from_str::<Aviso>(&payload) {
+                            Ok(aviso) => interno.carregar_e_entregar(aviso).await,
+                            Err(erro) => tracing::warn!(erro = %erro, "aviso ilegível no canal de notificações"),
+                        }
+                    })
+                }));
+                Ok(())
+            })
+            .await
+            .map(|_| ())
+    }
+}
+
+impl Interno {
+    async fn carregar_e_entregar(&self, aviso: Aviso) {
+        match self.repositorios.notificacoes.buscar(aviso.id).await {
+            Ok(Some(notificacao)) => self.entregar(notificacao.id_usuario, RespostaNotificacao::from(notificacao)),
+            // Apagada antes da entrega (a conta foi anonimizada)
+            Ok(None) => {}
+            Err(erro) => tracing::warn!(erro = %erro, id_notificacao = aviso.id, "falha ao carregar a notificação avisada"),
+        }
+    }
+
+    fn entregar(&self, id_usuario: i32, notificacao: RespostaNotificacao) {
+        let notificacao = Arc::new(notificacao);
+        let mut conexoes = self.conexoes.lock().unwrap();
+        let Some(do_usuario) = conexoes.get_mut(&id_usuario) else {
+            return;
+        };
+
+        // Nunca espera por um cliente: quem está com a fila cheia sai do registro,
+        // e a conexão é encerrada depois de enviar o que já estava na fila
+        do_usuario.retain(|conexao| match conexao.fila.try_send(notificacao.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                tracing::warn!(id_usuario, "cliente de notificações lento; desconectando");
+                metricas::registrar_cliente_lento_notificacoes();
+                false
+            }
+            Err(TrySendError::Closed(_)) => false,
+        });
+        if do_usuario.is_empty() {
+            conexoes.remove(&id_usuario);
+        }
+    }
+}
+
+/// `false` se o envio falhou ou o cliente não o aceitou dentro do prazo.
+async fn enviar(envio: &mut SplitSink<WebSocket, Message>, mensagem: Message) -> bool {
+    matches!(tokio::time::timeout(PRAZO_ENVIO, envio.send(mensagem)).await, Ok(Ok(())))
+}
+
+fn texto(valor: Value) -> Message {
+    Message::Text(valor.to_string().into())
+}
+
+/// As notificações de uma conexão, na ordem em que foram criadas.
+pub struct Assinatura {
+    id: u64,
+    id_usuario: i32,
+    receptor: mpsc::Receiver<Arc<RespostaNotificacao>>,
+    interno: Arc<Interno>,
+}
+
+impl Assinatura {
+    /// A próxima notificação; `None` quando a conexão ficou para trás e foi
+    /// descartada (veja [`Interno::entregar`]), depois de esvaziada a fila.
+    pub async fn proxima(&mut self) -> Option<Arc<RespostaNotificacao>> {
+        self.receptor.recv().await
+    }
+}
+
+impl Drop for Assinatura {
+    fn drop(&mut self) {
+        metricas::registrar_conexao_notificacoes(false);
+
+        let mut conexoes = self.interno.conexoes.lock().unwrap();
+        if let Some(do_usuario) = conexoes.get_mut(&self.id_usuario) {
+            do_usuario.retain(|conexao| conexao.id != self.id);
+            if do_usuario.is_empty() {
+                conexoes.remove(&self.id_usuario);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn com_usuario() -> (Notificacoes, i32) {
+        let repositorios = Repositorios::em_memoria();
+        let id_usuario = repositorios.usuarios.criar("ana", "ana@example.com", "hash").await.unwrap().id;
+        (Notificacoes::new(&Banco::Memoria(repositorios.clone()), repositorios), id_usuario)
+    }
+
+    #[tokio::test]
+    async fn entrega_so_as_conexoes_do_usuario() {
+        let (notificacoes, id_usuario) = com_usuario().await;
+        let mut da_ana = notificacoes.assinar(id_usuario).await.unwrap();
+        let mut de_outro = notificacoes.assinar(id_usuario + 1).await.unwrap();
+
+        notificacoes.notificar(id_usuario, tipos::POSTAGEM_REMOVIDA, json!({ "post_id": 1 })).await.unwrap();
+
+        let recebida = da_ana.proxima().await.unwrap();
+        assert_eq!(recebida.tipo, tipos::POSTAGEM_REMOVIDA);
+        assert_eq!(recebida.dados, json!({ "post_id": 1 }));
+        assert!(de_outro.receptor.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn cliente_com_a_fila_cheia_e_descartado() {
+        let (notificacoes, id_usuario) = com_usuario().await;
+        let mut assinatura = notificacoes.assinar(id_usuario).await.unwrap();
+
+        for indice in 0..=CAPACIDADE_FILA {
+            notificacoes.notificar(id_usuario, tipos::NOVO_LOGIN, json!({ "indice": indice })).await.unwrap();
+        }
+
+        // O que coube na fila ainda é entregue; depois a conexão termina
+        for _ in 0..CAPACIDADE_FILA {
+            assert!(assinatura.proxima().await.is_some());
+        }
+        assert!(assinatura.proxima().await.is_none());
+        assert!(notificacoes.interno.conexoes.lock().unwrap().is_empty());
+
+        // As notificações continuam gravadas para a listagem
+        let repositorio = &notificacoes.interno.repositorios.notificacoes;
+        assert_eq!(repositorio.contar_nao_lidas(id_usuario).await.unwrap(), CAPACIDADE_FILA as i64 + 1);
+    }
+}
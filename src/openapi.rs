@@ -0,0 +1,261 @@
+use axum::{
+    extract::OriginalUri,
+    response::{Html, IntoResponse, Json},
+};
+use schemars::{generate::SchemaSettings, json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde_json::{json, Map, Value};
+use std::{borrow::Cow, sync::OnceLock};
+
+use crate::rotas::{todas_as_rotas, Acesso, Conteudo, Rota};
+
+const ESQUEMA_SEGURANCA: &str = "bearerAuth";
+
+static ESPECIFICACAO: OnceLock<Value> = OnceLock::new();
+
+/// Montada sob um prefixo (veja `ConstrutorAplicacao::prefixo`), a especificação
+/// ganha um `servers` apontando para ele, e o "Try it out" do /docs segue funcionando.
+pub async fn obter_especificacao(OriginalUri(uri): OriginalUri) -> impl IntoResponse {
+    let mut especificacao = ESPECIFICACAO.get_or_init(gerar_especificacao).clone();
+
+    let prefixo = uri.path().strip_suffix("/openapi.json").unwrap_or_default();
+    if !prefixo.is_empty() {
+        especificacao["servers"] = json!([{ "url": prefixo }]);
+    }
+
+    Json(especificacao)
+}
+
+pub async fn pagina_documentacao() -> Html<&'static str> {
+    Html(include_str!("../static/docs.html"))
+}
+
+/// Corpo das respostas de erro com detalhes (`ErroApi`), documentado à parte
+/// porque é montado com `serde_json::json!`.
+pub struct CorpoErro;
+
+impl JsonSchema for CorpoErro {
+    fn schema_name() -> Cow<'static, str> {
+        "CorpoErro".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "object",
+            "required": ["error"],
+            "properties": {
+                "error": { "type": "string" },
+                "fields": {
+                    "description": "Mensagens de validação por campo",
+                    "type": "object",
+                    "additionalProperties": { "type": "array", "items": { "type": "string" } }
+                }
+            }
+        })
+    }
+}
+
+/// Gera o documento OpenAPI 3.1 a partir das tabelas de rotas e dos tipos de `models.rs`.
+/// Rotas sem documentação ficam de fora (e são apontadas pelos testes deste módulo).
+pub fn gerar_especificacao() -> Value {
+    let mut gerador = SchemaSettings::draft2020_12()
+        .with(|configuracao| configuracao.definitions_path = "/components/schemas".into())
+        .into_generator();
+
+    let mut caminhos = Map::new();
+
+    for (caminho, rota) in todas_as_rotas() {
+        let Some(operacao) = documentar_operacao(&rota, &mut gerador) else {
+            continue;
+        };
+
+        caminhos
+            .entry(caminho)
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .expect("item de caminho é um objeto")
+            .insert(rota.metodo.as_str().to_lowercase(), operacao);
+    }
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "API Blog",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "API REST de blog com autenticação JWT. As rotas sem versão (`/api/...`) \
+                            continuam respondendo, depreciadas, com o mesmo contrato de `/api/v1`."
+        },
+        "paths": caminhos,
+        "components": {
+            "schemas": gerador.take_definitions(true),
+            "securitySchemes": {
+                ESQUEMA_SEGURANCA: {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "bearerFormat": "JWT"
+                }
+            }
+        }
+    })
+}
+
+fn documentar_conteudo(conteudo: &Conteudo, gerador: &mut SchemaGenerator) -> Value {
+    match conteudo {
+        Conteudo::Json(esquema) => json!({ "application/json": { "schema": esquema(gerador) } }),
+        Conteudo::JsonLines => json!({ "application/x-ndjson": { "schema": { "type": "string" } } }),
+        Conteudo::Eventos => json!({ "text/event-stream": { "schema": { "type": "string" } } }),
+        Conteudo::Zip => json!({ "application/zip": { "schema": { "type": "string", "format": "binary" } } }),
+        Conteudo::Html => json!({ "text/html": { "schema": { "type": "string" } } }),
+        Conteudo::Texto => json!({ "text/plain": { "schema": { "type": "string" } } }),
+    }
+}
+
+fn documentar_operacao(rota: &Rota, gerador: &mut SchemaGenerator) -> Option<Value> {
+    let documentacao = rota.documentacao.as_ref()?;
+
+    let mut respostas = Map::new();
+    for resposta in &documentacao.respostas {
+        let mut descricao = json!({ "description": resposta.descricao });
+        if let Some(conteudo) = &resposta.conteudo {
+            descricao["content"] = documentar_conteudo(conteudo, gerador);
+        }
+        respostas.insert(resposta.status.to_string(), descricao);
+    }
+
+    let mut operacao = json!({
+        "tags": [documentacao.tag],
+        "summary": documentacao.resumo,
+    });
+
+    if !documentacao.parametros.is_empty() {
+        operacao["parameters"] = documentacao
+            .parametros
+            .iter()
+            .map(|parametro| {
+                json!({
+                    "name": parametro.nome,
+                    "in": if parametro.na_consulta { "query" } else { "path" },
+                    "required": !parametro.na_consulta,
+                    "description": parametro.descricao,
+                    "schema": (parametro.esquema)(gerador),
+                })
+            })
+            .collect();
+    }
+
+    if let Some(corpo) = &documentacao.corpo {
+        operacao["requestBody"] = json!({
+            "required": true,
+            "content": documentar_conteudo(corpo, gerador),
+        });
+    }
+
+    if rota.acesso.exige_token() {
+        operacao["security"] = json!([{ ESQUEMA_SEGURANCA: [] }]);
+        respostas
+            .entry("401")
+            .or_insert_with(|| json!({ "description": "Token ausente, inválido, expirado ou de sessão revogada" }));
+    }
+    if rota.acesso == Acesso::Admin {
+        respostas
+            .entry("403")
+            .or_insert_with(|| json!({ "description": "O usuário autenticado não é administrador" }));
+    }
+
+    operacao["responses"] = Value::Object(respostas);
+    Some(operacao)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parametros_do_caminho(caminho: &str) -> Vec<&str> {
+        caminho
+            .split('/')
+            .filter_map(|segmento| segmento.strip_prefix('{')?.strip_suffix('}'))
+            .collect()
+    }
+
+    #[test]
+    fn toda_rota_esta_documentada() {
+        for (caminho, rota) in todas_as_rotas() {
+            let documentacao = rota
+                .documentacao
+                .as_ref()
+                .unwrap_or_else(|| panic!("{} {caminho} não está documentada", rota.metodo));
+
+            assert!(!documentacao.resumo.is_empty(), "{} {caminho} sem resumo", rota.metodo);
+            assert!(!documentacao.respostas.is_empty(), "{} {caminho} sem respostas", rota.metodo);
+
+            let mut documentados: Vec<&str> = documentacao
+                .parametros
+                .iter()
+                .filter(|p| !p.na_consulta)
+                .map(|p| p.nome)
+                .collect();
+            let mut esperados = parametros_do_caminho(&caminho);
+            documentados.sort_unstable();
+            esperados.sort_unstable();
+            assert_eq!(documentados, esperados, "parâmetros de {} {caminho}", rota.metodo);
+        }
+    }
+
+    #[test]
+    fn especificacao_contem_todas_as_rotas() {
+        let especificacao = gerar_especificacao();
+
+        for (caminho, rota) in todas_as_rotas() {
+            let operacao = &especificacao["paths"][&caminho][rota.metodo.as_str().to_lowercase()];
+            assert!(operacao.is_object(), "{} {caminho} ausente do OpenAPI", rota.metodo);
+
+            let exige_token = operacao.get("security").is_some();
+            assert_eq!(
+                exige_token,
+                rota.acesso.exige_token(),
+                "requisito de autenticação de {} {caminho}",
+                rota.metodo
+            );
+        }
+    }
+
+    #[test]
+    fn referencias_apontam_para_esquemas_existentes() {
+        fn coletar<'a>(valor: &'a Value, referencias: &mut Vec<&'a str>) {
+            match valor {
+                Value::Object(campos) => {
+                    if let Some(Value::String(referencia)) = campos.get("$ref") {
+                        referencias.push(referencia);
+                    }
+                    campos.values().for_each(|filho| coletar(filho, referencias));
+                }
+                Value::Array(itens) => itens.iter().for_each(|filho| coletar(filho, referencias)),
+                _ => {}
+            }
+        }
+
+        let especificacao = gerar_especificacao();
+        let mut referencias = Vec::new();
+        coletar(&especificacao, &mut referencias);
+
+        assert!(!referencias.is_empty());
+        for referencia in referencias {
+            let nome = referencia
+                .strip_prefix("#/components/schemas/")
+                .unwrap_or_else(|| panic!("referência fora de components: {referencia}"));
+            assert!(
+                especificacao["components"]["schemas"][nome].is_object(),
+                "esquema {nome} não definido"
+            );
+        }
+    }
+
+    #[test]
+    fn esquemas_usam_o_contrato_em_ingles() {
+        let especificacao = gerar_especificacao();
+        let postagem = &especificacao["components"]["schemas"]["RespostaPostagem"]["properties"];
+
+        for campo in ["id", "title", "content", "user_id", "created_at"] {
+            assert!(postagem[campo].is_object(), "RespostaPostagem sem o campo {campo}");
+        }
+    }
+}
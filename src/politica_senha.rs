@@ -0,0 +1,299 @@
+use sha1::{Digest, Sha1};
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    path::PathBuf,
+    sync::OnceLock,
+};
+
+use crate::configuracao::variavel_ou;
+
+const SENHAS_VAZADAS_EMBUTIDAS: &str = include_str!("../static/senhas_vazadas.txt");
+
+const TAMANHO_MINIMO_PADRAO: usize = 10;
+const TAMANHO_MAXIMO_PADRAO: usize = 72; // bcrypt ignora o que passar de 72 bytes
+const ENTROPIA_MINIMA_PADRAO: f64 = 40.0;
+
+// Tamanho do prefixo do SHA-1 usado nas consultas por intervalo (k-anonimato)
+const TAMANHO_PREFIXO: usize = 5;
+
+pub struct PoliticaSenha {
+    pub tamanho_minimo: usize,
+    pub tamanho_maximo: usize,
+    pub entropia_minima: f64,
+    senhas_vazadas: SenhasVazadas,
+}
+
+static POLITICA: OnceLock<PoliticaSenha> = OnceLock::new();
+
+pub fn politica() -> &'static PoliticaSenha {
+    POLITICA.get_or_init(PoliticaSenha::do_ambiente)
+}
+
+impl PoliticaSenha {
+    pub fn do_ambiente() -> Self {
+        let diretorio_intervalos = std::env::var("SENHAS_VAZADAS_DIR").ok().map(PathBuf::from);
+        if let Some(diretorio) = &diretorio_intervalos
+            && !diretorio.is_dir()
+        {
+            tracing::warn!(diretorio = %diretorio.display(), "SENHAS_VAZADAS_DIR não é um diretório");
+        }
+
+        let senhas_vazadas = SenhasVazadas {
+            embutidas: carregar_lista_completa(SENHAS_VAZADAS_EMBUTIDAS),
+            diretorio_intervalos,
+        };
+
+        PoliticaSenha {
+            tamanho_minimo: variavel_ou("SENHA_TAMANHO_MINIMO", TAMANHO_MINIMO_PADRAO),
+            tamanho_maximo: variavel_ou("SENHA_TAMANHO_MAXIMO", TAMANHO_MAXIMO_PADRAO),
+            entropia_minima: variavel_ou("SENHA_ENTROPIA_MINIMA", ENTROPIA_MINIMA_PADRAO),
+            senhas_vazadas,
+        }
+    }
+
+    /// Retorna a lista de regras violadas pela senha (vazia quando a senha é aceita).
+    /// `contexto` traz dados do próprio usuário (nome, email) que não podem aparecer na senha.
+    /// Assíncrona porque a base de senhas vazadas pode estar em disco.
+    pub async fn validar(&self, senha: &str, contexto: &[&str]) -> Vec<String> {
+        let mut violacoes = Vec::new();
+        let tamanho = senha.chars().count();
+
+        if tamanho < self.tamanho_minimo {
+            violacoes.push(format!("A senha deve ter pelo menos {} caracteres", self.tamanho_minimo));
+        }
+
+        if senha.len() > self.tamanho_maximo {
+            violacoes.push(format!("A senha deve ter no máximo {} bytes", self.tamanho_maximo));
+        }
+
+        let senha_minuscula = senha.to_lowercase();
+        let contem_dado_pessoal = contexto
+            .iter()
+            .flat_map(|dado| dado.split('@').next())
+            .map(|dado| dado.trim().to_lowercase())
+            .any(|dado| dado.chars().count() >= 3 && senha_minuscula.contains(&dado));
+
+        if contem_dado_pessoal {
+            violacoes.push("A senha não pode conter o nome de usuário ou o email".to_string());
+        }
+
+        if estimar_entropia(senha) < self.entropia_minima {
+            violacoes.push(
+                "A senha é muito previsível: combine letras maiúsculas, minúsculas, números e símbolos".to_string(),
+            );
+        }
+
+        if self.senhas_vazadas.contem(senha).await {
+            violacoes.push("Esta senha aparece em vazamentos de dados conhecidos; escolha outra".to_string());
+        }
+
+        violacoes
+    }
+}
+
+/// Estimativa de entropia em bits baseada no alfabeto usado.
+/// Caracteres repetidos ou em sequência (`aaa`, `abc`, `321`) contam apenas 1 bit.
+pub fn estimar_entropia(senha: &str) -> f64 {
+    let mut tamanho_alfabeto = 0u32;
+    if senha.chars().any(|c| c.is_ascii_lowercase()) {
+        tamanho_alfabeto += 26;
+    }
+    if senha.chars().any(|c| c.is_ascii_uppercase()) {
+        tamanho_alfabeto += 26;
+    }
+    if senha.chars().any(|c| c.is_ascii_digit()) {
+        tamanho_alfabeto += 10;
+    }
+    if senha.chars().any(|c| c.is_ascii_punctuation() || c == ' ') {
+        tamanho_alfabeto += 33;
+    }
+    if !senha.is_ascii() {
+        tamanho_alfabeto += 100;
+    }
+
+    if tamanho_alfabeto == 0 {
+        return 0.0;
+    }
+
+    let bits_por_caractere = (tamanho_alfabeto as f64).log2();
+    let mut anterior: Option<char> = None;
+    let mut entropia = 0.0;
+
+    for atual in senha.chars() {
+        let previsivel = anterior
+            .map(|anterior| (atual as i64 - anterior as i64).abs() <= 1)
+            .unwrap_or(false);
+
+        entropia += if previsivel { 1.0 } else { bits_por_caractere };
+        anterior = Some(atual);
+    }
+
+    entropia
+}
+
+/// Base de senhas vazadas no formato do Have I Been Pwned: hashes SHA-1 em hexadecimal
+/// maiúsculo. A lista embutida usa o arquivo completo (`HASH:OCORRENCIAS`); bases grandes
+/// podem ficar em disco como arquivos de intervalo (`PREFIXO.txt` contendo `SUFIXO:OCORRENCIAS`),
+/// lidos sob demanda sem carregar o corpus inteiro em memória.
+struct SenhasVazadas {
+    embutidas: HashMap<String, HashSet<String>>,
+    diretorio_intervalos: Option<PathBuf>,
+}
+
+impl SenhasVazadas {
+    async fn contem(&self, senha: &str) -> bool {
+        let hash = hash_sha1(senha);
+        let (prefixo, sufixo) = hash.split_at(TAMANHO_PREFIXO);
+
+        if self
+            .embutidas
+            .get(prefixo)
+            .is_some_and(|sufixos| sufixos.contains(sufixo))
+        {
+            return true;
+        }
+
+        self.intervalo_em_disco(prefixo).await.contains(sufixo)
+    }
+
+    async fn intervalo_em_disco(&self, prefixo: &str) -> HashSet<String> {
+        let Some(diretorio) = &self.diretorio_intervalos else {
+            return HashSet::new();
+        };
+
+        let arquivo = diretorio.join(format!("{prefixo}.txt"));
+        match tokio::fs::read_to_string(&arquivo).await {
+            Ok(conteudo) => ler_sufixos(&conteudo).collect(),
+            // Um arquivo ausente significa que nenhuma senha vazada tem esse prefixo
+            Err(erro) if erro.kind() == io::ErrorKind::NotFound => HashSet::new(),
+            // Qualquer outra falha não deve barrar cadastros, mas precisa aparecer
+            Err(erro) => {
+                tracing::error!(erro = %erro, arquivo = %arquivo.display(), "falha ao ler o intervalo de senhas vazadas");
+                HashSet::new()
+            }
+        }
+    }
+}
+
+fn carregar_lista_completa(conteudo: &str) -> HashMap<String, HashSet<String>> {
+    let mut intervalos: HashMap<String, HashSet<String>> = HashMap::new();
+
+    for hash in ler_sufixos(conteudo).filter(|hash| hash.len() > TAMANHO_PREFIXO) {
+        let (prefixo, sufixo) = hash.split_at(TAMANHO_PREFIXO);
+        intervalos
+            .entry(prefixo.to_string())
+            .or_default()
+            .insert(sufixo.to_string());
+    }
+
+    intervalos
+}
+
+fn ler_sufixos(conteudo: &str) -> impl Iterator<Item = String> + '_ {
+    conteudo
+        .lines()
+        .map(str::trim)
+        .filter(|linha| !linha.is_empty() && !linha.starts_with('#'))
+        .filter_map(|linha| linha.split(':').next())
+        .map(|hash| hash.to_ascii_uppercase())
+}
+
+fn hash_sha1(senha: &str) -> String {
+    Sha1::digest(senha.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SENHA_FORTE: &str = "Vx9#qLm2$Tr8";
+
+    fn politica_teste(diretorio_intervalos: Option<PathBuf>) -> PoliticaSenha {
+        PoliticaSenha {
+            tamanho_minimo: TAMANHO_MINIMO_PADRAO,
+            tamanho_maximo: TAMANHO_MAXIMO_PADRAO,
+            entropia_minima: ENTROPIA_MINIMA_PADRAO,
+            senhas_vazadas: SenhasVazadas {
+                embutidas: carregar_lista_completa(SENHAS_VAZADAS_EMBUTIDAS),
+                diretorio_intervalos,
+            },
+        }
+    }
+
+    fn viola(violacoes: &[String], trecho: &str) -> bool {
+        violacoes.iter().any(|violacao| violacao.contains(trecho))
+    }
+
+    #[tokio::test]
+    async fn aceita_senha_forte() {
+        assert!(politica_teste(None).validar(SENHA_FORTE, &["ana", "ana@example.com"]).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn limita_o_tamanho() {
+        let politica = politica_teste(None);
+
+        assert!(viola(&politica.validar("Vx9#qLm2$", &[]).await, "pelo menos 10"));
+        assert!(!viola(&politica.validar(SENHA_FORTE, &[]).await, "pelo menos"));
+
+        // O máximo é em bytes (o limite do bcrypt), não em caracteres
+        let longa = format!("{SENHA_FORTE}{}", "ç".repeat(31));
+        assert_eq!(longa.chars().count(), 43);
+        assert!(viola(&politica.validar(&longa, &[]).await, "no máximo 72 bytes"));
+    }
+
+    #[test]
+    fn estima_a_entropia_pelo_alfabeto_e_pelas_sequencias() {
+        assert_eq!(estimar_entropia(""), 0.0);
+        // Repetições e sequências valem 1 bit cada
+        assert_eq!(estimar_entropia("aaaa"), 26f64.log2() + 3.0);
+        assert_eq!(estimar_entropia("abcd"), 26f64.log2() + 3.0);
+        assert_eq!(estimar_entropia("4321"), 10f64.log2() + 3.0);
+        assert_eq!(estimar_entropia("aZ"), 2.0 * 52f64.log2());
+        assert!(estimar_entropia(SENHA_FORTE) >= ENTROPIA_MINIMA_PADRAO);
+        assert!(estimar_entropia("abcdefghijklmnop") < ENTROPIA_MINIMA_PADRAO);
+    }
+
+    #[tokio::test]
+    async fn recusa_dados_pessoais() {
+        let politica = politica_teste(None);
+        let mensagem = "nome de usuário ou o email";
+
+        for (senha, contexto) in [
+            ("Mariana#2024xQ", ["mariana", "outra@example.com"]),
+            ("xQ#9MARIANA!k", ["mariana", "outra@example.com"]),
+            // Do email, só a parte antes do @
+            ("Vx9#joaquim!Q", ["outro", "joaquim@example.com"]),
+        ] {
+            assert!(viola(&politica.validar(senha, &contexto).await, mensagem), "{senha}");
+        }
+
+        // Dados com menos de 3 caracteres são ignorados
+        assert!(!viola(&politica.validar("Vx9#qLm2$Tr8ab", &["ab", "ab@example.com"]).await, mensagem));
+    }
+
+    #[tokio::test]
+    async fn consulta_o_intervalo_do_prefixo_em_disco() {
+        let diretorio = std::env::temp_dir().join(format!("senhas-vazadas-{}", std::process::id()));
+        std::fs::create_dir_all(&diretorio).unwrap();
+        let hash = hash_sha1(SENHA_FORTE);
+        let (prefixo, sufixo) = hash.split_at(TAMANHO_PREFIXO);
+        std::fs::write(diretorio.join(format!("{prefixo}.txt")), format!("{}:3\n{sufixo}:12\n", "0".repeat(35))).unwrap();
+
+        let politica = politica_teste(Some(diretorio.clone()));
+        assert!(viola(&politica.validar(SENHA_FORTE, &[]).await, "vazamentos"));
+        // Prefixo sem arquivo: nenhuma senha vazada com ele
+        assert!(politica.validar("Qw7&zRt4!Kp6", &[]).await.is_empty());
+
+        // Um arquivo ilegível é registrado e não barra a senha
+        std::fs::remove_file(diretorio.join(format!("{prefixo}.txt"))).unwrap();
+        std::fs::create_dir(diretorio.join(format!("{prefixo}.txt"))).unwrap();
+        assert!(politica.validar(SENHA_FORTE, &[]).await.is_empty());
+
+        std::fs::remove_dir_all(diretorio).unwrap();
+    }
+}
@@ -0,0 +1,318 @@
+//! Direitos do titular dos dados: exportação dos dados pessoais num `.zip` e
+//! exclusão da conta a pedido do usuário.
+//!
+//! A exportação traz o perfil, os posts, as sessões, as notificações, os
+//! webhooks (sem o segredo) e os eventos de auditoria da conta.
+//!
+//! A exclusão não apaga a conta. Ela é agendada para depois de um prazo de
+//! carência (`CONTA_EXCLUSAO_CARENCIA_DIAS`), durante o qual pode ser cancelada;
+//! vencido o prazo, a conta é anonimizada: nome, email e senha são trocados, as
+//! sessões, as notificações e os webhooks são apagados e os eventos de auditoria
+//! da conta perdem IP, User-Agent, email e nome. Os posts continuam publicados,
+//! sem dados pessoais do autor.
+
+use chrono::{DateTime, Duration, Utc};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::io::{Cursor, Write};
+use uuid::Uuid;
+use zip::{result::ZipResult, write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    auditoria::{acoes, alvos, NovoEvento},
+    configuracao::variavel_ou,
+    fila::{ErroTarefa, TipoTarefa},
+    models::{
+        EventoAuditoria, Notificacao, Postagem, RespostaEventoAuditoria, RespostaNotificacao, RespostaPostagem,
+        RespostaWebhook, Sessao, Usuario, Webhook,
+    },
+    repositorios::{Repositorios, Resultado},
+};
+
+const CARENCIA_PADRAO_DIAS: i64 = 30;
+
+/// Notificações lidas por consulta ao montar a exportação.
+const PAGINA_NOTIFICACOES: i64 = 1000;
+
+/// Valores de `diff.reason` no evento de anonimização.
+pub const MOTIVO_CARENCIA: &str = "grace_period_expired";
+pub const MOTIVO_ADMINISTRADOR: &str = "admin";
+
+/// Prazo entre o pedido de exclusão e a anonimização da conta.
+pub fn carencia() -> Duration {
+    Duration::days(variavel_ou("CONTA_EXCLUSAO_CARENCIA_DIAS", CARENCIA_PADRAO_DIAS))
+}
+
+/// Anonimiza a conta e registra o evento de auditoria; `false` se ela já estava anonimizada.
+pub async fn anonimizar(repositorios: &Repositorios, id_usuario: i32, motivo: &'static str) -> Resultado<bool> {
+    // Únicos e dentro das regras de nome de usuário; `.invalid` nunca recebe emails
+    let nome_usuario = format!("usuario_removido_{id_usuario}");
+    let email = format!("removido-{id_usuario}@anonimo.invalid");
+
+    if !repositorios.usuarios.anonimizar(id_usuario, &nome_usuario, &email).await? {
+        return Ok(false);
+    }
+
+    let evento = NovoEvento::new(acoes::CONTA_ANONIMIZADA)
+        .alvo(alvos::USUARIO, id_usuario)
+        .diff(json!({ "reason": motivo }));
+    repositorios.auditoria.registrar(&evento).await?;
+    Ok(true)
+}
+
+/// Anonimiza as contas com a exclusão agendada para até `agora` e devolve os ids.
+pub async fn processar_exclusoes(repositorios: &Repositorios, agora: DateTime<Utc>) -> Resultado<Vec<i32>> {
+    let mut anonimizadas = Vec::new();
+    for id_usuario in repositorios.usuarios.exclusoes_vencidas(agora).await? {
+        if anonimizar(repositorios, id_usuario, MOTIVO_CARENCIA).await? {
+            anonimizadas.push(id_usuario);
+        }
+    }
+    Ok(anonimizadas)
+}
+
+/// Tarefa agendada que processa as exclusões vencidas. Com a carência medida
+/// em dias, rodar a cada poucos minutos basta.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExclusoesVencidas;
+
+impl TipoTarefa for ExclusoesVencidas {
+    const TIPO: &'static str = "accounts.purge_expired";
+
+    fn executar(self, repositorios: Repositorios) -> BoxFuture<'static, Result<(), ErroTarefa>> {
+        Box::pin(async move {
+            let ids = processar_exclusoes(&repositorios, Utc::now()).await?;
+            if !ids.is_empty() {
+                tracing::info!(contas = ?ids, "Contas anonimizadas ao fim da carência");
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Tudo o que o blog guarda sobre um usuário.
+pub struct DadosPessoais {
+    usuario: Usuario,
+    postagens: Vec<Postagem>,
+    sessoes: Vec<Sessao>,
+    notificacoes: Vec<Notificacao>,
+    webhooks: Vec<Webhook>,
+    eventos: Vec<EventoAuditoria>,
+    exportado_em: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct PerfilExportado<'a> {
+    id: i32,
+    username: &'a str,
+    email: &'a str,
+    is_admin: bool,
+    created_at: DateTime<Utc>,
+    disabled_at: Option<DateTime<Utc>>,
+    deletion_scheduled_at: Option<DateTime<Utc>>,
+    exported_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct SessaoExportada<'a> {
+    id: Uuid,
+    user_agent: Option<&'a str>,
+    ip: Option<&'a str>,
+    created_at: DateTime<Utc>,
+    last_seen_at: DateTime<Utc>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+impl DadosPessoais {
+    /// `None` quando o usuário não existe.
+    pub async fn coletar(repositorios: &Repositorios, id_usuario: i32) -> Resultado<Option<Self>> {
+        let Some(usuario) = repositorios.usuarios.buscar_por_id(id_usuario).await? else {
+            return Ok(None);
+        };
+        let postagens = repositorios.postagens.listar_do_usuario(id_usuario).await?;
+        let sessoes = repositorios.sessoes.listar_do_usuario(id_usuario).await?;
+        let webhooks = repositorios.webhooks.listar_do_usuario(id_usuario).await?;
+        let eventos = repositorios.auditoria.listar_do_usuario(id_usuario).await?;
+
+        let mut notificacoes = Vec::new();
+        loop {
+            let antes_do_id = notificacoes.last().map(|notificacao: &Notificacao| notificacao.id);
+            let pagina = repositorios
+                .notificacoes
+                .listar(id_usuario, false, antes_do_id, PAGINA_NOTIFICACOES)
+                .await?;
+            let ultima = (pagina.len() as i64) < PAGINA_NOTIFICACOES;
+            notificacoes.extend(pagina);
+            if ultima {
+                break;
+            }
+        }
+
+        Ok(Some(DadosPessoais {
+            usuario,
+            postagens,
+            sessoes,
+            notificacoes,
+            webhooks,
+            eventos,
+            exportado_em: Utc::now(),
+        }))
+    }
+
+    pub fn nome_arquivo(&self) -> String {
+        format!("dados-{}-{}.zip", self.usuario.nome_usuario, self.exportado_em.format("%Y%m%d"))
+    }
+
+    /// O arquivo `.zip`: os dados em JSON (`profile.json`, `posts.json`,
+    /// `sessions.json`, `notifications.json`, `webhooks.json`, `audit.json`) e
+    /// legíveis em Markdown (`profile.md`, `posts/*.md`).
+    pub fn compactar(&self) -> ZipResult<Vec<u8>> {
+        let mut arquivo = ZipWriter::new(Cursor::new(Vec::new()));
+        let opcoes = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        let usuario = &self.usuario;
+        let perfil = PerfilExportado {
+            id: usuario.id,
+            username: &usuario.nome_usuario,
+            email: &usuario.email,
+            is_admin: usuario.admin,
+            created_at: usuario.criado_em,
+            disabled_at: usuario.desativado_em,
+            deletion_scheduled_at: usuario.exclusao_agendada_em,
+            exported_at: self.exportado_em,
+        };
+        let postagens: Vec<RespostaPostagem> = self.postagens.iter().cloned().map(RespostaPostagem::from).collect();
+        let sessoes: Vec<SessaoExportada> = self
+            .sessoes
+            .iter()
+            .map(|sessao| SessaoExportada {
+                id: sessao.id,
+                user_agent: sessao.user_agent.as_deref(),
+                ip: sessao.ip.as_deref(),
+                created_at: sessao.criado_em,
+                last_seen_at: sessao.visto_em,
+                revoked_at: sessao.revogada_em,
+            })
+            .collect();
+        let notificacoes: Vec<RespostaNotificacao> =
+            self.notificacoes.iter().cloned().map(RespostaNotificacao::from).collect();
+        // O segredo dos webhooks fica de fora, como na API
+        let webhooks: Vec<RespostaWebhook> = self.webhooks.iter().cloned().map(RespostaWebhook::from).collect();
+        let eventos: Vec<RespostaEventoAuditoria> =
+            self.eventos.iter().cloned().map(RespostaEventoAuditoria::from).collect();
+
+        for (nome, valor) in [
+            ("profile.json", serde_json::to_vec_pretty(&perfil)),
+            ("posts.json", serde_json::to_vec_pretty(&postagens)),
+            ("sessions.json", serde_json::to_vec_pretty(&sessoes)),
+            ("notifications.json", serde_json::to_vec_pretty(&notificacoes)),
+            ("webhooks.json", serde_json::to_vec_pretty(&webhooks)),
+            ("audit.json", serde_json::to_vec_pretty(&eventos)),
+        ] {
+            arquivo.start_file(nome, opcoes)?;
+            arquivo.write_all(&valor.map_err(std::io::Error::from)?)?;
+        }
+
+        arquivo.start_file("profile.md", opcoes)?;
+        arquivo.write_all(self.perfil_markdown().as_bytes())?;
+
+        for postagem in &self.postagens {
+            arquivo.start_file(format!("posts/{}", arquivo_postagem(postagem)), opcoes)?;
+            arquivo.write_all(postagem_markdown(postagem).as_bytes())?;
+        }
+
+        Ok(arquivo.finish()?.into_inner())
+    }
+
+    fn perfil_markdown(&self) -> String {
+        let usuario = &self.usuario;
+        let mut texto = format!(
+            "# Dados de {}\n\nExportados em {}.\n\n\
+             | Campo | Valor |\n|---|---|\n\
+             | ID | {} |\n| Nome de usuário | {} |\n| Email | {} |\n| Conta criada em | {} |\n| Administrador | {} |\n",
+            usuario.nome_usuario,
+            data(self.exportado_em),
+            usuario.id,
+            usuario.nome_usuario,
+            usuario.email,
+            data(usuario.criado_em),
+            if usuario.admin { "sim" } else { "não" },
+        );
+        if let Some(desativado_em) = usuario.desativado_em {
+            texto.push_str(&format!("| Desativada em | {} |\n", data(desativado_em)));
+        }
+        if let Some(exclusao) = usuario.exclusao_agendada_em {
+            texto.push_str(&format!("| Exclusão agendada para | {} |\n", data(exclusao)));
+        }
+
+        texto.push_str(&format!("\n## Posts ({})\n\n", self.postagens.len()));
+        for postagem in &self.postagens {
+            texto.push_str(&format!(
+                "- [{}](posts/{}), {}\n",
+                postagem.titulo,
+                arquivo_postagem(postagem),
+                data(postagem.criado_em)
+            ));
+        }
+
+        texto.push_str(&format!("\n## Sessões ({})\n\n", self.sessoes.len()));
+        if !self.sessoes.is_empty() {
+            texto.push_str("| Criada em | Último acesso | IP | Dispositivo | Revogada em |\n|---|---|---|---|---|\n");
+        }
+        for sessao in &self.sessoes {
+            texto.push_str(&format!(
+                "| {} | {} | {} | {} | {} |\n",
+                data(sessao.criado_em),
+                data(sessao.visto_em),
+                sessao.ip.as_deref().unwrap_or("-"),
+                sessao.user_agent.as_deref().unwrap_or("-").replace('|', "\\|"),
+                sessao.revogada_em.map(data).unwrap_or_else(|| "-".to_string()),
+            ));
+        }
+
+        texto.push_str(&format!("\n## Webhooks ({})\n\n", self.webhooks.len()));
+        for webhook in &self.webhooks {
+            texto.push_str(&format!("- {} ({}), {}\n", webhook.url, webhook.eventos.0.join(", "), data(webhook.criado_em)));
+        }
+
+        texto.push_str(&format!(
+            "\n## Outros dados\n\n- {} notificações, em `notifications.json`\n- {} eventos de auditoria, em `audit.json`\n",
+            self.notificacoes.len(),
+            self.eventos.len()
+        ));
+        texto
+    }
+}
+
+fn postagem_markdown(postagem: &Postagem) -> String {
+    let mut datas = format!("Publicado em {}", data(postagem.criado_em));
+    if postagem.atualizado_em != postagem.criado_em {
+        datas.push_str(&format!(", atualizado em {}", data(postagem.atualizado_em)));
+    }
+    format!("# {}\n\n*{datas}.*\n\n{}\n", postagem.titulo, postagem.conteudo)
+}
+
+/// `posts/42-titulo-do-post.md`: o id mantém o nome único mesmo com títulos repetidos.
+fn arquivo_postagem(postagem: &Postagem) -> String {
+    let mut slug = String::new();
+    for caractere in postagem.titulo.to_lowercase().chars() {
+        if caractere.is_alphanumeric() {
+            slug.push(caractere);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug: String = slug.chars().take(60).collect();
+    let slug = slug.trim_end_matches('-');
+
+    if slug.is_empty() {
+        format!("{}.md", postagem.id)
+    } else {
+        format!("{}-{slug}.md", postagem.id)
+    }
+}
+
+fn data(data: DateTime<Utc>) -> String {
+    data.format("%Y-%m-%d %H:%M UTC").to_string()
+}
@@ -0,0 +1,393 @@
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    auditoria::{FiltroEventos, NovoEvento},
+    fila::{FiltroTarefas, NovaTarefa, ResultadoExecucao},
+    models::{
+        EntregaPendente, EntregaWebhook, EventoAuditoria, Notificacao, Postagem, Sessao, Tarefa, TentativaEntrega, Usuario,
+        Webhook,
+    },
+    webhooks::ResultadoEnvio,
+};
+
+mod memoria;
+mod postgres;
+mod sqlite;
+
+pub use memoria::RepositorioMemoria;
+pub use postgres::RepositorioPostgres;
+pub use sqlite::RepositorioSqlite;
+
+/// Os erros dos repositórios são os do sqlx, também nas implementações sem SQL,
+/// para os handlers tratarem todas da mesma forma.
+pub type Resultado<T> = Result<T, sqlx::Error>;
+
+/// Se o erro é a violação de uma restrição `UNIQUE` (`23505` no PostgreSQL,
+/// `UNIQUE constraint failed` no SQLite), como quando dois cadastros com o
+/// mesmo email passam juntos pela verificação de duplicidade.
+pub fn violacao_unicidade(erro: &sqlx::Error) -> bool {
+    matches!(erro, sqlx::Error::Database(erro) if erro.is_unique_violation())
+}
+
+/// O que acompanha uma escrita e vai na mesma transação que ela: o evento de
+/// auditoria e as entregas dos webhooks (a caixa de saída). Gravados em
+/// separado, uma falha no meio deixaria o post sem auditoria ou sem webhook.
+pub struct Efeitos {
+    pub auditoria: NovoEvento,
+    pub webhook: EventoWebhook,
+}
+
+/// Evento para os webhooks que o assinam, já com o payload entregue
+/// (veja [`webhooks::evento`](crate::webhooks::evento)).
+pub struct EventoWebhook {
+    pub evento: &'static str,
+    pub id: Uuid,
+    pub payload: serde_json::Value,
+    pub so_admins: bool,
+}
+
+/// Monta os [`Efeitos`] a partir da entidade gravada, que só então tem id e datas.
+pub type MontarEfeitos<'a, T> = &'a (dyn Fn(&T) -> Efeitos + Send + Sync);
+
+pub trait RepositorioUsuarios: Send + Sync {
+    fn buscar_por_id(&self, id: i32) -> BoxFuture<'_, Resultado<Option<Usuario>>>;
+
+    /// Os usuários com esses ids, numa só consulta; ids que não existem ficam de fora.
+    fn buscar_varios<'a>(&'a self, ids: &'a [i32]) -> BoxFuture<'a, Resultado<Vec<Usuario>>>;
+
+    /// Busca pelo email já normalizado (minúsculo).
+    fn buscar_por_email<'a>(&'a self, email: &'a str) -> BoxFuture<'a, Resultado<Option<Usuario>>>;
+
+    fn buscar_por_nome_usuario<'a>(&'a self, nome_usuario: &'a str) -> BoxFuture<'a, Resultado<Option<Usuario>>>;
+
+    /// Usuário que já usa o email ou o nome de usuário informado.
+    fn buscar_conflito<'a>(&'a self, email: &'a str, nome_usuario: &'a str) -> BoxFuture<'a, Resultado<Option<Usuario>>>;
+
+    fn criar<'a>(&'a self, nome_usuario: &'a str, email: &'a str, hash_senha: &'a str) -> BoxFuture<'a, Resultado<Usuario>>;
+
+    /// Como `criar`, gravando os efeitos do cadastro na mesma transação.
+    fn criar_com_efeitos<'a>(
+        &'a self,
+        nome_usuario: &'a str,
+        email: &'a str,
+        hash_senha: &'a str,
+        efeitos: MontarEfeitos<'a, Usuario>,
+    ) -> BoxFuture<'a, Resultado<Usuario>>;
+
+    /// Todos os usuários, dos mais novos para os mais antigos.
+    fn listar(&self) -> BoxFuture<'_, Resultado<Vec<Usuario>>>;
+
+    fn contar(&self) -> BoxFuture<'_, Resultado<i64>>;
+
+    /// `false` quando o usuário não existe (o mesmo vale para as operações abaixo).
+    fn definir_admin(&self, id: i32, admin: bool) -> BoxFuture<'_, Resultado<bool>>;
+
+    /// Desativa (ou reativa) a conta. Não revoga as sessões: veja `RepositorioSessoes::revogar_todas`.
+    fn definir_desativado(&self, id: i32, desativado: bool) -> BoxFuture<'_, Resultado<bool>>;
+
+    fn atualizar_senha<'a>(&'a self, id: i32, hash_senha: &'a str) -> BoxFuture<'a, Resultado<bool>>;
+
+    /// Agenda (ou, com `None`, cancela) a anonimização da conta. `false` também
+    /// quando a conta já foi anonimizada.
+    fn agendar_exclusao(&self, id: i32, data: Option<DateTime<Utc>>) -> BoxFuture<'_, Resultado<bool>>;
+
+    /// Contas com a exclusão agendada para até `ate`.
+    fn exclusoes_vencidas(&self, ate: DateTime<Utc>) -> BoxFuture<'_, Resultado<Vec<i32>>>;
+
+    /// Troca nome e email pelos informados, invalida a senha, desativa a conta
+    /// e apaga as sessões, as notificações e os webhooks; as postagens ficam.
+    /// Nos eventos de auditoria da conta, apaga o IP e o User-Agent e tira do
+    /// `diff` o email, o hash do email e o nome de usuário. `false` se já estava anonimizada.
+    fn anonimizar<'a>(&'a self, id: i32, nome_usuario: &'a str, email: &'a str) -> BoxFuture<'a, Resultado<bool>>;
+
+    /// Até `limite` usuários com id maior que `apos_id`, em ordem de id (exportação).
+    fn listar_pagina(&self, apos_id: i32, limite: i64) -> BoxFuture<'_, Resultado<Vec<Usuario>>>;
+
+    /// Insere o usuário com todos os campos, inclusive as datas (importação).
+    /// Com `manter_id`, usa `usuario.id`, que precisa estar livre; sem ele, o id é novo.
+    fn importar<'a>(&'a self, usuario: &'a Usuario, manter_id: bool) -> BoxFuture<'a, Resultado<Usuario>>;
+}
+
+pub trait RepositorioPostagens: Send + Sync {
+    /// As `limite` postagens mais recentes de todos os usuários.
+    fn listar_recentes(&self, limite: i64) -> BoxFuture<'_, Resultado<Vec<Postagem>>>;
+
+    fn listar_do_usuario(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<Vec<Postagem>>>;
+
+    fn buscar(&self, id: i32) -> BoxFuture<'_, Resultado<Option<Postagem>>>;
+
+    /// Só encontra a postagem se ela pertencer ao usuário.
+    fn buscar_do_usuario(&self, id: i32, id_usuario: i32) -> BoxFuture<'_, Resultado<Option<Postagem>>>;
+
+    fn criar<'a>(&'a self, id_usuario: i32, titulo: &'a str, conteudo: &'a str) -> BoxFuture<'a, Resultado<Postagem>>;
+
+    /// Como `criar`, gravando os efeitos na mesma transação.
+    fn criar_com_efeitos<'a>(
+        &'a self,
+        id_usuario: i32,
+        titulo: &'a str,
+        conteudo: &'a str,
+        efeitos: MontarEfeitos<'a, Postagem>,
+    ) -> BoxFuture<'a, Resultado<Postagem>>;
+
+    /// Atualiza uma postagem do usuário. Com `versao`, só atualiza se o
+    /// `updated_at` ainda for esse (concorrência otimista); `None` quando nada mudou.
+    fn atualizar<'a>(
+        &'a self,
+        id: i32,
+        id_usuario: i32,
+        titulo: &'a str,
+        conteudo: &'a str,
+        versao: Option<DateTime<Utc>>,
+    ) -> BoxFuture<'a, Resultado<Option<Postagem>>>;
+
+    /// Como `atualizar`, gravando os efeitos na mesma transação quando a postagem muda.
+    fn atualizar_com_efeitos<'a>(
+        &'a self,
+        id: i32,
+        id_usuario: i32,
+        titulo: &'a str,
+        conteudo: &'a str,
+        versao: Option<DateTime<Utc>>,
+        efeitos: MontarEfeitos<'a, Postagem>,
+    ) -> BoxFuture<'a, Resultado<Option<Postagem>>>;
+
+    /// `false` quando a postagem não existe ou é de outro usuário.
+    fn deletar(&self, id: i32, id_usuario: i32) -> BoxFuture<'_, Resultado<bool>>;
+
+    /// Como `deletar`, gravando os efeitos na mesma transação quando a postagem é apagada.
+    fn deletar_com_efeitos<'a>(&'a self, id: i32, id_usuario: i32, efeitos: &'a Efeitos) -> BoxFuture<'a, Resultado<bool>>;
+
+    /// Apaga a postagem de qualquer usuário (moderação); `false` quando não existe.
+    fn remover(&self, id: i32) -> BoxFuture<'_, Resultado<bool>>;
+
+    /// Até `limite` postagens com id maior que `apos_id`, em ordem de id (exportação).
+    fn listar_pagina(&self, apos_id: i32, limite: i64) -> BoxFuture<'_, Resultado<Vec<Postagem>>>;
+
+    /// Até `limite` postagens com id menor que `antes_do_id` (sem ele, desde a
+    /// mais nova), das mais novas para as mais antigas.
+    fn listar_anteriores(&self, antes_do_id: Option<i32>, limite: i64) -> BoxFuture<'_, Resultado<Vec<Postagem>>>;
+
+    /// Como `listar_anteriores`, mas até `limite` postagens de cada usuário,
+    /// numa só consulta; o resultado vem agrupado por usuário.
+    fn listar_anteriores_dos_usuarios<'a>(
+        &'a self,
+        ids_usuarios: &'a [i32],
+        antes_do_id: Option<i32>,
+        limite: i64,
+    ) -> BoxFuture<'a, Resultado<Vec<Postagem>>>;
+
+    /// Quantas postagens cada usuário tem; usuários sem postagens ficam de fora.
+    fn contar_dos_usuarios<'a>(&'a self, ids_usuarios: &'a [i32]) -> BoxFuture<'a, Resultado<Vec<(i32, i64)>>>;
+
+    /// Como `RepositorioUsuarios::importar`; `postagem.id_usuario` já precisa existir.
+    fn importar<'a>(&'a self, postagem: &'a Postagem, manter_id: bool) -> BoxFuture<'a, Resultado<Postagem>>;
+}
+
+pub trait RepositorioSessoes: Send + Sync {
+    fn criar(&self, id_usuario: i32, user_agent: Option<String>, ip: String) -> BoxFuture<'_, Resultado<Uuid>>;
+
+    /// Último acesso de uma sessão não revogada do usuário.
+    fn visto_em(&self, id: Uuid, id_usuario: i32) -> BoxFuture<'_, Resultado<Option<DateTime<Utc>>>>;
+
+    fn registrar_acesso(&self, id: Uuid) -> BoxFuture<'_, Resultado<()>>;
+
+    /// Sessões não revogadas criadas depois de `criadas_apos`, pelo último acesso.
+    fn listar_ativas(&self, id_usuario: i32, criadas_apos: DateTime<Utc>) -> BoxFuture<'_, Resultado<Vec<Sessao>>>;
+
+    /// `false` quando a sessão não existe, é de outro usuário ou já foi revogada.
+    fn revogar(&self, id: Uuid, id_usuario: i32) -> BoxFuture<'_, Resultado<bool>>;
+
+    /// Revoga todas as sessões ativas do usuário e devolve quantas eram.
+    fn revogar_todas(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<u64>>;
+
+    /// Todas as sessões do usuário, inclusive as revogadas, das mais novas para as mais antigas.
+    fn listar_do_usuario(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<Vec<Sessao>>>;
+
+    /// Até `limite` sessões com id maior que `apos`, em ordem de id (exportação).
+    fn listar_pagina(&self, apos: Uuid, limite: i64) -> BoxFuture<'_, Resultado<Vec<Sessao>>>;
+
+    /// Insere a sessão com o mesmo id e as mesmas datas; `false` se o id já existe.
+    fn importar<'a>(&'a self, sessao: &'a Sessao) -> BoxFuture<'a, Resultado<bool>>;
+}
+
+pub trait RepositorioAuditoria: Send + Sync {
+    /// Acrescenta um evento; não há operação para alterar ou apagar eventos.
+    fn registrar<'a>(&'a self, evento: &'a NovoEvento) -> BoxFuture<'a, Resultado<()>>;
+
+    /// Até `limite` eventos que atendem ao filtro, dos mais novos para os mais antigos.
+    fn listar<'a>(&'a self, filtro: &'a FiltroEventos, limite: i64) -> BoxFuture<'a, Resultado<Vec<EventoAuditoria>>>;
+
+    /// Os eventos feitos pelo usuário e os que têm a conta dele como alvo sem
+    /// outro usuário como autor (logins que falharam, ações do sistema e do
+    /// `blogctl`), dos mais novos para os mais antigos.
+    fn listar_do_usuario(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<Vec<EventoAuditoria>>>;
+}
+
+pub trait RepositorioNotificacoes: Send + Sync {
+    fn criar<'a>(&'a self, id_usuario: i32, tipo: &'a str, dados: &'a serde_json::Value) -> BoxFuture<'a, Resultado<Notificacao>>;
+
+    /// Usado por quem recebe o `NOTIFY`, que traz só o id.
+    fn buscar(&self, id: i64) -> BoxFuture<'_, Resultado<Option<Notificacao>>>;
+
+    /// Até `limite` notificações do usuário com id menor que `antes_do_id`, das
+    /// mais novas para as mais antigas; com `nao_lidas`, só as que faltam ler.
+    fn listar(
+        &self,
+        id_usuario: i32,
+        nao_lidas: bool,
+        antes_do_id: Option<i64>,
+        limite: i64,
+    ) -> BoxFuture<'_, Resultado<Vec<Notificacao>>>;
+
+    fn contar_nao_lidas(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<i64>>;
+
+    /// `false` quando a notificação não existe ou é de outro usuário. Marcar de
+    /// novo uma já lida mantém a data da primeira leitura.
+    fn marcar_lida(&self, id: i64, id_usuario: i32) -> BoxFuture<'_, Resultado<bool>>;
+
+    /// Marca todas as não lidas do usuário e devolve quantas eram.
+    fn marcar_todas_lidas(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<u64>>;
+}
+
+pub trait RepositorioWebhooks: Send + Sync {
+    fn criar<'a>(&'a self, id_usuario: i32, url: &'a str, eventos: &'a [String], segredo: &'a str) -> BoxFuture<'a, Resultado<Webhook>>;
+
+    /// Webhooks do usuário, dos mais novos para os mais antigos.
+    fn listar_do_usuario(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<Vec<Webhook>>>;
+
+    /// Só encontra o webhook se ele pertencer ao usuário.
+    fn buscar_do_usuario(&self, id: i32, id_usuario: i32) -> BoxFuture<'_, Resultado<Option<Webhook>>>;
+
+    /// Apaga o webhook com as entregas; `false` quando não existe ou é de outro usuário.
+    fn deletar(&self, id: i32, id_usuario: i32) -> BoxFuture<'_, Resultado<bool>>;
+
+    /// Cria uma entrega pendente para cada webhook que assina o evento e cujo dono
+    /// está com a conta ativa (e é administrador, com `so_admins`). Devolve quantas.
+    fn enfileirar<'a>(
+        &'a self,
+        evento: &'a str,
+        id_evento: Uuid,
+        payload: &'a serde_json::Value,
+        so_admins: bool,
+    ) -> BoxFuture<'a, Resultado<u64>>;
+
+    /// Reserva até `limite` entregas pendentes vencidas em `agora`: conta a
+    /// tentativa e adia a seguinte para `reservar_ate`, de modo que outra
+    /// instância não envie a mesma entrega enquanto esta a envia.
+    fn reservar_entregas(
+        &self,
+        agora: DateTime<Utc>,
+        reservar_ate: DateTime<Utc>,
+        limite: i64,
+    ) -> BoxFuture<'_, Resultado<Vec<EntregaPendente>>>;
+
+    /// Grava a tentativa no histórico e atualiza a situação da entrega.
+    fn registrar_envio<'a>(&'a self, id_entrega: i64, resultado: &'a ResultadoEnvio) -> BoxFuture<'a, Resultado<()>>;
+
+    /// Desfaz a reserva de uma entrega que não chegou a ser enviada
+    /// (encerramento da instância): descontada a tentativa, ela fica vencida em
+    /// `agora`. `false` quando a entrega já não está nessa tentativa.
+    fn liberar_entrega(&self, id_entrega: i64, tentativa: i32, agora: DateTime<Utc>) -> BoxFuture<'_, Resultado<bool>>;
+
+    /// Até `limite` entregas do webhook com id menor que `antes_do_id`, das
+    /// mais novas para as mais antigas.
+    fn listar_entregas(
+        &self,
+        id_webhook: i32,
+        antes_do_id: Option<i64>,
+        limite: i64,
+    ) -> BoxFuture<'_, Resultado<Vec<EntregaWebhook>>>;
+
+    fn buscar_entrega(&self, id: i64, id_webhook: i32) -> BoxFuture<'_, Resultado<Option<EntregaWebhook>>>;
+
+    /// Tentativas de envio da entrega, da primeira à última.
+    fn listar_tentativas(&self, id_entrega: i64) -> BoxFuture<'_, Resultado<Vec<TentativaEntrega>>>;
+
+    /// Enfileira de novo o evento de uma entrega, como uma entrega nova com o
+    /// mesmo `event_id` e o mesmo corpo; `None` quando a original não existe.
+    fn reenviar(&self, id: i64, id_webhook: i32) -> BoxFuture<'_, Resultado<Option<EntregaWebhook>>>;
+}
+
+pub trait RepositorioTarefas: Send + Sync {
+    /// Grava a tarefa como pendente; `None` quando já existe uma com a mesma `chave`.
+    fn enfileirar<'a>(&'a self, tarefa: &'a NovaTarefa) -> BoxFuture<'a, Resultado<Option<Tarefa>>>;
+
+    /// Reserva até `limite` tarefas dos `tipos` informados vencidas em `agora`:
+    /// as pendentes e as em execução cuja reserva expirou (a instância que as
+    /// executava caiu). Marca como `running`, conta a tentativa e reserva até
+    /// `reservar_ate`, de modo que outra instância não execute a mesma tarefa.
+    fn reservar<'a>(
+        &'a self,
+        tipos: &'a [&'static str],
+        agora: DateTime<Utc>,
+        reservar_ate: DateTime<Utc>,
+        limite: i64,
+    ) -> BoxFuture<'a, Resultado<Vec<Tarefa>>>;
+
+    /// Grava o resultado da execução, se a tarefa ainda estiver reservada para
+    /// ela (mesma tentativa); `false` quando outra instância já a retomou.
+    fn concluir<'a>(&'a self, id: i64, resultado: &'a ResultadoExecucao) -> BoxFuture<'a, Resultado<bool>>;
+
+    /// Devolve à fila, sem contar a tentativa, uma tarefa reservada que não
+    /// chegou a terminar (encerramento da instância); `false` quando ela já não
+    /// está reservada para essa tentativa.
+    fn liberar(&self, id: i64, tentativa: i32) -> BoxFuture<'_, Resultado<bool>>;
+
+    /// Até `limite` tarefas que atendem ao filtro, das mais novas para as mais antigas.
+    fn listar<'a>(&'a self, filtro: &'a FiltroTarefas, limite: i64) -> BoxFuture<'a, Resultado<Vec<Tarefa>>>;
+
+    fn buscar(&self, id: i64) -> BoxFuture<'_, Resultado<Option<Tarefa>>>;
+
+    /// Devolve à fila, para executar em `agora` e com as tentativas zeradas, uma
+    /// tarefa pendente ou morta; `None` quando não existe, está em execução ou já foi concluída.
+    fn tentar_de_novo(&self, id: i64, agora: DateTime<Utc>) -> BoxFuture<'_, Resultado<Option<Tarefa>>>;
+
+    /// Apaga as tarefas concluídas com sucesso antes de `antes_de` e devolve quantas.
+    fn apagar_concluidas(&self, antes_de: DateTime<Utc>) -> BoxFuture<'_, Resultado<u64>>;
+}
+
+/// Repositórios usados pelos handlers, todos sobre o mesmo armazenamento.
+#[derive(Clone)]
+pub struct Repositorios {
+    pub usuarios: Arc<dyn RepositorioUsuarios>,
+    pub postagens: Arc<dyn RepositorioPostagens>,
+    pub sessoes: Arc<dyn RepositorioSessoes>,
+    pub auditoria: Arc<dyn RepositorioAuditoria>,
+    pub notificacoes: Arc<dyn RepositorioNotificacoes>,
+    pub webhooks: Arc<dyn RepositorioWebhooks>,
+    pub tarefas: Arc<dyn RepositorioTarefas>,
+}
+
+impl Repositorios {
+    pub fn new<R>(repositorio: R) -> Self
+    where
+        R: RepositorioUsuarios
+            + RepositorioPostagens
+            + RepositorioSessoes
+            + RepositorioAuditoria
+            + RepositorioNotificacoes
+            + RepositorioWebhooks
+            + RepositorioTarefas
+            + 'static,
+    {
+        let repositorio = Arc::new(repositorio);
+        Repositorios {
+            usuarios: repositorio.clone(),
+            postagens: repositorio.clone(),
+            sessoes: repositorio.clone(),
+            auditoria: repositorio.clone(),
+            notificacoes: repositorio.clone(),
+            webhooks: repositorio.clone(),
+            tarefas: repositorio,
+        }
+    }
+
+    /// Repositórios sem banco, para testes.
+    pub fn em_memoria() -> Self {
+        Repositorios::new(RepositorioMemoria::default())
+    }
+}
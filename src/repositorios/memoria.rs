@@ -0,0 +1,1223 @@
+use chrono::{DateTime, Utc};
+use futures::future::{self, BoxFuture};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard},
+};
+use uuid::Uuid;
+
+use super::{
+    Efeitos, MontarEfeitos, RepositorioAuditoria, RepositorioNotificacoes, RepositorioPostagens, RepositorioSessoes, RepositorioTarefas,
+    RepositorioUsuarios, RepositorioWebhooks, Resultado,
+};
+use crate::{
+    auditoria::{alvos, FiltroEventos, NovoEvento},
+    fila::{self, FiltroTarefas, NovaTarefa, ResultadoExecucao},
+    models::{
+        EntregaPendente, EntregaWebhook, EventoAuditoria, Notificacao, Postagem, Sessao, Tarefa, TentativaEntrega, Usuario,
+        Webhook,
+    },
+    webhooks::{situacoes, ResultadoEnvio},
+};
+
+/// Armazenamento em memória, sem persistência, para testes de unidade.
+/// Reproduz as restrições do esquema que os handlers dependem (email e nome
+/// de usuário únicos, postagem de um usuário existente).
+#[derive(Default)]
+pub struct RepositorioMemoria {
+    dados: Mutex<Dados>,
+}
+
+#[derive(Default)]
+struct Dados {
+    usuarios: Vec<Usuario>,
+    postagens: Vec<Postagem>,
+    sessoes: Vec<Sessao>,
+    eventos: Vec<EventoAuditoria>,
+    notificacoes: Vec<Notificacao>,
+    webhooks: Vec<Webhook>,
+    entregas: Vec<EntregaWebhook>,
+    tentativas: Vec<TentativaEntrega>,
+    tarefas: Vec<Tarefa>,
+    ultimo_id_usuario: i32,
+    ultimo_id_postagem: i32,
+    ultimo_id_webhook: i32,
+}
+
+impl Dados {
+    /// Apaga os webhooks que atendem a `remover`, com as entregas e as tentativas.
+    fn remover_webhooks(&mut self, remover: impl Fn(&Webhook) -> bool) -> usize {
+        let ids: Vec<i32> = self.webhooks.iter().filter(|webhook| remover(webhook)).map(|webhook| webhook.id).collect();
+        self.webhooks.retain(|webhook| !ids.contains(&webhook.id));
+
+        let entregas: Vec<i64> = self
+            .entregas
+            .iter()
+            .filter(|entrega| ids.contains(&entrega.id_webhook))
+            .map(|entrega| entrega.id)
+            .collect();
+        self.entregas.retain(|entrega| !entregas.contains(&entrega.id));
+        self.tentativas.retain(|tentativa| !entregas.contains(&tentativa.id_entrega));
+
+        ids.len()
+    }
+
+    fn novo_usuario(&mut self, nome_usuario: &str, email: &str, hash_senha: &str) -> Resultado<Usuario> {
+        if self
+            .usuarios
+            .iter()
+            .any(|usuario| usuario.email.to_lowercase() == email.to_lowercase() || usuario.nome_usuario == nome_usuario)
+        {
+            return violacao_unicidade("email ou nome de usuário já cadastrado");
+        }
+
+        self.ultimo_id_usuario += 1;
+        let usuario = Usuario {
+            id: self.ultimo_id_usuario,
+            nome_usuario: nome_usuario.to_string(),
+            email: email.to_string(),
+            hash_senha: hash_senha.to_string(),
+            criado_em: Utc::now(),
+            admin: false,
+            desativado_em: None,
+            exclusao_agendada_em: None,
+            anonimizado_em: None,
+        };
+        self.usuarios.push(usuario.clone());
+
+        Ok(usuario)
+    }
+
+    fn nova_postagem(&mut self, id_usuario: i32, titulo: &str, conteudo: &str) -> Resultado<Postagem> {
+        if !self.usuarios.iter().any(|usuario| usuario.id == id_usuario) {
+            return Err(sqlx::Error::InvalidArgument(format!("usuário {id_usuario} não existe")));
+        }
+
+        self.ultimo_id_postagem += 1;
+        let agora = Utc::now();
+        let postagem = Postagem {
+            id: self.ultimo_id_postagem,
+            titulo: titulo.to_string(),
+            conteudo: conteudo.to_string(),
+            id_usuario,
+            criado_em: agora,
+            atualizado_em: agora,
+        };
+        self.postagens.push(postagem.clone());
+
+        Ok(postagem)
+    }
+
+    fn editar_postagem(
+        &mut self,
+        id: i32,
+        id_usuario: i32,
+        titulo: &str,
+        conteudo: &str,
+        versao: Option<DateTime<Utc>>,
+    ) -> Option<Postagem> {
+        let postagem = self.postagens.iter_mut().find(|postagem| {
+            postagem.id == id
+                && postagem.id_usuario == id_usuario
+                && versao.is_none_or(|versao| postagem.atualizado_em == versao)
+        })?;
+
+        postagem.titulo = titulo.to_string();
+        postagem.conteudo = conteudo.to_string();
+        postagem.atualizado_em = Utc::now();
+        Some(postagem.clone())
+    }
+
+    fn apagar_postagem(&mut self, id: i32, id_usuario: i32) -> bool {
+        let antes = self.postagens.len();
+        self.postagens
+            .retain(|postagem| !(postagem.id == id && postagem.id_usuario == id_usuario));
+        self.postagens.len() < antes
+    }
+
+    fn novo_evento(&mut self, evento: &NovoEvento) {
+        let id = self.eventos.len() as i64 + 1;
+        self.eventos.push(EventoAuditoria {
+            id,
+            id_ator: evento.id_ator,
+            acao: evento.acao.to_string(),
+            tipo_alvo: evento.tipo_alvo.map(str::to_string),
+            id_alvo: evento.id_alvo.clone(),
+            ip: evento.ip.clone(),
+            user_agent: evento.user_agent.clone(),
+            diff: sqlx::types::Json(evento.diff.clone()),
+            criado_em: Utc::now(),
+        });
+    }
+
+    fn enfileirar_entregas(&mut self, evento: &str, id_evento: Uuid, payload: &serde_json::Value, so_admins: bool) -> u64 {
+        let destinos: Vec<i32> = self
+            .webhooks
+            .iter()
+            .filter(|webhook| webhook.eventos.iter().any(|assinado| assinado == evento))
+            .filter(|webhook| {
+                self.usuarios.iter().any(|usuario| {
+                    usuario.id == webhook.id_usuario && usuario.desativado_em.is_none() && (!so_admins || usuario.admin)
+                })
+            })
+            .map(|webhook| webhook.id)
+            .collect();
+
+        for id_webhook in &destinos {
+            self.nova_entrega(*id_webhook, id_evento, evento, payload.clone());
+        }
+        destinos.len() as u64
+    }
+
+    fn gravar_efeitos(&mut self, efeitos: &Efeitos) {
+        self.novo_evento(&efeitos.auditoria);
+        let webhook = &efeitos.webhook;
+        self.enfileirar_entregas(webhook.evento, webhook.id, &webhook.payload, webhook.so_admins);
+    }
+
+    fn nova_entrega(&mut self, id_webhook: i32, id_evento: Uuid, evento: &str, payload: serde_json::Value) -> EntregaWebhook {
+        let agora = Utc::now();
+        let entrega = EntregaWebhook {
+            id: self.entregas.last().map_or(1, |ultima| ultima.id + 1),
+            id_webhook,
+            id_evento,
+            evento: evento.to_string(),
+            payload: sqlx::types::Json(payload),
+            status: situacoes::PENDENTE.to_string(),
+            tentativas: 0,
+            proxima_tentativa_em: agora,
+            ultimo_status: None,
+            ultimo_erro: None,
+            criado_em: agora,
+            entregue_em: None,
+        };
+        self.entregas.push(entrega.clone());
+        entrega
+    }
+}
+
+impl RepositorioMemoria {
+    fn dados(&self) -> MutexGuard<'_, Dados> {
+        self.dados.lock().unwrap()
+    }
+}
+
+fn pronto<'a, T: Send + 'a>(valor: Resultado<T>) -> BoxFuture<'a, Resultado<T>> {
+    Box::pin(future::ready(valor))
+}
+
+/// O erro que o banco daria ao violar uma restrição `UNIQUE`, para os handlers
+/// tratarem os dois armazenamentos da mesma forma.
+#[derive(Debug)]
+struct ViolacaoUnicidade(&'static str);
+
+impl std::fmt::Display for ViolacaoUnicidade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl std::error::Error for ViolacaoUnicidade {}
+
+impl sqlx::error::DatabaseError for ViolacaoUnicidade {
+    fn message(&self) -> &str {
+        self.0
+    }
+
+    fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+        self
+    }
+
+    fn kind(&self) -> sqlx::error::ErrorKind {
+        sqlx::error::ErrorKind::UniqueViolation
+    }
+}
+
+fn violacao_unicidade<T>(mensagem: &'static str) -> Resultado<T> {
+    Err(sqlx::Error::Database(Box::new(ViolacaoUnicidade(mensagem))))
+}
+
+/// Mais recentes primeiro; o id desempata registros criados no mesmo instante.
+fn recentes_primeiro(postagens: &mut [Postagem]) {
+    postagens.sort_by(|a, b| b.criado_em.cmp(&a.criado_em).then(b.id.cmp(&a.id)));
+}
+
+impl RepositorioUsuarios for RepositorioMemoria {
+    fn buscar_por_id(&self, id: i32) -> BoxFuture<'_, Resultado<Option<Usuario>>> {
+        let usuario = self.dados().usuarios.iter().find(|usuario| usuario.id == id).cloned();
+        pronto(Ok(usuario))
+    }
+
+    fn buscar_varios<'a>(&'a self, ids: &'a [i32]) -> BoxFuture<'a, Resultado<Vec<Usuario>>> {
+        let usuarios = self.dados().usuarios.iter().filter(|usuario| ids.contains(&usuario.id)).cloned().collect();
+        pronto(Ok(usuarios))
+    }
+
+    fn buscar_por_email<'a>(&'a self, email: &'a str) -> BoxFuture<'a, Resultado<Option<Usuario>>> {
+        let usuario = self
+            .dados()
+            .usuarios
+            .iter()
+            .find(|usuario| usuario.email.to_lowercase() == email)
+            .cloned();
+        pronto(Ok(usuario))
+    }
+
+    fn buscar_por_nome_usuario<'a>(&'a self, nome_usuario: &'a str) -> BoxFuture<'a, Resultado<Option<Usuario>>> {
+        let usuario = self
+            .dados()
+            .usuarios
+            .iter()
+            .find(|usuario| usuario.nome_usuario == nome_usuario)
+            .cloned();
+        pronto(Ok(usuario))
+    }
+
+    fn buscar_conflito<'a>(&'a self, email: &'a str, nome_usuario: &'a str) -> BoxFuture<'a, Resultado<Option<Usuario>>> {
+        let usuario = self
+            .dados()
+            .usuarios
+            .iter()
+            .find(|usuario| usuario.email.to_lowercase() == email.to_lowercase() || usuario.nome_usuario == nome_usuario)
+            .cloned();
+        pronto(Ok(usuario))
+    }
+
+    fn criar<'a>(&'a self, nome_usuario: &'a str, email: &'a str, hash_senha: &'a str) -> BoxFuture<'a, Resultado<Usuario>> {
+        pronto(self.dados().novo_usuario(nome_usuario, email, hash_senha))
+    }
+
+    fn criar_com_efeitos<'a>(
+        &'a self,
+        nome_usuario: &'a str,
+        email: &'a str,
+        hash_senha: &'a str,
+        efeitos: MontarEfeitos<'a, Usuario>,
+    ) -> BoxFuture<'a, Resultado<Usuario>> {
+        // Tudo sob a mesma trava: ninguém vê o usuário sem os efeitos
+        let mut dados = self.dados();
+        let usuario = dados.novo_usuario(nome_usuario, email, hash_senha);
+        if let Ok(usuario) = &usuario {
+            dados.gravar_efeitos(&efeitos(usuario));
+        }
+        pronto(usuario)
+    }
+
+    fn listar(&self) -> BoxFuture<'_, Resultado<Vec<Usuario>>> {
+        let mut usuarios = self.dados().usuarios.clone();
+        usuarios.sort_by(|a, b| b.criado_em.cmp(&a.criado_em).then(b.id.cmp(&a.id)));
+        pronto(Ok(usuarios))
+    }
+
+    fn contar(&self) -> BoxFuture<'_, Resultado<i64>> {
+        pronto(Ok(self.dados().usuarios.len() as i64))
+    }
+
+    fn definir_admin(&self, id: i32, admin: bool) -> BoxFuture<'_, Resultado<bool>> {
+        let mut dados = self.dados();
+        let usuario = dados.usuarios.iter_mut().find(|usuario| usuario.id == id);
+        let encontrado = usuario.map(|usuario| usuario.admin = admin).is_some();
+        pronto(Ok(encontrado))
+    }
+
+    fn definir_desativado(&self, id: i32, desativado: bool) -> BoxFuture<'_, Resultado<bool>> {
+        let mut dados = self.dados();
+        let usuario = dados.usuarios.iter_mut().find(|usuario| usuario.id == id);
+        let encontrado = usuario
+            .map(|usuario| {
+                usuario.desativado_em = if desativado {
+                    Some(usuario.desativado_em.unwrap_or_else(Utc::now))
+                } else {
+                    None
+                };
+            })
+            .is_some();
+        pronto(Ok(encontrado))
+    }
+
+    fn atualizar_senha<'a>(&'a self, id: i32, hash_senha: &'a str) -> BoxFuture<'a, Resultado<bool>> {
+        let mut dados = self.dados();
+        let usuario = dados.usuarios.iter_mut().find(|usuario| usuario.id == id);
+        let encontrado = usuario.map(|usuario| usuario.hash_senha = hash_senha.to_string()).is_some();
+        pronto(Ok(encontrado))
+    }
+
+    fn agendar_exclusao(&self, id: i32, data: Option<DateTime<Utc>>) -> BoxFuture<'_, Resultado<bool>> {
+        let mut dados = self.dados();
+        let usuario = dados
+            .usuarios
+            .iter_mut()
+            .find(|usuario| usuario.id == id && usuario.anonimizado_em.is_none());
+        let encontrado = usuario.map(|usuario| usuario.exclusao_agendada_em = data).is_some();
+        pronto(Ok(encontrado))
+    }
+
+    fn exclusoes_vencidas(&self, ate: DateTime<Utc>) -> BoxFuture<'_, Resultado<Vec<i32>>> {
+        let mut vencidas: Vec<(DateTime<Utc>, i32)> = self
+            .dados()
+            .usuarios
+            .iter()
+            .filter_map(|usuario| usuario.exclusao_agendada_em.filter(|data| *data <= ate).map(|data| (data, usuario.id)))
+            .collect();
+        vencidas.sort();
+        pronto(Ok(vencidas.into_iter().map(|(_, id)| id).collect()))
+    }
+
+    fn anonimizar<'a>(&'a self, id: i32, nome_usuario: &'a str, email: &'a str) -> BoxFuture<'a, Resultado<bool>> {
+        let mut dados = self.dados();
+        let agora = Utc::now();
+        let Some(usuario) = dados
+            .usuarios
+            .iter_mut()
+            .find(|usuario| usuario.id == id && usuario.anonimizado_em.is_none())
+        else {
+            return pronto(Ok(false));
+        };
+
+        let email_anterior = std::mem::replace(&mut usuario.email, email.to_string());
+        usuario.nome_usuario = nome_usuario.to_string();
+        usuario.hash_senha = "!".to_string();
+        usuario.admin = false;
+        usuario.desativado_em = Some(usuario.desativado_em.unwrap_or(agora));
+        usuario.exclusao_agendada_em = None;
+        usuario.anonimizado_em = Some(agora);
+        dados.sessoes.retain(|sessao| sessao.id_usuario != id);
+        dados.notificacoes.retain(|notificacao| notificacao.id_usuario != id);
+        dados.remover_webhooks(|webhook| webhook.id_usuario == id);
+
+        for evento in dados.eventos.iter_mut().filter(|evento| {
+            evento_do_usuario(evento, id) || evento.diff.0.get("email").and_then(|email| email.as_str()) == Some(&email_anterior)
+        }) {
+            evento.ip = None;
+            evento.user_agent = None;
+            if let Some(campos) = evento.diff.0.as_object_mut() {
+                for campo in ["email", "email_hash", "username"] {
+                    campos.remove(campo);
+                }
+            }
+        }
+
+        pronto(Ok(true))
+    }
+
+    fn listar_pagina(&self, apos_id: i32, limite: i64) -> BoxFuture<'_, Resultado<Vec<Usuario>>> {
+        let mut usuarios: Vec<Usuario> = self.dados().usuarios.iter().filter(|usuario| usuario.id > apos_id).cloned().collect();
+        usuarios.sort_by_key(|usuario| usuario.id);
+        usuarios.truncate(limite.max(0) as usize);
+        pronto(Ok(usuarios))
+    }
+
+    fn importar<'a>(&'a self, usuario: &'a Usuario, manter_id: bool) -> BoxFuture<'a, Resultado<Usuario>> {
+        let mut dados = self.dados();
+
+        if dados.usuarios.iter().any(|existente| {
+            existente.email == usuario.email
+                || existente.nome_usuario == usuario.nome_usuario
+                || (manter_id && existente.id == usuario.id)
+        }) {
+            return pronto(violacao_unicidade("id, email ou nome de usuário já cadastrado"));
+        }
+
+        let mut importado = usuario.clone();
+        if manter_id {
+            dados.ultimo_id_usuario = dados.ultimo_id_usuario.max(usuario.id);
+        } else {
+            dados.ultimo_id_usuario += 1;
+            importado.id = dados.ultimo_id_usuario;
+        }
+        dados.usuarios.push(importado.clone());
+
+        pronto(Ok(importado))
+    }
+}
+
+impl RepositorioPostagens for RepositorioMemoria {
+    fn listar_recentes(&self, limite: i64) -> BoxFuture<'_, Resultado<Vec<Postagem>>> {
+        let mut postagens = self.dados().postagens.clone();
+        recentes_primeiro(&mut postagens);
+        postagens.truncate(limite.max(0) as usize);
+        pronto(Ok(postagens))
+    }
+
+    fn listar_do_usuario(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<Vec<Postagem>>> {
+        let mut postagens: Vec<Postagem> = self
+            .dados()
+            .postagens
+            .iter()
+            .filter(|postagem| postagem.id_usuario == id_usuario)
+            .cloned()
+            .collect();
+        recentes_primeiro(&mut postagens);
+        pronto(Ok(postagens))
+    }
+
+    fn buscar(&self, id: i32) -> BoxFuture<'_, Resultado<Option<Postagem>>> {
+        let postagem = self.dados().postagens.iter().find(|postagem| postagem.id == id).cloned();
+        pronto(Ok(postagem))
+    }
+
+    fn buscar_do_usuario(&self, id: i32, id_usuario: i32) -> BoxFuture<'_, Resultado<Option<Postagem>>> {
+        let postagem = self
+            .dados()
+            .postagens
+            .iter()
+            .find(|postagem| postagem.id == id && postagem.id_usuario == id_usuario)
+            .cloned();
+        pronto(Ok(postagem))
+    }
+
+    fn criar<'a>(&'a self, id_usuario: i32, titulo: &'a str, conteudo: &'a str) -> BoxFuture<'a, Resultado<Postagem>> {
+        pronto(self.dados().nova_postagem(id_usuario, titulo, conteudo))
+    }
+
+    fn criar_com_efeitos<'a>(
+        &'a self,
+        id_usuario: i32,
+        titulo: &'a str,
+        conteudo: &'a str,
+        efeitos: MontarEfeitos<'a, Postagem>,
+    ) -> BoxFuture<'a, Resultado<Postagem>> {
+        let mut dados = self.dados();
+        let postagem = dados.nova_postagem(id_usuario, titulo, conteudo);
+        if let Ok(postagem) = &postagem {
+            dados.gravar_efeitos(&efeitos(postagem));
+        }
+        pronto(postagem)
+    }
+
+    fn atualizar<'a>(
+        &'a self,
+        id: i32,
+        id_usuario: i32,
+        titulo: &'a str,
+        conteudo: &'a str,
+        versao: Option<DateTime<Utc>>,
+    ) -> BoxFuture<'a, Resultado<Option<Postagem>>> {
+        pronto(Ok(self.dados().editar_postagem(id, id_usuario, titulo, conteudo, versao)))
+    }
+
+    fn atualizar_com_efeitos<'a>(
+        &'a self,
+        id: i32,
+        id_usuario: i32,
+        titulo: &'a str,
+        conteudo: &'a str,
+        versao: Option<DateTime<Utc>>,
+        efeitos: MontarEfeitos<'a, Postagem>,
+    ) -> BoxFuture<'a, Resultado<Option<Postagem>>> {
+        let mut dados = self.dados();
+        let postagem = dados.editar_postagem(id, id_usuario, titulo, conteudo, versao);
+        if let Some(postagem) = &postagem {
+            dados.gravar_efeitos(&efeitos(postagem));
+        }
+        pronto(Ok(postagem))
+    }
+
+    fn deletar(&self, id: i32, id_usuario: i32) -> BoxFuture<'_, Resultado<bool>> {
+        pronto(Ok(self.dados().apagar_postagem(id, id_usuario)))
+    }
+
+    fn deletar_com_efeitos<'a>(&'a self, id: i32, id_usuario: i32, efeitos: &'a Efeitos) -> BoxFuture<'a, Resultado<bool>> {
+        let mut dados = self.dados();
+        let apagada = dados.apagar_postagem(id, id_usuario);
+        if apagada {
+            dados.gravar_efeitos(efeitos);
+        }
+        pronto(Ok(apagada))
+    }
+
+    fn remover(&self, id: i32) -> BoxFuture<'_, Resultado<bool>> {
+        let mut dados = self.dados();
+        let antes = dados.postagens.len();
+        dados.postagens.retain(|postagem| postagem.id != id);
+
+        pronto(Ok(dados.postagens.len() < antes))
+    }
+
+    fn listar_pagina(&self, apos_id: i32, limite: i64) -> BoxFuture<'_, Resultado<Vec<Postagem>>> {
+        let mut postagens: Vec<Postagem> =
+            self.dados().postagens.iter().filter(|postagem| postagem.id > apos_id).cloned().collect();
+        postagens.sort_by_key(|postagem| postagem.id);
+        postagens.truncate(limite.max(0) as usize);
+        pronto(Ok(postagens))
+    }
+
+    fn listar_anteriores(&self, antes_do_id: Option<i32>, limite: i64) -> BoxFuture<'_, Resultado<Vec<Postagem>>> {
+        let mut postagens: Vec<Postagem> = self
+            .dados()
+            .postagens
+            .iter()
+            .filter(|postagem| antes_do_id.is_none_or(|antes_do_id| postagem.id < antes_do_id))
+            .cloned()
+            .collect();
+        postagens.sort_by_key(|postagem| std::cmp::Reverse(postagem.id));
+        postagens.truncate(limite.max(0) as usize);
+        pronto(Ok(postagens))
+    }
+
+    fn listar_anteriores_dos_usuarios<'a>(
+        &'a self,
+        ids_usuarios: &'a [i32],
+        antes_do_id: Option<i32>,
+        limite: i64,
+    ) -> BoxFuture<'a, Resultado<Vec<Postagem>>> {
+        let mut postagens: Vec<Postagem> = self
+            .dados()
+            .postagens
+            .iter()
+            .filter(|postagem| ids_usuarios.contains(&postagem.id_usuario))
+            .filter(|postagem| antes_do_id.is_none_or(|antes_do_id| postagem.id < antes_do_id))
+            .cloned()
+            .collect();
+        postagens.sort_by_key(|postagem| (postagem.id_usuario, std::cmp::Reverse(postagem.id)));
+
+        let mut por_usuario: HashMap<i32, i64> = HashMap::new();
+        postagens.retain(|postagem| {
+            let quantidade = por_usuario.entry(postagem.id_usuario).or_default();
+            *quantidade += 1;
+            *quantidade <= limite
+        });
+        pronto(Ok(postagens))
+    }
+
+    fn contar_dos_usuarios<'a>(&'a self, ids_usuarios: &'a [i32]) -> BoxFuture<'a, Resultado<Vec<(i32, i64)>>> {
+        let mut contagens: HashMap<i32, i64> = HashMap::new();
+        for postagem in self.dados().postagens.iter().filter(|postagem| ids_usuarios.contains(&postagem.id_usuario)) {
+            *contagens.entry(postagem.id_usuario).or_default() += 1;
+        }
+        pronto(Ok(contagens.into_iter().collect()))
+    }
+
+    fn importar<'a>(&'a self, postagem: &'a Postagem, manter_id: bool) -> BoxFuture<'a, Resultado<Postagem>> {
+        let mut dados = self.dados();
+
+        if !dados.usuarios.iter().any(|usuario| usuario.id == postagem.id_usuario) {
+            return pronto(Err(sqlx::Error::InvalidArgument(format!("usuário {} não existe", postagem.id_usuario))));
+        }
+        if manter_id && dados.postagens.iter().any(|existente| existente.id == postagem.id) {
+            return pronto(Err(sqlx::Error::InvalidArgument(format!("postagem {} já existe", postagem.id))));
+        }
+
+        let mut importada = postagem.clone();
+        if manter_id {
+            dados.ultimo_id_postagem = dados.ultimo_id_postagem.max(postagem.id);
+        } else {
+            dados.ultimo_id_postagem += 1;
+            importada.id = dados.ultimo_id_postagem;
+        }
+        dados.postagens.push(importada.clone());
+
+        pronto(Ok(importada))
+    }
+}
+
+impl RepositorioSessoes for RepositorioMemoria {
+    fn criar(&self, id_usuario: i32, user_agent: Option<String>, ip: String) -> BoxFuture<'_, Resultado<Uuid>> {
+        let agora = Utc::now();
+        let sessao = Sessao {
+            id: Uuid::new_v4(),
+            id_usuario,
+            user_agent,
+            ip: Some(ip),
+            criado_em: agora,
+            visto_em: agora,
+            revogada_em: None,
+        };
+
+        let id = sessao.id;
+        self.dados().sessoes.push(sessao);
+        pronto(Ok(id))
+    }
+
+    fn visto_em(&self, id: Uuid, id_usuario: i32) -> BoxFuture<'_, Resultado<Option<DateTime<Utc>>>> {
+        let visto_em = self
+            .dados()
+            .sessoes
+            .iter()
+            .find(|sessao| sessao.id == id && sessao.id_usuario == id_usuario && sessao.revogada_em.is_none())
+            .map(|sessao| sessao.visto_em);
+        pronto(Ok(visto_em))
+    }
+
+    fn registrar_acesso(&self, id: Uuid) -> BoxFuture<'_, Resultado<()>> {
+        if let Some(sessao) = self.dados().sessoes.iter_mut().find(|sessao| sessao.id == id) {
+            sessao.visto_em = Utc::now();
+        }
+        pronto(Ok(()))
+    }
+
+    fn listar_ativas(&self, id_usuario: i32, criadas_apos: DateTime<Utc>) -> BoxFuture<'_, Resultado<Vec<Sessao>>> {
+        let mut sessoes: Vec<Sessao> = self
+            .dados()
+            .sessoes
+            .iter()
+            .filter(|sessao| {
+                sessao.id_usuario == id_usuario && sessao.revogada_em.is_none() && sessao.criado_em > criadas_apos
+            })
+            .cloned()
+            .collect();
+        sessoes.sort_by_key(|sessao| std::cmp::Reverse(sessao.visto_em));
+        pronto(Ok(sessoes))
+    }
+
+    fn revogar(&self, id: Uuid, id_usuario: i32) -> BoxFuture<'_, Resultado<bool>> {
+        let mut dados = self.dados();
+        let sessao = dados
+            .sessoes
+            .iter_mut()
+            .find(|sessao| sessao.id == id && sessao.id_usuario == id_usuario && sessao.revogada_em.is_none());
+
+        let revogada = match sessao {
+            Some(sessao) => {
+                sessao.revogada_em = Some(Utc::now());
+                true
+            }
+            None => false,
+        };
+        pronto(Ok(revogada))
+    }
+
+    fn revogar_todas(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<u64>> {
+        let agora = Utc::now();
+        let mut revogadas = 0;
+        for sessao in self
+            .dados()
+            .sessoes
+            .iter_mut()
+            .filter(|sessao| sessao.id_usuario == id_usuario && sessao.revogada_em.is_none())
+        {
+            sessao.revogada_em = Some(agora);
+            revogadas += 1;
+        }
+        pronto(Ok(revogadas))
+    }
+
+    fn listar_do_usuario(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<Vec<Sessao>>> {
+        let mut sessoes: Vec<Sessao> = self
+            .dados()
+            .sessoes
+            .iter()
+            .filter(|sessao| sessao.id_usuario == id_usuario)
+            .cloned()
+            .collect();
+        sessoes.sort_by_key(|sessao| std::cmp::Reverse(sessao.criado_em));
+        pronto(Ok(sessoes))
+    }
+
+    fn listar_pagina(&self, apos: Uuid, limite: i64) -> BoxFuture<'_, Resultado<Vec<Sessao>>> {
+        let mut sessoes: Vec<Sessao> = self.dados().sessoes.iter().filter(|sessao| sessao.id > apos).cloned().collect();
+        sessoes.sort_by_key(|sessao| sessao.id);
+        sessoes.truncate(limite.max(0) as usize);
+        pronto(Ok(sessoes))
+    }
+
+    fn importar<'a>(&'a self, sessao: &'a Sessao) -> BoxFuture<'a, Resultado<bool>> {
+        let mut dados = self.dados();
+        if dados.sessoes.iter().any(|existente| existente.id == sessao.id) {
+            return pronto(Ok(false));
+        }
+        dados.sessoes.push(sessao.clone());
+        pronto(Ok(true))
+    }
+}
+
+impl RepositorioAuditoria for RepositorioMemoria {
+    fn registrar<'a>(&'a self, evento: &'a NovoEvento) -> BoxFuture<'a, Resultado<()>> {
+        self.dados().novo_evento(evento);
+        pronto(Ok(()))
+    }
+
+    fn listar<'a>(&'a self, filtro: &'a FiltroEventos, limite: i64) -> BoxFuture<'a, Resultado<Vec<EventoAuditoria>>> {
+        let eventos = self
+            .dados()
+            .eventos
+            .iter()
+            .rev()
+            .filter(|evento| filtro.id_ator.is_none_or(|id| evento.id_ator == Some(id)))
+            .filter(|evento| filtro.acao.as_ref().is_none_or(|acao| &evento.acao == acao))
+            .filter(|evento| filtro.desde.is_none_or(|desde| evento.criado_em >= desde))
+            .filter(|evento| filtro.ate.is_none_or(|ate| evento.criado_em < ate))
+            .filter(|evento| filtro.antes_do_id.is_none_or(|id| evento.id < id))
+            .take(limite.max(0) as usize)
+            .cloned()
+            .collect();
+        pronto(Ok(eventos))
+    }
+
+    fn listar_do_usuario(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<Vec<EventoAuditoria>>> {
+        let eventos = self
+            .dados()
+            .eventos
+            .iter()
+            .rev()
+            .filter(|evento| evento_do_usuario(evento, id_usuario))
+            .cloned()
+            .collect();
+        pronto(Ok(eventos))
+    }
+}
+
+/// Feito pelo usuário ou, sem autor, com a conta dele como alvo.
+fn evento_do_usuario(evento: &EventoAuditoria, id_usuario: i32) -> bool {
+    match evento.id_ator {
+        Some(id_ator) => id_ator == id_usuario,
+        None => evento.tipo_alvo.as_deref() == Some(alvos::USUARIO) && evento.id_alvo == Some(id_usuario.to_string()),
+    }
+}
+
+impl RepositorioNotificacoes for RepositorioMemoria {
+    fn criar<'a>(&'a self, id_usuario: i32, tipo: &'a str, dados: &'a serde_json::Value) -> BoxFuture<'a, Resultado<Notificacao>> {
+        let mut armazenados = self.dados();
+
+        if !armazenados.usuarios.iter().any(|usuario| usuario.id == id_usuario) {
+            return pronto(Err(sqlx::Error::InvalidArgument(format!("usuário {id_usuario} não existe"))));
+        }
+
+        let notificacao = Notificacao {
+            id: armazenados.notificacoes.last().map_or(1, |ultima| ultima.id + 1),
+            id_usuario,
+            tipo: tipo.to_string(),
+            dados: sqlx::types::Json(dados.clone()),
+            criado_em: Utc::now(),
+            lido_em: None,
+        };
+        armazenados.notificacoes.push(notificacao.clone());
+
+        pronto(Ok(notificacao))
+    }
+
+    fn buscar(&self, id: i64) -> BoxFuture<'_, Resultado<Option<Notificacao>>> {
+        let notificacao = self.dados().notificacoes.iter().find(|notificacao| notificacao.id == id).cloned();
+        pronto(Ok(notificacao))
+    }
+
+    fn listar(
+        &self,
+        id_usuario: i32,
+        nao_lidas: bool,
+        antes_do_id: Option<i64>,
+        limite: i64,
+    ) -> BoxFuture<'_, Resultado<Vec<Notificacao>>> {
+        let notificacoes = self
+            .dados()
+            .notificacoes
+            .iter()
+            .rev()
+            .filter(|notificacao| notificacao.id_usuario == id_usuario)
+            .filter(|notificacao| !nao_lidas || notificacao.lido_em.is_none())
+            .filter(|notificacao| antes_do_id.is_none_or(|id| notificacao.id < id))
+            .take(limite.max(0) as usize)
+            .cloned()
+            .collect();
+        pronto(Ok(notificacoes))
+    }
+
+    fn contar_nao_lidas(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<i64>> {
+        let total = self
+            .dados()
+            .notificacoes
+            .iter()
+            .filter(|notificacao| notificacao.id_usuario == id_usuario && notificacao.lido_em.is_none())
+            .count();
+        pronto(Ok(total as i64))
+    }
+
+    fn marcar_lida(&self, id: i64, id_usuario: i32) -> BoxFuture<'_, Resultado<bool>> {
+        let mut dados = self.dados();
+        let Some(notificacao) = dados
+            .notificacoes
+            .iter_mut()
+            .find(|notificacao| notificacao.id == id && notificacao.id_usuario == id_usuario)
+        else {
+            return pronto(Ok(false));
+        };
+
+        notificacao.lido_em.get_or_insert_with(Utc::now);
+        pronto(Ok(true))
+    }
+
+    fn marcar_todas_lidas(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<u64>> {
+        let agora = Utc::now();
+        let mut marcadas = 0;
+        for notificacao in self.dados().notificacoes.iter_mut() {
+            if notificacao.id_usuario == id_usuario && notificacao.lido_em.is_none() {
+                notificacao.lido_em = Some(agora);
+                marcadas += 1;
+            }
+        }
+        pronto(Ok(marcadas))
+    }
+}
+
+impl RepositorioWebhooks for RepositorioMemoria {
+    fn criar<'a>(&'a self, id_usuario: i32, url: &'a str, eventos: &'a [String], segredo: &'a str) -> BoxFuture<'a, Resultado<Webhook>> {
+        let mut dados = self.dados();
+
+        if !dados.usuarios.iter().any(|usuario| usuario.id == id_usuario) {
+            return pronto(Err(sqlx::Error::InvalidArgument(format!("usuário {id_usuario} não existe"))));
+        }
+
+        dados.ultimo_id_webhook += 1;
+        let webhook = Webhook {
+            id: dados.ultimo_id_webhook,
+            id_usuario,
+            url: url.to_string(),
+            eventos: sqlx::types::Json(eventos.to_vec()),
+            segredo: segredo.to_string(),
+            criado_em: Utc::now(),
+        };
+        dados.webhooks.push(webhook.clone());
+
+        pronto(Ok(webhook))
+    }
+
+    fn listar_do_usuario(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<Vec<Webhook>>> {
+        let webhooks = self
+            .dados()
+            .webhooks
+            .iter()
+            .rev()
+            .filter(|webhook| webhook.id_usuario == id_usuario)
+            .cloned()
+            .collect();
+        pronto(Ok(webhooks))
+    }
+
+    fn buscar_do_usuario(&self, id: i32, id_usuario: i32) -> BoxFuture<'_, Resultado<Option<Webhook>>> {
+        let webhook = self
+            .dados()
+            .webhooks
+            .iter()
+            .find(|webhook| webhook.id == id && webhook.id_usuario == id_usuario)
+            .cloned();
+        pronto(Ok(webhook))
+    }
+
+    fn deletar(&self, id: i32, id_usuario: i32) -> BoxFuture<'_, Resultado<bool>> {
+        let removidos = self
+            .dados()
+            .remover_webhooks(|webhook| webhook.id == id && webhook.id_usuario == id_usuario);
+        pronto(Ok(removidos > 0))
+    }
+
+    fn enfileirar<'a>(
+        &'a self,
+        evento: &'a str,
+        id_evento: Uuid,
+        payload: &'a serde_json::Value,
+        so_admins: bool,
+    ) -> BoxFuture<'a, Resultado<u64>> {
+        pronto(Ok(self.dados().enfileirar_entregas(evento, id_evento, payload, so_admins)))
+    }
+
+    fn reservar_entregas(
+        &self,
+        agora: DateTime<Utc>,
+        reservar_ate: DateTime<Utc>,
+        limite: i64,
+    ) -> BoxFuture<'_, Resultado<Vec<EntregaPendente>>> {
+        let mut dados = self.dados();
+        let Dados { entregas, webhooks, usuarios, .. } = &mut *dados;
+
+        let mut vencidas: Vec<&mut EntregaWebhook> = entregas
+            .iter_mut()
+            .filter(|entrega| entrega.status == situacoes::PENDENTE && entrega.proxima_tentativa_em <= agora)
+            .collect();
+        vencidas.sort_by_key(|entrega| entrega.proxima_tentativa_em);
+
+        let mut reservadas = Vec::new();
+        for entrega in vencidas.into_iter().take(limite.max(0) as usize) {
+            let Some(webhook) = webhooks.iter().find(|webhook| webhook.id == entrega.id_webhook) else {
+                continue;
+            };
+            entrega.tentativas += 1;
+            entrega.proxima_tentativa_em = reservar_ate;
+            reservadas.push(EntregaPendente {
+                entrega: entrega.clone(),
+                url: webhook.url.clone(),
+                segredo: webhook.segredo.clone(),
+                dono_admin: usuarios.iter().any(|usuario| usuario.id == webhook.id_usuario && usuario.admin),
+            });
+        }
+        pronto(Ok(reservadas))
+    }
+
+    fn registrar_envio<'a>(&'a self, id_entrega: i64, resultado: &'a ResultadoEnvio) -> BoxFuture<'a, Resultado<()>> {
+        let mut dados = self.dados();
+
+        let tentativa = TentativaEntrega {
+            id: dados.tentativas.last().map_or(1, |ultima| ultima.id + 1),
+            id_entrega,
+            tentada_em: resultado.tentada_em,
+            codigo_status: resultado.codigo_status,
+            erro: resultado.erro.clone(),
+            duracao_ms: resultado.duracao_ms,
+        };
+        dados.tentativas.push(tentativa);
+
+        if let Some(entrega) = dados.entregas.iter_mut().find(|entrega| entrega.id == id_entrega) {
+            entrega.status = resultado.situacao().to_string();
+            entrega.ultimo_status = resultado.codigo_status;
+            entrega.ultimo_erro = resultado.erro.clone();
+            if let Some(proxima) = resultado.proxima_tentativa {
+                entrega.proxima_tentativa_em = proxima;
+            }
+            if resultado.situacao() == situacoes::ENTREGUE {
+                entrega.entregue_em = Some(resultado.tentada_em);
+            }
+        }
+        pronto(Ok(()))
+    }
+
+    fn liberar_entrega(&self, id_entrega: i64, tentativa: i32, agora: DateTime<Utc>) -> BoxFuture<'_, Resultado<bool>> {
+        let mut dados = self.dados();
+        let Some(entrega) = dados.entregas.iter_mut().find(|entrega| {
+            entrega.id == id_entrega && entrega.status == situacoes::PENDENTE && entrega.tentativas == tentativa
+        }) else {
+            return pronto(Ok(false));
+        };
+
+        entrega.tentativas -= 1;
+        entrega.proxima_tentativa_em = agora;
+        pronto(Ok(true))
+    }
+
+    fn listar_entregas(
+        &self,
+        id_webhook: i32,
+        antes_do_id: Option<i64>,
+        limite: i64,
+    ) -> BoxFuture<'_, Resultado<Vec<EntregaWebhook>>> {
+        let entregas = self
+            .dados()
+            .entregas
+            .iter()
+            .rev()
+            .filter(|entrega| entrega.id_webhook == id_webhook)
+            .filter(|entrega| antes_do_id.is_none_or(|id| entrega.id < id))
+            .take(limite.max(0) as usize)
+            .cloned()
+            .collect();
+        pronto(Ok(entregas))
+    }
+
+    fn buscar_entrega(&self, id: i64, id_webhook: i32) -> BoxFuture<'_, Resultado<Option<EntregaWebhook>>> {
+        let entrega = self
+            .dados()
+            .entregas
+            .iter()
+            .find(|entrega| entrega.id == id && entrega.id_webhook == id_webhook)
+            .cloned();
+        pronto(Ok(entrega))
+    }
+
+    fn listar_tentativas(&self, id_entrega: i64) -> BoxFuture<'_, Resultado<Vec<TentativaEntrega>>> {
+        let tentativas = self
+            .dados()
+            .tentativas
+            .iter()
+            .filter(|tentativa| tentativa.id_entrega == id_entrega)
+            .cloned()
+            .collect();
+        pronto(Ok(tentativas))
+    }
+
+    fn reenviar(&self, id: i64, id_webhook: i32) -> BoxFuture<'_, Resultado<Option<EntregaWebhook>>> {
+        let mut dados = self.dados();
+        let Some(original) = dados
+            .entregas
+            .iter()
+            .find(|entrega| entrega.id == id && entrega.id_webhook == id_webhook)
+            .cloned()
+        else {
+            return pronto(Ok(None));
+        };
+
+        let entrega = dados.nova_entrega(id_webhook, original.id_evento, &original.evento, original.payload.0);
+        pronto(Ok(Some(entrega)))
+    }
+}
+
+impl RepositorioTarefas for RepositorioMemoria {
+    fn enfileirar<'a>(&'a self, tarefa: &'a NovaTarefa) -> BoxFuture<'a, Resultado<Option<Tarefa>>> {
+        let mut dados = self.dados();
+        if tarefa.chave.is_some() && dados.tarefas.iter().any(|existente| existente.chave == tarefa.chave) {
+            return pronto(Ok(None));
+        }
+
+        let agora = Utc::now();
+        let nova = Tarefa {
+            id: dados.tarefas.last().map_or(1, |ultima| ultima.id + 1),
+            tipo: tarefa.tipo.to_string(),
+            payload: sqlx::types::Json(tarefa.payload.clone()),
+            status: fila::situacoes::PENDENTE.to_string(),
+            tentativas: 0,
+            maximo_tentativas: tarefa.maximo_tentativas,
+            executar_em: tarefa.executar_em,
+            reservada_ate: None,
+            ultimo_erro: None,
+            chave: tarefa.chave.clone(),
+            criado_em: agora,
+            atualizado_em: agora,
+            concluida_em: None,
+        };
+        dados.tarefas.push(nova.clone());
+        pronto(Ok(Some(nova)))
+    }
+
+    fn reservar<'a>(
+        &'a self,
+        tipos: &'a [&'static str],
+        agora: DateTime<Utc>,
+        reservar_ate: DateTime<Utc>,
+        limite: i64,
+    ) -> BoxFuture<'a, Resultado<Vec<Tarefa>>> {
+        let mut dados = self.dados();
+
+        let mut vencidas: Vec<&mut Tarefa> = dados
+            .tarefas
+            .iter_mut()
+            .filter(|tarefa| tipos.contains(&tarefa.tipo.as_str()))
+            .filter(|tarefa| match tarefa.status.as_str() {
+                fila::situacoes::PENDENTE => tarefa.executar_em <= agora,
+                fila::situacoes::EM_EXECUCAO => tarefa.reservada_ate.is_some_and(|reservada_ate| reservada_ate <= agora),
+                _ => false,
+            })
+            .collect();
+        vencidas.sort_by_key(|tarefa| tarefa.executar_em);
+
+        let mut reservadas = Vec::new();
+        for tarefa in vencidas.into_iter().take(limite.max(0) as usize) {
+            tarefa.status = fila::situacoes::EM_EXECUCAO.to_string();
+            tarefa.tentativas += 1;
+            tarefa.reservada_ate = Some(reservar_ate);
+            tarefa.atualizado_em = Utc::now();
+            reservadas.push(tarefa.clone());
+        }
+        pronto(Ok(reservadas))
+    }
+
+    fn concluir<'a>(&'a self, id: i64, resultado: &'a ResultadoExecucao) -> BoxFuture<'a, Resultado<bool>> {
+        let mut dados = self.dados();
+        let Some(tarefa) = dados.tarefas.iter_mut().find(|tarefa| {
+            tarefa.id == id && tarefa.status == fila::situacoes::EM_EXECUCAO && tarefa.tentativas == resultado.tentativa
+        }) else {
+            return pronto(Ok(false));
+        };
+
+        tarefa.status = resultado.situacao().to_string();
+        tarefa.ultimo_erro = resultado.erro.clone();
+        tarefa.reservada_ate = None;
+        if let Some(proxima) = resultado.proxima_tentativa {
+            tarefa.executar_em = proxima;
+        }
+        tarefa.concluida_em = (resultado.situacao() != fila::situacoes::PENDENTE).then_some(resultado.concluida_em);
+        tarefa.atualizado_em = Utc::now();
+        pronto(Ok(true))
+    }
+
+    fn liberar(&self, id: i64, tentativa: i32) -> BoxFuture<'_, Resultado<bool>> {
+        let mut dados = self.dados();
+        let Some(tarefa) = dados.tarefas.iter_mut().find(|tarefa| {
+            tarefa.id == id && tarefa.status == fila::situacoes::EM_EXECUCAO && tarefa.tentativas == tentativa
+        }) else {
+            return pronto(Ok(false));
+        };
+
+        tarefa.status = fila::situacoes::PENDENTE.to_string();
+        tarefa.tentativas -= 1;
+        tarefa.reservada_ate = None;
+        tarefa.atualizado_em = Utc::now();
+        pronto(Ok(true))
+    }
+
+    fn listar<'a>(&'a self, filtro: &'a FiltroTarefas, limite: i64) -> BoxFuture<'a, Resultado<Vec<Tarefa>>> {
+        let tarefas = self
+            .dados()
+            .tarefas
+            .iter()
+            .rev()
+            .filter(|tarefa| filtro.status.as_ref().is_none_or(|status| &tarefa.status == status))
+            .filter(|tarefa| filtro.tipo.as_ref().is_none_or(|tipo| &tarefa.tipo == tipo))
+            .filter(|tarefa| filtro.antes_do_id.is_none_or(|id| tarefa.id < id))
+            .take(limite.max(0) as usize)
+            .cloned()
+            .collect();
+        pronto(Ok(tarefas))
+    }
+
+    fn buscar(&self, id: i64) -> BoxFuture<'_, Resultado<Option<Tarefa>>> {
+        let tarefa = self.dados().tarefas.iter().find(|tarefa| tarefa.id == id).cloned();
+        pronto(Ok(tarefa))
+    }
+
+    fn tentar_de_novo(&self, id: i64, agora: DateTime<Utc>) -> BoxFuture<'_, Resultado<Option<Tarefa>>> {
+        let mut dados = self.dados();
+        let Some(tarefa) = dados.tarefas.iter_mut().find(|tarefa| {
+            tarefa.id == id && [fila::situacoes::PENDENTE, fila::situacoes::MORTA].contains(&tarefa.status.as_str())
+        }) else {
+            return pronto(Ok(None));
+        };
+
+        tarefa.status = fila::situacoes::PENDENTE.to_string();
+        tarefa.tentativas = 0;
+        tarefa.executar_em = agora;
+        tarefa.reservada_ate = None;
+        tarefa.concluida_em = None;
+        tarefa.atualizado_em = Utc::now();
+        pronto(Ok(Some(tarefa.clone())))
+    }
+
+    fn apagar_concluidas(&self, antes_de: DateTime<Utc>) -> BoxFuture<'_, Resultado<u64>> {
+        let mut dados = self.dados();
+        let antes = dados.tarefas.len();
+        dados.tarefas.retain(|tarefa| {
+            !(tarefa.status == fila::situacoes::CONCLUIDA && tarefa.concluida_em.is_some_and(|concluida_em| concluida_em < antes_de))
+        });
+        pronto(Ok((antes - dados.tarefas.len()) as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn atualizacao_respeita_dono_e_versao() {
+        let repositorio = RepositorioMemoria::default();
+        let dono = RepositorioUsuarios::criar(&repositorio, "dono", "dono@example.com", "hash").await.unwrap();
+        let postagem = RepositorioPostagens::criar(&repositorio, dono.id, "Título", "Conteúdo").await.unwrap();
+
+        let de_outro = repositorio.atualizar(postagem.id, dono.id + 1, "X", "Y", None).await.unwrap();
+        assert!(de_outro.is_none());
+
+        let versao_antiga = postagem.atualizado_em - chrono::Duration::seconds(1);
+        let conflito = repositorio.atualizar(postagem.id, dono.id, "X", "Y", Some(versao_antiga)).await.unwrap();
+        assert!(conflito.is_none());
+
+        let atualizada = repositorio
+            .atualizar(postagem.id, dono.id, "Novo", "Texto", Some(postagem.atualizado_em))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(atualizada.titulo, "Novo");
+    }
+
+    #[tokio::test]
+    async fn email_e_nome_de_usuario_sao_unicos() {
+        let repositorio = RepositorioMemoria::default();
+        RepositorioUsuarios::criar(&repositorio, "ana", "ana@example.com", "hash").await.unwrap();
+
+        assert!(RepositorioUsuarios::criar(&repositorio, "ana", "outra@example.com", "hash").await.is_err());
+        assert!(RepositorioUsuarios::criar(&repositorio, "outra", "ana@example.com", "hash").await.is_err());
+        assert!(repositorio.buscar_conflito("nova@example.com", "ana").await.unwrap().is_some());
+    }
+}
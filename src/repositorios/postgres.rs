@@ -0,0 +1,1273 @@
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use super::{
+    Efeitos, MontarEfeitos, RepositorioAuditoria, RepositorioNotificacoes, RepositorioPostagens, RepositorioSessoes, RepositorioTarefas,
+    RepositorioUsuarios, RepositorioWebhooks, Resultado,
+};
+use crate::{
+    auditoria::{FiltroEventos, NovoEvento},
+    fila::{FiltroTarefas, NovaTarefa, ResultadoExecucao},
+    models::{
+        EntregaPendente, EntregaWebhook, EventoAuditoria, Notificacao, Postagem, Sessao, Tarefa, TentativaEntrega, Usuario,
+        Webhook,
+    },
+    webhooks::ResultadoEnvio,
+};
+
+fn consulta(operacao: &'static str) -> tracing::Span {
+    crate::db::consulta("postgresql", operacao)
+}
+
+pub struct RepositorioPostgres {
+    pool: PgPool,
+}
+
+impl RepositorioPostgres {
+    pub fn new(pool: PgPool) -> Self {
+        RepositorioPostgres { pool }
+    }
+}
+
+// As escritas abaixo servem tanto ao pool (métodos simples) quanto a uma
+// transação (métodos `_com_efeitos`, que gravam a auditoria e as entregas junto)
+
+async fn inserir_usuario<'e>(
+    executor: impl PgExecutor<'e>,
+    nome_usuario: &str,
+    email: &str,
+    hash_senha: &str,
+) -> Resultado<Usuario> {
+    sqlx::query_as::<_, Usuario>(
+        r#"
+        INSERT INTO users (username, email, password_hash)
+        VALUES ($1, $2, $3)
+        RETURNING *
+        "#,
+    )
+    .bind(nome_usuario)
+    .bind(email)
+    .bind(hash_senha)
+    .fetch_one(executor)
+    .instrument(consulta("INSERT users"))
+    .await
+}
+
+async fn inserir_postagem<'e>(executor: impl PgExecutor<'e>, id_usuario: i32, titulo: &str, conteudo: &str) -> Resultado<Postagem> {
+    sqlx::query_as::<_, Postagem>(
+        r#"
+        INSERT INTO posts (title, content, user_id)
+        VALUES ($1, $2, $3)
+        RETURNING *
+        "#,
+    )
+    .bind(titulo)
+    .bind(conteudo)
+    .bind(id_usuario)
+    .fetch_one(executor)
+    .instrument(consulta("INSERT posts"))
+    .await
+}
+
+async fn atualizar_postagem<'e>(
+    executor: impl PgExecutor<'e>,
+    id: i32,
+    id_usuario: i32,
+    titulo: &str,
+    conteudo: &str,
+    versao: Option<DateTime<Utc>>,
+) -> Resultado<Option<Postagem>> {
+    sqlx::query_as::<_, Postagem>(
+        r#"
+        UPDATE posts
+        SET title = $1, content = $2, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $3 AND user_id = $4 AND ($5::timestamptz IS NULL OR updated_at = $5)
+        RETURNING *
+        "#,
+    )
+    .bind(titulo)
+    .bind(conteudo)
+    .bind(id)
+    .bind(id_usuario)
+    .bind(versao)
+    .fetch_optional(executor)
+    .instrument(consulta("UPDATE posts"))
+    .await
+}
+
+async fn apagar_postagem<'e>(executor: impl PgExecutor<'e>, id: i32, id_usuario: i32) -> Resultado<bool> {
+    let resultado = sqlx::query("DELETE FROM posts WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(id_usuario)
+        .execute(executor)
+        .instrument(consulta("DELETE posts"))
+        .await?;
+
+    Ok(resultado.rows_affected() > 0)
+}
+
+async fn inserir_evento<'e>(executor: impl PgExecutor<'e>, evento: &NovoEvento) -> Resultado<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO audit_events (actor_id, action, target_type, target_id, ip, user_agent, diff)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(evento.id_ator)
+    .bind(evento.acao)
+    .bind(evento.tipo_alvo)
+    .bind(&evento.id_alvo)
+    .bind(&evento.ip)
+    .bind(&evento.user_agent)
+    .bind(&evento.diff)
+    .execute(executor)
+    .instrument(consulta("INSERT audit_events"))
+    .await?;
+
+    Ok(())
+}
+
+async fn inserir_entregas<'e>(
+    executor: impl PgExecutor<'e>,
+    evento: &str,
+    id_evento: Uuid,
+    payload: &serde_json::Value,
+    so_admins: bool,
+) -> Resultado<u64> {
+    let resultado = sqlx::query(
+        r#"
+        INSERT INTO webhook_deliveries (webhook_id, event_id, event, payload)
+        SELECT webhooks.id, $1, $2, $3
+        FROM webhooks
+        JOIN users ON users.id = webhooks.user_id
+        WHERE webhooks.events ? $2
+          AND users.disabled_at IS NULL
+          AND (NOT $4 OR users.is_admin)
+        "#,
+    )
+    .bind(id_evento)
+    .bind(evento)
+    .bind(payload)
+    .bind(so_admins)
+    .execute(executor)
+    .instrument(consulta("INSERT webhook_deliveries"))
+    .await?;
+
+    Ok(resultado.rows_affected())
+}
+
+async fn gravar_efeitos(transacao: &mut PgConnection, efeitos: &Efeitos) -> Resultado<()> {
+    inserir_evento(&mut *transacao, &efeitos.auditoria).await?;
+    let webhook = &efeitos.webhook;
+    inserir_entregas(&mut *transacao, webhook.evento, webhook.id, &webhook.payload, webhook.so_admins).await?;
+    Ok(())
+}
+
+impl RepositorioUsuarios for RepositorioPostgres {
+    fn buscar_por_id(&self, id: i32) -> BoxFuture<'_, Resultado<Option<Usuario>>> {
+        Box::pin(
+            sqlx::query_as::<_, Usuario>("SELECT * FROM users WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT users")),
+        )
+    }
+
+    fn buscar_varios<'a>(&'a self, ids: &'a [i32]) -> BoxFuture<'a, Resultado<Vec<Usuario>>> {
+        Box::pin(
+            sqlx::query_as::<_, Usuario>("SELECT * FROM users WHERE id = ANY($1)")
+                .bind(ids)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT users")),
+        )
+    }
+
+    fn buscar_por_email<'a>(&'a self, email: &'a str) -> BoxFuture<'a, Resultado<Option<Usuario>>> {
+        Box::pin(
+            sqlx::query_as::<_, Usuario>("SELECT * FROM users WHERE lower(email) = $1")
+                .bind(email)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT users")),
+        )
+    }
+
+    fn buscar_por_nome_usuario<'a>(&'a self, nome_usuario: &'a str) -> BoxFuture<'a, Resultado<Option<Usuario>>> {
+        Box::pin(
+            sqlx::query_as::<_, Usuario>("SELECT * FROM users WHERE username = $1")
+                .bind(nome_usuario)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT users")),
+        )
+    }
+
+    fn buscar_conflito<'a>(&'a self, email: &'a str, nome_usuario: &'a str) -> BoxFuture<'a, Resultado<Option<Usuario>>> {
+        Box::pin(
+            sqlx::query_as::<_, Usuario>("SELECT * FROM users WHERE lower(email) = lower($1) OR username = $2 LIMIT 1")
+                .bind(email)
+                .bind(nome_usuario)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT users")),
+        )
+    }
+
+    fn criar<'a>(&'a self, nome_usuario: &'a str, email: &'a str, hash_senha: &'a str) -> BoxFuture<'a, Resultado<Usuario>> {
+        Box::pin(inserir_usuario(&self.pool, nome_usuario, email, hash_senha))
+    }
+
+    fn criar_com_efeitos<'a>(
+        &'a self,
+        nome_usuario: &'a str,
+        email: &'a str,
+        hash_senha: &'a str,
+        efeitos: MontarEfeitos<'a, Usuario>,
+    ) -> BoxFuture<'a, Resultado<Usuario>> {
+        Box::pin(async move {
+            let mut transacao = self.pool.begin().await?;
+            let usuario = inserir_usuario(&mut *transacao, nome_usuario, email, hash_senha).await?;
+            gravar_efeitos(&mut transacao, &efeitos(&usuario)).await?;
+            transacao.commit().await?;
+            Ok(usuario)
+        })
+    }
+
+    fn listar(&self) -> BoxFuture<'_, Resultado<Vec<Usuario>>> {
+        Box::pin(
+            sqlx::query_as::<_, Usuario>("SELECT * FROM users ORDER BY created_at DESC")
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT users")),
+        )
+    }
+
+    fn contar(&self) -> BoxFuture<'_, Resultado<i64>> {
+        Box::pin(
+            sqlx::query_scalar("SELECT COUNT(*) FROM users")
+                .fetch_one(&self.pool)
+                .instrument(consulta("SELECT users")),
+        )
+    }
+
+    fn definir_admin(&self, id: i32, admin: bool) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query("UPDATE users SET is_admin = $1 WHERE id = $2")
+                .bind(admin)
+                .bind(id)
+                .execute(&self.pool)
+                .instrument(consulta("UPDATE users"))
+                .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn definir_desativado(&self, id: i32, desativado: bool) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(async move {
+            // Reaplicar a desativação mantém a data original
+            let resultado = sqlx::query(
+                r#"
+                UPDATE users
+                SET disabled_at = CASE WHEN $1 THEN COALESCE(disabled_at, CURRENT_TIMESTAMP) END
+                WHERE id = $2
+                "#,
+            )
+            .bind(desativado)
+            .bind(id)
+            .execute(&self.pool)
+            .instrument(consulta("UPDATE users"))
+            .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn atualizar_senha<'a>(&'a self, id: i32, hash_senha: &'a str) -> BoxFuture<'a, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
+                .bind(hash_senha)
+                .bind(id)
+                .execute(&self.pool)
+                .instrument(consulta("UPDATE users"))
+                .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn agendar_exclusao(&self, id: i32, data: Option<DateTime<Utc>>) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query(
+                "UPDATE users SET deletion_scheduled_at = $1 WHERE id = $2 AND anonymized_at IS NULL"
+            )
+            .bind(data)
+            .bind(id)
+            .execute(&self.pool)
+            .instrument(consulta("UPDATE users"))
+            .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn exclusoes_vencidas(&self, ate: DateTime<Utc>) -> BoxFuture<'_, Resultado<Vec<i32>>> {
+        Box::pin(
+            sqlx::query_scalar("SELECT id FROM users WHERE deletion_scheduled_at <= $1 ORDER BY deletion_scheduled_at")
+                .bind(ate)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT users")),
+        )
+    }
+
+    fn anonimizar<'a>(&'a self, id: i32, nome_usuario: &'a str, email: &'a str) -> BoxFuture<'a, Resultado<bool>> {
+        Box::pin(async move {
+            let mut transacao = self.pool.begin().await?;
+
+            // Antes de trocar o email, que também identifica as falhas de login
+            // antigas, gravadas com o email digitado. É a redação que o gatilho
+            // de audit_events aceita; se a conta já estava anonimizada, o
+            // rollback a desfaz.
+            sqlx::query(
+                r#"
+                UPDATE audit_events
+                SET ip = NULL, user_agent = NULL, diff = diff - ARRAY['email', 'email_hash', 'username']
+                WHERE actor_id = $1
+                   OR (actor_id IS NULL AND target_type = 'user' AND target_id = $1::TEXT)
+                   OR diff->>'email' = (SELECT email FROM users WHERE id = $1)
+                "#,
+            )
+            .bind(id)
+            .execute(&mut *transacao)
+            .instrument(consulta("UPDATE audit_events"))
+            .await?;
+
+            // '!' nunca é um hash bcrypt válido: nenhuma senha confere
+            let resultado = sqlx::query(
+                r#"
+                UPDATE users
+                SET username = $1, email = $2, password_hash = '!', is_admin = FALSE,
+                    disabled_at = COALESCE(disabled_at, CURRENT_TIMESTAMP),
+                    deletion_scheduled_at = NULL, anonymized_at = CURRENT_TIMESTAMP
+                WHERE id = $3 AND anonymized_at IS NULL
+                "#,
+            )
+            .bind(nome_usuario)
+            .bind(email)
+            .bind(id)
+            .execute(&mut *transacao)
+            .instrument(consulta("UPDATE users"))
+            .await?;
+            if resultado.rows_affected() == 0 {
+                return Ok(false);
+            }
+
+            // IP e user agent também são dados pessoais
+            sqlx::query("DELETE FROM sessions WHERE user_id = $1")
+                .bind(id)
+                .execute(&mut *transacao)
+                .instrument(consulta("DELETE sessions"))
+                .await?;
+            sqlx::query("DELETE FROM notifications WHERE user_id = $1")
+                .bind(id)
+                .execute(&mut *transacao)
+                .instrument(consulta("DELETE notifications"))
+                .await?;
+            // As URLs podem identificar o dono; as entregas vão junto (ON DELETE CASCADE)
+            sqlx::query("DELETE FROM webhooks WHERE user_id = $1")
+                .bind(id)
+                .execute(&mut *transacao)
+                .instrument(consulta("DELETE webhooks"))
+                .await?;
+
+            transacao.commit().await?;
+            Ok(true)
+        })
+    }
+
+    fn listar_pagina(&self, apos_id: i32, limite: i64) -> BoxFuture<'_, Resultado<Vec<Usuario>>> {
+        Box::pin(
+            sqlx::query_as::<_, Usuario>("SELECT * FROM users WHERE id > $1 ORDER BY id LIMIT $2")
+                .bind(apos_id)
+                .bind(limite)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT users")),
+        )
+    }
+
+    fn importar<'a>(&'a self, usuario: &'a Usuario, manter_id: bool) -> BoxFuture<'a, Resultado<Usuario>> {
+        Box::pin(async move {
+            let importado = sqlx::query_as::<_, Usuario>(
+                r#"
+                INSERT INTO users (
+                    id, username, email, password_hash, created_at, is_admin, disabled_at,
+                    deletion_scheduled_at, anonymized_at
+                )
+                VALUES (COALESCE($1, nextval(pg_get_serial_sequence('users', 'id'))), $2, $3, $4, $5, $6, $7, $8, $9)
+                RETURNING *
+                "#,
+            )
+            .bind(manter_id.then_some(usuario.id))
+            .bind(&usuario.nome_usuario)
+            .bind(&usuario.email)
+            .bind(&usuario.hash_senha)
+            .bind(usuario.criado_em)
+            .bind(usuario.admin)
+            .bind(usuario.desativado_em)
+            .bind(usuario.exclusao_agendada_em)
+            .bind(usuario.anonimizado_em)
+            .fetch_one(&self.pool)
+            .instrument(consulta("INSERT users"))
+            .await?;
+
+            if manter_id {
+                // Um id explícito não avança a sequência: o próximo cadastro colidiria com ele
+                sqlx::query("SELECT setval(pg_get_serial_sequence('users', 'id'), (SELECT MAX(id) FROM users))")
+                    .execute(&self.pool)
+                    .instrument(consulta("SELECT setval"))
+                    .await?;
+            }
+
+            Ok(importado)
+        })
+    }
+}
+
+impl RepositorioPostagens for RepositorioPostgres {
+    fn listar_recentes(&self, limite: i64) -> BoxFuture<'_, Resultado<Vec<Postagem>>> {
+        Box::pin(
+            sqlx::query_as::<_, Postagem>("SELECT * FROM posts ORDER BY created_at DESC LIMIT $1")
+                .bind(limite)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT posts")),
+        )
+    }
+
+    fn listar_do_usuario(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<Vec<Postagem>>> {
+        Box::pin(
+            sqlx::query_as::<_, Postagem>("SELECT * FROM posts WHERE user_id = $1 ORDER BY created_at DESC")
+                .bind(id_usuario)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT posts")),
+        )
+    }
+
+    fn buscar(&self, id: i32) -> BoxFuture<'_, Resultado<Option<Postagem>>> {
+        Box::pin(
+            sqlx::query_as::<_, Postagem>("SELECT * FROM posts WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT posts")),
+        )
+    }
+
+    fn buscar_do_usuario(&self, id: i32, id_usuario: i32) -> BoxFuture<'_, Resultado<Option<Postagem>>> {
+        Box::pin(
+            sqlx::query_as::<_, Postagem>("SELECT * FROM posts WHERE id = $1 AND user_id = $2")
+                .bind(id)
+                .bind(id_usuario)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT posts")),
+        )
+    }
+
+    fn criar<'a>(&'a self, id_usuario: i32, titulo: &'a str, conteudo: &'a str) -> BoxFuture<'a, Resultado<Postagem>> {
+        Box::pin(inserir_postagem(&self.pool, id_usuario, titulo, conteudo))
+    }
+
+    fn criar_com_efeitos<'a>(
+        &'a self,
+        id_usuario: i32,
+        titulo: &'a str,
+        conteudo: &'a str,
+        efeitos: MontarEfeitos<'a, Postagem>,
+    ) -> BoxFuture<'a, Resultado<Postagem>> {
+        Box::pin(async move {
+            let mut transacao = self.pool.begin().await?;
+            let postagem = inserir_postagem(&mut *transacao, id_usuario, titulo, conteudo).await?;
+            gravar_efeitos(&mut transacao, &efeitos(&postagem)).await?;
+            transacao.commit().await?;
+            Ok(postagem)
+        })
+    }
+
+    fn atualizar<'a>(
+        &'a self,
+        id: i32,
+        id_usuario: i32,
+        titulo: &'a str,
+        conteudo: &'a str,
+        versao: Option<DateTime<Utc>>,
+    ) -> BoxFuture<'a, Resultado<Option<Postagem>>> {
+        Box::pin(atualizar_postagem(&self.pool, id, id_usuario, titulo, conteudo, versao))
+    }
+
+    fn atualizar_com_efeitos<'a>(
+        &'a self,
+        id: i32,
+        id_usuario: i32,
+        titulo: &'a str,
+        conteudo: &'a str,
+        versao: Option<DateTime<Utc>>,
+        efeitos: MontarEfeitos<'a, Postagem>,
+    ) -> BoxFuture<'a, Resultado<Option<Postagem>>> {
+        Box::pin(async move {
+            let mut transacao = self.pool.begin().await?;
+            let Some(postagem) = atualizar_postagem(&mut *transacao, id, id_usuario, titulo, conteudo, versao).await? else {
+                return Ok(None);
+            };
+            gravar_efeitos(&mut transacao, &efeitos(&postagem)).await?;
+            transacao.commit().await?;
+            Ok(Some(postagem))
+        })
+    }
+
+    fn deletar(&self, id: i32, id_usuario: i32) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(apagar_postagem(&self.pool, id, id_usuario))
+    }
+
+    fn deletar_com_efeitos<'a>(&'a self, id: i32, id_usuario: i32, efeitos: &'a Efeitos) -> BoxFuture<'a, Resultado<bool>> {
+        Box::pin(async move {
+            let mut transacao = self.pool.begin().await?;
+            if !apagar_postagem(&mut *transacao, id, id_usuario).await? {
+                return Ok(false);
+            }
+            gravar_efeitos(&mut transacao, efeitos).await?;
+            transacao.commit().await?;
+            Ok(true)
+        })
+    }
+
+    fn remover(&self, id: i32) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query("DELETE FROM posts WHERE id = $1")
+                .bind(id)
+                .execute(&self.pool)
+                .instrument(consulta("DELETE posts"))
+                .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn listar_pagina(&self, apos_id: i32, limite: i64) -> BoxFuture<'_, Resultado<Vec<Postagem>>> {
+        Box::pin(
+            sqlx::query_as::<_, Postagem>("SELECT * FROM posts WHERE id > $1 ORDER BY id LIMIT $2")
+                .bind(apos_id)
+                .bind(limite)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT posts")),
+        )
+    }
+
+    fn listar_anteriores(&self, antes_do_id: Option<i32>, limite: i64) -> BoxFuture<'_, Resultado<Vec<Postagem>>> {
+        Box::pin(
+            sqlx::query_as::<_, Postagem>(
+                "SELECT * FROM posts WHERE ($1::INT IS NULL OR id < $1) ORDER BY id DESC LIMIT $2",
+            )
+            .bind(antes_do_id)
+            .bind(limite)
+            .fetch_all(&self.pool)
+            .instrument(consulta("SELECT posts")),
+        )
+    }
+
+    fn listar_anteriores_dos_usuarios<'a>(
+        &'a self,
+        ids_usuarios: &'a [i32],
+        antes_do_id: Option<i32>,
+        limite: i64,
+    ) -> BoxFuture<'a, Resultado<Vec<Postagem>>> {
+        Box::pin(
+            sqlx::query_as::<_, Postagem>(
+                r#"
+                SELECT id, title, content, user_id, created_at, updated_at
+                FROM (
+                    SELECT *, ROW_NUMBER() OVER (PARTITION BY user_id ORDER BY id DESC) AS posicao
+                    FROM posts
+                    WHERE user_id = ANY($1) AND ($2::INT IS NULL OR id < $2)
+                ) AS paginas
+                WHERE posicao <= $3
+                ORDER BY user_id, id DESC
+                "#,
+            )
+            .bind(ids_usuarios)
+            .bind(antes_do_id)
+            .bind(limite)
+            .fetch_all(&self.pool)
+            .instrument(consulta("SELECT posts")),
+        )
+    }
+
+    fn contar_dos_usuarios<'a>(&'a self, ids_usuarios: &'a [i32]) -> BoxFuture<'a, Resultado<Vec<(i32, i64)>>> {
+        Box::pin(
+            sqlx::query_as("SELECT user_id, COUNT(*) FROM posts WHERE user_id = ANY($1) GROUP BY user_id")
+                .bind(ids_usuarios)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT posts")),
+        )
+    }
+
+    fn importar<'a>(&'a self, postagem: &'a Postagem, manter_id: bool) -> BoxFuture<'a, Resultado<Postagem>> {
+        Box::pin(async move {
+            let importada = sqlx::query_as::<_, Postagem>(
+                r#"
+                INSERT INTO posts (id, title, content, user_id, created_at, updated_at)
+                VALUES (COALESCE($1, nextval(pg_get_serial_sequence('posts', 'id'))), $2, $3, $4, $5, $6)
+                RETURNING *
+                "#,
+            )
+            .bind(manter_id.then_some(postagem.id))
+            .bind(&postagem.titulo)
+            .bind(&postagem.conteudo)
+            .bind(postagem.id_usuario)
+            .bind(postagem.criado_em)
+            .bind(postagem.atualizado_em)
+            .fetch_one(&self.pool)
+            .instrument(consulta("INSERT posts"))
+            .await?;
+
+            if manter_id {
+                // Um id explícito não avança a sequência: o próximo cadastro colidiria com ele
+                sqlx::query("SELECT setval(pg_get_serial_sequence('posts', 'id'), (SELECT MAX(id) FROM posts))")
+                    .execute(&self.pool)
+                    .instrument(consulta("SELECT setval"))
+                    .await?;
+            }
+
+            Ok(importada)
+        })
+    }
+}
+
+impl RepositorioSessoes for RepositorioPostgres {
+    fn criar(&self, id_usuario: i32, user_agent: Option<String>, ip: String) -> BoxFuture<'_, Resultado<Uuid>> {
+        Box::pin(
+            sqlx::query_scalar(
+                r#"
+                INSERT INTO sessions (id, user_id, user_agent, ip)
+                VALUES ($1, $2, $3, $4)
+                RETURNING id
+                "#,
+            )
+            .bind(Uuid::new_v4())
+            .bind(id_usuario)
+            .bind(user_agent)
+            .bind(ip)
+            .fetch_one(&self.pool)
+            .instrument(consulta("INSERT sessions")),
+        )
+    }
+
+    fn visto_em(&self, id: Uuid, id_usuario: i32) -> BoxFuture<'_, Resultado<Option<DateTime<Utc>>>> {
+        Box::pin(
+            sqlx::query_scalar(
+                "SELECT last_seen_at FROM sessions WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL"
+            )
+            .bind(id)
+            .bind(id_usuario)
+            .fetch_optional(&self.pool)
+            .instrument(consulta("SELECT sessions")),
+        )
+    }
+
+    fn registrar_acesso(&self, id: Uuid) -> BoxFuture<'_, Resultado<()>> {
+        Box::pin(async move {
+            sqlx::query("UPDATE sessions SET last_seen_at = CURRENT_TIMESTAMP WHERE id = $1")
+                .bind(id)
+                .execute(&self.pool)
+                .instrument(consulta("UPDATE sessions"))
+                .await?;
+
+            Ok(())
+        })
+    }
+
+    fn listar_ativas(&self, id_usuario: i32, criadas_apos: DateTime<Utc>) -> BoxFuture<'_, Resultado<Vec<Sessao>>> {
+        Box::pin(
+            sqlx::query_as::<_, Sessao>(
+                r#"
+                SELECT * FROM sessions
+                WHERE user_id = $1 AND revoked_at IS NULL AND created_at > $2
+                ORDER BY last_seen_at DESC
+                "#,
+            )
+            .bind(id_usuario)
+            .bind(criadas_apos)
+            .fetch_all(&self.pool)
+            .instrument(consulta("SELECT sessions")),
+        )
+    }
+
+    fn revogar(&self, id: Uuid, id_usuario: i32) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query(
+                r#"
+                UPDATE sessions
+                SET revoked_at = CURRENT_TIMESTAMP
+                WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+                "#,
+            )
+            .bind(id)
+            .bind(id_usuario)
+            .execute(&self.pool)
+            .instrument(consulta("UPDATE sessions"))
+            .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn revogar_todas(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<u64>> {
+        Box::pin(async move {
+            let resultado = sqlx::query(
+                "UPDATE sessions SET revoked_at = CURRENT_TIMESTAMP WHERE user_id = $1 AND revoked_at IS NULL"
+            )
+            .bind(id_usuario)
+            .execute(&self.pool)
+            .instrument(consulta("UPDATE sessions"))
+            .await?;
+
+            Ok(resultado.rows_affected())
+        })
+    }
+
+    fn listar_do_usuario(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<Vec<Sessao>>> {
+        Box::pin(
+            sqlx::query_as::<_, Sessao>("SELECT * FROM sessions WHERE user_id = $1 ORDER BY created_at DESC")
+                .bind(id_usuario)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT sessions")),
+        )
+    }
+
+    fn listar_pagina(&self, apos: Uuid, limite: i64) -> BoxFuture<'_, Resultado<Vec<Sessao>>> {
+        Box::pin(
+            sqlx::query_as::<_, Sessao>("SELECT * FROM sessions WHERE id > $1 ORDER BY id LIMIT $2")
+                .bind(apos)
+                .bind(limite)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT sessions")),
+        )
+    }
+
+    fn importar<'a>(&'a self, sessao: &'a Sessao) -> BoxFuture<'a, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query(
+                r#"
+                INSERT INTO sessions (id, user_id, user_agent, ip, created_at, last_seen_at, revoked_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT (id) DO NOTHING
+                "#,
+            )
+            .bind(sessao.id)
+            .bind(sessao.id_usuario)
+            .bind(&sessao.user_agent)
+            .bind(&sessao.ip)
+            .bind(sessao.criado_em)
+            .bind(sessao.visto_em)
+            .bind(sessao.revogada_em)
+            .execute(&self.pool)
+            .instrument(consulta("INSERT sessions"))
+            .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+}
+
+impl RepositorioAuditoria for RepositorioPostgres {
+    fn registrar<'a>(&'a self, evento: &'a NovoEvento) -> BoxFuture<'a, Resultado<()>> {
+        Box::pin(inserir_evento(&self.pool, evento))
+    }
+
+    fn listar<'a>(&'a self, filtro: &'a FiltroEventos, limite: i64) -> BoxFuture<'a, Resultado<Vec<EventoAuditoria>>> {
+        Box::pin(
+            sqlx::query_as::<_, EventoAuditoria>(
+                r#"
+                SELECT * FROM audit_events
+                WHERE ($1::INTEGER IS NULL OR actor_id = $1)
+                  AND ($2::VARCHAR IS NULL OR action = $2)
+                  AND ($3::TIMESTAMPTZ IS NULL OR created_at >= $3)
+                  AND ($4::TIMESTAMPTZ IS NULL OR created_at < $4)
+                  AND ($5::BIGINT IS NULL OR id < $5)
+                ORDER BY id DESC
+                LIMIT $6
+                "#,
+            )
+            .bind(filtro.id_ator)
+            .bind(&filtro.acao)
+            .bind(filtro.desde)
+            .bind(filtro.ate)
+            .bind(filtro.antes_do_id)
+            .bind(limite)
+            .fetch_all(&self.pool)
+            .instrument(consulta("SELECT audit_events")),
+        )
+    }
+
+    fn listar_do_usuario(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<Vec<EventoAuditoria>>> {
+        Box::pin(
+            sqlx::query_as::<_, EventoAuditoria>(
+                r#"
+                SELECT * FROM audit_events
+                WHERE actor_id = $1 OR (actor_id IS NULL AND target_type = 'user' AND target_id = $1::TEXT)
+                ORDER BY id DESC
+                "#,
+            )
+            .bind(id_usuario)
+            .fetch_all(&self.pool)
+            .instrument(consulta("SELECT audit_events")),
+        )
+    }
+}
+
+impl RepositorioNotificacoes for RepositorioPostgres {
+    fn criar<'a>(&'a self, id_usuario: i32, tipo: &'a str, dados: &'a serde_json::Value) -> BoxFuture<'a, Resultado<Notificacao>> {
+        Box::pin(
+            sqlx::query_as::<_, Notificacao>("INSERT INTO notifications (user_id, kind, data) VALUES ($1, $2, $3) RETURNING *")
+                .bind(id_usuario)
+                .bind(tipo)
+                .bind(dados)
+                .fetch_one(&self.pool)
+                .instrument(consulta("INSERT notifications")),
+        )
+    }
+
+    fn buscar(&self, id: i64) -> BoxFuture<'_, Resultado<Option<Notificacao>>> {
+        Box::pin(
+            sqlx::query_as::<_, Notificacao>("SELECT * FROM notifications WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT notifications")),
+        )
+    }
+
+    fn listar(
+        &self,
+        id_usuario: i32,
+        nao_lidas: bool,
+        antes_do_id: Option<i64>,
+        limite: i64,
+    ) -> BoxFuture<'_, Resultado<Vec<Notificacao>>> {
+        Box::pin(
+            sqlx::query_as::<_, Notificacao>(
+                r#"
+                SELECT * FROM notifications
+                WHERE user_id = $1
+                  AND (NOT $2 OR read_at IS NULL)
+                  AND ($3::BIGINT IS NULL OR id < $3)
+                ORDER BY id DESC
+                LIMIT $4
+                "#,
+            )
+            .bind(id_usuario)
+            .bind(nao_lidas)
+            .bind(antes_do_id)
+            .bind(limite)
+            .fetch_all(&self.pool)
+            .instrument(consulta("SELECT notifications")),
+        )
+    }
+
+    fn contar_nao_lidas(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<i64>> {
+        Box::pin(
+            sqlx::query_scalar("SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND read_at IS NULL")
+                .bind(id_usuario)
+                .fetch_one(&self.pool)
+                .instrument(consulta("SELECT notifications")),
+        )
+    }
+
+    fn marcar_lida(&self, id: i64, id_usuario: i32) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query("UPDATE notifications SET read_at = COALESCE(read_at, CURRENT_TIMESTAMP) WHERE id = $1 AND user_id = $2")
+                .bind(id)
+                .bind(id_usuario)
+                .execute(&self.pool)
+                .instrument(consulta("UPDATE notifications"))
+                .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn marcar_todas_lidas(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<u64>> {
+        Box::pin(async move {
+            let resultado = sqlx::query("UPDATE notifications SET read_at = CURRENT_TIMESTAMP WHERE user_id = $1 AND read_at IS NULL")
+                .bind(id_usuario)
+                .execute(&self.pool)
+                .instrument(consulta("UPDATE notifications"))
+                .await?;
+
+            Ok(resultado.rows_affected())
+        })
+    }
+}
+
+impl RepositorioWebhooks for RepositorioPostgres {
+    fn criar<'a>(&'a self, id_usuario: i32, url: &'a str, eventos: &'a [String], segredo: &'a str) -> BoxFuture<'a, Resultado<Webhook>> {
+        Box::pin(
+            sqlx::query_as::<_, Webhook>("INSERT INTO webhooks (user_id, url, events, secret) VALUES ($1, $2, $3, $4) RETURNING *")
+                .bind(id_usuario)
+                .bind(url)
+                .bind(sqlx::types::Json(eventos))
+                .bind(segredo)
+                .fetch_one(&self.pool)
+                .instrument(consulta("INSERT webhooks")),
+        )
+    }
+
+    fn listar_do_usuario(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<Vec<Webhook>>> {
+        Box::pin(
+            sqlx::query_as::<_, Webhook>("SELECT * FROM webhooks WHERE user_id = $1 ORDER BY id DESC")
+                .bind(id_usuario)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT webhooks")),
+        )
+    }
+
+    fn buscar_do_usuario(&self, id: i32, id_usuario: i32) -> BoxFuture<'_, Resultado<Option<Webhook>>> {
+        Box::pin(
+            sqlx::query_as::<_, Webhook>("SELECT * FROM webhooks WHERE id = $1 AND user_id = $2")
+                .bind(id)
+                .bind(id_usuario)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT webhooks")),
+        )
+    }
+
+    fn deletar(&self, id: i32, id_usuario: i32) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query("DELETE FROM webhooks WHERE id = $1 AND user_id = $2")
+                .bind(id)
+                .bind(id_usuario)
+                .execute(&self.pool)
+                .instrument(consulta("DELETE webhooks"))
+                .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn enfileirar<'a>(
+        &'a self,
+        evento: &'a str,
+        id_evento: Uuid,
+        payload: &'a serde_json::Value,
+        so_admins: bool,
+    ) -> BoxFuture<'a, Resultado<u64>> {
+        Box::pin(inserir_entregas(&self.pool, evento, id_evento, payload, so_admins))
+    }
+
+    fn reservar_entregas(
+        &self,
+        agora: DateTime<Utc>,
+        reservar_ate: DateTime<Utc>,
+        limite: i64,
+    ) -> BoxFuture<'_, Resultado<Vec<EntregaPendente>>> {
+        // SKIP LOCKED: instâncias concorrentes reservam entregas diferentes, sem esperar umas pelas outras
+        Box::pin(
+            sqlx::query_as::<_, EntregaPendente>(
+                r#"
+                UPDATE webhook_deliveries
+                SET attempts = webhook_deliveries.attempts + 1, next_attempt_at = $2
+                FROM webhooks
+                JOIN users ON users.id = webhooks.user_id
+                WHERE webhooks.id = webhook_deliveries.webhook_id
+                  AND webhook_deliveries.id IN (
+                      SELECT id FROM webhook_deliveries
+                      WHERE status = 'pending' AND next_attempt_at <= $1
+                      ORDER BY next_attempt_at
+                      LIMIT $3
+                      FOR UPDATE SKIP LOCKED
+                  )
+                RETURNING webhook_deliveries.*, webhooks.url, webhooks.secret, users.is_admin
+                "#,
+            )
+            .bind(agora)
+            .bind(reservar_ate)
+            .bind(limite)
+            .fetch_all(&self.pool)
+            .instrument(consulta("UPDATE webhook_deliveries")),
+        )
+    }
+
+    fn registrar_envio<'a>(&'a self, id_entrega: i64, resultado: &'a ResultadoEnvio) -> BoxFuture<'a, Resultado<()>> {
+        Box::pin(async move {
+            let mut transacao = self.pool.begin().await?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO webhook_delivery_attempts (delivery_id, attempted_at, status_code, error, duration_ms)
+                VALUES ($1, $2, $3, $4, $5)
+                "#,
+            )
+            .bind(id_entrega)
+            .bind(resultado.tentada_em)
+            .bind(resultado.codigo_status)
+            .bind(&resultado.erro)
+            .bind(resultado.duracao_ms)
+            .execute(&mut *transacao)
+            .instrument(consulta("INSERT webhook_delivery_attempts"))
+            .await?;
+
+            sqlx::query(
+                r#"
+                UPDATE webhook_deliveries
+                SET status = $1, last_status_code = $2, last_error = $3,
+                    next_attempt_at = COALESCE($4, next_attempt_at),
+                    delivered_at = CASE WHEN $1 = 'succeeded' THEN $5 ELSE delivered_at END
+                WHERE id = $6
+                "#,
+            )
+            .bind(resultado.situacao())
+            .bind(resultado.codigo_status)
+            .bind(&resultado.erro)
+            .bind(resultado.proxima_tentativa)
+            .bind(resultado.tentada_em)
+            .bind(id_entrega)
+            .execute(&mut *transacao)
+            .instrument(consulta("UPDATE webhook_deliveries"))
+            .await?;
+
+            transacao.commit().await
+        })
+    }
+
+    fn liberar_entrega(&self, id_entrega: i64, tentativa: i32, agora: DateTime<Utc>) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query(
+                r#"
+                UPDATE webhook_deliveries
+                SET attempts = attempts - 1, next_attempt_at = $3
+                WHERE id = $1 AND status = 'pending' AND attempts = $2
+                "#,
+            )
+            .bind(id_entrega)
+            .bind(tentativa)
+            .bind(agora)
+            .execute(&self.pool)
+            .instrument(consulta("UPDATE webhook_deliveries"))
+            .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn listar_entregas(
+        &self,
+        id_webhook: i32,
+        antes_do_id: Option<i64>,
+        limite: i64,
+    ) -> BoxFuture<'_, Resultado<Vec<EntregaWebhook>>> {
+        Box::pin(
+            sqlx::query_as::<_, EntregaWebhook>(
+                r#"
+                SELECT * FROM webhook_deliveries
+                WHERE webhook_id = $1 AND ($2::BIGINT IS NULL OR id < $2)
+                ORDER BY id DESC
+                LIMIT $3
+                "#,
+            )
+            .bind(id_webhook)
+            .bind(antes_do_id)
+            .bind(limite)
+            .fetch_all(&self.pool)
+            .instrument(consulta("SELECT webhook_deliveries")),
+        )
+    }
+
+    fn buscar_entrega(&self, id: i64, id_webhook: i32) -> BoxFuture<'_, Resultado<Option<EntregaWebhook>>> {
+        Box::pin(
+            sqlx::query_as::<_, EntregaWebhook>("SELECT * FROM webhook_deliveries WHERE id = $1 AND webhook_id = $2")
+                .bind(id)
+                .bind(id_webhook)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT webhook_deliveries")),
+        )
+    }
+
+    fn listar_tentativas(&self, id_entrega: i64) -> BoxFuture<'_, Resultado<Vec<TentativaEntrega>>> {
+        Box::pin(
+            sqlx::query_as::<_, TentativaEntrega>("SELECT * FROM webhook_delivery_attempts WHERE delivery_id = $1 ORDER BY id")
+                .bind(id_entrega)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT webhook_delivery_attempts")),
+        )
+    }
+
+    fn reenviar(&self, id: i64, id_webhook: i32) -> BoxFuture<'_, Resultado<Option<EntregaWebhook>>> {
+        Box::pin(
+            sqlx::query_as::<_, EntregaWebhook>(
+                r#"
+                INSERT INTO webhook_deliveries (webhook_id, event_id, event, payload)
+                SELECT webhook_id, event_id, event, payload FROM webhook_deliveries
+                WHERE id = $1 AND webhook_id = $2
+                RETURNING *
+                "#,
+            )
+            .bind(id)
+            .bind(id_webhook)
+            .fetch_optional(&self.pool)
+            .instrument(consulta("INSERT webhook_deliveries")),
+        )
+    }
+}
+
+impl RepositorioTarefas for RepositorioPostgres {
+    fn enfileirar<'a>(&'a self, tarefa: &'a NovaTarefa) -> BoxFuture<'a, Resultado<Option<Tarefa>>> {
+        Box::pin(
+            sqlx::query_as::<_, Tarefa>(
+                r#"
+                INSERT INTO jobs (kind, payload, max_attempts, run_at, unique_key)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (unique_key) DO NOTHING
+                RETURNING *
+                "#,
+            )
+            .bind(tarefa.tipo)
+            .bind(&tarefa.payload)
+            .bind(tarefa.maximo_tentativas)
+            .bind(tarefa.executar_em)
+            .bind(&tarefa.chave)
+            .fetch_optional(&self.pool)
+            .instrument(consulta("INSERT jobs")),
+        )
+    }
+
+    fn reservar<'a>(
+        &'a self,
+        tipos: &'a [&'static str],
+        agora: DateTime<Utc>,
+        reservar_ate: DateTime<Utc>,
+        limite: i64,
+    ) -> BoxFuture<'a, Resultado<Vec<Tarefa>>> {
+        // SKIP LOCKED: trabalhadores concorrentes reservam tarefas diferentes, sem esperar uns pelos outros
+        Box::pin(
+            sqlx::query_as::<_, Tarefa>(
+                r#"
+                UPDATE jobs
+                SET status = 'running', attempts = attempts + 1, locked_until = $3, updated_at = CURRENT_TIMESTAMP
+                WHERE id IN (
+                    SELECT id FROM jobs
+                    WHERE kind = ANY($1)
+                      AND ((status = 'pending' AND run_at <= $2) OR (status = 'running' AND locked_until <= $2))
+                    ORDER BY run_at
+                    LIMIT $4
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING *
+                "#,
+            )
+            .bind(tipos)
+            .bind(agora)
+            .bind(reservar_ate)
+            .bind(limite)
+            .fetch_all(&self.pool)
+            .instrument(consulta("UPDATE jobs")),
+        )
+    }
+
+    fn concluir<'a>(&'a self, id: i64, resultado: &'a ResultadoExecucao) -> BoxFuture<'a, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado_consulta = sqlx::query(
+                r#"
+                UPDATE jobs
+                SET status = $1, last_error = $2, locked_until = NULL, run_at = COALESCE($3, run_at),
+                    finished_at = CASE WHEN $1 = 'pending' THEN NULL ELSE $4::TIMESTAMPTZ END,
+                    updated_at = CURRENT_TIMESTAMP
+                WHERE id = $5 AND status = 'running' AND attempts = $6
+                "#,
+            )
+            .bind(resultado.situacao())
+            .bind(&resultado.erro)
+            .bind(resultado.proxima_tentativa)
+            .bind(resultado.concluida_em)
+            .bind(id)
+            .bind(resultado.tentativa)
+            .execute(&self.pool)
+            .instrument(consulta("UPDATE jobs"))
+            .await?;
+
+            Ok(resultado_consulta.rows_affected() > 0)
+        })
+    }
+
+    fn liberar(&self, id: i64, tentativa: i32) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query(
+                r#"
+                UPDATE jobs
+                SET status = 'pending', attempts = attempts - 1, locked_until = NULL, updated_at = CURRENT_TIMESTAMP
+                WHERE id = $1 AND status = 'running' AND attempts = $2
+                "#,
+            )
+            .bind(id)
+            .bind(tentativa)
+            .execute(&self.pool)
+            .instrument(consulta("UPDATE jobs"))
+            .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn listar<'a>(&'a self, filtro: &'a FiltroTarefas, limite: i64) -> BoxFuture<'a, Resultado<Vec<Tarefa>>> {
+        Box::pin(
+            sqlx::query_as::<_, Tarefa>(
+                r#"
+                SELECT * FROM jobs
+                WHERE ($1::VARCHAR IS NULL OR status = $1)
+                  AND ($2::VARCHAR IS NULL OR kind = $2)
+                  AND ($3::BIGINT IS NULL OR id < $3)
+                ORDER BY id DESC
+                LIMIT $4
+                "#,
+            )
+            .bind(&filtro.status)
+            .bind(&filtro.tipo)
+            .bind(filtro.antes_do_id)
+            .bind(limite)
+            .fetch_all(&self.pool)
+            .instrument(consulta("SELECT jobs")),
+        )
+    }
+
+    fn buscar(&self, id: i64) -> BoxFuture<'_, Resultado<Option<Tarefa>>> {
+        Box::pin(
+            sqlx::query_as::<_, Tarefa>("SELECT * FROM jobs WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT jobs")),
+        )
+    }
+
+    fn tentar_de_novo(&self, id: i64, agora: DateTime<Utc>) -> BoxFuture<'_, Resultado<Option<Tarefa>>> {
+        Box::pin(
+            sqlx::query_as::<_, Tarefa>(
+                r#"
+                UPDATE jobs
+                SET status = 'pending', attempts = 0, run_at = $2, locked_until = NULL, finished_at = NULL,
+                    updated_at = CURRENT_TIMESTAMP
+                WHERE id = $1 AND status IN ('pending', 'dead')
+                RETURNING *
+                "#,
+            )
+            .bind(id)
+            .bind(agora)
+            .fetch_optional(&self.pool)
+            .instrument(consulta("UPDATE jobs")),
+        )
+    }
+
+    fn apagar_concluidas(&self, antes_de: DateTime<Utc>) -> BoxFuture<'_, Resultado<u64>> {
+        Box::pin(async move {
+            let resultado = sqlx::query("DELETE FROM jobs WHERE status = 'succeeded' AND finished_at < $1")
+                .bind(antes_de)
+                .execute(&self.pool)
+                .instrument(consulta("DELETE jobs"))
+                .await?;
+            Ok(resultado.rows_affected())
+        })
+    }
+}
@@ -0,0 +1,1306 @@
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use sqlx::{SqliteConnection, SqliteExecutor, SqlitePool};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use super::{
+    Efeitos, MontarEfeitos, RepositorioAuditoria, RepositorioNotificacoes, RepositorioPostagens, RepositorioSessoes, RepositorioTarefas,
+    RepositorioUsuarios, RepositorioWebhooks, Resultado,
+};
+use crate::{
+    auditoria::{FiltroEventos, NovoEvento},
+    fila::{FiltroTarefas, NovaTarefa, ResultadoExecucao},
+    models::{
+        EntregaPendente, EntregaWebhook, EventoAuditoria, Notificacao, Postagem, Sessao, Tarefa, TentativaEntrega, Usuario,
+        Webhook,
+    },
+    webhooks::ResultadoEnvio,
+};
+
+// O SQLite guarda datas como texto. As datas são sempre geradas aqui (e não com
+// CURRENT_TIMESTAMP) para ficarem no mesmo formato dos valores comparados nas
+// consultas, como o `updated_at` da concorrência otimista.
+
+fn consulta(operacao: &'static str) -> tracing::Span {
+    crate::db::consulta("sqlite", operacao)
+}
+
+pub struct RepositorioSqlite {
+    pool: SqlitePool,
+}
+
+impl RepositorioSqlite {
+    pub fn new(pool: SqlitePool) -> Self {
+        RepositorioSqlite { pool }
+    }
+}
+
+// As escritas abaixo servem tanto ao pool (métodos simples) quanto a uma
+// transação (métodos `_com_efeitos`, que gravam a auditoria e as entregas junto)
+
+async fn inserir_usuario<'e>(
+    executor: impl SqliteExecutor<'e>,
+    nome_usuario: &str,
+    email: &str,
+    hash_senha: &str,
+) -> Resultado<Usuario> {
+    sqlx::query_as::<_, Usuario>(
+        r#"
+        INSERT INTO users (username, email, password_hash, created_at)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+        "#,
+    )
+    .bind(nome_usuario)
+    .bind(email)
+    .bind(hash_senha)
+    .bind(Utc::now())
+    .fetch_one(executor)
+    .instrument(consulta("INSERT users"))
+    .await
+}
+
+async fn inserir_postagem<'e>(
+    executor: impl SqliteExecutor<'e>,
+    id_usuario: i32,
+    titulo: &str,
+    conteudo: &str,
+) -> Resultado<Postagem> {
+    sqlx::query_as::<_, Postagem>(
+        r#"
+        INSERT INTO posts (title, content, user_id, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $4)
+        RETURNING *
+        "#,
+    )
+    .bind(titulo)
+    .bind(conteudo)
+    .bind(id_usuario)
+    .bind(Utc::now())
+    .fetch_one(executor)
+    .instrument(consulta("INSERT posts"))
+    .await
+}
+
+async fn atualizar_postagem<'e>(
+    executor: impl SqliteExecutor<'e>,
+    id: i32,
+    id_usuario: i32,
+    titulo: &str,
+    conteudo: &str,
+    versao: Option<DateTime<Utc>>,
+) -> Resultado<Option<Postagem>> {
+    sqlx::query_as::<_, Postagem>(
+        r#"
+        UPDATE posts
+        SET title = $1, content = $2, updated_at = $6
+        WHERE id = $3 AND user_id = $4 AND ($5 IS NULL OR updated_at = $5)
+        RETURNING *
+        "#,
+    )
+    .bind(titulo)
+    .bind(conteudo)
+    .bind(id)
+    .bind(id_usuario)
+    .bind(versao)
+    .bind(Utc::now())
+    .fetch_optional(executor)
+    .instrument(consulta("UPDATE posts"))
+    .await
+}
+
+async fn apagar_postagem<'e>(executor: impl SqliteExecutor<'e>, id: i32, id_usuario: i32) -> Resultado<bool> {
+    let resultado = sqlx::query("DELETE FROM posts WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(id_usuario)
+        .execute(executor)
+        .instrument(consulta("DELETE posts"))
+        .await?;
+
+    Ok(resultado.rows_affected() > 0)
+}
+
+async fn inserir_evento<'e>(executor: impl SqliteExecutor<'e>, evento: &NovoEvento) -> Resultado<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO audit_events (actor_id, action, target_type, target_id, ip, user_agent, diff, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+    )
+    .bind(evento.id_ator)
+    .bind(evento.acao)
+    .bind(evento.tipo_alvo)
+    .bind(&evento.id_alvo)
+    .bind(&evento.ip)
+    .bind(&evento.user_agent)
+    .bind(&evento.diff)
+    .bind(Utc::now())
+    .execute(executor)
+    .instrument(consulta("INSERT audit_events"))
+    .await?;
+
+    Ok(())
+}
+
+async fn inserir_entregas<'e>(
+    executor: impl SqliteExecutor<'e>,
+    evento: &str,
+    id_evento: Uuid,
+    payload: &serde_json::Value,
+    so_admins: bool,
+) -> Resultado<u64> {
+    let resultado = sqlx::query(
+        r#"
+        INSERT INTO webhook_deliveries (webhook_id, event_id, event, payload, next_attempt_at, created_at)
+        SELECT webhooks.id, $1, $2, $3, $5, $5
+        FROM webhooks
+        JOIN users ON users.id = webhooks.user_id
+        WHERE EXISTS (SELECT 1 FROM json_each(webhooks.events) WHERE json_each.value = $2)
+          AND users.disabled_at IS NULL
+          AND (NOT $4 OR users.is_admin)
+        "#,
+    )
+    .bind(id_evento)
+    .bind(evento)
+    .bind(payload)
+    .bind(so_admins)
+    .bind(Utc::now())
+    .execute(executor)
+    .instrument(consulta("INSERT webhook_deliveries"))
+    .await?;
+
+    Ok(resultado.rows_affected())
+}
+
+async fn gravar_efeitos(transacao: &mut SqliteConnection, efeitos: &Efeitos) -> Resultado<()> {
+    inserir_evento(&mut *transacao, &efeitos.auditoria).await?;
+    let webhook = &efeitos.webhook;
+    inserir_entregas(&mut *transacao, webhook.evento, webhook.id, &webhook.payload, webhook.so_admins).await?;
+    Ok(())
+}
+
+impl RepositorioUsuarios for RepositorioSqlite {
+    fn buscar_por_id(&self, id: i32) -> BoxFuture<'_, Resultado<Option<Usuario>>> {
+        Box::pin(
+            sqlx::query_as::<_, Usuario>("SELECT * FROM users WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT users")),
+        )
+    }
+
+    fn buscar_varios<'a>(&'a self, ids: &'a [i32]) -> BoxFuture<'a, Resultado<Vec<Usuario>>> {
+        Box::pin(
+            sqlx::query_as::<_, Usuario>("SELECT * FROM users WHERE id IN (SELECT value FROM json_each($1))")
+                .bind(serde_json::json!(ids))
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT users")),
+        )
+    }
+
+    fn buscar_por_email<'a>(&'a self, email: &'a str) -> BoxFuture<'a, Resultado<Option<Usuario>>> {
+        Box::pin(
+            sqlx::query_as::<_, Usuario>("SELECT * FROM users WHERE lower(email) = $1")
+                .bind(email)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT users")),
+        )
+    }
+
+    fn buscar_por_nome_usuario<'a>(&'a self, nome_usuario: &'a str) -> BoxFuture<'a, Resultado<Option<Usuario>>> {
+        Box::pin(
+            sqlx::query_as::<_, Usuario>("SELECT * FROM users WHERE username = $1")
+                .bind(nome_usuario)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT users")),
+        )
+    }
+
+    fn buscar_conflito<'a>(&'a self, email: &'a str, nome_usuario: &'a str) -> BoxFuture<'a, Resultado<Option<Usuario>>> {
+        Box::pin(
+            sqlx::query_as::<_, Usuario>("SELECT * FROM users WHERE lower(email) = lower($1) OR username = $2 LIMIT 1")
+                .bind(email)
+                .bind(nome_usuario)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT users")),
+        )
+    }
+
+    fn criar<'a>(&'a self, nome_usuario: &'a str, email: &'a str, hash_senha: &'a str) -> BoxFuture<'a, Resultado<Usuario>> {
+        Box::pin(inserir_usuario(&self.pool, nome_usuario, email, hash_senha))
+    }
+
+    fn criar_com_efeitos<'a>(
+        &'a self,
+        nome_usuario: &'a str,
+        email: &'a str,
+        hash_senha: &'a str,
+        efeitos: MontarEfeitos<'a, Usuario>,
+    ) -> BoxFuture<'a, Resultado<Usuario>> {
+        Box::pin(async move {
+            let mut transacao = self.pool.begin().await?;
+            let usuario = inserir_usuario(&mut *transacao, nome_usuario, email, hash_senha).await?;
+            gravar_efeitos(&mut transacao, &efeitos(&usuario)).await?;
+            transacao.commit().await?;
+            Ok(usuario)
+        })
+    }
+
+    fn listar(&self) -> BoxFuture<'_, Resultado<Vec<Usuario>>> {
+        Box::pin(
+            sqlx::query_as::<_, Usuario>("SELECT * FROM users ORDER BY created_at DESC")
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT users")),
+        )
+    }
+
+    fn contar(&self) -> BoxFuture<'_, Resultado<i64>> {
+        Box::pin(
+            sqlx::query_scalar("SELECT COUNT(*) FROM users")
+                .fetch_one(&self.pool)
+                .instrument(consulta("SELECT users")),
+        )
+    }
+
+    fn definir_admin(&self, id: i32, admin: bool) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query("UPDATE users SET is_admin = $1 WHERE id = $2")
+                .bind(admin)
+                .bind(id)
+                .execute(&self.pool)
+                .instrument(consulta("UPDATE users"))
+                .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn definir_desativado(&self, id: i32, desativado: bool) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(async move {
+            // Reaplicar a desativação mantém a data original
+            let resultado = sqlx::query(
+                r#"
+                UPDATE users
+                SET disabled_at = CASE WHEN $1 THEN COALESCE(disabled_at, $2) END
+                WHERE id = $3
+                "#,
+            )
+            .bind(desativado)
+            .bind(Utc::now())
+            .bind(id)
+            .execute(&self.pool)
+            .instrument(consulta("UPDATE users"))
+            .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn atualizar_senha<'a>(&'a self, id: i32, hash_senha: &'a str) -> BoxFuture<'a, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
+                .bind(hash_senha)
+                .bind(id)
+                .execute(&self.pool)
+                .instrument(consulta("UPDATE users"))
+                .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn agendar_exclusao(&self, id: i32, data: Option<DateTime<Utc>>) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query(
+                "UPDATE users SET deletion_scheduled_at = $1 WHERE id = $2 AND anonymized_at IS NULL"
+            )
+            .bind(data)
+            .bind(id)
+            .execute(&self.pool)
+            .instrument(consulta("UPDATE users"))
+            .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn exclusoes_vencidas(&self, ate: DateTime<Utc>) -> BoxFuture<'_, Resultado<Vec<i32>>> {
+        Box::pin(
+            sqlx::query_scalar("SELECT id FROM users WHERE deletion_scheduled_at <= $1 ORDER BY deletion_scheduled_at")
+                .bind(ate)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT users")),
+        )
+    }
+
+    fn anonimizar<'a>(&'a self, id: i32, nome_usuario: &'a str, email: &'a str) -> BoxFuture<'a, Resultado<bool>> {
+        Box::pin(async move {
+            let mut transacao = self.pool.begin().await?;
+
+            // Antes de trocar o email, que também identifica as falhas de login
+            // antigas, gravadas com o email digitado. É a redação que o gatilho
+            // de audit_events aceita; se a conta já estava anonimizada, o
+            // rollback a desfaz.
+            sqlx::query(
+                r#"
+                UPDATE audit_events
+                SET ip = NULL, user_agent = NULL, diff = json_remove(diff, '$.email', '$.email_hash', '$.username')
+                WHERE actor_id = $1
+                   OR (actor_id IS NULL AND target_type = 'user' AND target_id = CAST($1 AS TEXT))
+                   OR json_extract(diff, '$.email') = (SELECT email FROM users WHERE id = $1)
+                "#,
+            )
+            .bind(id)
+            .execute(&mut *transacao)
+            .instrument(consulta("UPDATE audit_events"))
+            .await?;
+
+            // '!' nunca é um hash bcrypt válido: nenhuma senha confere
+            let resultado = sqlx::query(
+                r#"
+                UPDATE users
+                SET username = $1, email = $2, password_hash = '!', is_admin = 0,
+                    disabled_at = COALESCE(disabled_at, $3),
+                    deletion_scheduled_at = NULL, anonymized_at = $3
+                WHERE id = $4 AND anonymized_at IS NULL
+                "#,
+            )
+            .bind(nome_usuario)
+            .bind(email)
+            .bind(Utc::now())
+            .bind(id)
+            .execute(&mut *transacao)
+            .instrument(consulta("UPDATE users"))
+            .await?;
+            if resultado.rows_affected() == 0 {
+                return Ok(false);
+            }
+
+            // IP e user agent também são dados pessoais
+            sqlx::query("DELETE FROM sessions WHERE user_id = $1")
+                .bind(id)
+                .execute(&mut *transacao)
+                .instrument(consulta("DELETE sessions"))
+                .await?;
+            sqlx::query("DELETE FROM notifications WHERE user_id = $1")
+                .bind(id)
+                .execute(&mut *transacao)
+                .instrument(consulta("DELETE notifications"))
+                .await?;
+            // As URLs podem identificar o dono; as entregas vão junto (ON DELETE CASCADE)
+            sqlx::query("DELETE FROM webhooks WHERE user_id = $1")
+                .bind(id)
+                .execute(&mut *transacao)
+                .instrument(consulta("DELETE webhooks"))
+                .await?;
+
+            transacao.commit().await?;
+            Ok(true)
+        })
+    }
+
+    fn listar_pagina(&self, apos_id: i32, limite: i64) -> BoxFuture<'_, Resultado<Vec<Usuario>>> {
+        Box::pin(
+            sqlx::query_as::<_, Usuario>("SELECT * FROM users WHERE id > $1 ORDER BY id LIMIT $2")
+                .bind(apos_id)
+                .bind(limite)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT users")),
+        )
+    }
+
+    fn importar<'a>(&'a self, usuario: &'a Usuario, manter_id: bool) -> BoxFuture<'a, Resultado<Usuario>> {
+        Box::pin(
+            sqlx::query_as::<_, Usuario>(
+                r#"
+                INSERT INTO users (
+                    id, username, email, password_hash, created_at, is_admin, disabled_at,
+                    deletion_scheduled_at, anonymized_at
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                RETURNING *
+                "#,
+            )
+            .bind(manter_id.then_some(usuario.id))
+            .bind(&usuario.nome_usuario)
+            .bind(&usuario.email)
+            .bind(&usuario.hash_senha)
+            .bind(usuario.criado_em)
+            .bind(usuario.admin)
+            .bind(usuario.desativado_em)
+            .bind(usuario.exclusao_agendada_em)
+            .bind(usuario.anonimizado_em)
+            .fetch_one(&self.pool)
+            .instrument(consulta("INSERT users")),
+        )
+    }
+}
+
+impl RepositorioPostagens for RepositorioSqlite {
+    fn listar_recentes(&self, limite: i64) -> BoxFuture<'_, Resultado<Vec<Postagem>>> {
+        Box::pin(
+            sqlx::query_as::<_, Postagem>("SELECT * FROM posts ORDER BY created_at DESC LIMIT $1")
+                .bind(limite)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT posts")),
+        )
+    }
+
+    fn listar_do_usuario(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<Vec<Postagem>>> {
+        Box::pin(
+            sqlx::query_as::<_, Postagem>("SELECT * FROM posts WHERE user_id = $1 ORDER BY created_at DESC")
+                .bind(id_usuario)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT posts")),
+        )
+    }
+
+    fn buscar(&self, id: i32) -> BoxFuture<'_, Resultado<Option<Postagem>>> {
+        Box::pin(
+            sqlx::query_as::<_, Postagem>("SELECT * FROM posts WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT posts")),
+        )
+    }
+
+    fn buscar_do_usuario(&self, id: i32, id_usuario: i32) -> BoxFuture<'_, Resultado<Option<Postagem>>> {
+        Box::pin(
+            sqlx::query_as::<_, Postagem>("SELECT * FROM posts WHERE id = $1 AND user_id = $2")
+                .bind(id)
+                .bind(id_usuario)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT posts")),
+        )
+    }
+
+    fn criar<'a>(&'a self, id_usuario: i32, titulo: &'a str, conteudo: &'a str) -> BoxFuture<'a, Resultado<Postagem>> {
+        Box::pin(inserir_postagem(&self.pool, id_usuario, titulo, conteudo))
+    }
+
+    fn criar_com_efeitos<'a>(
+        &'a self,
+        id_usuario: i32,
+        titulo: &'a str,
+        conteudo: &'a str,
+        efeitos: MontarEfeitos<'a, Postagem>,
+    ) -> BoxFuture<'a, Resultado<Postagem>> {
+        Box::pin(async move {
+            let mut transacao = self.pool.begin().await?;
+            let postagem = inserir_postagem(&mut *transacao, id_usuario, titulo, conteudo).await?;
+            gravar_efeitos(&mut transacao, &efeitos(&postagem)).await?;
+            transacao.commit().await?;
+            Ok(postagem)
+        })
+    }
+
+    fn atualizar<'a>(
+        &'a self,
+        id: i32,
+        id_usuario: i32,
+        titulo: &'a str,
+        conteudo: &'a str,
+        versao: Option<DateTime<Utc>>,
+    ) -> BoxFuture<'a, Resultado<Option<Postagem>>> {
+        Box::pin(atualizar_postagem(&self.pool, id, id_usuario, titulo, conteudo, versao))
+    }
+
+    fn atualizar_com_efeitos<'a>(
+        &'a self,
+        id: i32,
+        id_usuario: i32,
+        titulo: &'a str,
+        conteudo: &'a str,
+        versao: Option<DateTime<Utc>>,
+        efeitos: MontarEfeitos<'a, Postagem>,
+    ) -> BoxFuture<'a, Resultado<Option<Postagem>>> {
+        Box::pin(async move {
+            let mut transacao = self.pool.begin().await?;
+            let Some(postagem) = atualizar_postagem(&mut *transacao, id, id_usuario, titulo, conteudo, versao).await? else {
+                return Ok(None);
+            };
+            gravar_efeitos(&mut transacao, &efeitos(&postagem)).await?;
+            transacao.commit().await?;
+            Ok(Some(postagem))
+        })
+    }
+
+    fn deletar(&self, id: i32, id_usuario: i32) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(apagar_postagem(&self.pool, id, id_usuario))
+    }
+
+    fn deletar_com_efeitos<'a>(&'a self, id: i32, id_usuario: i32, efeitos: &'a Efeitos) -> BoxFuture<'a, Resultado<bool>> {
+        Box::pin(async move {
+            let mut transacao = self.pool.begin().await?;
+            if !apagar_postagem(&mut *transacao, id, id_usuario).await? {
+                return Ok(false);
+            }
+            gravar_efeitos(&mut transacao, efeitos).await?;
+            transacao.commit().await?;
+            Ok(true)
+        })
+    }
+
+    fn remover(&self, id: i32) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query("DELETE FROM posts WHERE id = $1")
+                .bind(id)
+                .execute(&self.pool)
+                .instrument(consulta("DELETE posts"))
+                .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn listar_pagina(&self, apos_id: i32, limite: i64) -> BoxFuture<'_, Resultado<Vec<Postagem>>> {
+        Box::pin(
+            sqlx::query_as::<_, Postagem>("SELECT * FROM posts WHERE id > $1 ORDER BY id LIMIT $2")
+                .bind(apos_id)
+                .bind(limite)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT posts")),
+        )
+    }
+
+    fn listar_anteriores(&self, antes_do_id: Option<i32>, limite: i64) -> BoxFuture<'_, Resultado<Vec<Postagem>>> {
+        Box::pin(
+            sqlx::query_as::<_, Postagem>("SELECT * FROM posts WHERE ($1 IS NULL OR id < $1) ORDER BY id DESC LIMIT $2")
+                .bind(antes_do_id)
+                .bind(limite)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT posts")),
+        )
+    }
+
+    fn listar_anteriores_dos_usuarios<'a>(
+        &'a self,
+        ids_usuarios: &'a [i32],
+        antes_do_id: Option<i32>,
+        limite: i64,
+    ) -> BoxFuture<'a, Resultado<Vec<Postagem>>> {
+        Box::pin(
+            sqlx::query_as::<_, Postagem>(
+                r#"
+                SELECT id, title, content, user_id, created_at, updated_at
+                FROM (
+                    SELECT *, ROW_NUMBER() OVER (PARTITION BY user_id ORDER BY id DESC) AS posicao
+                    FROM posts
+                    WHERE user_id IN (SELECT value FROM json_each($1)) AND ($2 IS NULL OR id < $2)
+                ) AS paginas
+                WHERE posicao <= $3
+                ORDER BY user_id, id DESC
+                "#,
+            )
+            .bind(serde_json::json!(ids_usuarios))
+            .bind(antes_do_id)
+            .bind(limite)
+            .fetch_all(&self.pool)
+            .instrument(consulta("SELECT posts")),
+        )
+    }
+
+    fn contar_dos_usuarios<'a>(&'a self, ids_usuarios: &'a [i32]) -> BoxFuture<'a, Resultado<Vec<(i32, i64)>>> {
+        Box::pin(
+            sqlx::query_as(
+                "SELECT user_id, COUNT(*) FROM posts WHERE user_id IN (SELECT value FROM json_each($1)) GROUP BY user_id",
+            )
+            .bind(serde_json::json!(ids_usuarios))
+            .fetch_all(&self.pool)
+            .instrument(consulta("SELECT posts")),
+        )
+    }
+
+    fn importar<'a>(&'a self, postagem: &'a Postagem, manter_id: bool) -> BoxFuture<'a, Resultado<Postagem>> {
+        Box::pin(
+            sqlx::query_as::<_, Postagem>(
+                r#"
+                INSERT INTO posts (id, title, content, user_id, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING *
+                "#,
+            )
+            .bind(manter_id.then_some(postagem.id))
+            .bind(&postagem.titulo)
+            .bind(&postagem.conteudo)
+            .bind(postagem.id_usuario)
+            .bind(postagem.criado_em)
+            .bind(postagem.atualizado_em)
+            .fetch_one(&self.pool)
+            .instrument(consulta("INSERT posts")),
+        )
+    }
+}
+
+impl RepositorioSessoes for RepositorioSqlite {
+    fn criar(&self, id_usuario: i32, user_agent: Option<String>, ip: String) -> BoxFuture<'_, Resultado<Uuid>> {
+        let agora = Utc::now();
+
+        Box::pin(
+            sqlx::query_scalar(
+                r#"
+                INSERT INTO sessions (id, user_id, user_agent, ip, created_at, last_seen_at)
+                VALUES ($1, $2, $3, $4, $5, $5)
+                RETURNING id
+                "#,
+            )
+            .bind(Uuid::new_v4())
+            .bind(id_usuario)
+            .bind(user_agent)
+            .bind(ip)
+            .bind(agora)
+            .fetch_one(&self.pool)
+            .instrument(consulta("INSERT sessions")),
+        )
+    }
+
+    fn visto_em(&self, id: Uuid, id_usuario: i32) -> BoxFuture<'_, Resultado<Option<DateTime<Utc>>>> {
+        Box::pin(
+            sqlx::query_scalar(
+                "SELECT last_seen_at FROM sessions WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL"
+            )
+            .bind(id)
+            .bind(id_usuario)
+            .fetch_optional(&self.pool)
+            .instrument(consulta("SELECT sessions")),
+        )
+    }
+
+    fn registrar_acesso(&self, id: Uuid) -> BoxFuture<'_, Resultado<()>> {
+        Box::pin(async move {
+            sqlx::query("UPDATE sessions SET last_seen_at = $1 WHERE id = $2")
+                .bind(Utc::now())
+                .bind(id)
+                .execute(&self.pool)
+                .instrument(consulta("UPDATE sessions"))
+                .await?;
+
+            Ok(())
+        })
+    }
+
+    fn listar_ativas(&self, id_usuario: i32, criadas_apos: DateTime<Utc>) -> BoxFuture<'_, Resultado<Vec<Sessao>>> {
+        Box::pin(
+            sqlx::query_as::<_, Sessao>(
+                r#"
+                SELECT * FROM sessions
+                WHERE user_id = $1 AND revoked_at IS NULL AND created_at > $2
+                ORDER BY last_seen_at DESC
+                "#,
+            )
+            .bind(id_usuario)
+            .bind(criadas_apos)
+            .fetch_all(&self.pool)
+            .instrument(consulta("SELECT sessions")),
+        )
+    }
+
+    fn revogar(&self, id: Uuid, id_usuario: i32) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query(
+                r#"
+                UPDATE sessions
+                SET revoked_at = $1
+                WHERE id = $2 AND user_id = $3 AND revoked_at IS NULL
+                "#,
+            )
+            .bind(Utc::now())
+            .bind(id)
+            .bind(id_usuario)
+            .execute(&self.pool)
+            .instrument(consulta("UPDATE sessions"))
+            .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn revogar_todas(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<u64>> {
+        Box::pin(async move {
+            let resultado = sqlx::query("UPDATE sessions SET revoked_at = $1 WHERE user_id = $2 AND revoked_at IS NULL")
+                .bind(Utc::now())
+                .bind(id_usuario)
+                .execute(&self.pool)
+                .instrument(consulta("UPDATE sessions"))
+                .await?;
+
+            Ok(resultado.rows_affected())
+        })
+    }
+
+    fn listar_do_usuario(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<Vec<Sessao>>> {
+        Box::pin(
+            sqlx::query_as::<_, Sessao>("SELECT * FROM sessions WHERE user_id = $1 ORDER BY created_at DESC")
+                .bind(id_usuario)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT sessions")),
+        )
+    }
+
+    fn listar_pagina(&self, apos: Uuid, limite: i64) -> BoxFuture<'_, Resultado<Vec<Sessao>>> {
+        Box::pin(
+            sqlx::query_as::<_, Sessao>("SELECT * FROM sessions WHERE id > $1 ORDER BY id LIMIT $2")
+                .bind(apos)
+                .bind(limite)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT sessions")),
+        )
+    }
+
+    fn importar<'a>(&'a self, sessao: &'a Sessao) -> BoxFuture<'a, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query(
+                r#"
+                INSERT INTO sessions (id, user_id, user_agent, ip, created_at, last_seen_at, revoked_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT (id) DO NOTHING
+                "#,
+            )
+            .bind(sessao.id)
+            .bind(sessao.id_usuario)
+            .bind(&sessao.user_agent)
+            .bind(&sessao.ip)
+            .bind(sessao.criado_em)
+            .bind(sessao.visto_em)
+            .bind(sessao.revogada_em)
+            .execute(&self.pool)
+            .instrument(consulta("INSERT sessions"))
+            .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+}
+
+impl RepositorioAuditoria for RepositorioSqlite {
+    fn registrar<'a>(&'a self, evento: &'a NovoEvento) -> BoxFuture<'a, Resultado<()>> {
+        Box::pin(inserir_evento(&self.pool, evento))
+    }
+
+    fn listar<'a>(&'a self, filtro: &'a FiltroEventos, limite: i64) -> BoxFuture<'a, Resultado<Vec<EventoAuditoria>>> {
+        Box::pin(
+            sqlx::query_as::<_, EventoAuditoria>(
+                r#"
+                SELECT * FROM audit_events
+                WHERE ($1 IS NULL OR actor_id = $1)
+                  AND ($2 IS NULL OR action = $2)
+                  AND ($3 IS NULL OR created_at >= $3)
+                  AND ($4 IS NULL OR created_at < $4)
+                  AND ($5 IS NULL OR id < $5)
+                ORDER BY id DESC
+                LIMIT $6
+                "#,
+            )
+            .bind(filtro.id_ator)
+            .bind(&filtro.acao)
+            .bind(filtro.desde)
+            .bind(filtro.ate)
+            .bind(filtro.antes_do_id)
+            .bind(limite)
+            .fetch_all(&self.pool)
+            .instrument(consulta("SELECT audit_events")),
+        )
+    }
+
+    fn listar_do_usuario(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<Vec<EventoAuditoria>>> {
+        Box::pin(
+            sqlx::query_as::<_, EventoAuditoria>(
+                r#"
+                SELECT * FROM audit_events
+                WHERE actor_id = $1 OR (actor_id IS NULL AND target_type = 'user' AND target_id = CAST($1 AS TEXT))
+                ORDER BY id DESC
+                "#,
+            )
+            .bind(id_usuario)
+            .fetch_all(&self.pool)
+            .instrument(consulta("SELECT audit_events")),
+        )
+    }
+}
+
+impl RepositorioNotificacoes for RepositorioSqlite {
+    fn criar<'a>(&'a self, id_usuario: i32, tipo: &'a str, dados: &'a serde_json::Value) -> BoxFuture<'a, Resultado<Notificacao>> {
+        Box::pin(
+            sqlx::query_as::<_, Notificacao>(
+                "INSERT INTO notifications (user_id, kind, data, created_at) VALUES ($1, $2, $3, $4) RETURNING *",
+            )
+            .bind(id_usuario)
+            .bind(tipo)
+            .bind(dados)
+            .bind(Utc::now())
+            .fetch_one(&self.pool)
+            .instrument(consulta("INSERT notifications")),
+        )
+    }
+
+    fn buscar(&self, id: i64) -> BoxFuture<'_, Resultado<Option<Notificacao>>> {
+        Box::pin(
+            sqlx::query_as::<_, Notificacao>("SELECT * FROM notifications WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT notifications")),
+        )
+    }
+
+    fn listar(
+        &self,
+        id_usuario: i32,
+        nao_lidas: bool,
+        antes_do_id: Option<i64>,
+        limite: i64,
+    ) -> BoxFuture<'_, Resultado<Vec<Notificacao>>> {
+        Box::pin(
+            sqlx::query_as::<_, Notificacao>(
+                r#"
+                SELECT * FROM notifications
+                WHERE user_id = $1
+                  AND (NOT $2 OR read_at IS NULL)
+                  AND ($3 IS NULL OR id < $3)
+                ORDER BY id DESC
+                LIMIT $4
+                "#,
+            )
+            .bind(id_usuario)
+            .bind(nao_lidas)
+            .bind(antes_do_id)
+            .bind(limite)
+            .fetch_all(&self.pool)
+            .instrument(consulta("SELECT notifications")),
+        )
+    }
+
+    fn contar_nao_lidas(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<i64>> {
+        Box::pin(
+            sqlx::query_scalar("SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND read_at IS NULL")
+                .bind(id_usuario)
+                .fetch_one(&self.pool)
+                .instrument(consulta("SELECT notifications")),
+        )
+    }
+
+    fn marcar_lida(&self, id: i64, id_usuario: i32) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query("UPDATE notifications SET read_at = COALESCE(read_at, $1) WHERE id = $2 AND user_id = $3")
+                .bind(Utc::now())
+                .bind(id)
+                .bind(id_usuario)
+                .execute(&self.pool)
+                .instrument(consulta("UPDATE notifications"))
+                .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn marcar_todas_lidas(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<u64>> {
+        Box::pin(async move {
+            let resultado = sqlx::query("UPDATE notifications SET read_at = $1 WHERE user_id = $2 AND read_at IS NULL")
+                .bind(Utc::now())
+                .bind(id_usuario)
+                .execute(&self.pool)
+                .instrument(consulta("UPDATE notifications"))
+                .await?;
+
+            Ok(resultado.rows_affected())
+        })
+    }
+}
+
+impl RepositorioWebhooks for RepositorioSqlite {
+    fn criar<'a>(&'a self, id_usuario: i32, url: &'a str, eventos: &'a [String], segredo: &'a str) -> BoxFuture<'a, Resultado<Webhook>> {
+        Box::pin(
+            sqlx::query_as::<_, Webhook>(
+                "INSERT INTO webhooks (user_id, url, events, secret, created_at) VALUES ($1, $2, $3, $4, $5) RETURNING *",
+            )
+            .bind(id_usuario)
+            .bind(url)
+            .bind(sqlx::types::Json(eventos))
+            .bind(segredo)
+            .bind(Utc::now())
+            .fetch_one(&self.pool)
+            .instrument(consulta("INSERT webhooks")),
+        )
+    }
+
+    fn listar_do_usuario(&self, id_usuario: i32) -> BoxFuture<'_, Resultado<Vec<Webhook>>> {
+        Box::pin(
+            sqlx::query_as::<_, Webhook>("SELECT * FROM webhooks WHERE user_id = $1 ORDER BY id DESC")
+                .bind(id_usuario)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT webhooks")),
+        )
+    }
+
+    fn buscar_do_usuario(&self, id: i32, id_usuario: i32) -> BoxFuture<'_, Resultado<Option<Webhook>>> {
+        Box::pin(
+            sqlx::query_as::<_, Webhook>("SELECT * FROM webhooks WHERE id = $1 AND user_id = $2")
+                .bind(id)
+                .bind(id_usuario)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT webhooks")),
+        )
+    }
+
+    fn deletar(&self, id: i32, id_usuario: i32) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query("DELETE FROM webhooks WHERE id = $1 AND user_id = $2")
+                .bind(id)
+                .bind(id_usuario)
+                .execute(&self.pool)
+                .instrument(consulta("DELETE webhooks"))
+                .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn enfileirar<'a>(
+        &'a self,
+        evento: &'a str,
+        id_evento: Uuid,
+        payload: &'a serde_json::Value,
+        so_admins: bool,
+    ) -> BoxFuture<'a, Resultado<u64>> {
+        Box::pin(inserir_entregas(&self.pool, evento, id_evento, payload, so_admins))
+    }
+
+    fn reservar_entregas(
+        &self,
+        agora: DateTime<Utc>,
+        reservar_ate: DateTime<Utc>,
+        limite: i64,
+    ) -> BoxFuture<'_, Resultado<Vec<EntregaPendente>>> {
+        Box::pin(async move {
+            let mut transacao = self.pool.begin().await?;
+
+            let vencidas = sqlx::query_as::<_, EntregaPendente>(
+                r#"
+                SELECT webhook_deliveries.*, webhooks.url, webhooks.secret, users.is_admin
+                FROM webhook_deliveries
+                JOIN webhooks ON webhooks.id = webhook_deliveries.webhook_id
+                JOIN users ON users.id = webhooks.user_id
+                WHERE webhook_deliveries.status = 'pending' AND webhook_deliveries.next_attempt_at <= $1
+                ORDER BY webhook_deliveries.next_attempt_at
+                LIMIT $2
+                "#,
+            )
+            .bind(agora)
+            .bind(limite)
+            .fetch_all(&mut *transacao)
+            .instrument(consulta("SELECT webhook_deliveries"))
+            .await?;
+
+            // Sem SKIP LOCKED no SQLite: a condição do UPDATE descarta o que outra conexão já reservou
+            let mut reservadas = Vec::with_capacity(vencidas.len());
+            for mut pendente in vencidas {
+                let resultado = sqlx::query(
+                    r#"
+                    UPDATE webhook_deliveries SET attempts = attempts + 1, next_attempt_at = $1
+                    WHERE id = $2 AND status = 'pending' AND next_attempt_at <= $3
+                    "#,
+                )
+                .bind(reservar_ate)
+                .bind(pendente.entrega.id)
+                .bind(agora)
+                .execute(&mut *transacao)
+                .instrument(consulta("UPDATE webhook_deliveries"))
+                .await?;
+
+                if resultado.rows_affected() > 0 {
+                    pendente.entrega.tentativas += 1;
+                    pendente.entrega.proxima_tentativa_em = reservar_ate;
+                    reservadas.push(pendente);
+                }
+            }
+
+            transacao.commit().await?;
+            Ok(reservadas)
+        })
+    }
+
+    fn registrar_envio<'a>(&'a self, id_entrega: i64, resultado: &'a ResultadoEnvio) -> BoxFuture<'a, Resultado<()>> {
+        Box::pin(async move {
+            let mut transacao = self.pool.begin().await?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO webhook_delivery_attempts (delivery_id, attempted_at, status_code, error, duration_ms)
+                VALUES ($1, $2, $3, $4, $5)
+                "#,
+            )
+            .bind(id_entrega)
+            .bind(resultado.tentada_em)
+            .bind(resultado.codigo_status)
+            .bind(&resultado.erro)
+            .bind(resultado.duracao_ms)
+            .execute(&mut *transacao)
+            .instrument(consulta("INSERT webhook_delivery_attempts"))
+            .await?;
+
+            sqlx::query(
+                r#"
+                UPDATE webhook_deliveries
+                SET status = $1, last_status_code = $2, last_error = $3,
+                    next_attempt_at = COALESCE($4, next_attempt_at),
+                    delivered_at = CASE WHEN $1 = 'succeeded' THEN $5 ELSE delivered_at END
+                WHERE id = $6
+                "#,
+            )
+            .bind(resultado.situacao())
+            .bind(resultado.codigo_status)
+            .bind(&resultado.erro)
+            .bind(resultado.proxima_tentativa)
+            .bind(resultado.tentada_em)
+            .bind(id_entrega)
+            .execute(&mut *transacao)
+            .instrument(consulta("UPDATE webhook_deliveries"))
+            .await?;
+
+            transacao.commit().await
+        })
+    }
+
+    fn liberar_entrega(&self, id_entrega: i64, tentativa: i32, agora: DateTime<Utc>) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query(
+                r#"
+                UPDATE webhook_deliveries
+                SET attempts = attempts - 1, next_attempt_at = $3
+                WHERE id = $1 AND status = 'pending' AND attempts = $2
+                "#,
+            )
+            .bind(id_entrega)
+            .bind(tentativa)
+            .bind(agora)
+            .execute(&self.pool)
+            .instrument(consulta("UPDATE webhook_deliveries"))
+            .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn listar_entregas(
+        &self,
+        id_webhook: i32,
+        antes_do_id: Option<i64>,
+        limite: i64,
+    ) -> BoxFuture<'_, Resultado<Vec<EntregaWebhook>>> {
+        Box::pin(
+            sqlx::query_as::<_, EntregaWebhook>(
+                r#"
+                SELECT * FROM webhook_deliveries
+                WHERE webhook_id = $1 AND ($2 IS NULL OR id < $2)
+                ORDER BY id DESC
+                LIMIT $3
+                "#,
+            )
+            .bind(id_webhook)
+            .bind(antes_do_id)
+            .bind(limite)
+            .fetch_all(&self.pool)
+            .instrument(consulta("SELECT webhook_deliveries")),
+        )
+    }
+
+    fn buscar_entrega(&self, id: i64, id_webhook: i32) -> BoxFuture<'_, Resultado<Option<EntregaWebhook>>> {
+        Box::pin(
+            sqlx::query_as::<_, EntregaWebhook>("SELECT * FROM webhook_deliveries WHERE id = $1 AND webhook_id = $2")
+                .bind(id)
+                .bind(id_webhook)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT webhook_deliveries")),
+        )
+    }
+
+    fn listar_tentativas(&self, id_entrega: i64) -> BoxFuture<'_, Resultado<Vec<TentativaEntrega>>> {
+        Box::pin(
+            sqlx::query_as::<_, TentativaEntrega>("SELECT * FROM webhook_delivery_attempts WHERE delivery_id = $1 ORDER BY id")
+                .bind(id_entrega)
+                .fetch_all(&self.pool)
+                .instrument(consulta("SELECT webhook_delivery_attempts")),
+        )
+    }
+
+    fn reenviar(&self, id: i64, id_webhook: i32) -> BoxFuture<'_, Resultado<Option<EntregaWebhook>>> {
+        Box::pin(
+            sqlx::query_as::<_, EntregaWebhook>(
+                r#"
+                INSERT INTO webhook_deliveries (webhook_id, event_id, event, payload, next_attempt_at, created_at)
+                SELECT webhook_id, event_id, event, payload, $3, $3 FROM webhook_deliveries
+                WHERE id = $1 AND webhook_id = $2
+                RETURNING *
+                "#,
+            )
+            .bind(id)
+            .bind(id_webhook)
+            .bind(Utc::now())
+            .fetch_optional(&self.pool)
+            .instrument(consulta("INSERT webhook_deliveries")),
+        )
+    }
+}
+
+impl RepositorioTarefas for RepositorioSqlite {
+    fn enfileirar<'a>(&'a self, tarefa: &'a NovaTarefa) -> BoxFuture<'a, Resultado<Option<Tarefa>>> {
+        Box::pin(
+            sqlx::query_as::<_, Tarefa>(
+                r#"
+                INSERT INTO jobs (kind, payload, max_attempts, run_at, unique_key, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $6)
+                ON CONFLICT (unique_key) DO NOTHING
+                RETURNING *
+                "#,
+            )
+            .bind(tarefa.tipo)
+            .bind(&tarefa.payload)
+            .bind(tarefa.maximo_tentativas)
+            .bind(tarefa.executar_em)
+            .bind(&tarefa.chave)
+            .bind(Utc::now())
+            .fetch_optional(&self.pool)
+            .instrument(consulta("INSERT jobs")),
+        )
+    }
+
+    fn reservar<'a>(
+        &'a self,
+        tipos: &'a [&'static str],
+        agora: DateTime<Utc>,
+        reservar_ate: DateTime<Utc>,
+        limite: i64,
+    ) -> BoxFuture<'a, Resultado<Vec<Tarefa>>> {
+        // Sem SKIP LOCKED no SQLite, mas também sem escritas concorrentes: o
+        // UPDATE inteiro roda com o banco bloqueado para as outras conexões
+        Box::pin(
+            sqlx::query_as::<_, Tarefa>(
+                r#"
+                UPDATE jobs
+                SET status = 'running', attempts = attempts + 1, locked_until = $3, updated_at = $5
+                WHERE id IN (
+                    SELECT id FROM jobs
+                    WHERE kind IN (SELECT value FROM json_each($1))
+                      AND ((status = 'pending' AND run_at <= $2) OR (status = 'running' AND locked_until <= $2))
+                    ORDER BY run_at
+                    LIMIT $4
+                )
+                RETURNING *
+                "#,
+            )
+            .bind(serde_json::json!(tipos))
+            .bind(agora)
+            .bind(reservar_ate)
+            .bind(limite)
+            .bind(Utc::now())
+            .fetch_all(&self.pool)
+            .instrument(consulta("UPDATE jobs")),
+        )
+    }
+
+    fn concluir<'a>(&'a self, id: i64, resultado: &'a ResultadoExecucao) -> BoxFuture<'a, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado_consulta = sqlx::query(
+                r#"
+                UPDATE jobs
+                SET status = $1, last_error = $2, locked_until = NULL, run_at = COALESCE($3, run_at),
+                    finished_at = CASE WHEN $1 = 'pending' THEN NULL ELSE $4 END,
+                    updated_at = $7
+                WHERE id = $5 AND status = 'running' AND attempts = $6
+                "#,
+            )
+            .bind(resultado.situacao())
+            .bind(&resultado.erro)
+            .bind(resultado.proxima_tentativa)
+            .bind(resultado.concluida_em)
+            .bind(id)
+            .bind(resultado.tentativa)
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .instrument(consulta("UPDATE jobs"))
+            .await?;
+
+            Ok(resultado_consulta.rows_affected() > 0)
+        })
+    }
+
+    fn liberar(&self, id: i64, tentativa: i32) -> BoxFuture<'_, Resultado<bool>> {
+        Box::pin(async move {
+            let resultado = sqlx::query(
+                r#"
+                UPDATE jobs
+                SET status = 'pending', attempts = attempts - 1, locked_until = NULL, updated_at = $3
+                WHERE id = $1 AND status = 'running' AND attempts = $2
+                "#,
+            )
+            .bind(id)
+            .bind(tentativa)
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .instrument(consulta("UPDATE jobs"))
+            .await?;
+
+            Ok(resultado.rows_affected() > 0)
+        })
+    }
+
+    fn listar<'a>(&'a self, filtro: &'a FiltroTarefas, limite: i64) -> BoxFuture<'a, Resultado<Vec<Tarefa>>> {
+        Box::pin(
+            sqlx::query_as::<_, Tarefa>(
+                r#"
+                SELECT * FROM jobs
+                WHERE ($1 IS NULL OR status = $1)
+                  AND ($2 IS NULL OR kind = $2)
+                  AND ($3 IS NULL OR id < $3)
+                ORDER BY id DESC
+                LIMIT $4
+                "#,
+            )
+            .bind(&filtro.status)
+            .bind(&filtro.tipo)
+            .bind(filtro.antes_do_id)
+            .bind(limite)
+            .fetch_all(&self.pool)
+            .instrument(consulta("SELECT jobs")),
+        )
+    }
+
+    fn buscar(&self, id: i64) -> BoxFuture<'_, Resultado<Option<Tarefa>>> {
+        Box::pin(
+            sqlx::query_as::<_, Tarefa>("SELECT * FROM jobs WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .instrument(consulta("SELECT jobs")),
+        )
+    }
+
+    fn tentar_de_novo(&self, id: i64, agora: DateTime<Utc>) -> BoxFuture<'_, Resultado<Option<Tarefa>>> {
+        Box::pin(
+            sqlx::query_as::<_, Tarefa>(
+                r#"
+                UPDATE jobs
+                SET status = 'pending', attempts = 0, run_at = $2, locked_until = NULL, finished_at = NULL,
+                    updated_at = $3
+                WHERE id = $1 AND status IN ('pending', 'dead')
+                RETURNING *
+                "#,
+            )
+            .bind(id)
+            .bind(agora)
+            .bind(Utc::now())
+            .fetch_optional(&self.pool)
+            .instrument(consulta("UPDATE jobs")),
+        )
+    }
+
+    fn apagar_concluidas(&self, antes_de: DateTime<Utc>) -> BoxFuture<'_, Resultado<u64>> {
+        Box::pin(async move {
+            let resultado = sqlx::query("DELETE FROM jobs WHERE status = 'succeeded' AND finished_at < $1")
+                .bind(antes_de)
+                .execute(&self.pool)
+                .instrument(consulta("DELETE jobs"))
+                .await?;
+            Ok(resultado.rows_affected())
+        })
+    }
+}
@@ -0,0 +1,477 @@
+use axum::{
+    handler::Handler,
+    http::Method,
+    middleware::{from_fn, from_fn_with_state},
+    routing::{on, MethodFilter, MethodRouter},
+    Router,
+};
+use schemars::{JsonSchema, Schema, SchemaGenerator};
+
+use crate::{estado::Estado, graphql, handlers, metricas, middleware, models::*, openapi, saude, transferencia::ResumoImportacao};
+
+pub type GeradorEsquema = fn(&mut SchemaGenerator) -> Schema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Acesso {
+    Publica,
+    /// Exige `Authorization: Bearer <token>` (grupo `rotas_protegidas`)
+    Protegida,
+    /// Exige o token de um administrador (grupo `rotas_admin`)
+    Admin,
+}
+
+impl Acesso {
+    pub fn exige_token(self) -> bool {
+        self != Acesso::Publica
+    }
+}
+
+/// Uma entrada da tabela de rotas: o handler e a documentação usada no OpenAPI.
+pub struct Rota {
+    pub metodo: Method,
+    pub caminho: &'static str,
+    pub acesso: Acesso,
+    pub handler: MethodRouter<Estado>,
+    pub documentacao: Option<Operacao>,
+}
+
+#[derive(Default)]
+pub struct Operacao {
+    pub resumo: &'static str,
+    pub tag: &'static str,
+    pub parametros: Vec<Parametro>,
+    pub corpo: Option<Conteudo>,
+    pub respostas: Vec<RespostaDocumentada>,
+}
+
+pub struct Parametro {
+    pub nome: &'static str,
+    pub descricao: &'static str,
+    pub esquema: GeradorEsquema,
+    /// Parâmetro opcional da query string; os demais são segmentos do caminho
+    pub na_consulta: bool,
+}
+
+pub struct RespostaDocumentada {
+    pub status: u16,
+    pub descricao: &'static str,
+    pub conteudo: Option<Conteudo>,
+}
+
+pub enum Conteudo {
+    Json(GeradorEsquema),
+    /// `application/x-ndjson`: um objeto JSON por linha
+    JsonLines,
+    /// `text/event-stream` (Server-Sent Events)
+    Eventos,
+    Zip,
+    Html,
+    Texto,
+}
+
+fn esquema<T: JsonSchema>(gerador: &mut SchemaGenerator) -> Schema {
+    gerador.subschema_for::<T>()
+}
+
+impl Rota {
+    pub fn publica<H, T>(metodo: Method, caminho: &'static str, handler: H) -> Self
+    where
+        H: Handler<T, Estado>,
+        T: 'static,
+    {
+        Self::nova(metodo, caminho, Acesso::Publica, handler)
+    }
+
+    pub fn protegida<H, T>(metodo: Method, caminho: &'static str, handler: H) -> Self
+    where
+        H: Handler<T, Estado>,
+        T: 'static,
+    {
+        Self::nova(metodo, caminho, Acesso::Protegida, handler)
+    }
+
+    pub fn admin<H, T>(metodo: Method, caminho: &'static str, handler: H) -> Self
+    where
+        H: Handler<T, Estado>,
+        T: 'static,
+    {
+        Self::nova(metodo, caminho, Acesso::Admin, handler)
+    }
+
+    fn nova<H, T>(metodo: Method, caminho: &'static str, acesso: Acesso, handler: H) -> Self
+    where
+        H: Handler<T, Estado>,
+        T: 'static,
+    {
+        let filtro = MethodFilter::try_from(metodo.clone()).expect("método HTTP sem suporte no roteador");
+
+        Rota {
+            metodo,
+            caminho,
+            acesso,
+            handler: on(filtro, handler),
+            documentacao: None,
+        }
+    }
+
+    pub fn documentar(mut self, tag: &'static str, resumo: &'static str) -> Self {
+        self.documentacao = Some(Operacao { resumo, tag, ..Default::default() });
+        self
+    }
+
+    pub fn parametro<T: JsonSchema>(mut self, nome: &'static str, descricao: &'static str) -> Self {
+        self.operacao().parametros.push(Parametro { nome, descricao, esquema: esquema::<T>, na_consulta: false });
+        self
+    }
+
+    pub fn consulta<T: JsonSchema>(mut self, nome: &'static str, descricao: &'static str) -> Self {
+        self.operacao().parametros.push(Parametro { nome, descricao, esquema: esquema::<T>, na_consulta: true });
+        self
+    }
+
+    pub fn corpo<T: JsonSchema>(mut self) -> Self {
+        self.operacao().corpo = Some(Conteudo::Json(esquema::<T>));
+        self
+    }
+
+    pub fn corpo_json_lines(mut self) -> Self {
+        self.operacao().corpo = Some(Conteudo::JsonLines);
+        self
+    }
+
+    pub fn resposta<T: JsonSchema>(self, status: u16, descricao: &'static str) -> Self {
+        self.adicionar_resposta(status, descricao, Some(Conteudo::Json(esquema::<T>)))
+    }
+
+    pub fn resposta_html(self, descricao: &'static str) -> Self {
+        self.adicionar_resposta(200, descricao, Some(Conteudo::Html))
+    }
+
+    pub fn resposta_texto(self, descricao: &'static str) -> Self {
+        self.adicionar_resposta(200, descricao, Some(Conteudo::Texto))
+    }
+
+    pub fn resposta_json_lines(self, descricao: &'static str) -> Self {
+        self.adicionar_resposta(200, descricao, Some(Conteudo::JsonLines))
+    }
+
+    pub fn resposta_eventos(self, descricao: &'static str) -> Self {
+        self.adicionar_resposta(200, descricao, Some(Conteudo::Eventos))
+    }
+
+    pub fn resposta_zip(self, descricao: &'static str) -> Self {
+        self.adicionar_resposta(200, descricao, Some(Conteudo::Zip))
+    }
+
+    pub fn resposta_vazia(self, status: u16, descricao: &'static str) -> Self {
+        self.adicionar_resposta(status, descricao, None)
+    }
+
+    pub fn erro(self, status: u16, descricao: &'static str) -> Self {
+        self.resposta::<openapi::CorpoErro>(status, descricao)
+    }
+
+    fn adicionar_resposta(mut self, status: u16, descricao: &'static str, conteudo: Option<Conteudo>) -> Self {
+        self.operacao().respostas.push(RespostaDocumentada { status, descricao, conteudo });
+        self
+    }
+
+    fn operacao(&mut self) -> &mut Operacao {
+        self.documentacao
+            .as_mut()
+            .expect("chame `documentar` antes de descrever parâmetros, corpo ou respostas")
+    }
+}
+
+/// Prefixo do contrato atual da API; as mesmas rotas também respondem,
+/// depreciadas, sob `PREFIXO_LEGADO`.
+pub const PREFIXO_API: &str = "/api/v1";
+pub const PREFIXO_LEGADO: &str = "/api";
+
+/// Rotas fora da API versionada (interface web, saúde, métricas e documentação).
+pub fn rotas_raiz() -> Vec<Rota> {
+    vec![
+        Rota::publica(Method::GET, "/home", handlers::pagina_home)
+            .documentar("interface", "Interface web de testes")
+            .resposta_html("Página HTML"),
+        Rota::publica(Method::GET, "/health", saude::pronto)
+            .documentar("saude", "Mesmo que /health/ready")
+            .resposta::<saude::RespostaSaude>(200, "Pronta para receber tráfego")
+            .resposta::<saude::RespostaSaude>(503, "Alguma dependência indisponível"),
+        Rota::publica(Method::GET, "/health/live", saude::vivo)
+            .documentar("saude", "Sonda de vida: o processo está respondendo")
+            .resposta::<saude::RespostaSaude>(200, "Processo no ar"),
+        Rota::publica(Method::GET, "/health/ready", saude::pronto)
+            .documentar("saude", "Sonda de prontidão: banco, migrações e pool de conexões")
+            .resposta::<saude::RespostaSaude>(200, "Pronta para receber tráfego")
+            .resposta::<saude::RespostaSaude>(503, "Alguma dependência indisponível"),
+        Rota::publica(Method::GET, "/health/startup", saude::iniciado)
+            .documentar("saude", "Sonda de inicialização: migrações e seed concluídos")
+            .resposta::<saude::RespostaSaude>(200, "Inicialização concluída")
+            .resposta::<saude::RespostaSaude>(503, "Ainda inicializando"),
+        Rota::publica(Method::GET, "/metrics", metricas::exportar)
+            .documentar("saude", "Métricas no formato do Prometheus")
+            .resposta_texto("Métricas em texto (exposition format 0.0.4)")
+            .resposta_vazia(503, "Exportador de métricas não inicializado"),
+        Rota::publica(Method::GET, "/openapi.json", openapi::obter_especificacao)
+            .documentar("documentacao", "Especificação OpenAPI 3.1 da API")
+            .resposta::<serde_json::Value>(200, "Documento OpenAPI"),
+        Rota::publica(Method::GET, "/docs", openapi::pagina_documentacao)
+            .documentar("documentacao", "Explorador interativo da API")
+            .resposta_html("Página HTML"),
+    ]
+}
+
+/// Rotas da API, relativas a `PREFIXO_API`.
+pub fn rotas_api() -> Vec<Rota> {
+    vec![
+        // ========== Rotas Públicas ==========
+        Rota::publica(Method::POST, "/auth/register", handlers::registrar)
+            .documentar("auth", "Registrar um novo usuário")
+            .corpo::<CriarUsuarioRequisicao>()
+            .resposta::<RespostaUsuario>(200, "Usuário criado")
+            .erro(409, "Email ou nome de usuário já cadastrado")
+            .erro(422, "Dados inválidos"),
+        Rota::publica(Method::POST, "/auth/login", handlers::login)
+            .documentar("auth", "Autenticar e abrir uma sessão")
+            .corpo::<LoginRequisicao>()
+            .resposta::<RespostaLogin>(200, "Token JWT da nova sessão")
+            .resposta_vazia(401, "Credenciais inválidas")
+            .resposta_vazia(403, "Conta desativada")
+            .erro(422, "Dados inválidos"),
+        Rota::publica(Method::GET, "/posts", handlers::obter_postagens_publicas)
+            .documentar("posts", "Listar as postagens mais recentes")
+            .resposta::<Vec<RespostaPostagem>>(200, "Últimas 10 postagens")
+            .resposta_vazia(304, "Lista inalterada desde o ETag informado em If-None-Match"),
+        Rota::publica(Method::GET, "/posts/stream", handlers::transmitir_postagens)
+            .documentar("posts", "Acompanhar criações, edições e exclusões de posts em tempo real")
+            .resposta_eventos(
+                "Eventos post.created, post.updated e post.deleted; reset quando o Last-Event-ID \
+                 já saiu do histórico",
+            )
+            .resposta_vazia(503, "Feed indisponível (sem conexão com o banco)"),
+        Rota::publica(Method::GET, "/posts/{id}", handlers::obter_postagem)
+            .documentar("posts", "Buscar uma postagem")
+            .parametro::<i32>("id", "ID da postagem")
+            .resposta::<RespostaPostagem>(200, "Postagem encontrada")
+            .resposta_vazia(304, "Postagem inalterada (If-None-Match ou If-Modified-Since)")
+            .resposta_vazia(404, "Postagem não encontrada"),
+        Rota::publica(Method::GET, "/users", handlers::obter_todos_usuarios)
+            .documentar("usuarios", "Listar usuários")
+            .resposta::<Vec<RespostaUsuario>>(200, "Usuários cadastrados"),
+        Rota::publica(Method::POST, "/graphql", graphql::executar)
+            .documentar(
+                "graphql",
+                "Executar uma consulta ou mutação GraphQL (as mutações exigem o token em Authorization)",
+            )
+            .corpo::<graphql::RequisicaoGraphql>()
+            .resposta::<serde_json::Value>(200, "data e errors, como na especificação do GraphQL")
+            .resposta_vazia(401, "Token inválido, expirado ou de sessão revogada"),
+        Rota::publica(Method::GET, "/graphql", graphql::pagina_graphiql)
+            .documentar("graphql", "GraphiQL: explorador interativo do esquema GraphQL")
+            .resposta_html("Página HTML"),
+        // ========== Rotas Protegidas ==========
+        Rota::protegida(Method::GET, "/profile", handlers::obter_perfil)
+            .documentar("perfil", "Perfil do usuário autenticado")
+            .resposta::<RespostaPerfil>(200, "Perfil")
+            .resposta_vazia(404, "Usuário não encontrado"),
+        Rota::protegida(Method::GET, "/profile/export", handlers::exportar_perfil)
+            .documentar("perfil", "Baixar os dados pessoais (perfil, posts, sessões, notificações, webhooks e auditoria)")
+            .resposta_zip("Arquivo .zip com os dados em JSON e em Markdown"),
+        Rota::protegida(Method::DELETE, "/profile", handlers::excluir_perfil)
+            .documentar("perfil", "Pedir a exclusão da conta")
+            .corpo::<ExcluirContaRequisicao>()
+            .resposta::<RespostaExclusaoConta>(202, "Exclusão agendada para o fim do prazo de carência")
+            .resposta_vazia(403, "Senha incorreta")
+            .erro(422, "Dados inválidos"),
+        Rota::protegida(Method::DELETE, "/profile/deletion", handlers::cancelar_exclusao_perfil)
+            .documentar("perfil", "Cancelar o pedido de exclusão da conta")
+            .resposta_vazia(204, "Pedido cancelado")
+            .resposta_vazia(404, "Nenhuma exclusão agendada"),
+        Rota::protegida(Method::GET, "/profile/sessions", handlers::obter_sessoes)
+            .documentar("perfil", "Listar sessões ativas")
+            .resposta::<Vec<RespostaSessao>>(200, "Sessões ativas do usuário"),
+        Rota::protegida(Method::DELETE, "/profile/sessions/{id}", handlers::revogar_sessao)
+            .documentar("perfil", "Encerrar uma sessão")
+            .parametro::<uuid::Uuid>("id", "ID da sessão")
+            .resposta_vazia(204, "Sessão revogada")
+            .resposta_vazia(404, "Sessão não encontrada"),
+        Rota::protegida(Method::GET, "/notifications", handlers::obter_notificacoes)
+            .documentar("notificacoes", "Listar as notificações do usuário autenticado")
+            .consulta::<bool>("unread", "Só as não lidas")
+            .consulta::<i64>("before_id", "Notificações com id menor que este (próxima página)")
+            .consulta::<i64>("limit", "Máximo de notificações (padrão 50, no máximo 200)")
+            .resposta::<Vec<RespostaNotificacao>>(200, "Notificações, das mais novas para as mais antigas")
+            .resposta_vazia(400, "Filtro inválido"),
+        Rota::protegida(Method::GET, "/notifications/ws", handlers::conectar_notificacoes)
+            .documentar(
+                "notificacoes",
+                "Receber as notificações em tempo real por WebSocket (no navegador, o token vai no \
+                 subprotocolo: Sec-WebSocket-Protocol: bearer, <token>)",
+            )
+            .resposta_vazia(101, "Conexão aceita; mensagens hello e notification em JSON")
+            .resposta_vazia(503, "Notificações indisponíveis (sem conexão com o banco)"),
+        Rota::protegida(Method::POST, "/notifications/read-all", handlers::marcar_todas_notificacoes_lidas)
+            .documentar("notificacoes", "Marcar todas as notificações como lidas")
+            .resposta::<RespostaNotificacoesLidas>(200, "Notificações marcadas"),
+        Rota::protegida(Method::POST, "/notifications/{id}/read", handlers::marcar_notificacao_lida)
+            .documentar("notificacoes", "Marcar uma notificação como lida")
+            .parametro::<i64>("id", "ID da notificação")
+            .resposta_vazia(204, "Notificação marcada")
+            .resposta_vazia(404, "Notificação não encontrada ou de outro usuário"),
+        Rota::protegida(Method::POST, "/webhooks", handlers::criar_webhook)
+            .documentar(
+                "webhooks",
+                "Cadastrar um webhook: cada evento assinado é enviado por POST, assinado com \
+                 HMAC-SHA256 em X-Webhook-Signature",
+            )
+            .corpo::<CriarWebhookRequisicao>()
+            .resposta::<RespostaWebhookCriado>(201, "Webhook criado, com o segredo (exibido só agora)")
+            .erro(422, "Dados inválidos, destino interno ou evento reservado a administradores"),
+        Rota::protegida(Method::GET, "/webhooks", handlers::obter_webhooks)
+            .documentar("webhooks", "Listar os webhooks do usuário autenticado")
+            .resposta::<Vec<RespostaWebhook>>(200, "Webhooks, dos mais novos para os mais antigos"),
+        Rota::protegida(Method::DELETE, "/webhooks/{id}", handlers::deletar_webhook)
+            .documentar("webhooks", "Apagar um webhook e o histórico de entregas")
+            .parametro::<i32>("id", "ID do webhook")
+            .resposta_vazia(204, "Webhook apagado")
+            .resposta_vazia(404, "Webhook não encontrado ou de outro usuário"),
+        Rota::protegida(Method::GET, "/webhooks/{id}/deliveries", handlers::obter_entregas_webhook)
+            .documentar("webhooks", "Listar as entregas de um webhook")
+            .parametro::<i32>("id", "ID do webhook")
+            .consulta::<i64>("before_id", "Entregas com id menor que este (próxima página)")
+            .consulta::<i64>("limit", "Máximo de entregas (padrão 50, no máximo 200)")
+            .resposta::<Vec<RespostaEntregaWebhook>>(200, "Entregas, das mais novas para as mais antigas")
+            .resposta_vazia(400, "Filtro inválido")
+            .resposta_vazia(404, "Webhook não encontrado ou de outro usuário"),
+        Rota::protegida(Method::GET, "/webhooks/{id}/deliveries/{delivery_id}", handlers::obter_entrega_webhook)
+            .documentar("webhooks", "Detalhar uma entrega: corpo enviado e tentativas, com os status HTTP")
+            .parametro::<i32>("id", "ID do webhook")
+            .parametro::<i64>("delivery_id", "ID da entrega")
+            .resposta::<RespostaDetalheEntrega>(200, "Entrega")
+            .resposta_vazia(404, "Webhook ou entrega não encontrados"),
+        Rota::protegida(
+            Method::POST,
+            "/webhooks/{id}/deliveries/{delivery_id}/redeliver",
+            handlers::reenviar_entrega_webhook,
+        )
+        .documentar("webhooks", "Reenviar o evento de uma entrega, numa entrega nova com o mesmo X-Webhook-Id")
+        .parametro::<i32>("id", "ID do webhook")
+        .parametro::<i64>("delivery_id", "ID da entrega")
+        .resposta::<RespostaEntregaWebhook>(202, "Nova entrega enfileirada")
+        .resposta_vazia(404, "Webhook ou entrega não encontrados"),
+        Rota::protegida(Method::GET, "/posts/my", handlers::obter_minhas_postagens)
+            .documentar("posts", "Listar as postagens do usuário autenticado")
+            .resposta::<Vec<RespostaPostagem>>(200, "Postagens do usuário")
+            .resposta_vazia(304, "Lista inalterada desde o ETag informado em If-None-Match"),
+        Rota::protegida(Method::POST, "/posts", handlers::criar_postagem)
+            .documentar("posts", "Criar uma postagem")
+            .corpo::<CriarPostagemRequisicao>()
+            .resposta::<RespostaPostagem>(200, "Postagem criada")
+            .erro(422, "Dados inválidos"),
+        Rota::protegida(Method::PUT, "/posts/{id}", handlers::atualizar_postagem)
+            .documentar("posts", "Atualizar uma postagem própria")
+            .parametro::<i32>("id", "ID da postagem")
+            .corpo::<CriarPostagemRequisicao>()
+            .resposta::<RespostaPostagem>(200, "Postagem atualizada")
+            .resposta_vazia(404, "Postagem não encontrada ou de outro usuário")
+            .resposta_vazia(412, "If-Match não corresponde à versão atual da postagem")
+            .erro(422, "Dados inválidos"),
+        Rota::protegida(Method::DELETE, "/posts/{id}", handlers::deletar_postagem)
+            .documentar("posts", "Deletar uma postagem própria")
+            .parametro::<i32>("id", "ID da postagem")
+            .resposta_vazia(204, "Postagem deletada")
+            .resposta_vazia(404, "Postagem não encontrada ou de outro usuário"),
+        // ========== Rotas de Administração ==========
+        Rota::admin(Method::GET, "/admin/export", handlers::exportar_dados)
+            .documentar("admin", "Exportar usuários, posts e sessões em JSON Lines")
+            .resposta_json_lines("Cabeçalho, um registro por linha e o manifesto com contagens e SHA-256"),
+        Rota::admin(Method::POST, "/admin/import", handlers::importar_dados)
+            .documentar("admin", "Importar um arquivo gerado por /admin/export")
+            .corpo_json_lines()
+            .resposta::<ResumoImportacao>(200, "Importação concluída")
+            .erro(409, "Nomes de usuário já usados por outras contas no destino")
+            .resposta_vazia(413, "Arquivo maior que IMPORTACAO_LIMITE_MB")
+            .erro(422, "Arquivo ilegível, truncado ou com checksum que não confere"),
+        Rota::admin(Method::GET, "/admin/audit", handlers::obter_eventos_auditoria)
+            .documentar("admin", "Consultar o registro de auditoria")
+            .consulta::<i32>("actor_id", "Só eventos deste usuário")
+            .consulta::<String>("action", "Só eventos desta ação (ex.: auth.login_failed)")
+            .consulta::<chrono::DateTime<chrono::Utc>>("since", "Eventos a partir desta data (inclusive)")
+            .consulta::<chrono::DateTime<chrono::Utc>>("until", "Eventos anteriores a esta data")
+            .consulta::<i64>("before_id", "Eventos com id menor que este (próxima página)")
+            .consulta::<i64>("limit", "Máximo de eventos (padrão 100, no máximo 1000)")
+            .resposta::<Vec<RespostaEventoAuditoria>>(200, "Eventos, dos mais novos para os mais antigos")
+            .resposta_vazia(400, "Filtro inválido"),
+        Rota::admin(Method::GET, "/admin/jobs", handlers::obter_tarefas)
+            .documentar("admin", "Consultar a fila de tarefas em segundo plano")
+            .consulta::<String>("status", "Só tarefas nesta situação: pending, running, succeeded ou dead")
+            .consulta::<String>("kind", "Só tarefas deste tipo (ex.: accounts.purge_expired)")
+            .consulta::<i64>("before_id", "Tarefas com id menor que este (próxima página)")
+            .consulta::<i64>("limit", "Máximo de tarefas (padrão 50, no máximo 500)")
+            .resposta::<Vec<RespostaTarefa>>(200, "Tarefas, das mais novas para as mais antigas")
+            .resposta_vazia(400, "Filtro inválido"),
+        Rota::admin(Method::GET, "/admin/jobs/{id}", handlers::obter_tarefa)
+            .documentar("admin", "Detalhar uma tarefa, com o payload e o último erro")
+            .parametro::<i64>("id", "ID da tarefa")
+            .resposta::<RespostaTarefa>(200, "Tarefa")
+            .resposta_vazia(404, "Tarefa não encontrada"),
+        Rota::admin(Method::POST, "/admin/jobs/{id}/retry", handlers::tentar_tarefa_de_novo)
+            .documentar("admin", "Devolver à fila uma tarefa morta (ou adiantar uma pendente), com as tentativas zeradas")
+            .parametro::<i64>("id", "ID da tarefa")
+            .resposta::<RespostaTarefa>(202, "Tarefa de volta à fila")
+            .resposta_vazia(404, "Tarefa não encontrada")
+            .erro(409, "Tarefa em execução ou já concluída"),
+    ]
+}
+
+/// Todas as rotas documentáveis, com o caminho completo.
+pub fn todas_as_rotas() -> Vec<(String, Rota)> {
+    let rotas_raiz = rotas_raiz()
+        .into_iter()
+        .map(|rota| (rota.caminho.to_string(), rota));
+    let rotas_api = rotas_api()
+        .into_iter()
+        .map(|rota| (format!("{PREFIXO_API}{}", rota.caminho), rota));
+
+    rotas_raiz.chain(rotas_api).collect()
+}
+
+/// Monta o roteador a partir das tabelas: as rotas protegidas recebem o
+/// `middleware_auth` (e as de administração, também o `middleware_admin`), e a
+/// API é servida em `/api/v1` e, depreciada, em `/api`.
+pub fn roteador(estado: Estado) -> Router<Estado> {
+    let mut aplicacao = Router::new();
+    for rota in rotas_raiz() {
+        aplicacao = aplicacao.route(rota.caminho, rota.handler);
+    }
+
+    aplicacao.merge(roteador_api(estado))
+}
+
+/// Só a API (`/api/v1` e `/api`), sem as rotas operacionais da raiz
+/// (saúde, métricas, documentação e interface web).
+pub fn roteador_api(estado: Estado) -> Router<Estado> {
+    let mut rotas_publicas = Router::new();
+    let mut rotas_protegidas = Router::new();
+    let mut rotas_admin = Router::new();
+
+    for rota in rotas_api() {
+        match rota.acesso {
+            Acesso::Publica => rotas_publicas = rotas_publicas.route(rota.caminho, rota.handler),
+            Acesso::Protegida => rotas_protegidas = rotas_protegidas.route(rota.caminho, rota.handler),
+            Acesso::Admin => rotas_admin = rotas_admin.route(rota.caminho, rota.handler),
+        }
+    }
+
+    // A última camada roda primeiro: o middleware_admin já encontra o usuário autenticado
+    let rotas_admin = rotas_admin
+        .layer(from_fn_with_state(estado.clone(), middleware::middleware_admin))
+        .layer(from_fn_with_state(estado.clone(), middleware::middleware_auth));
+    let rotas_protegidas = rotas_protegidas.layer(from_fn_with_state(estado, middleware::middleware_auth));
+    let api = Router::new().merge(rotas_publicas).merge(rotas_protegidas).merge(rotas_admin);
+
+    Router::new()
+        .nest(PREFIXO_API, api.clone())
+        .nest(PREFIXO_LEGADO, api.layer(from_fn(middleware::marcar_rota_legada)))
+}
@@ -0,0 +1,353 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::db::Banco;
+
+// Sondas precisam responder rápido mesmo com o banco travado
+const TEMPO_LIMITE_BANCO: Duration = Duration::from_secs(2);
+
+// Fração das conexões em uso a partir da qual a instância deixa de receber tráfego
+const LIMITE_SATURACAO_POOL: f64 = 1.0;
+
+/// Em que ponto da vida a aplicação está, para as sondas: se a inicialização
+/// terminou e se o encerramento começou. Cada aplicação montada tem a sua
+/// (veja [`ConstrutorAplicacao::prontidao`](crate::ConstrutorAplicacao::prontidao)).
+#[derive(Clone)]
+pub struct Prontidao {
+    inicializada: Arc<AtomicBool>,
+    encerrando: Arc<AtomicBool>,
+}
+
+impl Prontidao {
+    /// Ainda inicializando: `/health/startup` e `/health/ready` respondem `503`
+    /// até [`Prontidao::marcar_inicializada`].
+    pub fn iniciando() -> Self {
+        Prontidao {
+            inicializada: Arc::new(AtomicBool::new(false)),
+            encerrando: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Já inicializada, para quem roda as migrações antes de montar a aplicação.
+    pub fn inicializada() -> Self {
+        let prontidao = Self::iniciando();
+        prontidao.marcar_inicializada();
+        prontidao
+    }
+
+    /// Chamado quando as migrações e o seed terminam; até lá a aplicação não fica pronta.
+    pub fn marcar_inicializada(&self) {
+        self.inicializada.store(true, Ordering::Release);
+    }
+
+    /// Chamado ao receber o sinal de encerramento: a prontidão passa a falhar de vez.
+    pub fn marcar_encerrando(&self) {
+        self.encerrando.store(true, Ordering::Release);
+    }
+
+    fn esta_inicializada(&self) -> bool {
+        self.inicializada.load(Ordering::Acquire)
+    }
+
+    fn esta_encerrando(&self) -> bool {
+        self.encerrando.load(Ordering::Acquire)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Situacao {
+    Ok,
+    Starting,
+    Unavailable,
+    ShuttingDown,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RespostaSaude {
+    pub status: Situacao,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checks: Option<Verificacoes>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Verificacoes {
+    pub startup: VerificacaoInicializacao,
+    pub database: VerificacaoBanco,
+    pub migrations: VerificacaoMigracoes,
+    /// Ausente quando não há pool de conexões (`DATABASE_URL=memoria`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool: Option<VerificacaoPool>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct VerificacaoInicializacao {
+    pub status: Situacao,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct VerificacaoBanco {
+    pub status: Situacao,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct VerificacaoMigracoes {
+    pub status: Situacao,
+    /// Última migração aplicada no banco
+    pub applied_version: Option<i64>,
+    /// Última migração embutida no binário
+    pub expected_version: i64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct VerificacaoPool {
+    pub status: Situacao,
+    pub size: u32,
+    pub idle: usize,
+    pub max: u32,
+    /// Fração das conexões em uso (0 a 1)
+    pub saturation: f64,
+}
+
+impl RespostaSaude {
+    fn simples(status: Situacao) -> Self {
+        RespostaSaude { status, checks: None }
+    }
+}
+
+impl IntoResponse for RespostaSaude {
+    fn into_response(self) -> Response {
+        let status = if self.status == Situacao::Ok {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+/// `GET /health/live`: o processo está de pé; não depende do banco.
+pub async fn vivo() -> RespostaSaude {
+    RespostaSaude::simples(Situacao::Ok)
+}
+
+/// `GET /health/startup`: falha até as migrações e o seed terminarem.
+pub async fn iniciado(State(prontidao): State<Prontidao>) -> RespostaSaude {
+    if prontidao.esta_inicializada() {
+        RespostaSaude::simples(Situacao::Ok)
+    } else {
+        RespostaSaude::simples(Situacao::Starting)
+    }
+}
+
+/// `GET /health/ready`: a instância pode receber tráfego. Responde `503`
+/// com o detalhe de cada dependência quando alguma falha.
+pub async fn pronto(State(banco): State<Banco>, State(prontidao): State<Prontidao>) -> RespostaSaude {
+    if prontidao.esta_encerrando() {
+        return RespostaSaude::simples(Situacao::ShuttingDown);
+    }
+
+    let startup = VerificacaoInicializacao {
+        status: if prontidao.esta_inicializada() { Situacao::Ok } else { Situacao::Starting },
+    };
+
+    // Medida antes do ping, que ocupa uma conexão
+    let pool_verificado = verificar_pool(&banco);
+    let (database, migrations) = tokio::join!(verificar_banco(&banco), verificar_migracoes(&banco));
+
+    let pronto = [startup.status, database.status, migrations.status]
+        .into_iter()
+        .chain(pool_verificado.as_ref().map(|pool| pool.status))
+        .all(|status| status == Situacao::Ok);
+
+    RespostaSaude {
+        status: if pronto { Situacao::Ok } else { Situacao::Unavailable },
+        checks: Some(Verificacoes {
+            startup,
+            database,
+            migrations,
+            pool: pool_verificado,
+        }),
+    }
+}
+
+async fn verificar_banco(banco: &Banco) -> VerificacaoBanco {
+    let inicio = Instant::now();
+    let ping = tokio::time::timeout(TEMPO_LIMITE_BANCO, banco.ping()).await;
+
+    // A resposta é pública: o erro do driver (host, usuário, banco) fica só no log
+    let erro = match ping {
+        Ok(Ok(_)) => None,
+        Ok(Err(erro)) => {
+            tracing::warn!(erro = %erro, "banco indisponível na verificação de prontidão");
+            Some("falha ao consultar o banco".to_string())
+        }
+        Err(_) => {
+            tracing::warn!("banco sem resposta na verificação de prontidão");
+            Some(format!("sem resposta em {}s", TEMPO_LIMITE_BANCO.as_secs()))
+        }
+    };
+
+    match erro {
+        None => VerificacaoBanco {
+            status: Situacao::Ok,
+            latency_ms: Some(inicio.elapsed().as_millis()),
+            error: None,
+        },
+        Some(erro) => VerificacaoBanco {
+            status: Situacao::Unavailable,
+            latency_ms: None,
+            error: Some(erro),
+        },
+    }
+}
+
+async fn verificar_migracoes(banco: &Banco) -> VerificacaoMigracoes {
+    let expected_version = banco.versao_esperada();
+    let applied_version = tokio::time::timeout(TEMPO_LIMITE_BANCO, banco.versao_aplicada())
+        .await
+        .ok()
+        .and_then(Result::ok)
+        .flatten();
+
+    // Versão mais nova que a do binário é de uma instância já atualizada
+    // (deploy em andamento); as migrações só acrescentam, então esta segue servindo
+    VerificacaoMigracoes {
+        status: if applied_version >= Some(expected_version) {
+            Situacao::Ok
+        } else {
+            Situacao::Unavailable
+        },
+        applied_version,
+        expected_version,
+    }
+}
+
+fn verificar_pool(banco: &Banco) -> Option<VerificacaoPool> {
+    let pool = banco.estatisticas_pool()?;
+    let (size, idle, max) = (pool.tamanho, pool.ociosas, pool.maximo);
+    let em_uso = size.saturating_sub(idle as u32);
+    let saturation = f64::from(em_uso) / f64::from(max.max(1));
+
+    Some(VerificacaoPool {
+        status: if saturation >= LIMITE_SATURACAO_POOL {
+            Situacao::Unavailable
+        } else {
+            Situacao::Ok
+        },
+        size,
+        idle,
+        max,
+        saturation,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositorios::Repositorios;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    fn memoria() -> Banco {
+        Banco::Memoria(Repositorios::em_memoria())
+    }
+
+    async fn sqlite(conexoes: u32) -> Banco {
+        let pool = SqlitePoolOptions::new().max_connections(conexoes).connect("sqlite::memory:").await.unwrap();
+        Banco::Sqlite(pool)
+    }
+
+    #[tokio::test]
+    async fn pronto_so_depois_da_inicializacao() {
+        let prontidao = Prontidao::iniciando();
+
+        let resposta = pronto(State(memoria()), State(prontidao.clone())).await;
+        assert_eq!(resposta.status, Situacao::Unavailable);
+        assert_eq!(resposta.checks.unwrap().startup.status, Situacao::Starting);
+
+        prontidao.marcar_inicializada();
+        let resposta = pronto(State(memoria()), State(prontidao)).await;
+        assert_eq!(resposta.status, Situacao::Ok);
+        assert_eq!(resposta.into_response().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn encerrando_deixa_de_ficar_pronto() {
+        let prontidao = Prontidao::inicializada();
+        prontidao.marcar_encerrando();
+
+        let resposta = pronto(State(memoria()), State(prontidao)).await;
+        assert_eq!(resposta.status, Situacao::ShuttingDown);
+        assert!(resposta.checks.is_none());
+        assert_eq!(resposta.into_response().status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn pool_saturado_tira_a_instancia_do_trafego() {
+        let banco = sqlite(1).await;
+        let Banco::Sqlite(pool) = &banco else { unreachable!() };
+
+        let conexao = pool.acquire().await.unwrap();
+        let saturado = verificar_pool(&banco).unwrap();
+        assert_eq!(saturado.status, Situacao::Unavailable);
+        assert_eq!(saturado.saturation, 1.0);
+
+        drop(conexao);
+        // A conexão volta ao pool de forma assíncrona
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let livre = verificar_pool(&banco).unwrap();
+        assert_eq!(livre.status, Situacao::Ok);
+        assert_eq!(livre.saturation, 0.0);
+
+        assert!(verificar_pool(&memoria()).is_none());
+    }
+
+    #[tokio::test]
+    async fn migracoes_aceitam_versao_igual_ou_mais_nova() {
+        let banco = sqlite(1).await;
+        assert_eq!(verificar_migracoes(&banco).await.status, Situacao::Unavailable);
+
+        banco.executar_migracoes().await.unwrap();
+        assert_eq!(verificar_migracoes(&banco).await.status, Situacao::Ok);
+
+        // Uma instância mais nova já aplicou a próxima migração
+        let Banco::Sqlite(pool) = &banco else { unreachable!() };
+        let proxima = banco.versao_esperada() + 1;
+        sqlx::query(
+            "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time) VALUES ($1, 'nova', TRUE, x'00', 0)",
+        )
+        .bind(proxima)
+        .execute(pool)
+        .await
+        .unwrap();
+        let migracoes = verificar_migracoes(&banco).await;
+        assert_eq!(migracoes.status, Situacao::Ok);
+        assert_eq!(migracoes.applied_version, Some(proxima));
+    }
+
+    #[tokio::test]
+    async fn erro_do_banco_nao_vaza_na_resposta() {
+        let banco = sqlite(1).await;
+        banco.fechar().await;
+
+        let verificacao = verificar_banco(&banco).await;
+        assert_eq!(verificacao.status, Situacao::Unavailable);
+        assert_eq!(verificacao.error.as_deref(), Some("falha ao consultar o banco"));
+    }
+}
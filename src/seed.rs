@@ -0,0 +1,358 @@
+//! Dados iniciais: fixtures nomeadas (YAML ou JSON) e geração em massa para
+//! testes de carga. Nada aqui roda na inicialização do servidor; quem aplica
+//! é o `blogctl seed` (ou `SEED=<fixture>`, opcional, no `main.rs`).
+//!
+//! A aplicação é idempotente: usuários são identificados pelo email e posts
+//! pelo par (autor, título). Reaplicar a mesma fixture não duplica nada, e
+//! um post cujo conteúdo mudou na fixture é atualizado.
+
+mod gerador;
+
+pub use gerador::{Gerador, Idioma};
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    auth::hash_senha,
+    configuracao::variavel_ou,
+    erros::ErrosValidacao,
+    models::{CriarPostagemRequisicao, Postagem, TAMANHO_MAXIMO_EMAIL, TAMANHO_MAXIMO_NOME_USUARIO},
+    repositorios::Repositorios,
+    validacao::{normalizar_email, Regra, Validar},
+};
+
+/// Fixture embutida no binário, disponível mesmo sem o diretório `fixtures/`.
+pub const FIXTURE_PADRAO: &str = "demo";
+const DEMO_YAML: &str = include_str!("../fixtures/demo.yaml");
+
+const EXTENSOES: [&str; 3] = ["yaml", "yml", "json"];
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Fixture {
+    #[serde(default, rename = "users")]
+    pub usuarios: Vec<UsuarioFixture>,
+    #[serde(default, rename = "posts")]
+    pub postagens: Vec<PostagemFixture>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UsuarioFixture {
+    #[serde(rename = "username")]
+    pub nome_usuario: String,
+    pub email: String,
+    #[serde(rename = "password")]
+    pub senha: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub admin: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PostagemFixture {
+    /// Nome de usuário do autor, da própria fixture ou já cadastrado.
+    #[serde(rename = "author")]
+    pub autor: String,
+    #[serde(rename = "title")]
+    pub titulo: String,
+    #[serde(rename = "content")]
+    pub conteudo: String,
+}
+
+#[derive(Debug)]
+pub enum ErroSeed {
+    FixtureNaoEncontrada(String),
+    Leitura(PathBuf, std::io::Error),
+    Formato(String),
+    Invalida(Vec<String>),
+    Banco(sqlx::Error),
+    Hash(bcrypt::BcryptError),
+}
+
+impl std::fmt::Display for ErroSeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErroSeed::FixtureNaoEncontrada(nome) => write!(
+                f,
+                "fixture {nome} não encontrada (procurada como arquivo e em {})",
+                diretorio_fixtures().display()
+            ),
+            ErroSeed::Leitura(caminho, erro) => write!(f, "{}: {erro}", caminho.display()),
+            ErroSeed::Formato(erro) => write!(f, "fixture mal formada: {erro}"),
+            ErroSeed::Invalida(problemas) => write!(f, "fixture inválida:\n  {}", problemas.join("\n  ")),
+            ErroSeed::Banco(erro) => write!(f, "{erro}"),
+            ErroSeed::Hash(erro) => write!(f, "erro ao fazer hash da senha: {erro}"),
+        }
+    }
+}
+
+impl std::error::Error for ErroSeed {}
+
+impl From<sqlx::Error> for ErroSeed {
+    fn from(erro: sqlx::Error) -> Self {
+        ErroSeed::Banco(erro)
+    }
+}
+
+/// O que a aplicação de uma fixture mudou no banco.
+#[derive(Debug, Default)]
+pub struct Resumo {
+    pub usuarios_criados: usize,
+    pub usuarios_existentes: usize,
+    pub postagens_criadas: usize,
+    pub postagens_atualizadas: usize,
+    pub postagens_existentes: usize,
+    /// Ids das postagens atualizadas, para invalidar o cache de leitura.
+    pub ids_atualizados: Vec<i32>,
+}
+
+impl std::fmt::Display for Resumo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "usuários: {} criados, {} já existiam; posts: {} criados, {} atualizados, {} já existiam",
+            self.usuarios_criados,
+            self.usuarios_existentes,
+            self.postagens_criadas,
+            self.postagens_atualizadas,
+            self.postagens_existentes
+        )
+    }
+}
+
+/// Diretório das fixtures nomeadas (`FIXTURES_DIR`, padrão `fixtures`).
+pub fn diretorio_fixtures() -> PathBuf {
+    PathBuf::from(variavel_ou("FIXTURES_DIR", "fixtures".to_string()))
+}
+
+/// Nomes das fixtures em `diretorio_fixtures()`, mais a embutida.
+pub fn fixtures_disponiveis() -> Vec<String> {
+    let mut nomes: Vec<String> = std::fs::read_dir(diretorio_fixtures())
+        .into_iter()
+        .flatten()
+        .filter_map(|entrada| {
+            let caminho = entrada.ok()?.path();
+            let extensao = caminho.extension()?.to_str()?;
+            EXTENSOES
+                .contains(&extensao)
+                .then(|| caminho.file_stem()?.to_str().map(str::to_string))?
+        })
+        .collect();
+
+    nomes.push(FIXTURE_PADRAO.to_string());
+    nomes.sort();
+    nomes.dedup();
+    nomes
+}
+
+impl Fixture {
+    /// Os 3 usuários e 6 posts de exemplo, embutidos no binário.
+    pub fn demo() -> Self {
+        Self::de_yaml(DEMO_YAML).expect("fixtures/demo.yaml embutida é válida")
+    }
+
+    /// Aceita um caminho de arquivo ou o nome de uma fixture em
+    /// `diretorio_fixtures()` (`<nome>.yaml`, `.yml` ou `.json`).
+    pub fn carregar(nome_ou_caminho: &str) -> Result<Self, ErroSeed> {
+        let caminho = Path::new(nome_ou_caminho);
+        if caminho.is_file() {
+            return Self::ler(caminho);
+        }
+
+        let diretorio = diretorio_fixtures();
+        for extensao in EXTENSOES {
+            let caminho = diretorio.join(format!("{nome_ou_caminho}.{extensao}"));
+            if caminho.is_file() {
+                return Self::ler(&caminho);
+            }
+        }
+
+        if nome_ou_caminho == FIXTURE_PADRAO {
+            return Ok(Self::demo());
+        }
+        Err(ErroSeed::FixtureNaoEncontrada(nome_ou_caminho.to_string()))
+    }
+
+    fn ler(caminho: &Path) -> Result<Self, ErroSeed> {
+        let conteudo = std::fs::read_to_string(caminho).map_err(|erro| ErroSeed::Leitura(caminho.to_path_buf(), erro))?;
+
+        if caminho.extension().is_some_and(|extensao| extensao == "json") {
+            serde_json::from_str(&conteudo).map_err(|erro| ErroSeed::Formato(erro.to_string()))
+        } else {
+            Self::de_yaml(&conteudo)
+        }
+    }
+
+    fn de_yaml(conteudo: &str) -> Result<Self, ErroSeed> {
+        serde_yaml::from_str(conteudo).map_err(|erro| ErroSeed::Formato(erro.to_string()))
+    }
+
+    /// Grava a fixture em YAML ou, com extensão `.json`, em JSON.
+    pub fn salvar(&self, caminho: &Path) -> Result<(), ErroSeed> {
+        let conteudo = if caminho.extension().is_some_and(|extensao| extensao == "json") {
+            serde_json::to_string_pretty(self).map_err(|erro| ErroSeed::Formato(erro.to_string()))?
+        } else {
+            serde_yaml::to_string(self).map_err(|erro| ErroSeed::Formato(erro.to_string()))?
+        };
+        std::fs::write(caminho, conteudo).map_err(|erro| ErroSeed::Leitura(caminho.to_path_buf(), erro))
+    }
+
+    /// Normaliza emails e títulos e confere os campos com as regras da API.
+    /// A política de senhas não se aplica: fixtures são dados de desenvolvimento.
+    fn validar(&mut self) -> Result<(), ErroSeed> {
+        let mut problemas = Vec::new();
+        let mut nomes = HashSet::new();
+        let mut emails = HashSet::new();
+
+        for (indice, usuario) in self.usuarios.iter_mut().enumerate() {
+            usuario.nome_usuario = usuario.nome_usuario.trim().to_string();
+            usuario.email = normalizar_email(&usuario.email);
+
+            let mut erros = ErrosValidacao::default();
+            erros.campo("username", &usuario.nome_usuario, &[
+                Regra::Obrigatorio,
+                Regra::TamanhoMinimo(3),
+                Regra::TamanhoMaximo(TAMANHO_MAXIMO_NOME_USUARIO),
+                Regra::SemEspacos,
+                Regra::Alfanumerico("_.-"),
+            ]);
+            erros.campo("email", &usuario.email, &[
+                Regra::Obrigatorio,
+                Regra::TamanhoMaximo(TAMANHO_MAXIMO_EMAIL),
+                Regra::Email,
+            ]);
+            erros.campo("password", &usuario.senha, &[Regra::Obrigatorio]);
+            if !nomes.insert(usuario.nome_usuario.clone()) {
+                erros.adicionar("username", "Repetido na fixture");
+            }
+            if !emails.insert(usuario.email.clone()) {
+                erros.adicionar("email", "Repetido na fixture");
+            }
+            anotar(&mut problemas, format!("users[{indice}]"), erros);
+        }
+
+        for (indice, postagem) in self.postagens.iter_mut().enumerate() {
+            let mut requisicao = CriarPostagemRequisicao {
+                titulo: std::mem::take(&mut postagem.titulo),
+                conteudo: std::mem::take(&mut postagem.conteudo),
+            };
+            requisicao.normalizar();
+
+            let mut erros = ErrosValidacao::default();
+            erros.campo("author", &postagem.autor, &[Regra::Obrigatorio]);
+            requisicao.validar(&mut erros);
+            anotar(&mut problemas, format!("posts[{indice}]"), erros);
+
+            postagem.titulo = requisicao.titulo;
+            postagem.conteudo = requisicao.conteudo;
+        }
+
+        if problemas.is_empty() { Ok(()) } else { Err(ErroSeed::Invalida(problemas)) }
+    }
+}
+
+fn anotar(problemas: &mut Vec<String>, onde: String, erros: ErrosValidacao) {
+    if !erros.is_empty() {
+        problemas.extend(erros.to_string().lines().map(|linha| format!("{onde}.{linha}")));
+    }
+}
+
+/// Aplica a fixture. Usuários que já existem (mesmo email) são mantidos como
+/// estão, inclusive a senha; só ganham o privilégio de administrador se a
+/// fixture pedir. Um email novo com nome de usuário já usado por outra conta
+/// é um erro.
+pub async fn aplicar(repositorios: &Repositorios, mut fixture: Fixture) -> Result<Resumo, ErroSeed> {
+    fixture.validar()?;
+    let mut resumo = Resumo::default();
+
+    // bcrypt é caro de propósito: senhas repetidas (como as da geração em massa) viram um hash só
+    let mut hashes: HashMap<String, String> = HashMap::new();
+    let mut ids_usuarios: HashMap<String, i32> = HashMap::new();
+
+    for usuario in &fixture.usuarios {
+        let id = match repositorios.usuarios.buscar_por_email(&usuario.email).await? {
+            Some(existente) => {
+                resumo.usuarios_existentes += 1;
+                existente.id
+            }
+            None => {
+                if repositorios.usuarios.buscar_por_nome_usuario(&usuario.nome_usuario).await?.is_some() {
+                    return Err(ErroSeed::Invalida(vec![format!(
+                        "o nome de usuário {} já pertence a outra conta",
+                        usuario.nome_usuario
+                    )]));
+                }
+
+                let hash = match hashes.get(&usuario.senha) {
+                    Some(hash) => hash.clone(),
+                    None => {
+                        let hash = hash_senha(&usuario.senha).map_err(ErroSeed::Hash)?;
+                        hashes.insert(usuario.senha.clone(), hash.clone());
+                        hash
+                    }
+                };
+                resumo.usuarios_criados += 1;
+                repositorios.usuarios.criar(&usuario.nome_usuario, &usuario.email, &hash).await?.id
+            }
+        };
+
+        if usuario.admin {
+            repositorios.usuarios.definir_admin(id, true).await?;
+        }
+        ids_usuarios.insert(usuario.nome_usuario.clone(), id);
+    }
+
+    // Posts de cada autor carregados uma vez, para achar o que já existe pelo título
+    let mut postagens_por_autor: HashMap<i32, Vec<Postagem>> = HashMap::new();
+
+    for postagem in &fixture.postagens {
+        let id_usuario = match ids_usuarios.get(&postagem.autor) {
+            Some(id) => *id,
+            None => {
+                let usuario = repositorios.usuarios.buscar_por_nome_usuario(&postagem.autor).await?;
+                let id = usuario
+                    .ok_or_else(|| ErroSeed::Invalida(vec![format!("autor {} não encontrado", postagem.autor)]))?
+                    .id;
+                ids_usuarios.insert(postagem.autor.clone(), id);
+                id
+            }
+        };
+
+        let existentes = match postagens_por_autor.entry(id_usuario) {
+            std::collections::hash_map::Entry::Occupied(entrada) => entrada.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entrada) => {
+                entrada.insert(repositorios.postagens.listar_do_usuario(id_usuario).await?)
+            }
+        };
+
+        match existentes.iter().find(|existente| existente.titulo == postagem.titulo) {
+            Some(existente) if existente.conteudo == postagem.conteudo => resumo.postagens_existentes += 1,
+            Some(existente) => {
+                repositorios
+                    .postagens
+                    .atualizar(existente.id, id_usuario, &postagem.titulo, &postagem.conteudo, None)
+                    .await?;
+                resumo.postagens_atualizadas += 1;
+                resumo.ids_atualizados.push(existente.id);
+            }
+            None => {
+                let criada = repositorios.postagens.criar(id_usuario, &postagem.titulo, &postagem.conteudo).await?;
+                existentes.push(criada);
+                resumo.postagens_criadas += 1;
+            }
+        }
+    }
+
+    tracing::info!(
+        usuarios_criados = resumo.usuarios_criados,
+        postagens_criadas = resumo.postagens_criadas,
+        postagens_atualizadas = resumo.postagens_atualizadas,
+        "Seed aplicado"
+    );
+    Ok(resumo)
+}
@@ -0,0 +1,255 @@
+//! Geração em massa de usuários e posts para testes de carga. A mesma semente
+//! (com o mesmo idioma e as mesmas quantidades) gera sempre a mesma fixture,
+//! então rodar de novo não cria nada a mais.
+
+use fake::{
+    faker::name::raw::{FirstName, LastName},
+    locales::{EN, PT_BR},
+    Fake,
+};
+use rand::{rngs::StdRng, seq::IndexedRandom, Rng, SeedableRng};
+use std::collections::HashSet;
+
+use super::{Fixture, PostagemFixture, UsuarioFixture};
+use crate::models::TAMANHO_MAXIMO_NOME_USUARIO;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Idioma {
+    Pt,
+    En,
+}
+
+impl std::str::FromStr for Idioma {
+    type Err = String;
+
+    fn from_str(valor: &str) -> Result<Self, Self::Err> {
+        match valor.to_ascii_lowercase().as_str() {
+            "pt" | "pt-br" | "pt_br" => Ok(Idioma::Pt),
+            "en" => Ok(Idioma::En),
+            _ => Err(format!("idioma {valor} não suportado (use pt ou en)")),
+        }
+    }
+}
+
+struct Textos {
+    temas: &'static [&'static str],
+    titulos: &'static [&'static str],
+    frases: &'static [&'static str],
+    parte: &'static str,
+}
+
+const TEXTOS_PT: Textos = Textos {
+    temas: &[
+        "Rust", "PostgreSQL", "Axum", "SQLite", "Docker", "Kubernetes", "testes automatizados", "observabilidade",
+        "cache", "filas de mensagens", "APIs REST", "segurança de senhas", "café especial", "trilhas de bicicleta",
+        "fotografia de rua", "leitura", "pão de fermentação natural", "viagens de trem", "jardinagem",
+        "trabalho remoto", "música ao vivo", "xadrez",
+    ],
+    titulos: &[
+        "Aprendendo {tema} do zero",
+        "O que ninguém me contou sobre {tema}",
+        "Cinco lições depois de um ano com {tema}",
+        "{tema} na prática",
+        "Por que voltei a pensar em {tema}",
+        "Um fim de semana com {tema}",
+        "Erros comuns com {tema}",
+        "Notas rápidas sobre {tema}",
+        "{tema}: vale a pena?",
+        "Meu guia de bolso de {tema}",
+    ],
+    frases: &[
+        "Comecei sem muita expectativa, mas {tema} acabou mudando a forma como organizo meu dia.",
+        "A documentação ajuda, só que nada substitui errar algumas vezes.",
+        "O primeiro passo foi entender o problema antes de escolher a ferramenta.",
+        "Anotei tudo o que deu errado, e a lista ficou maior do que eu gostaria.",
+        "Vale a pena conversar com quem já passou por isso.",
+        "Com {tema}, a paciência conta tanto quanto a técnica.",
+        "No começo tudo parecia complicado demais.",
+        "Depois de algumas semanas, as peças começaram a se encaixar.",
+        "Ainda tenho muito a aprender, mas o caminho ficou mais claro.",
+        "O segredo foi reservar um tempo fixo toda semana para {tema}.",
+        "Medir antes de mudar qualquer coisa me poupou muito retrabalho.",
+        "Fiz uma lista curta de objetivos e tentei cumprir um de cada vez.",
+        "A comunidade em torno de {tema} é surpreendentemente acolhedora.",
+        "Nem toda dica da internet serve para o seu caso.",
+        "Quando algo deu errado, voltar ao básico resolveu quase sempre.",
+        "Compartilho aqui o que funcionou para mim.",
+        "Se eu pudesse dar um conselho, seria começar pequeno.",
+        "Os resultados apareceram devagar, mas apareceram.",
+        "Gostaria de ter descoberto {tema} bem antes.",
+        "Conte nos comentários como tem sido a sua experiência.",
+    ],
+    parte: "parte",
+};
+
+const TEXTOS_EN: Textos = Textos {
+    temas: &[
+        "Rust", "PostgreSQL", "Axum", "SQLite", "Docker", "Kubernetes", "automated testing", "observability",
+        "caching", "message queues", "REST APIs", "password security", "specialty coffee", "mountain biking",
+        "street photography", "reading", "sourdough baking", "train travel", "gardening", "remote work",
+        "live music", "chess",
+    ],
+    titulos: &[
+        "Learning {tema} from scratch",
+        "What nobody told me about {tema}",
+        "Five lessons after a year with {tema}",
+        "{tema} in practice",
+        "Why I keep coming back to {tema}",
+        "A weekend with {tema}",
+        "Common mistakes with {tema}",
+        "Quick notes on {tema}",
+        "{tema}: is it worth it?",
+        "My pocket guide to {tema}",
+    ],
+    frases: &[
+        "I started without many expectations, but {tema} ended up changing how I plan my day.",
+        "The documentation helps, but nothing replaces getting it wrong a few times.",
+        "The first step was understanding the problem before picking a tool.",
+        "I wrote down everything that went wrong, and the list was longer than I'd like.",
+        "It pays to talk to people who have been through it.",
+        "With {tema}, patience matters as much as technique.",
+        "At first everything seemed far too complicated.",
+        "After a few weeks, the pieces started to fall into place.",
+        "I still have a lot to learn, but the path is clearer now.",
+        "The trick was setting aside a fixed time every week for {tema}.",
+        "Measuring before changing anything saved me a lot of rework.",
+        "I made a short list of goals and tackled them one at a time.",
+        "The community around {tema} is surprisingly welcoming.",
+        "Not every tip on the internet fits your situation.",
+        "Whenever something broke, going back to basics almost always fixed it.",
+        "Here is what worked for me.",
+        "If I could give one piece of advice, it would be to start small.",
+        "The results came slowly, but they came.",
+        "I wish I had discovered {tema} much earlier.",
+        "Let me know in the comments how it has been going for you.",
+    ],
+    parte: "part",
+};
+
+pub struct Gerador {
+    rng: StdRng,
+    idioma: Idioma,
+}
+
+impl Gerador {
+    pub fn new(semente: u64, idioma: Idioma) -> Self {
+        Gerador { rng: StdRng::seed_from_u64(semente), idioma }
+    }
+
+    /// `usuarios` contas, todas com a mesma `senha`, e `postagens` posts
+    /// distribuídos entre elas ao acaso.
+    pub fn gerar(&mut self, usuarios: usize, postagens: usize, senha: &str) -> Fixture {
+        let mut nomes = HashSet::new();
+        let usuarios: Vec<UsuarioFixture> = (0..usuarios)
+            .map(|_| {
+                let nome_usuario = self.nome_usuario(&mut nomes);
+                UsuarioFixture {
+                    email: format!("{nome_usuario}@example.com"),
+                    nome_usuario,
+                    senha: senha.to_string(),
+                    admin: false,
+                }
+            })
+            .collect();
+
+        let mut titulos = HashSet::new();
+        let postagens = if usuarios.is_empty() {
+            Vec::new()
+        } else {
+            (0..postagens)
+                .map(|_| {
+                    let autor = usuarios[self.rng.random_range(0..usuarios.len())].nome_usuario.clone();
+                    let tema = *self.textos().temas.choose(&mut self.rng).unwrap();
+                    let titulo = self.titulo(&autor, tema, &mut titulos);
+                    PostagemFixture { autor, titulo, conteudo: self.conteudo(tema) }
+                })
+                .collect()
+        };
+
+        Fixture { usuarios, postagens }
+    }
+
+    fn textos(&self) -> &'static Textos {
+        match self.idioma {
+            Idioma::Pt => &TEXTOS_PT,
+            Idioma::En => &TEXTOS_EN,
+        }
+    }
+
+    /// `nome.sobrenome`, sem acentos, com um número quando já foi usado.
+    fn nome_usuario(&mut self, usados: &mut HashSet<String>) -> String {
+        let (nome, sobrenome): (String, String) = match self.idioma {
+            Idioma::Pt => (FirstName(PT_BR).fake_with_rng(&mut self.rng), LastName(PT_BR).fake_with_rng(&mut self.rng)),
+            Idioma::En => (FirstName(EN).fake_with_rng(&mut self.rng), LastName(EN).fake_with_rng(&mut self.rng)),
+        };
+
+        let mut base = format!("{}.{}", sem_acentos(&nome), sem_acentos(&sobrenome));
+        // Espaço para o sufixo numérico
+        base.truncate(TAMANHO_MAXIMO_NOME_USUARIO - 6);
+
+        let mut candidato = base.clone();
+        let mut sufixo = 1;
+        while !usados.insert(candidato.clone()) {
+            sufixo += 1;
+            candidato = format!("{base}{sufixo}");
+        }
+        candidato
+    }
+
+    /// Títulos não se repetem para o mesmo autor: o par (autor, título) identifica o post.
+    fn titulo(&mut self, autor: &str, tema: &str, usados: &mut HashSet<(String, String)>) -> String {
+        let textos = self.textos();
+        let base = textos.titulos.choose(&mut self.rng).unwrap().replace("{tema}", tema);
+
+        let mut titulo = primeira_maiuscula(&base);
+        let mut parte = 1;
+        while !usados.insert((autor.to_string(), titulo.clone())) {
+            parte += 1;
+            titulo = format!("{} ({} {parte})", primeira_maiuscula(&base), textos.parte);
+        }
+        titulo
+    }
+
+    /// De dois a quatro parágrafos de duas a quatro frases, sem repetir frases.
+    fn conteudo(&mut self, tema: &str) -> String {
+        let tamanhos: Vec<usize> = (0..self.rng.random_range(2..=4)).map(|_| self.rng.random_range(2..=4)).collect();
+        let mut frases = self
+            .textos()
+            .frases
+            .choose_multiple(&mut self.rng, tamanhos.iter().sum())
+            .map(|frase| frase.replace("{tema}", tema));
+
+        tamanhos
+            .iter()
+            .map(|tamanho| frases.by_ref().take(*tamanho).collect::<Vec<_>>().join(" "))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+fn primeira_maiuscula(texto: &str) -> String {
+    let mut caracteres = texto.chars();
+    match caracteres.next() {
+        Some(primeiro) => primeiro.to_uppercase().chain(caracteres).collect(),
+        None => String::new(),
+    }
+}
+
+/// Minúsculas ASCII, para caber nas regras de nome de usuário.
+fn sem_acentos(texto: &str) -> String {
+    texto
+        .to_lowercase()
+        .chars()
+        .filter_map(|caractere| match caractere {
+            'á' | 'à' | 'â' | 'ã' | 'ä' => Some('a'),
+            'é' | 'è' | 'ê' | 'ë' => Some('e'),
+            'í' | 'ì' | 'î' | 'ï' => Some('i'),
+            'ó' | 'ò' | 'ô' | 'õ' | 'ö' => Some('o'),
+            'ú' | 'ù' | 'û' | 'ü' => Some('u'),
+            'ç' => Some('c'),
+            'ñ' => Some('n'),
+            'a'..='z' => Some(caractere),
+            _ => None,
+        })
+        .collect()
+}
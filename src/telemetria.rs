@@ -0,0 +1,145 @@
+use axum::{body::Body, extract::MatchedPath, http::Request};
+use tower_http::{
+    classify::{ServerErrorsAsFailures, SharedClassifier},
+    trace::{DefaultOnFailure, DefaultOnResponse, MakeSpan, TraceLayer},
+    LatencyUnit,
+};
+use tracing::{Level, Span};
+use tracing_subscriber::{fmt::format::FmtSpan, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+const FILTRO_PADRAO: &str = "hello_rust=info,tower_http=info,sqlx=warn";
+
+const CABECALHO_REQUEST_ID: &str = "x-request-id";
+
+/// Mantém vivos os exportadores configurados; `finalizar` envia o que ainda estiver em buffer.
+pub struct Telemetria {
+    #[cfg(feature = "otlp")]
+    provedor: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Telemetria {
+    pub fn finalizar(self) {
+        #[cfg(feature = "otlp")]
+        if let Some(provedor) = self.provedor
+            && let Err(erro) = provedor.shutdown()
+        {
+            eprintln!("Erro ao finalizar o exportador OTLP: {erro}");
+        }
+    }
+}
+
+/// Configura o `tracing`:
+/// - `RUST_LOG` define os níveis (padrão: `hello_rust=info,tower_http=info,sqlx=warn`)
+/// - `LOG_FORMAT=json` gera uma linha JSON por evento; `LOG_FORMAT=pretty` usa o formato multilinha
+/// - com a feature `otlp` e `OTEL_EXPORTER_OTLP_ENDPOINT` definido, os spans também são exportados
+pub fn inicializar() -> Telemetria {
+    let filtro = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(FILTRO_PADRAO));
+
+    // O evento de fechamento de cada span traz a duração (`time.busy` / `time.idle`)
+    let formato = std::env::var("LOG_FORMAT").unwrap_or_default().to_lowercase();
+    let camada_log = match formato.as_str() {
+        "json" => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .with_span_events(FmtSpan::CLOSE)
+            .boxed(),
+        "pretty" => tracing_subscriber::fmt::layer()
+            .pretty()
+            .with_span_events(FmtSpan::CLOSE)
+            .boxed(),
+        _ => tracing_subscriber::fmt::layer()
+            .with_span_events(FmtSpan::CLOSE)
+            .boxed(),
+    };
+
+    #[cfg(feature = "otlp")]
+    let (camada_otlp, provedor) = match otlp::criar_camada() {
+        Some((camada, provedor)) => (Some(camada), Some(provedor)),
+        None => (None, None),
+    };
+    #[cfg(not(feature = "otlp"))]
+    let camada_otlp: Option<tracing_subscriber::layer::Identity> = None;
+
+    tracing_subscriber::registry()
+        .with(filtro)
+        .with(camada_log)
+        .with(camada_otlp)
+        .init();
+
+    Telemetria {
+        #[cfg(feature = "otlp")]
+        provedor,
+    }
+}
+
+/// Camada que abre um span por requisição (com o request id e a rota) e registra
+/// cada resposta com status e latência.
+pub fn camada_trace() -> TraceLayer<SharedClassifier<ServerErrorsAsFailures>, SpanRequisicao> {
+    TraceLayer::new_for_http()
+        .make_span_with(SpanRequisicao)
+        .on_response(DefaultOnResponse::new().level(Level::INFO).latency_unit(LatencyUnit::Millis))
+        .on_failure(DefaultOnFailure::new().level(Level::ERROR).latency_unit(LatencyUnit::Millis))
+}
+
+#[derive(Clone, Copy)]
+pub struct SpanRequisicao;
+
+impl MakeSpan<Body> for SpanRequisicao {
+    fn make_span(&mut self, requisicao: &Request<Body>) -> Span {
+        let request_id = requisicao
+            .headers()
+            .get(CABECALHO_REQUEST_ID)
+            .and_then(|valor| valor.to_str().ok())
+            .unwrap_or_default();
+        let rota = requisicao
+            .extensions()
+            .get::<MatchedPath>()
+            .map(MatchedPath::as_str)
+            .unwrap_or_default();
+
+        tracing::info_span!(
+            "requisicao",
+            request_id,
+            metodo = %requisicao.method(),
+            rota,
+            uri = %requisicao.uri(),
+        )
+    }
+}
+
+#[cfg(feature = "otlp")]
+mod otlp {
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::{trace::SdkTracerProvider, Resource};
+    use tracing_subscriber::{registry::LookupSpan, Layer};
+
+    pub fn criar_camada<S>() -> Option<(impl Layer<S>, SdkTracerProvider)>
+    where
+        S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let endpoint = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok()?;
+
+        let exportador = match SpanExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+            .build()
+        {
+            Ok(exportador) => exportador,
+            Err(erro) => {
+                eprintln!("Erro ao configurar o exportador OTLP: {erro}");
+                return None;
+            }
+        };
+
+        let nome_servico = std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| env!("CARGO_PKG_NAME").to_string());
+        let provedor = SdkTracerProvider::builder()
+            .with_batch_exporter(exportador)
+            .with_resource(Resource::builder().with_service_name(nome_servico).build())
+            .build();
+
+        let camada = tracing_opentelemetry::layer().with_tracer(provedor.tracer(env!("CARGO_PKG_NAME")));
+        Some((camada, provedor))
+    }
+}
@@ -0,0 +1,260 @@
+use axum::{
+    extract::Request,
+    http::{header::{HOST, STRICT_TRANSPORT_SECURITY}, HeaderValue, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    Router,
+};
+use axum_server::tls_rustls::RustlsConfig;
+use std::{
+    io,
+    net::SocketAddr,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+use tower_http::set_header::SetResponseHeaderLayer;
+
+use crate::configuracao::variavel_ou;
+
+/// TLS opcional, ativado quando `TLS_CERTIFICADO` e `TLS_CHAVE` apontam para arquivos PEM:
+/// - `TLS_INTERVALO_RECARGA_SEGUNDOS`: frequência da verificação de mudanças nos arquivos (padrão: `10`)
+/// - `HTTP_REDIRECIONAMENTO_ENDERECO`: se definido, escuta HTTP nesse endereço e redireciona para HTTPS
+/// - `HSTS_MAX_AGE_SEGUNDOS`: `max-age` do `Strict-Transport-Security` (padrão: um ano)
+#[derive(Clone)]
+pub struct ConfiguracaoTls {
+    pub certificado: PathBuf,
+    pub chave: PathBuf,
+    pub intervalo_recarga: Duration,
+    pub endereco_redirecionamento: Option<SocketAddr>,
+    pub hsts_max_age: u64,
+}
+
+impl ConfiguracaoTls {
+    pub fn do_ambiente() -> Option<Self> {
+        let certificado = std::env::var("TLS_CERTIFICADO").ok().map(PathBuf::from);
+        let chave = std::env::var("TLS_CHAVE").ok().map(PathBuf::from);
+
+        let (certificado, chave) = match (certificado, chave) {
+            (Some(certificado), Some(chave)) => (certificado, chave),
+            (None, None) => return None,
+            _ => {
+                tracing::warn!("TLS_CERTIFICADO e TLS_CHAVE precisam ser definidos juntos; servindo apenas HTTP");
+                return None;
+            }
+        };
+
+        let endereco_redirecionamento = std::env::var("HTTP_REDIRECIONAMENTO_ENDERECO")
+            .ok()
+            .and_then(|endereco| match endereco.parse() {
+                Ok(endereco) => Some(endereco),
+                Err(_) => {
+                    tracing::warn!(endereco, "HTTP_REDIRECIONAMENTO_ENDERECO inválido; redirecionamento desativado");
+                    None
+                }
+            });
+
+        Some(ConfiguracaoTls {
+            certificado,
+            chave,
+            intervalo_recarga: Duration::from_secs(variavel_ou("TLS_INTERVALO_RECARGA_SEGUNDOS", 10)),
+            endereco_redirecionamento,
+            hsts_max_age: variavel_ou("HSTS_MAX_AGE_SEGUNDOS", 31_536_000),
+        })
+    }
+
+    /// Carrega o par certificado/chave; o ALPN anuncia `h2` e `http/1.1`.
+    pub async fn carregar(&self) -> io::Result<RustlsConfig> {
+        // Apenas o provedor `ring` é compilado; instalar de novo só falha se já estiver instalado
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        RustlsConfig::from_pem_file(&self.certificado, &self.chave).await
+    }
+
+    fn modificacao(&self) -> Option<(SystemTime, SystemTime)> {
+        let modificado_em = |caminho: &PathBuf| std::fs::metadata(caminho).and_then(|dados| dados.modified()).ok();
+        Some((modificado_em(&self.certificado)?, modificado_em(&self.chave)?))
+    }
+}
+
+/// Tarefa de fundo: recarrega o certificado quando os arquivos mudam, sem
+/// derrubar as conexões abertas. Um par inválido é ignorado e o atual continua em uso.
+pub async fn recarregar_ao_mudar(configuracao: ConfiguracaoTls, rustls: RustlsConfig) {
+    let mut ultima_modificacao = configuracao.modificacao();
+    let mut intervalo = tokio::time::interval(configuracao.intervalo_recarga);
+    intervalo.tick().await;
+
+    loop {
+        intervalo.tick().await;
+
+        let modificacao = configuracao.modificacao();
+        if modificacao.is_none() || modificacao == ultima_modificacao {
+            continue;
+        }
+
+        match rustls.reload_from_pem_file(&configuracao.certificado, &configuracao.chave).await {
+            Ok(()) => {
+                ultima_modificacao = modificacao;
+                tracing::info!(certificado = %configuracao.certificado.display(), "Certificado TLS recarregado");
+            }
+            // Durante a troca os arquivos podem estar pela metade; tenta de novo no próximo ciclo
+            Err(erro) => tracing::warn!(erro = %erro, "falha ao recarregar o certificado TLS"),
+        }
+    }
+}
+
+/// `Strict-Transport-Security` em todas as respostas servidas via HTTPS.
+pub fn camada_hsts(max_age: u64) -> SetResponseHeaderLayer<HeaderValue> {
+    let valor = HeaderValue::from_str(&format!("max-age={max_age}; includeSubDomains"))
+        .expect("valor do HSTS é ASCII");
+    SetResponseHeaderLayer::if_not_present(STRICT_TRANSPORT_SECURITY, valor)
+}
+
+/// Roteador do listener HTTP: toda requisição recebe `308` para a mesma URL em HTTPS.
+pub fn roteador_redirecionamento(porta_https: u16) -> Router {
+    Router::new().fallback(move |requisicao: Request| async move { redirecionar(requisicao, porta_https) })
+}
+
+fn redirecionar(requisicao: Request, porta_https: u16) -> Response {
+    let Some(host) = requisicao.headers().get(HOST).and_then(|host| host.to_str().ok()) else {
+        return (StatusCode::BAD_REQUEST, "Cabeçalho Host ausente").into_response();
+    };
+
+    // Troca a porta do HTTP pela do HTTPS (omitida quando é a padrão)
+    let nome = match host.rsplit_once(':') {
+        Some((nome, porta)) if porta.chars().all(|c| c.is_ascii_digit()) => nome,
+        _ => host,
+    };
+    let autoridade = if porta_https == 443 {
+        nome.to_string()
+    } else {
+        format!("{nome}:{porta_https}")
+    };
+
+    let caminho = requisicao
+        .uri()
+        .path_and_query()
+        .map_or("/", |caminho| caminho.as_str());
+
+    Redirect::permanent(&format!("https://{autoridade}{caminho}")).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get};
+    use std::{fs, sync::Arc};
+    use tower::ServiceExt;
+
+    async fn redirecionamento(porta_https: u16, host: Option<&str>, caminho: &str) -> Response {
+        let mut requisicao = Request::builder().uri(caminho);
+        if let Some(host) = host {
+            requisicao = requisicao.header(HOST, host);
+        }
+        roteador_redirecionamento(porta_https)
+            .oneshot(requisicao.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    fn destino(resposta: &Response) -> &str {
+        assert_eq!(resposta.status(), StatusCode::PERMANENT_REDIRECT);
+        resposta.headers()["location"].to_str().unwrap()
+    }
+
+    #[tokio::test]
+    async fn redirecionamento_troca_a_porta_e_preserva_a_consulta() {
+        let resposta = redirecionamento(8443, Some("blog.example.com:8080"), "/api/v1/posts?page=2&limit=5").await;
+        assert_eq!(destino(&resposta), "https://blog.example.com:8443/api/v1/posts?page=2&limit=5");
+
+        let resposta = redirecionamento(8443, Some("blog.example.com"), "/home").await;
+        assert_eq!(destino(&resposta), "https://blog.example.com:8443/home");
+
+        let resposta = redirecionamento(8443, Some("[::1]:8080"), "/").await;
+        assert_eq!(destino(&resposta), "https://[::1]:8443/");
+    }
+
+    #[tokio::test]
+    async fn redirecionamento_omite_a_porta_padrao() {
+        let resposta = redirecionamento(443, Some("blog.example.com:80"), "/posts?q=rust").await;
+        assert_eq!(destino(&resposta), "https://blog.example.com/posts?q=rust");
+
+        let resposta = redirecionamento(443, Some("blog.example.com"), "/").await;
+        assert_eq!(destino(&resposta), "https://blog.example.com/");
+    }
+
+    #[tokio::test]
+    async fn redirecionamento_sem_host_e_recusado() {
+        let resposta = redirecionamento(443, None, "/").await;
+        assert_eq!(resposta.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn hsts_e_adicionado_sem_sobrescrever_o_da_rota() {
+        let roteador = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .route("/propria", get(|| async { ([(STRICT_TRANSPORT_SECURITY, "max-age=60")], "ok") }))
+            .layer(camada_hsts(31_536_000));
+
+        let resposta = roteador.clone().oneshot(Request::get("/").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(resposta.headers()[STRICT_TRANSPORT_SECURITY], "max-age=31536000; includeSubDomains");
+
+        let resposta = roteador.oneshot(Request::get("/propria").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(resposta.headers()[STRICT_TRANSPORT_SECURITY], "max-age=60");
+    }
+
+    /// Copia um par de `tests/certificados` para `diretorio` e marca os arquivos
+    /// com a data dada, para a mudança não depender da resolução do sistema de arquivos.
+    fn instalar_par(diretorio: &std::path::Path, par: &str, modificado_em: SystemTime) -> (PathBuf, PathBuf) {
+        let origem = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/certificados");
+        let (certificado, chave) = (diretorio.join("cert.pem"), diretorio.join("chave.key"));
+        fs::copy(origem.join(format!("{par}.pem")), &certificado).unwrap();
+        fs::copy(origem.join(format!("{par}.key")), &chave).unwrap();
+        for caminho in [&certificado, &chave] {
+            fs::File::options().write(true).open(caminho).unwrap().set_modified(modificado_em).unwrap();
+        }
+        (certificado, chave)
+    }
+
+    /// Espera a tarefa de recarga passar algumas vezes e diz se a configuração mudou.
+    async fn recarregou(rustls: &RustlsConfig, anterior: &Arc<rustls::ServerConfig>) -> bool {
+        for _ in 0..50 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            if !Arc::ptr_eq(&rustls.get_inner(), anterior) {
+                return true;
+            }
+        }
+        false
+    }
+
+    #[tokio::test]
+    async fn certificado_e_recarregado_quando_os_arquivos_mudam() {
+        let diretorio = std::env::temp_dir().join(format!("tls-recarga-{}", std::process::id()));
+        fs::create_dir_all(&diretorio).unwrap();
+        let inicio = SystemTime::now() - Duration::from_secs(60);
+        let (certificado, chave) = instalar_par(&diretorio, "a", inicio);
+
+        let configuracao = ConfiguracaoTls {
+            certificado: certificado.clone(),
+            chave,
+            intervalo_recarga: Duration::from_millis(10),
+            endereco_redirecionamento: None,
+            hsts_max_age: 0,
+        };
+        let rustls = configuracao.carregar().await.unwrap();
+        let tarefa = tokio::spawn(recarregar_ao_mudar(configuracao, rustls.clone()));
+
+        // Sem mudança nos arquivos, nada é recarregado
+        let original = rustls.get_inner();
+        assert!(!recarregou(&rustls, &original).await);
+
+        instalar_par(&diretorio, "b", inicio + Duration::from_secs(30));
+        assert!(recarregou(&rustls, &original).await);
+
+        // Um certificado pela metade é ignorado e o atual continua em uso
+        let atual = rustls.get_inner();
+        fs::write(&certificado, "-----BEGIN CERTIFICATE-----\n").unwrap();
+        assert!(!recarregou(&rustls, &atual).await);
+
+        tarefa.abort();
+        fs::remove_dir_all(&diretorio).unwrap();
+    }
+}
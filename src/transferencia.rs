@@ -0,0 +1,635 @@
+//! Exportação e importação completas em JSON Lines, para levar o conteúdo
+//! entre ambientes e entre bancos (PostgreSQL, SQLite, memória).
+//!
+//! Um arquivo exportado tem uma linha por registro:
+//!
+//! ```text
+//! {"type":"header","data":{"format":"hello_rust.export","format_version":1,"schema_version":4,...}}
+//! {"type":"user","data":{"id":1,"username":"admin",...}}
+//! {"type":"post","data":{"id":1,"user_id":1,...}}
+//! {"type":"session","data":{"id":"...","user_id":1,...}}
+//! {"type":"manifest","data":{"tables":{"users":{"count":3,"sha256":"..."},...}}}
+//! ```
+//!
+//! O manifesto vem por último, com a contagem e o SHA-256 das linhas de cada
+//! tabela: um arquivo truncado ou alterado é recusado antes de qualquer escrita.
+//! A importação preserva ids e datas sempre que o id está livre; num banco com
+//! dados, os ids em uso ganham valores novos e as chaves estrangeiras são
+//! remapeadas. Usuários com o mesmo email são considerados a mesma conta, e
+//! importar o mesmo arquivo de novo não duplica nada.
+
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+use uuid::Uuid;
+
+use crate::{
+    db::Banco,
+    models::{Postagem, Sessao, Usuario},
+    repositorios::Repositorios,
+};
+
+pub const FORMATO: &str = "hello_rust.export";
+/// Versão do layout das linhas; muda quando um registro muda de forma incompatível.
+pub const VERSAO_FORMATO: u32 = 1;
+
+const TAMANHO_PAGINA: i64 = 500;
+
+const TABELA_USUARIOS: &str = "users";
+const TABELA_POSTAGENS: &str = "posts";
+const TABELA_SESSOES: &str = "sessions";
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+enum Linha {
+    Header(Cabecalho),
+    User(RegistroUsuario),
+    Post(RegistroPostagem),
+    Session(RegistroSessao),
+    Manifest(Manifesto),
+}
+
+impl Linha {
+    fn tabela(&self) -> Option<&'static str> {
+        match self {
+            Linha::User(_) => Some(TABELA_USUARIOS),
+            Linha::Post(_) => Some(TABELA_POSTAGENS),
+            Linha::Session(_) => Some(TABELA_SESSOES),
+            Linha::Header(_) | Linha::Manifest(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cabecalho {
+    pub format: String,
+    pub format_version: u32,
+    /// Última migração aplicada no banco de origem.
+    pub schema_version: i64,
+    /// `postgresql`, `sqlite` ou `memoria`.
+    pub source: String,
+    pub exported_at: DateTime<Utc>,
+}
+
+impl Cabecalho {
+    pub async fn do_banco(banco: &Banco) -> Result<Self, sqlx::Error> {
+        Ok(Cabecalho {
+            format: FORMATO.to_string(),
+            format_version: VERSAO_FORMATO,
+            schema_version: banco.versao_aplicada().await?.unwrap_or_default(),
+            source: banco.sistema().to_string(),
+            exported_at: Utc::now(),
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifesto {
+    pub tables: BTreeMap<String, ResumoTabela>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResumoTabela {
+    pub count: u64,
+    /// SHA-256 (hex) das linhas da tabela, na ordem do arquivo, com as quebras de linha.
+    pub sha256: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RegistroUsuario {
+    id: i32,
+    username: String,
+    email: String,
+    password_hash: String,
+    is_admin: bool,
+    disabled_at: Option<DateTime<Utc>>,
+    // Ausentes em arquivos de bancos anteriores à migração 5
+    deletion_scheduled_at: Option<DateTime<Utc>>,
+    anonymized_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RegistroPostagem {
+    id: i32,
+    user_id: i32,
+    title: String,
+    content: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RegistroSessao {
+    id: Uuid,
+    user_id: i32,
+    user_agent: Option<String>,
+    ip: Option<String>,
+    created_at: DateTime<Utc>,
+    last_seen_at: DateTime<Utc>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<Usuario> for RegistroUsuario {
+    fn from(usuario: Usuario) -> Self {
+        RegistroUsuario {
+            id: usuario.id,
+            username: usuario.nome_usuario,
+            email: usuario.email,
+            password_hash: usuario.hash_senha,
+            is_admin: usuario.admin,
+            disabled_at: usuario.desativado_em,
+            deletion_scheduled_at: usuario.exclusao_agendada_em,
+            anonymized_at: usuario.anonimizado_em,
+            created_at: usuario.criado_em,
+        }
+    }
+}
+
+impl From<RegistroUsuario> for Usuario {
+    fn from(registro: RegistroUsuario) -> Self {
+        Usuario {
+            id: registro.id,
+            nome_usuario: registro.username,
+            email: registro.email,
+            hash_senha: registro.password_hash,
+            criado_em: registro.created_at,
+            admin: registro.is_admin,
+            desativado_em: registro.disabled_at,
+            exclusao_agendada_em: registro.deletion_scheduled_at,
+            anonimizado_em: registro.anonymized_at,
+        }
+    }
+}
+
+impl From<Postagem> for RegistroPostagem {
+    fn from(postagem: Postagem) -> Self {
+        RegistroPostagem {
+            id: postagem.id,
+            user_id: postagem.id_usuario,
+            title: postagem.titulo,
+            content: postagem.conteudo,
+            created_at: postagem.criado_em,
+            updated_at: postagem.atualizado_em,
+        }
+    }
+}
+
+impl From<RegistroPostagem> for Postagem {
+    fn from(registro: RegistroPostagem) -> Self {
+        Postagem {
+            id: registro.id,
+            titulo: registro.title,
+            conteudo: registro.content,
+            id_usuario: registro.user_id,
+            criado_em: registro.created_at,
+            atualizado_em: registro.updated_at,
+        }
+    }
+}
+
+impl From<Sessao> for RegistroSessao {
+    fn from(sessao: Sessao) -> Self {
+        RegistroSessao {
+            id: sessao.id,
+            user_id: sessao.id_usuario,
+            user_agent: sessao.user_agent,
+            ip: sessao.ip,
+            created_at: sessao.criado_em,
+            last_seen_at: sessao.visto_em,
+            revoked_at: sessao.revogada_em,
+        }
+    }
+}
+
+impl From<RegistroSessao> for Sessao {
+    fn from(registro: RegistroSessao) -> Self {
+        Sessao {
+            id: registro.id,
+            id_usuario: registro.user_id,
+            user_agent: registro.user_agent,
+            ip: registro.ip,
+            criado_em: registro.created_at,
+            visto_em: registro.last_seen_at,
+            revogada_em: registro.revoked_at,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ErroTransferencia {
+    Io(std::io::Error),
+    Banco(sqlx::Error),
+    /// Linha ilegível ou fora de ordem (numeradas a partir de 1)
+    Arquivo { linha: usize, mensagem: String },
+    /// Manifesto ausente, contagens ou checksums que não conferem, chaves estrangeiras quebradas
+    Integridade(String),
+    /// Registros que não podem entrar sem sobrescrever dados do destino
+    Conflito(Vec<String>),
+}
+
+impl std::fmt::Display for ErroTransferencia {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErroTransferencia::Io(erro) => write!(f, "{erro}"),
+            ErroTransferencia::Banco(erro) => write!(f, "{erro}"),
+            ErroTransferencia::Arquivo { linha, mensagem } => write!(f, "linha {linha}: {mensagem}"),
+            ErroTransferencia::Integridade(mensagem) => write!(f, "arquivo inconsistente: {mensagem}"),
+            ErroTransferencia::Conflito(conflitos) => {
+                write!(f, "conflitos com o banco de destino:\n  {}", conflitos.join("\n  "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ErroTransferencia {}
+
+impl From<std::io::Error> for ErroTransferencia {
+    fn from(erro: std::io::Error) -> Self {
+        ErroTransferencia::Io(erro)
+    }
+}
+
+impl From<sqlx::Error> for ErroTransferencia {
+    fn from(erro: sqlx::Error) -> Self {
+        ErroTransferencia::Banco(erro)
+    }
+}
+
+/// Contagem e checksum acumulados de uma tabela.
+#[derive(Default)]
+struct Soma {
+    quantidade: u64,
+    hash: Sha256,
+}
+
+impl Soma {
+    fn adicionar(&mut self, linha: &[u8]) {
+        self.quantidade += 1;
+        self.hash.update(linha);
+    }
+
+    fn resumo(self) -> ResumoTabela {
+        ResumoTabela {
+            count: self.quantidade,
+            sha256: format!("{:x}", self.hash.finalize()),
+        }
+    }
+}
+
+struct Escritor<W> {
+    destino: W,
+    somas: BTreeMap<&'static str, Soma>,
+}
+
+impl<W: AsyncWrite + Unpin> Escritor<W> {
+    async fn escrever(&mut self, linha: &Linha) -> Result<(), ErroTransferencia> {
+        let mut bytes = serde_json::to_vec(linha).map_err(std::io::Error::from)?;
+        bytes.push(b'\n');
+
+        if let Some(tabela) = linha.tabela() {
+            self.somas.entry(tabela).or_default().adicionar(&bytes);
+        }
+        self.destino.write_all(&bytes).await?;
+        Ok(())
+    }
+}
+
+/// Grava todos os usuários, posts e sessões em `destino`, página a página.
+///
+/// Não é um snapshot: posts e sessões de usuários criados durante a
+/// exportação ficam de fora, para o arquivo nunca ter chaves estrangeiras quebradas.
+pub async fn exportar<W: AsyncWrite + Unpin>(
+    repositorios: &Repositorios,
+    cabecalho: Cabecalho,
+    destino: W,
+) -> Result<Manifesto, ErroTransferencia> {
+    let mut escritor = Escritor { destino, somas: BTreeMap::new() };
+    escritor.escrever(&Linha::Header(cabecalho)).await?;
+
+    // Tabelas sempre presentes no manifesto, mesmo vazias
+    for tabela in [TABELA_USUARIOS, TABELA_POSTAGENS, TABELA_SESSOES] {
+        escritor.somas.entry(tabela).or_default();
+    }
+
+    let mut ids_usuarios = HashSet::new();
+    let mut ultimo_id = 0;
+    loop {
+        let pagina = repositorios.usuarios.listar_pagina(ultimo_id, TAMANHO_PAGINA).await?;
+        let Some(ultimo) = pagina.last() else { break };
+        ultimo_id = ultimo.id;
+
+        for usuario in pagina {
+            ids_usuarios.insert(usuario.id);
+            escritor.escrever(&Linha::User(usuario.into())).await?;
+        }
+    }
+
+    let mut ultimo_id = 0;
+    loop {
+        let pagina = repositorios.postagens.listar_pagina(ultimo_id, TAMANHO_PAGINA).await?;
+        let Some(ultima) = pagina.last() else { break };
+        ultimo_id = ultima.id;
+
+        for postagem in pagina.into_iter().filter(|postagem| ids_usuarios.contains(&postagem.id_usuario)) {
+            escritor.escrever(&Linha::Post(postagem.into())).await?;
+        }
+    }
+
+    let mut ultimo_id = Uuid::nil();
+    loop {
+        let pagina = repositorios.sessoes.listar_pagina(ultimo_id, TAMANHO_PAGINA).await?;
+        let Some(ultima) = pagina.last() else { break };
+        ultimo_id = ultima.id;
+
+        for sessao in pagina.into_iter().filter(|sessao| ids_usuarios.contains(&sessao.id_usuario)) {
+            escritor.escrever(&Linha::Session(sessao.into())).await?;
+        }
+    }
+
+    let manifesto = Manifesto {
+        tables: std::mem::take(&mut escritor.somas)
+            .into_iter()
+            .map(|(tabela, soma)| (tabela.to_string(), soma.resumo()))
+            .collect(),
+    };
+    escritor.escrever(&Linha::Manifest(manifesto.clone())).await?;
+    escritor.destino.flush().await?;
+
+    Ok(manifesto)
+}
+
+/// Conteúdo de um arquivo já verificado, pronto para ser aplicado.
+struct Conteudo {
+    cabecalho: Cabecalho,
+    usuarios: Vec<Usuario>,
+    postagens: Vec<Postagem>,
+    sessoes: Vec<Sessao>,
+}
+
+/// Lê o arquivo inteiro, conferindo formato, manifesto e chaves estrangeiras.
+async fn ler<R: AsyncBufRead + Unpin>(mut origem: R) -> Result<Conteudo, ErroTransferencia> {
+    let mut cabecalho = None;
+    let mut manifesto = None;
+    let mut somas: BTreeMap<&'static str, Soma> = BTreeMap::new();
+    let (mut usuarios, mut postagens, mut sessoes) = (Vec::new(), Vec::new(), Vec::new());
+
+    let mut texto = String::new();
+    let mut numero = 0;
+    loop {
+        texto.clear();
+        if origem.read_line(&mut texto).await? == 0 {
+            break;
+        }
+        numero += 1;
+        if texto.trim().is_empty() {
+            continue;
+        }
+
+        let erro = |mensagem: String| ErroTransferencia::Arquivo { linha: numero, mensagem };
+        if manifesto.is_some() {
+            return Err(erro("registro depois do manifesto".to_string()));
+        }
+
+        let linha: Linha = serde_json::from_str(&texto).map_err(|falha| erro(falha.to_string()))?;
+        if let Some(tabela) = linha.tabela() {
+            if cabecalho.is_none() {
+                return Err(erro("o arquivo precisa começar pelo cabeçalho".to_string()));
+            }
+            somas.entry(tabela).or_default().adicionar(texto.as_bytes());
+        }
+
+        match linha {
+            Linha::Header(lido) => {
+                if cabecalho.is_some() {
+                    return Err(erro("cabeçalho repetido".to_string()));
+                }
+                if lido.format != FORMATO || lido.format_version != VERSAO_FORMATO {
+                    return Err(erro(format!(
+                        "formato {} versão {} não suportado (esperado {FORMATO} versão {VERSAO_FORMATO})",
+                        lido.format, lido.format_version
+                    )));
+                }
+                cabecalho = Some(lido);
+            }
+            Linha::User(registro) => usuarios.push(Usuario::from(registro)),
+            Linha::Post(registro) => postagens.push(Postagem::from(registro)),
+            Linha::Session(registro) => sessoes.push(Sessao::from(registro)),
+            Linha::Manifest(lido) => manifesto = Some(lido),
+        }
+    }
+
+    let cabecalho = cabecalho.ok_or_else(|| ErroTransferencia::Integridade("arquivo vazio".to_string()))?;
+    let manifesto = manifesto
+        .ok_or_else(|| ErroTransferencia::Integridade("manifesto ausente (arquivo truncado?)".to_string()))?;
+
+    for (tabela, esperado) in &manifesto.tables {
+        let lido = somas.remove(tabela.as_str()).unwrap_or_default().resumo();
+        if lido.count != esperado.count {
+            return Err(ErroTransferencia::Integridade(format!(
+                "{tabela}: {} registros, o manifesto diz {}",
+                lido.count, esperado.count
+            )));
+        }
+        if lido.sha256 != esperado.sha256 {
+            return Err(ErroTransferencia::Integridade(format!("{tabela}: checksum não confere")));
+        }
+    }
+    if let Some(tabela) = somas.keys().next() {
+        return Err(ErroTransferencia::Integridade(format!("{tabela}: tabela fora do manifesto")));
+    }
+
+    verificar_referencias(&usuarios, &postagens, &sessoes)?;
+    Ok(Conteudo { cabecalho, usuarios, postagens, sessoes })
+}
+
+fn verificar_referencias(usuarios: &[Usuario], postagens: &[Postagem], sessoes: &[Sessao]) -> Result<(), ErroTransferencia> {
+    let mut ids_usuarios = HashSet::new();
+    for usuario in usuarios {
+        if !ids_usuarios.insert(usuario.id) {
+            return Err(ErroTransferencia::Integridade(format!("usuário {} repetido", usuario.id)));
+        }
+    }
+
+    let mut ids_postagens = HashSet::new();
+    for postagem in postagens {
+        if !ids_postagens.insert(postagem.id) {
+            return Err(ErroTransferencia::Integridade(format!("post {} repetido", postagem.id)));
+        }
+        if !ids_usuarios.contains(&postagem.id_usuario) {
+            return Err(ErroTransferencia::Integridade(format!(
+                "post {} aponta para o usuário {}, ausente do arquivo",
+                postagem.id, postagem.id_usuario
+            )));
+        }
+    }
+
+    for sessao in sessoes {
+        if !ids_usuarios.contains(&sessao.id_usuario) {
+            return Err(ErroTransferencia::Integridade(format!(
+                "sessão {} aponta para o usuário {}, ausente do arquivo",
+                sessao.id, sessao.id_usuario
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default, Serialize, JsonSchema)]
+pub struct ContagemImportacao {
+    /// Registros inseridos
+    pub imported: u64,
+    /// Registros que já estavam no destino e foram mantidos
+    pub existing: u64,
+}
+
+#[derive(Debug, Default, Serialize, JsonSchema)]
+pub struct ResumoImportacao {
+    /// Versão do esquema do banco de origem, segundo o cabeçalho
+    pub source_schema_version: i64,
+    pub users: ContagemImportacao,
+    pub posts: ContagemImportacao,
+    pub sessions: ContagemImportacao,
+    /// Usuários e posts que ganharam um id novo porque o original estava em uso
+    pub remapped_ids: u64,
+    /// Ids (no destino) dos posts inseridos, para invalidar o cache
+    #[serde(skip)]
+    pub ids_postagens: Vec<i32>,
+}
+
+impl std::fmt::Display for ResumoImportacao {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "usuários: {} importados, {} já existiam; posts: {} importados, {} já existiam; \
+             sessões: {} importadas, {} já existiam; {} ids remapeados",
+            self.users.imported,
+            self.users.existing,
+            self.posts.imported,
+            self.posts.existing,
+            self.sessions.imported,
+            self.sessions.existing,
+            self.remapped_ids
+        )
+    }
+}
+
+/// Verifica o arquivo inteiro e só então grava. Uma falha do banco no meio da
+/// gravação deixa o que já foi importado; como a importação é idempotente,
+/// basta repeti-la.
+pub async fn importar<R: AsyncBufRead + Unpin>(
+    repositorios: &Repositorios,
+    origem: R,
+) -> Result<ResumoImportacao, ErroTransferencia> {
+    let conteudo = ler(origem).await?;
+    let mut resumo = ResumoImportacao {
+        source_schema_version: conteudo.cabecalho.schema_version,
+        ..Default::default()
+    };
+
+    // Conflitos conferidos antes de gravar qualquer coisa
+    let mut existentes = HashMap::new();
+    let mut conflitos = Vec::new();
+    for usuario in &conteudo.usuarios {
+        match repositorios.usuarios.buscar_por_email(&usuario.email.to_lowercase()).await? {
+            Some(existente) => {
+                existentes.insert(usuario.id, existente.id);
+            }
+            None => {
+                if let Some(outro) = repositorios.usuarios.buscar_por_nome_usuario(&usuario.nome_usuario).await? {
+                    conflitos.push(format!(
+                        "usuário {} ({}): o nome já pertence a {}",
+                        usuario.nome_usuario, usuario.email, outro.email
+                    ));
+                }
+            }
+        }
+    }
+    if !conflitos.is_empty() {
+        return Err(ErroTransferencia::Conflito(conflitos));
+    }
+
+    // id no arquivo -> id no destino
+    let mut ids_usuarios: HashMap<i32, i32> = HashMap::new();
+    let mut pendentes = Vec::new();
+    for usuario in &conteudo.usuarios {
+        if let Some(id) = existentes.get(&usuario.id) {
+            ids_usuarios.insert(usuario.id, *id);
+            resumo.users.existing += 1;
+        } else {
+            let manter_id = repositorios.usuarios.buscar_por_id(usuario.id).await?.is_none();
+            pendentes.push((usuario, manter_id));
+        }
+    }
+    // Primeiro os ids livres: um id novo gerado antes poderia ocupar o de outro registro do arquivo
+    pendentes.sort_by_key(|(_, manter_id)| !manter_id);
+    for (usuario, manter_id) in pendentes {
+        let importado = repositorios.usuarios.importar(usuario, manter_id).await?;
+        if !manter_id {
+            resumo.remapped_ids += 1;
+        }
+        ids_usuarios.insert(usuario.id, importado.id);
+        resumo.users.imported += 1;
+    }
+
+    // Um post já importado é reconhecido pelo autor, título e data de criação
+    let mut postagens_por_autor: HashMap<i32, Vec<Postagem>> = HashMap::new();
+    let mut pendentes = Vec::new();
+    for postagem in conteudo.postagens {
+        let id_usuario = ids_usuarios[&postagem.id_usuario];
+        let do_autor = match postagens_por_autor.entry(id_usuario) {
+            std::collections::hash_map::Entry::Occupied(entrada) => entrada.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entrada) => {
+                entrada.insert(repositorios.postagens.listar_do_usuario(id_usuario).await?)
+            }
+        };
+
+        if do_autor
+            .iter()
+            .any(|existente| existente.titulo == postagem.titulo && mesmo_instante(existente.criado_em, postagem.criado_em))
+        {
+            resumo.posts.existing += 1;
+            continue;
+        }
+
+        let postagem = Postagem { id_usuario, ..postagem };
+        let manter_id = repositorios.postagens.buscar(postagem.id).await?.is_none();
+        do_autor.push(postagem.clone());
+        pendentes.push((postagem, manter_id));
+    }
+    pendentes.sort_by_key(|(_, manter_id)| !manter_id);
+    for (postagem, manter_id) in pendentes {
+        let importada = repositorios.postagens.importar(&postagem, manter_id).await?;
+        if !manter_id {
+            resumo.remapped_ids += 1;
+        }
+        resumo.ids_postagens.push(importada.id);
+        resumo.posts.imported += 1;
+    }
+
+    for sessao in conteudo.sessoes {
+        let sessao = Sessao { id_usuario: ids_usuarios[&sessao.id_usuario], ..sessao };
+        if repositorios.sessoes.importar(&sessao).await? {
+            resumo.sessions.imported += 1;
+        } else {
+            resumo.sessions.existing += 1;
+        }
+    }
+
+    tracing::info!(
+        usuarios = resumo.users.imported,
+        posts = resumo.posts.imported,
+        sessoes = resumo.sessions.imported,
+        ids_remapeados = resumo.remapped_ids,
+        "Importação concluída"
+    );
+    Ok(resumo)
+}
+
+// O PostgreSQL guarda microssegundos; a memória e o SQLite, nanossegundos
+fn mesmo_instante(a: DateTime<Utc>, b: DateTime<Utc>) -> bool {
+    a.timestamp_micros() == b.timestamp_micros()
+}
@@ -0,0 +1,316 @@
+//! Feed de posts em tempo real, servido por Server-Sent Events em
+//! `GET /api/v1/posts/stream`.
+//!
+//! Criações, edições e exclusões são publicadas com [`Transmissao::publicar`].
+//! No PostgreSQL a publicação é um `NOTIFY`: todas as instâncias ligadas ao
+//! banco, inclusive a que publicou, recebem o aviso por `LISTEN` e o repassam
+//! aos seus clientes com o mesmo id (da sequência `post_events_id_seq`). Nos
+//! demais bancos o aviso vai direto aos clientes da própria instância.
+//!
+//! Cada instância guarda os últimos eventos para reenviá-los a quem reconecta
+//! com `Last-Event-ID`. Quem ficou para trás além disso recebe `reset` e deve
+//! recarregar a lista.
+
+use axum::response::sse::Event;
+use futures::{future, stream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::PgPool;
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+use tokio::sync::{broadcast, broadcast::error::RecvError, OnceCell};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    db::{self, Banco},
+    models::RespostaPostagem,
+    repositorios::Repositorios,
+};
+
+/// Eventos guardados para o `Last-Event-ID`.
+const CAPACIDADE_HISTORICO: usize = 256;
+/// Eventos em trânsito por cliente; quem atrasa mais que isso recebe `reset`.
+const CAPACIDADE_CANAL: usize = 256;
+const CANAL: &str = "post_events";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TipoEvento {
+    #[serde(rename = "created")]
+    Criada,
+    #[serde(rename = "updated")]
+    Atualizada,
+    #[serde(rename = "deleted")]
+    Apagada,
+}
+
+impl TipoEvento {
+    /// Nome do evento SSE (`event:`).
+    pub fn nome(self) -> &'static str {
+        match self {
+            TipoEvento::Criada => "post.created",
+            TipoEvento::Atualizada => "post.updated",
+            TipoEvento::Apagada => "post.deleted",
+        }
+    }
+
+    fn texto(self) -> &'static str {
+        match self {
+            TipoEvento::Criada => "created",
+            TipoEvento::Atualizada => "updated",
+            TipoEvento::Apagada => "deleted",
+        }
+    }
+}
+
+/// O que é publicado (e viaja no `NOTIFY`); os dados do post são lidos por
+/// quem entrega, porque o payload do `NOTIFY` é limitado a 8000 bytes.
+#[derive(Debug, Serialize, Deserialize)]
+struct Aviso {
+    id: u64,
+    #[serde(rename = "type")]
+    tipo: TipoEvento,
+    post_id: i32,
+}
+
+#[derive(Debug)]
+pub struct EventoPostagem {
+    pub id: u64,
+    pub tipo: TipoEvento,
+    /// JSON do `data:`: o post, ou só `{"id": ...}` numa exclusão
+    pub dados: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum Mensagem {
+    Evento(Arc<EventoPostagem>),
+    /// O cliente perdeu eventos e deve recarregar a lista
+    Reiniciar,
+}
+
+impl Mensagem {
+    pub fn evento_sse(&self) -> Event {
+        match self {
+            Mensagem::Evento(evento) => Event::default()
+                .id(evento.id.to_string())
+                .event(evento.tipo.nome())
+                .data(&evento.dados),
+            // Sem `data`, o EventSource do navegador descarta o evento
+            Mensagem::Reiniciar => Event::default().event("reset").data("{}"),
+        }
+    }
+}
+
+/// Distribuição dos eventos de posts aos clientes do feed. Clonar é barato:
+/// os clones compartilham o canal e o histórico.
+#[derive(Clone)]
+pub struct Transmissao {
+    interno: Arc<Interno>,
+}
+
+struct Interno {
+    repositorios: Repositorios,
+    postgres: Option<PgPool>,
+    proximo_id: AtomicU64,
+    canal: broadcast::Sender<Arc<EventoPostagem>>,
+    historico: Mutex<VecDeque<Arc<EventoPostagem>>>,
+    escuta: OnceCell<()>,
+    encerramento: CancellationToken,
+}
+
+impl Transmissao {
+    pub fn new(banco: &Banco, repositorios: Repositorios) -> Self {
+        let postgres = match banco {
+            Banco::Postgres(pool) => Some(pool.clone()),
+            Banco::Sqlite(_) | Banco::Memoria(_) => None,
+        };
+
+        Transmissao {
+            interno: Arc::new(Interno {
+                repositorios,
+                postgres,
+                proximo_id: AtomicU64::new(1),
+                canal: broadcast::channel(CAPACIDADE_CANAL).0,
+                historico: Mutex::new(VecDeque::with_capacity(CAPACIDADE_HISTORICO)),
+                escuta: OnceCell::new(),
+                encerramento: CancellationToken::new(),
+            }),
+        }
+    }
+
+    /// Publica a mudança de um post. Uma falha só é registrada no log: o post
+    /// já foi gravado e os clientes se recuperam com `reset` ou recarregando.
+    pub async fn publicar(&self, tipo: TipoEvento, id_postagem: i32) {
+        let Some(pool) = &self.interno.postgres else {
+            let id = self.interno.proximo_id.fetch_add(1, Ordering::Relaxed);
+            self.interno.entregar(Aviso { id, tipo, post_id: id_postagem }).await;
+            return;
+        };
+
+        let resultado = sqlx::query(
+            r#"
+            SELECT pg_notify(
+                'post_events_' || current_schema(),
+                json_build_object('id', nextval('post_events_id_seq'), 'type', $1::TEXT, 'post_id', $2::INTEGER)::TEXT
+            )
+            "#,
+        )
+        .bind(tipo.texto())
+        .bind(id_postagem)
+        .execute(pool)
+        .await;
+
+        if let Err(erro) = resultado {
+            tracing::warn!(erro = %erro, id_postagem, "falha ao publicar o evento do post");
+        }
+    }
+
+    /// Eventos a partir de agora. Com `ultimo_id` (o `Last-Event-ID`), antes vêm
+    /// os eventos posteriores a ele ainda no histórico, ou `reset` se algum já saiu.
+    /// O fluxo termina com [`Transmissao::encerrar`].
+    pub async fn assinar(&self, ultimo_id: Option<u64>) -> Result<impl Stream<Item = Mensagem> + use<>, sqlx::Error> {
+        self.garantir_escuta().await?;
+
+        // Inscrito antes de ler o histórico: nada publicado no meio se perde
+        let receptor = self.interno.canal.subscribe();
+        let (atrasados, reenviados) = match ultimo_id {
+            None => (Vec::new(), HashSet::new()),
+            Some(ultimo_id) => {
+                let historico = self.interno.historico.lock().unwrap();
+                if historico.front().is_some_and(|evento| evento.id > ultimo_id.saturating_add(1)) {
+                    (vec![Mensagem::Reiniciar], HashSet::new())
+                } else {
+                    let eventos: Vec<Arc<EventoPostagem>> =
+                        historico.iter().filter(|evento| evento.id > ultimo_id).cloned().collect();
+                    let ids = eventos.iter().map(|evento| evento.id).collect();
+                    (eventos.into_iter().map(Mensagem::Evento).collect(), ids)
+                }
+            }
+        };
+
+        let ao_vivo = stream::unfold(receptor, |mut receptor| async move {
+            let mensagem = match receptor.recv().await {
+                Ok(evento) => Mensagem::Evento(evento),
+                Err(RecvError::Lagged(_)) => Mensagem::Reiniciar,
+                Err(RecvError::Closed) => return None,
+            };
+            Some((mensagem, receptor))
+        })
+        .filter(move |mensagem| {
+            let repetido = matches!(mensagem, Mensagem::Evento(evento) if reenviados.contains(&evento.id));
+            future::ready(!repetido)
+        });
+
+        Ok(stream::iter(atrasados)
+            .chain(ao_vivo)
+            .take_until(self.interno.encerramento.clone().cancelled_owned()))
+    }
+
+    /// Termina os fluxos abertos e a escuta do PostgreSQL; sem isso, as conexões
+    /// do feed segurariam o encerramento gracioso até o prazo.
+    pub fn encerrar(&self) {
+        self.interno.encerramento.cancel();
+    }
+
+    /// No PostgreSQL, começa a escutar o canal na primeira assinatura.
+    async fn garantir_escuta(&self) -> Result<(), sqlx::Error> {
+        let Some(pool) = &self.interno.postgres else {
+            return Ok(());
+        };
+
+        self.interno
+            .escuta
+            .get_or_try_init(|| async {
+                let ouvinte = db::escutar(pool, CANAL).await?;
+
+                // Só uma referência fraca: a escuta não mantém a `Transmissao` viva
+                let interno = Arc::downgrade(&self.interno);
+                tokio::spawn(db::repassar_avisos(ouvinte, self.interno.encerramento.clone(), move |payload| {
+                    let interno = interno.upgrade()?;
+                    Some(async move {
+                        match serde_json::from_str::<Aviso>(&payload) {
+                            Ok(aviso) => interno.entregar(aviso).await,
+                            Err(erro) => tracing::warn!(erro = %erro, "aviso de post ilegível no canal do feed"),
+                        }
+                    })
+                }));
+                Ok(())
+            })
+            .await
+            .map(|_| ())
+    }
+}
+
+impl Interno {
+    async fn entregar(&self, aviso: Aviso) {
+        let dados = match aviso.tipo {
+            TipoEvento::Apagada => json!({ "id": aviso.post_id }).to_string(),
+            TipoEvento::Criada | TipoEvento::Atualizada => match self.repositorios.postagens.buscar(aviso.post_id).await {
+                Ok(Some(postagem)) => match serde_json::to_string(&RespostaPostagem::from(postagem)) {
+                    Ok(dados) => dados,
+                    Err(erro) => {
+                        tracing::warn!(erro = %erro, id_postagem = aviso.post_id, "falha ao serializar o post do feed");
+                        return;
+                    }
+                },
+                // Apagado logo depois; o aviso da exclusão vem em seguida
+                Ok(None) => return,
+                Err(erro) => {
+                    tracing::warn!(erro = %erro, id_postagem = aviso.post_id, "falha ao ler o post do feed");
+                    return;
+                }
+            },
+        };
+
+        let evento = Arc::new(EventoPostagem { id: aviso.id, tipo: aviso.tipo, dados });
+        {
+            let mut historico = self.historico.lock().unwrap();
+            if historico.len() == CAPACIDADE_HISTORICO {
+                historico.pop_front();
+            }
+            historico.push_back(evento.clone());
+        }
+
+        // Sem clientes conectados o envio falha, e tudo bem
+        let _ = self.canal.send(evento);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transmissao() -> Transmissao {
+        let repositorios = Repositorios::em_memoria();
+        Transmissao::new(&Banco::Memoria(repositorios.clone()), repositorios)
+    }
+
+    #[tokio::test]
+    async fn last_event_id_fora_do_historico_pede_reset() {
+        let transmissao = transmissao();
+        // Exclusões não dependem do post existir
+        for id in 0..CAPACIDADE_HISTORICO as i32 + 2 {
+            transmissao.publicar(TipoEvento::Apagada, id).await;
+        }
+
+        let mut fluxo = Box::pin(transmissao.assinar(Some(1)).await.unwrap());
+        assert!(matches!(fluxo.next().await, Some(Mensagem::Reiniciar)));
+
+        // O evento 2 ainda saiu do histórico; o 3 é o mais antigo guardado
+        let mut fluxo = Box::pin(transmissao.assinar(Some(2)).await.unwrap());
+        assert!(matches!(fluxo.next().await, Some(Mensagem::Evento(evento)) if evento.id == 3));
+    }
+
+    #[tokio::test]
+    async fn encerrar_termina_os_fluxos() {
+        let transmissao = transmissao();
+        let mut fluxo = Box::pin(transmissao.assinar(None).await.unwrap());
+
+        transmissao.encerrar();
+        assert!(fluxo.next().await.is_none());
+    }
+}
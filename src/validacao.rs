@@ -0,0 +1,269 @@
+use axum::{
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    http::StatusCode,
+    Json,
+};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    erros::{ErroApi, ErrosValidacao},
+    middleware::UsoLegado,
+};
+
+/// Regras declarativas aplicadas a um campo de texto.
+pub enum Regra {
+    Obrigatorio,
+    TamanhoMinimo(usize),
+    TamanhoMaximo(usize),
+    SemEspacos,
+    /// Apenas letras ASCII, dígitos e os caracteres informados
+    Alfanumerico(&'static str),
+    Email,
+}
+
+impl Regra {
+    fn verificar(&self, valor: &str) -> Option<String> {
+        let tamanho = valor.chars().count();
+
+        match self {
+            Regra::Obrigatorio if valor.trim().is_empty() => Some("Campo obrigatório".to_string()),
+            Regra::TamanhoMinimo(minimo) if tamanho < *minimo => {
+                Some(format!("Deve ter pelo menos {minimo} caracteres"))
+            }
+            Regra::TamanhoMaximo(maximo) if tamanho > *maximo => {
+                Some(format!("Deve ter no máximo {maximo} caracteres"))
+            }
+            Regra::SemEspacos if valor.chars().any(char::is_whitespace) => {
+                Some("Não pode conter espaços".to_string())
+            }
+            Regra::Alfanumerico(permitidos)
+                if !valor
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || permitidos.contains(c)) =>
+            {
+                Some(format!("Use apenas letras, números e os caracteres {permitidos}"))
+            }
+            Regra::Email if !email_valido(valor) => Some("Email inválido".to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Modelos de entrada que sabem se normalizar e se validar.
+pub trait Validar {
+    /// Nomes antigos (em português) ainda aceitos durante o período de depreciação.
+    const CAMPOS_LEGADOS: &'static [&'static str] = &[];
+
+    /// Ajusta os campos antes da validação (ex.: remover espaços, padronizar caixa).
+    fn normalizar(&mut self) {}
+
+    fn validar(&self, erros: &mut ErrosValidacao);
+
+    /// Regras que dependem de IO (ex.: a base de senhas vazadas em disco); rodam
+    /// depois de `validar`, sem bloquear o runtime.
+    fn validar_assincrono(&self, _erros: &mut ErrosValidacao) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+}
+
+impl ErrosValidacao {
+    /// Aplica as regras ao valor, registrando uma mensagem para cada regra violada.
+    pub fn campo(&mut self, nome: &'static str, valor: &str, regras: &[Regra]) {
+        for regra in regras {
+            if let Some(mensagem) = regra.verificar(valor) {
+                self.adicionar(nome, mensagem);
+            }
+        }
+    }
+}
+
+/// Extrator JSON que normaliza e valida o corpo antes de entregá-lo ao handler.
+/// Todas as violações são devolvidas juntas em uma resposta `422`.
+/// O uso de campos legados é sinalizado para o `middleware_depreciacao`.
+pub struct JsonValidado<T>(pub T);
+
+impl<S, T> FromRequest<S> for JsonValidado<T>
+where
+    T: DeserializeOwned + Validar + Send + Sync,
+    S: Send + Sync,
+{
+    type Rejection = ErroApi;
+
+    async fn from_request(requisicao: Request, estado: &S) -> Result<Self, Self::Rejection> {
+        let uso_legado = requisicao.extensions().get::<UsoLegado>().cloned();
+
+        let Json(corpo) = Json::<serde_json::Value>::from_request(requisicao, estado)
+            .await
+            .map_err(|rejeicao: JsonRejection| ErroApi::Requisicao(rejeicao.status(), rejeicao.body_text()))?;
+
+        let usa_campos_legados = corpo
+            .as_object()
+            .is_some_and(|campos| T::CAMPOS_LEGADOS.iter().any(|campo| campos.contains_key(*campo)));
+        if let Some(uso_legado) = uso_legado
+            && usa_campos_legados
+        {
+            uso_legado.marcar();
+        }
+
+        let mut valor: T = serde_json::from_value(corpo).map_err(|erro| {
+            ErroApi::Requisicao(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Failed to deserialize the JSON body into the target type: {erro}"),
+            )
+        })?;
+
+        valor.normalizar();
+
+        let mut erros = ErrosValidacao::default();
+        valor.validar(&mut erros);
+        valor.validar_assincrono(&mut erros).await;
+        if !erros.is_empty() {
+            return Err(erros.into());
+        }
+
+        Ok(JsonValidado(valor))
+    }
+}
+
+pub fn normalizar_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+fn email_valido(email: &str) -> bool {
+    let Some((local, dominio)) = email.split_once('@') else {
+        return false;
+    };
+
+    !local.is_empty()
+        && !dominio.contains('@')
+        && !email.chars().any(char::is_whitespace)
+        && dominio.contains('.')
+        && !dominio.starts_with('.')
+        && !dominio.ends_with('.')
+        && !email.contains("..")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, response::IntoResponse};
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn cada_regra_so_acusa_o_que_viola() {
+        let casos: &[(Regra, &str, bool)] = &[
+            (Regra::Obrigatorio, "ana", false),
+            (Regra::Obrigatorio, "", true),
+            (Regra::Obrigatorio, "   ", true),
+            (Regra::TamanhoMinimo(3), "ana", false),
+            (Regra::TamanhoMinimo(3), "an", true),
+            // Conta caracteres, não bytes
+            (Regra::TamanhoMinimo(3), "çãé", false),
+            (Regra::TamanhoMaximo(3), "ana", false),
+            (Regra::TamanhoMaximo(3), "anas", true),
+            (Regra::TamanhoMaximo(3), "çãé", false),
+            (Regra::SemEspacos, "ana_maria", false),
+            (Regra::SemEspacos, "ana maria", true),
+            (Regra::SemEspacos, "ana\tmaria", true),
+            (Regra::Alfanumerico("_.-"), "ana.maria_1-2", false),
+            (Regra::Alfanumerico("_.-"), "ana+maria", true),
+            (Regra::Alfanumerico(""), "anaçmaria", true),
+            (Regra::Email, "ana@example.com", false),
+            (Regra::Email, "ana", true),
+        ];
+
+        for (regra, valor, violada) in casos {
+            assert_eq!(regra.verificar(valor).is_some(), *violada, "{valor:?}");
+        }
+    }
+
+    #[test]
+    fn emails_validos_e_invalidos() {
+        let casos = [
+            ("ana@example.com", true),
+            ("ana.maria+blog@mail.example.com.br", true),
+            ("ana", false),
+            ("@example.com", false),
+            ("ana@", false),
+            ("ana@example", false),
+            ("ana@@example.com", false),
+            ("ana@exa@mple.com", false),
+            ("ana maria@example.com", false),
+            ("ana@.example.com", false),
+            ("ana@example.com.", false),
+            ("ana..maria@example.com", false),
+        ];
+
+        for (email, valido) in casos {
+            assert_eq!(email_valido(email), valido, "{email}");
+        }
+    }
+
+    #[test]
+    fn normaliza_email() {
+        let casos = [
+            ("ana@example.com", "ana@example.com"),
+            ("  Ana@Example.COM \n", "ana@example.com"),
+            ("ÉLIS@EXAMPLE.COM", "élis@example.com"),
+        ];
+
+        for (entrada, esperado) in casos {
+            assert_eq!(normalizar_email(entrada), esperado, "{entrada:?}");
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Cadastro {
+        nome: String,
+        email: String,
+    }
+
+    impl Validar for Cadastro {
+        fn normalizar(&mut self) {
+            self.email = normalizar_email(&self.email);
+        }
+
+        fn validar(&self, erros: &mut ErrosValidacao) {
+            erros.campo("nome", &self.nome, &[Regra::Obrigatorio, Regra::TamanhoMinimo(3), Regra::SemEspacos]);
+            erros.campo("email", &self.email, &[Regra::Obrigatorio, Regra::Email]);
+        }
+    }
+
+    async fn extrair(corpo: Value) -> Result<JsonValidado<Cadastro>, ErroApi> {
+        let requisicao = Request::builder()
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(Body::from(corpo.to_string()))
+            .unwrap();
+        JsonValidado::<Cadastro>::from_request(requisicao, &()).await
+    }
+
+    #[tokio::test]
+    async fn corpo_valido_chega_normalizado() {
+        let JsonValidado(cadastro) = extrair(json!({ "nome": "ana", "email": " Ana@Example.com " })).await.unwrap();
+        assert_eq!(cadastro.email, "ana@example.com");
+    }
+
+    #[tokio::test]
+    async fn resposta_422_lista_todos_os_campos_violados() {
+        let Err(erro) = extrair(json!({ "nome": " a", "email": "" })).await else {
+            panic!("o corpo inválido foi aceito");
+        };
+        let resposta = erro.into_response();
+        assert_eq!(resposta.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let bytes = axum::body::to_bytes(resposta.into_body(), usize::MAX).await.unwrap();
+        let corpo: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            corpo,
+            json!({
+                "error": "Dados inválidos",
+                "fields": {
+                    "nome": ["Deve ter pelo menos 3 caracteres", "Não pode conter espaços"],
+                    "email": ["Campo obrigatório", "Email inválido"],
+                },
+            })
+        );
+    }
+}
@@ -0,0 +1,457 @@
+//! Webhooks: avisos por HTTP aos sistemas que assinam eventos do blog, em vez
+//! de consultarem `/api/posts` periodicamente.
+//!
+//! Cada evento vira uma entrega por webhook assinante, gravada em
+//! `webhook_deliveries` pela própria requisição que o gerou; a tarefa de fundo
+//! [`executar_entregas`] envia as pendentes. Uma falha (erro de rede ou resposta
+//! fora de 2xx) reagenda a entrega com espera exponencial, até
+//! `MAXIMO_TENTATIVAS`; cada tentativa fica no histórico da entrega.
+//!
+//! O corpo é `{"id", "event", "created_at", "data"}`, enviado por POST com
+//! `X-Webhook-Signature: sha256=<hex>`: o HMAC-SHA256, com o segredo do
+//! webhook, de `"{X-Webhook-Timestamp}.{corpo}"`. Quem recebe recalcula a
+//! assinatura e recusa timestamps antigos, o que impede repetir uma entrega
+//! capturada; `X-Webhook-Id` (o `id` do corpo) serve para descartar duplicatas.
+//!
+//! Webhooks de usuários comuns só alcançam IPs públicos: o cadastro recusa
+//! destinos locais na URL e, no envio, o [`ResolvedorPublico`] descarta os IPs
+//! internos para os quais o nome resolver. Redirecionamentos não são seguidos.
+
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::{
+    net::{IpAddr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::Instant,
+};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::{
+    encerramento::EmAndamento,
+    metricas,
+    models::EntregaPendente,
+    repositorios::{EventoWebhook, Repositorios, Resultado},
+};
+
+/// Eventos que podem ser assinados.
+pub mod eventos {
+    pub const POSTAGEM_CRIADA: &str = "post.created";
+    pub const POSTAGEM_ATUALIZADA: &str = "post.updated";
+    pub const POSTAGEM_APAGADA: &str = "post.deleted";
+    /// Traz o email do novo usuário: só administradores assinam
+    pub const USUARIO_REGISTRADO: &str = "user.registered";
+
+    pub const TODOS: &[&str] = &[POSTAGEM_CRIADA, POSTAGEM_ATUALIZADA, POSTAGEM_APAGADA, USUARIO_REGISTRADO];
+
+    pub fn so_admins(evento: &str) -> bool {
+        evento == USUARIO_REGISTRADO
+    }
+}
+
+/// Valores de `webhook_deliveries.status`.
+pub mod situacoes {
+    pub const PENDENTE: &str = "pending";
+    pub const ENTREGUE: &str = "succeeded";
+    /// Tentativas esgotadas; só volta a ser enviada pelo reenvio manual
+    pub const FALHOU: &str = "failed";
+}
+
+pub const CABECALHO_ID: &str = "X-Webhook-Id";
+pub const CABECALHO_EVENTO: &str = "X-Webhook-Event";
+pub const CABECALHO_TIMESTAMP: &str = "X-Webhook-Timestamp";
+pub const CABECALHO_ASSINATURA: &str = "X-Webhook-Signature";
+
+/// Envios de uma entrega antes de ela ser dada como falha (cerca de 1h de esperas no total).
+pub const MAXIMO_TENTATIVAS: i32 = 8;
+const ESPERA_INICIAL: Duration = Duration::seconds(30);
+const ESPERA_MAXIMA: Duration = Duration::hours(1);
+
+const PRAZO_ENVIO: std::time::Duration = std::time::Duration::from_secs(10);
+// Uma entrega reservada só volta a ser elegível depois disso: se a instância
+// cair no meio do envio, outra a retoma
+const DURACAO_RESERVA: Duration = Duration::seconds(60);
+const INTERVALO_VERIFICACAO: std::time::Duration = std::time::Duration::from_secs(5);
+// Entregas reservadas (e enviadas em paralelo) por vez
+const TAMANHO_LOTE: i64 = 20;
+
+const PREFIXO_SEGREDO: &str = "whsec_";
+
+/// Filtros de `GET /webhooks/{id}/deliveries`. Mais novas primeiro;
+/// `before_id` pagina a partir do último id visto.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FiltroEntregas {
+    #[serde(rename = "before_id")]
+    pub antes_do_id: Option<i64>,
+    #[serde(rename = "limit")]
+    pub limite: Option<i64>,
+}
+
+/// Resultado de um envio, gravado no histórico da entrega.
+#[derive(Debug, Clone)]
+pub struct ResultadoEnvio {
+    pub tentada_em: DateTime<Utc>,
+    pub codigo_status: Option<i32>,
+    /// Vazio quando o endpoint respondeu 2xx
+    pub erro: Option<String>,
+    pub duracao_ms: i32,
+    /// Quando tentar de novo, depois de uma falha; `None` encerra a entrega
+    pub proxima_tentativa: Option<DateTime<Utc>>,
+}
+
+impl ResultadoEnvio {
+    /// A situação em que a entrega fica.
+    pub fn situacao(&self) -> &'static str {
+        match (&self.erro, self.proxima_tentativa) {
+            (None, _) => situacoes::ENTREGUE,
+            (Some(_), Some(_)) => situacoes::PENDENTE,
+            (Some(_), None) => situacoes::FALHOU,
+        }
+    }
+}
+
+/// O evento como é entregue, com um id novo; para gravar junto com a escrita
+/// que o gerou (veja [`Efeitos`](crate::repositorios::Efeitos)).
+pub fn evento(evento: &'static str, dados: Value) -> EventoWebhook {
+    let id = Uuid::new_v4();
+    let payload = json!({
+        "id": id,
+        "event": evento,
+        "created_at": Utc::now(),
+        "data": dados,
+    });
+
+    EventoWebhook { evento, id, payload, so_admins: eventos::so_admins(evento) }
+}
+
+/// Enfileira o evento para os webhooks que o assinam e devolve quantas entregas criou.
+pub async fn publicar(repositorios: &Repositorios, evento: &'static str, dados: Value) -> Resultado<u64> {
+    let evento = self::evento(evento, dados);
+
+    repositorios
+        .webhooks
+        .enfileirar(evento.evento, evento.id, &evento.payload, evento.so_admins)
+        .await
+}
+
+/// Valor de `X-Webhook-Signature` para o corpo enviado no instante `timestamp` (Unix, em segundos).
+pub fn assinar(segredo: &str, timestamp: i64, corpo: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(segredo.as_bytes()).expect("o HMAC aceita chaves de qualquer tamanho");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(corpo);
+
+    let assinatura: String = mac.finalize().into_bytes().iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("sha256={assinatura}")
+}
+
+/// Segredo de um webhook novo: 256 bits aleatórios em hexadecimal.
+pub fn gerar_segredo() -> String {
+    let bytes: [u8; 32] = rand::random();
+    let hexadecimal: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("{PREFIXO_SEGREDO}{hexadecimal}")
+}
+
+/// Se um usuário comum pode cadastrar o destino: recusa `localhost` e IPs de
+/// loopback e de redes privadas, para o servidor não ser usado para alcançar a
+/// rede interna. Só olha a URL; nomes que resolvem para IPs internos são
+/// barrados no envio, pelo [`ResolvedorPublico`]. Administradores cadastram
+/// qualquer destino.
+pub fn destino_permitido(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+
+    match ip_na_url(host) {
+        Some(ip) => ip_publico(ip),
+        None => {
+            let dominio = host.trim_end_matches('.').to_lowercase();
+            dominio != "localhost" && !dominio.ends_with(".localhost")
+        }
+    }
+}
+
+/// O IP do host, quando a URL traz um IP em vez de um nome.
+fn ip_na_url(host: &str) -> Option<IpAddr> {
+    // IPv6 vem entre colchetes, como na URL
+    host.trim_start_matches('[').trim_end_matches(']').parse().ok()
+}
+
+fn ip_publico(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                // 100.64.0.0/10 (CGNAT)
+                || (ip.octets()[0] == 100 && (ip.octets()[1] & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ipv4) => ip_publico(IpAddr::V4(ipv4)),
+            None => !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() || local_ipv6(ip)),
+        },
+    }
+}
+
+// fc00::/7 (endereços locais únicos) e fe80::/10 (link-local)
+fn local_ipv6(ip: Ipv6Addr) -> bool {
+    let primeiro = ip.segments()[0];
+    (primeiro & 0xfe00) == 0xfc00 || (primeiro & 0xffc0) == 0xfe80
+}
+
+/// Espera antes do envio seguinte a `tentativa` (a primeira é 1): dobra a cada
+/// falha, a partir de 30 segundos, até no máximo 1 hora.
+pub fn espera(tentativa: i32) -> Duration {
+    let expoente = (tentativa.max(1) - 1).min(16) as u32;
+    (ESPERA_INICIAL * 2_i32.pow(expoente)).min(ESPERA_MAXIMA)
+}
+
+/// Resolve nomes pelo sistema, mas descarta os IPs que não são públicos
+/// (loopback, redes privadas, link-local, CGNAT, não especificados e
+/// multicast, em IPv4 e IPv6). Um nome que só resolve para esses IPs é um
+/// erro: o DNS de um destino aceito no cadastro não o leva à rede interna.
+pub struct ResolvedorPublico;
+
+impl Resolve for ResolvedorPublico {
+    fn resolve(&self, nome: Name) -> Resolving {
+        Box::pin(async move {
+            let publicos: Vec<SocketAddr> = tokio::net::lookup_host((nome.as_str(), 0))
+                .await?
+                .filter(|endereco| ip_publico(endereco.ip()))
+                .collect();
+            if publicos.is_empty() {
+                return Err(format!("{} não resolve para nenhum IP público", nome.as_str()).into());
+            }
+            Ok(Box::new(publicos.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Clientes HTTP das entregas: sem seguir redirecionamentos (um 3xx conta
+/// como falha) e com prazo para o endpoint responder.
+#[derive(Clone)]
+pub struct Clientes {
+    /// Para webhooks de usuários comuns: só alcança IPs públicos, resolvidos
+    /// pelo [`ResolvedorPublico`] e sem passar por proxy, que resolveria o nome por conta própria
+    publico: reqwest::Client,
+    /// Para webhooks de administradores, que podem apontar para a rede interna
+    interno: reqwest::Client,
+}
+
+impl Clientes {
+    pub fn new() -> Self {
+        let construtor = || {
+            reqwest::Client::builder()
+                .timeout(PRAZO_ENVIO)
+                .redirect(reqwest::redirect::Policy::none())
+                .user_agent(concat!("hello_rust-webhooks/", env!("CARGO_PKG_VERSION")))
+        };
+        Clientes {
+            publico: construtor()
+                .dns_resolver(Arc::new(ResolvedorPublico))
+                .no_proxy()
+                .build()
+                .expect("cliente HTTP dos webhooks"),
+            interno: construtor().build().expect("cliente HTTP dos webhooks"),
+        }
+    }
+}
+
+impl Default for Clientes {
+    fn default() -> Self {
+        Clientes::new()
+    }
+}
+
+/// Reserva as entregas vencidas em `agora` que cabem em `em_envio` (até
+/// `TAMANHO_LOTE` ao todo) e as põe em envio; devolve quantas reservou.
+pub async fn processar_entregas(
+    repositorios: &Repositorios,
+    clientes: &Clientes,
+    agora: DateTime<Utc>,
+    em_envio: &mut EmAndamento<(i64, i32)>,
+) -> Resultado<usize> {
+    let limite = TAMANHO_LOTE - em_envio.len() as i64;
+    if limite <= 0 {
+        return Ok(0);
+    }
+    let pendentes = repositorios
+        .webhooks
+        .reservar_entregas(agora, agora + DURACAO_RESERVA, limite)
+        .await?;
+    let total = pendentes.len();
+
+    for pendente in pendentes {
+        let (repositorios, clientes) = (repositorios.clone(), clientes.clone());
+        let reserva = (pendente.entrega.id, pendente.entrega.tentativas);
+        em_envio.iniciar(reserva, async move {
+            if let Err(erro) = entregar(&repositorios, &clientes, &pendente, agora).await {
+                tracing::warn!(id_entrega = pendente.entrega.id, erro = %erro, "falha ao gravar o envio do webhook");
+            }
+        });
+    }
+    Ok(total)
+}
+
+/// Envia uma entrega reservada e grava o resultado.
+async fn entregar(repositorios: &Repositorios, clientes: &Clientes, pendente: &EntregaPendente, agora: DateTime<Utc>) -> Resultado<()> {
+    let resultado = enviar(clientes, pendente, agora).await;
+    metricas::registrar_entrega_webhook(resultado.situacao());
+    repositorios.webhooks.registrar_envio(pendente.entrega.id, &resultado).await
+}
+
+async fn enviar(clientes: &Clientes, pendente: &EntregaPendente, agora: DateTime<Utc>) -> ResultadoEnvio {
+    let entrega = &pendente.entrega;
+    let corpo = serde_json::to_vec(&entrega.payload.0).expect("JSON serializável");
+    let tentada_em = Utc::now();
+    let timestamp = tentada_em.timestamp();
+    let cliente = if pendente.dono_admin { &clientes.interno } else { &clientes.publico };
+
+    let inicio = Instant::now();
+    // IPs escritos na URL não passam pelo resolvedor, e o dono pode ter deixado de ser administrador
+    let ip_interno = reqwest::Url::parse(&pendente.url)
+        .ok()
+        .and_then(|url| url.host_str().and_then(ip_na_url))
+        .is_some_and(|ip| !ip_publico(ip));
+    let resposta = if !pendente.dono_admin && ip_interno {
+        Err("IP local ou de rede privada, reservado a administradores".to_string())
+    } else {
+        cliente
+            .post(&pendente.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(CABECALHO_ID, entrega.id_evento.to_string())
+            .header(CABECALHO_EVENTO, &entrega.evento)
+            .header(CABECALHO_TIMESTAMP, timestamp.to_string())
+            .header(CABECALHO_ASSINATURA, assinar(&pendente.segredo, timestamp, &corpo))
+            .body(corpo)
+            .send()
+            .await
+            .map_err(|erro| descrever(&erro))
+    };
+    let duracao_ms = inicio.elapsed().as_millis().min(i32::MAX as u128) as i32;
+
+    let (codigo_status, erro) = match resposta {
+        Ok(resposta) if resposta.status().is_success() => (Some(resposta.status().as_u16() as i32), None),
+        Ok(resposta) => (Some(resposta.status().as_u16() as i32), Some(format!("HTTP {}", resposta.status()))),
+        Err(erro) => (None, Some(erro)),
+    };
+
+    let proxima_tentativa =
+        (erro.is_some() && entrega.tentativas < MAXIMO_TENTATIVAS).then(|| agora + espera(entrega.tentativas));
+    if let Some(erro) = &erro {
+        tracing::warn!(
+            id_entrega = entrega.id,
+            tentativa = entrega.tentativas,
+            erro = %erro,
+            "falha ao entregar webhook"
+        );
+    }
+
+    ResultadoEnvio { tentada_em, codigo_status, erro, duracao_ms, proxima_tentativa }
+}
+
+/// O erro e as causas dele: o do reqwest sozinho não diz o que falhou na conexão.
+fn descrever(erro: &dyn std::error::Error) -> String {
+    let mut descricao = erro.to_string();
+    let mut causa = erro.source();
+    while let Some(atual) = causa {
+        descricao.push_str(": ");
+        descricao.push_str(&atual.to_string());
+        causa = atual.source();
+    }
+    descricao
+}
+
+/// Tarefa de fundo: verifica as entregas pendentes a cada
+/// `INTERVALO_VERIFICACAO` e mantém até `TAMANHO_LOTE` em envio, reservando
+/// mais assim que uma termina. Só deve começar depois das migrações. Cancelado
+/// `parar`, não reserva mais nada e espera os envios em andamento por até
+/// `prazo`; as entregas que não terminarem voltam a vencer na hora, sem contar
+/// a tentativa.
+pub async fn executar_entregas(repositorios: Repositorios, parar: CancellationToken, prazo: std::time::Duration) {
+    let clientes = Clientes::new();
+    let mut em_envio = EmAndamento::default();
+    let mut intervalo = tokio::time::interval(INTERVALO_VERIFICACAO);
+
+    loop {
+        tokio::select! {
+            biased;
+            _ = parar.cancelled() => break,
+            _ = intervalo.tick() => {}
+            Some(_) = em_envio.proxima() => {}
+        }
+
+        if let Err(erro) = processar_entregas(&repositorios, &clientes, Utc::now(), &mut em_envio).await {
+            tracing::warn!(erro = %erro, "falha ao reservar as entregas de webhooks");
+        }
+    }
+
+    tracing::info!(em_envio = em_envio.len(), "Entregas de webhooks encerrando");
+    for (id, tentativa) in em_envio.encerrar(prazo).await {
+        match repositorios.webhooks.liberar_entrega(id, tentativa, Utc::now()).await {
+            Ok(true) => tracing::warn!(id_entrega = id, "entrega interrompida no encerramento; devolvida à fila"),
+            Ok(false) => {}
+            Err(erro) => tracing::warn!(id_entrega = id, erro = %erro, "falha ao devolver a entrega interrompida à fila"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assinatura_e_hmac_sha256_do_timestamp_e_do_corpo() {
+        let assinatura = assinar("whsec_teste", 1_700_000_000, br#"{"event":"post.created"}"#);
+        assert_eq!(assinatura, "sha256=a6d81173e693b1e3eb24628eb01cc01761830d1b94de15a86e46593cd74cd32e");
+    }
+
+    #[test]
+    fn espera_dobra_ate_o_limite() {
+        assert_eq!(espera(1), Duration::seconds(30));
+        assert_eq!(espera(2), Duration::seconds(60));
+        assert_eq!(espera(4), Duration::seconds(240));
+        assert_eq!(espera(MAXIMO_TENTATIVAS + 10), ESPERA_MAXIMA);
+    }
+
+    #[test]
+    fn destinos_internos_sao_recusados() {
+        for url in [
+            "http://localhost:8080/hook",
+            "http://api.localhost/hook",
+            "http://127.0.0.1/hook",
+            "http://10.1.2.3/hook",
+            "http://192.168.0.10/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://[fd00::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+            "http://100.64.1.1/hook",
+            "http://0.0.0.0/hook",
+            "http://224.0.0.1/hook",
+            "http://[ff02::1]/hook",
+        ] {
+            assert!(!destino_permitido(url), "{url}");
+        }
+
+        assert!(destino_permitido("https://hooks.example.com/blog"));
+        assert!(destino_permitido("http://203.0.113.7:8080/hook"));
+    }
+
+    #[tokio::test]
+    async fn resolvedor_recusa_nomes_que_so_resolvem_para_ips_internos() {
+        let erro = ResolvedorPublico.resolve("localhost".parse().unwrap()).await.err().unwrap();
+        assert_eq!(erro.to_string(), "localhost não resolve para nenhum IP público");
+    }
+}
@@ -0,0 +1,257 @@
+mod comum;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use comum::{AppTeste, PREFIXO, SEGREDO_JWT, SENHA_VALIDA};
+use serde_json::{json, Value};
+
+use hello_rust::{auditoria, configuracao::Configuracao, db::Banco};
+
+/// Um administrador novo; devolve o token e o id.
+async fn administrador(app: &AppTeste) -> (String, i64) {
+    let token = app.usuario("chefe").await;
+    let id = app.repositorios.usuarios.buscar_por_nome_usuario("chefe").await.unwrap().unwrap().id;
+    app.repositorios.usuarios.definir_admin(id, true).await.unwrap();
+    (token, id as i64)
+}
+
+async fn eventos(app: &AppTeste, token: &str, consulta: &str) -> Vec<Value> {
+    let resposta = app.get(&format!("/admin/audit?{consulta}"), Some(token)).await;
+    assert_eq!(resposta.status, StatusCode::OK, "{}", resposta.corpo);
+    resposta.corpo.as_array().unwrap().clone()
+}
+
+fn acoes(eventos: &[Value]) -> Vec<&str> {
+    eventos.iter().map(|evento| evento["action"].as_str().unwrap()).collect()
+}
+
+#[tokio::test]
+async fn registro_e_logins_sao_auditados() {
+    let app = AppTeste::nova().await;
+    let (admin, _) = administrador(&app).await;
+
+    app.registrar("ana", "ana@example.com", SENHA_VALIDA).await;
+    app.token("ana@example.com", SENHA_VALIDA).await;
+    app.login("ana@example.com", "senha-errada").await;
+    app.login("ninguem@example.com", SENHA_VALIDA).await;
+    let id_ana = app.repositorios.usuarios.buscar_por_nome_usuario("ana").await.unwrap().unwrap().id as i64;
+
+    let registro = &eventos(&app, &admin, "action=account.registered").await;
+    assert_eq!(registro.len(), 2);
+    assert_eq!(registro[0]["actor_id"], id_ana);
+    assert_eq!(registro[0]["target_id"], id_ana.to_string());
+    assert_eq!(registro[0]["diff"], json!({}));
+
+    let falhas = eventos(&app, &admin, "action=auth.login_failed").await;
+    assert_eq!(falhas.len(), 2);
+    // Dos mais novos para os mais antigos
+    // O email tentado não é gravado, só o HMAC dele
+    let email_hash = auditoria::hash_email(SEGREDO_JWT, "ninguem@example.com");
+    assert_eq!(falhas[0]["diff"], json!({ "email_hash": email_hash, "reason": "unknown_email" }));
+    assert_eq!(email_hash, auditoria::hash_email(SEGREDO_JWT, " Ninguem@Example.com"));
+    assert_ne!(email_hash, auditoria::hash_email("outra-chave", "ninguem@example.com"));
+    assert!(falhas[0]["target_id"].is_null());
+    assert_eq!(falhas[1]["diff"]["reason"], "wrong_password");
+    assert_eq!(falhas[1]["target_id"], id_ana.to_string());
+    assert_eq!(falhas[1]["ip"], "127.0.0.1");
+    assert!(falhas[1]["actor_id"].is_null());
+
+    let da_ana = eventos(&app, &admin, &format!("actor_id={id_ana}")).await;
+    assert_eq!(acoes(&da_ana), ["auth.login_succeeded", "account.registered"]);
+    assert!(da_ana[0]["diff"]["session_id"].is_string());
+}
+
+#[tokio::test]
+async fn alteracoes_de_posts_guardam_o_diff() {
+    let app = AppTeste::nova().await;
+    let (admin, _) = administrador(&app).await;
+    let token = app.usuario("ana").await;
+    let id_ana = app.get("/profile", Some(&token)).await.corpo["id"].clone();
+
+    let id = app.criar_postagem(&token, "Rascunho").await;
+    let resposta = app
+        .put(&format!("/posts/{id}"), Some(&token), json!({ "title": "Versão final", "content": "Conteúdo de teste" }))
+        .await;
+    assert_eq!(resposta.status, StatusCode::OK);
+    assert_eq!(app.delete(&format!("/posts/{id}"), Some(&token)).await.status, StatusCode::NO_CONTENT);
+
+    let todos = eventos(&app, &admin, &format!("actor_id={id_ana}")).await;
+    let postagens: Vec<&Value> = todos.iter().filter(|evento| evento["target_type"] == "post").collect();
+    assert_eq!(postagens.len(), 3);
+    let alvo = json!(id.to_string());
+    assert!(postagens.iter().all(|evento| evento["target_id"] == alvo));
+
+    let [apagada, atualizada, criada] = postagens[..] else { unreachable!() };
+    assert_eq!(criada["action"], "post.created");
+    assert_eq!(criada["diff"]["title"], json!([null, "Rascunho"]));
+    assert_eq!(atualizada["action"], "post.updated");
+    assert_eq!(atualizada["diff"], json!({ "title": ["Rascunho", "Versão final"] }));
+    assert_eq!(apagada["action"], "post.deleted");
+    assert_eq!(apagada["diff"]["content"], json!(["Conteúdo de teste", null]));
+
+    // Tentativas sobre posts de outros não viram eventos
+    let outro = app.usuario("bruno").await;
+    let id = app.criar_postagem(&token, "Da Ana").await;
+    assert_eq!(app.delete(&format!("/posts/{id}"), Some(&outro)).await.status, StatusCode::NOT_FOUND);
+    assert_eq!(eventos(&app, &admin, "action=post.deleted").await.len(), 1);
+}
+
+#[tokio::test]
+async fn consulta_filtra_por_periodo_e_pagina() {
+    let app = AppTeste::nova().await;
+    let (admin, _) = administrador(&app).await;
+    let inicio = chrono::Utc::now();
+    for nome in ["ana", "bruno", "carla"] {
+        app.registrar(nome, &format!("{nome}@example.com"), SENHA_VALIDA).await;
+    }
+
+    let data = |data: chrono::DateTime<chrono::Utc>| data.to_rfc3339_opts(chrono::SecondsFormat::Micros, true);
+    let depois = chrono::Utc::now() + chrono::Duration::seconds(1);
+    let consulta = format!("action=account.registered&since={}", data(inicio));
+    assert_eq!(eventos(&app, &admin, &consulta).await.len(), 3);
+    assert!(eventos(&app, &admin, &format!("since={}", data(depois))).await.is_empty());
+    assert_eq!(eventos(&app, &admin, &format!("action=account.registered&until={}", data(inicio))).await.len(), 1);
+
+    let primeira = eventos(&app, &admin, &format!("{consulta}&limit=2")).await;
+    assert_eq!(primeira.len(), 2);
+    let ultimo = primeira[1]["id"].as_i64().unwrap();
+    let segunda = eventos(&app, &admin, &format!("{consulta}&limit=2&before_id={ultimo}")).await;
+    assert_eq!(segunda.len(), 1);
+    let id_ana = app.repositorios.usuarios.buscar_por_nome_usuario("ana").await.unwrap().unwrap().id;
+    assert_eq!(segunda[0]["target_id"], id_ana.to_string());
+
+    let resposta = app.get("/admin/audit?since=ontem", Some(&admin)).await;
+    assert_eq!(resposta.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn consulta_e_restrita_e_acoes_de_admin_aparecem() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+    assert_eq!(app.get("/admin/audit", Some(&token)).await.status, StatusCode::FORBIDDEN);
+    assert_eq!(app.get("/admin/audit", None).await.status, StatusCode::UNAUTHORIZED);
+
+    let (admin, id_admin) = administrador(&app).await;
+    assert_eq!(app.get("/admin/export", Some(&admin)).await.status, StatusCode::OK);
+
+    let exportacoes = eventos(&app, &admin, "action=admin.data_exported").await;
+    assert_eq!(exportacoes.len(), 1);
+    assert_eq!(exportacoes[0]["actor_id"], id_admin);
+}
+
+#[tokio::test]
+async fn eventos_nao_podem_ser_alterados_nem_apagados() {
+    let app = AppTeste::nova().await;
+    app.usuario("ana").await;
+
+    // A redação aceita pelo gatilho não pode levar outras mudanças junto
+    let comandos = [
+        "UPDATE audit_events SET action = 'nada'",
+        "UPDATE audit_events SET ip = NULL, user_agent = NULL, action = 'nada'",
+        "UPDATE audit_events SET ip = NULL, user_agent = NULL, diff = '{}'",
+        "DELETE FROM audit_events",
+    ];
+    for comando in comandos {
+        let resultado = match &app.banco {
+            Banco::Postgres(pool) => sqlx::query(comando).execute(pool).await.map(|_| ()),
+            Banco::Sqlite(pool) => sqlx::query(comando).execute(pool).await.map(|_| ()),
+            Banco::Memoria(_) => unreachable!("os testes de integração usam um banco de verdade"),
+        };
+        let erro = resultado.unwrap_err().to_string();
+        assert!(erro.contains("append-only"), "{comando}: {erro}");
+    }
+}
+
+#[tokio::test]
+async fn falha_na_auditoria_desfaz_a_escrita_e_os_webhooks() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+    let webhook = app
+        .post("/webhooks", Some(&token), json!({ "url": "https://hooks.example.com/ana", "events": ["post.created"] }))
+        .await;
+    assert_eq!(webhook.status, StatusCode::CREATED);
+
+    // A auditoria de posts novos passa a falhar no meio da transação
+    match &app.banco {
+        Banco::Postgres(pool) => sqlx::raw_sql(
+            r#"
+            CREATE FUNCTION falhar_auditoria() RETURNS trigger AS $$
+            BEGIN RAISE EXCEPTION 'auditoria indisponível'; END
+            $$ LANGUAGE plpgsql;
+            CREATE TRIGGER falhar_auditoria BEFORE INSERT ON audit_events
+            FOR EACH ROW WHEN (NEW.action = 'post.created') EXECUTE FUNCTION falhar_auditoria();
+            "#,
+        )
+        .execute(pool)
+        .await
+        .map(|_| ()),
+        Banco::Sqlite(pool) => sqlx::raw_sql(
+            r#"
+            CREATE TRIGGER falhar_auditoria BEFORE INSERT ON audit_events
+            WHEN NEW.action = 'post.created'
+            BEGIN SELECT RAISE(ABORT, 'auditoria indisponível'); END;
+            "#,
+        )
+        .execute(pool)
+        .await
+        .map(|_| ()),
+        Banco::Memoria(_) => unreachable!("os testes de integração usam um banco de verdade"),
+    }
+    .unwrap();
+
+    let resposta = app.post("/posts", Some(&token), json!({ "title": "Sem rastro", "content": "Conteúdo" })).await;
+    assert_eq!(resposta.status, StatusCode::INTERNAL_SERVER_ERROR);
+
+    // Nem o post nem a entrega do webhook ficaram gravados
+    assert_eq!(app.get("/posts", None).await.corpo.as_array().unwrap().len(), 0);
+    let entregas: i64 = match &app.banco {
+        Banco::Postgres(pool) => sqlx::query_scalar("SELECT COUNT(*) FROM webhook_deliveries").fetch_one(pool).await,
+        Banco::Sqlite(pool) => sqlx::query_scalar("SELECT COUNT(*) FROM webhook_deliveries").fetch_one(pool).await,
+        Banco::Memoria(_) => unreachable!("os testes de integração usam um banco de verdade"),
+    }
+    .unwrap();
+    assert_eq!(entregas, 0);
+}
+
+/// Login da ana com um `X-Forwarded-For` forjado; devolve o IP gravado na sessão.
+async fn login_encaminhado(app: &AppTeste, x_forwarded_for: &str) -> String {
+    app.registrar("ana", "ana@example.com", SENHA_VALIDA).await;
+    let requisicao = Request::post(format!("{PREFIXO}/auth/login"))
+        .header("content-type", "application/json")
+        .header("x-forwarded-for", x_forwarded_for)
+        .body(Body::from(json!({ "email": "ana@example.com", "password": SENHA_VALIDA }).to_string()))
+        .unwrap();
+    let login = app.enviar(requisicao).await;
+    assert_eq!(login.status, StatusCode::OK, "{}", login.corpo);
+
+    let token = login.corpo["token"].as_str().unwrap();
+    let sessoes = app.get("/profile/sessions", Some(token)).await.corpo;
+    sessoes[0]["ip"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn x_forwarded_for_do_cliente_direto_e_ignorado() {
+    let app = AppTeste::nova().await;
+    let (admin, _) = administrador(&app).await;
+
+    // A conexão vem de 127.0.0.1, que não é um proxy confiável
+    assert_eq!(login_encaminhado(&app, "203.0.113.7").await, "127.0.0.1");
+    let login = &eventos(&app, &admin, "action=auth.login_succeeded").await[0];
+    assert_eq!(login["ip"], "127.0.0.1");
+}
+
+#[tokio::test]
+async fn x_forwarded_for_vale_atras_de_proxy_confiavel() {
+    let app = AppTeste::com_construtor(|construtor| {
+        construtor.configuracao(Configuracao {
+            proxies_confiaveis: vec!["127.0.0.1/32".parse().unwrap(), "10.0.0.0/8".parse().unwrap()],
+            ..Default::default()
+        })
+    })
+    .await;
+
+    // O que o cliente escreveu à esquerda do primeiro salto não confiável não vale
+    assert_eq!(login_encaminhado(&app, "1.2.3.4, 203.0.113.7, 10.1.2.3").await, "203.0.113.7");
+}
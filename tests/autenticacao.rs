@@ -0,0 +1,249 @@
+mod comum;
+
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode},
+};
+use comum::{AppTeste, PREFIXO, SENHA_VALIDA};
+use hello_rust::{repositorios, rotas};
+use serde_json::json;
+
+#[tokio::test]
+async fn registro_e_login() {
+    let app = AppTeste::nova().await;
+
+    let registro = app.registrar("ana", "Ana@Example.com", SENHA_VALIDA).await;
+    assert_eq!(registro.status, StatusCode::OK);
+    assert_eq!(registro.corpo["username"], "ana");
+    assert_eq!(registro.corpo["email"], "ana@example.com");
+    assert!(registro.corpo.get("password_hash").is_none());
+
+    let login = app.login("ana@example.com", SENHA_VALIDA).await;
+    assert_eq!(login.status, StatusCode::OK);
+    assert_eq!(login.corpo["user"]["id"], registro.corpo["id"]);
+
+    let token = login.corpo["token"].as_str().unwrap();
+    let perfil = app.get("/profile", Some(token)).await;
+    assert_eq!(perfil.status, StatusCode::OK);
+    assert_eq!(perfil.corpo["username"], "ana");
+}
+
+#[tokio::test]
+async fn registro_com_email_existente_conflita() {
+    let app = AppTeste::nova().await;
+    app.registrar("ana", "ana@example.com", SENHA_VALIDA).await;
+
+    let resposta = app.registrar("outra", "ANA@example.com", SENHA_VALIDA).await;
+    assert_eq!(resposta.status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn registro_com_nome_de_usuario_existente_conflita() {
+    let app = AppTeste::nova().await;
+    app.registrar("ana", "ana@example.com", SENHA_VALIDA).await;
+
+    let resposta = app.registrar("ana", "outra@example.com", SENHA_VALIDA).await;
+    assert_eq!(resposta.status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn registros_simultaneos_com_o_mesmo_email_conflitam() {
+    let app = AppTeste::nova().await;
+
+    // Todos passam juntos pela verificação de duplicidade; só a restrição do banco separa o primeiro
+    let nomes: Vec<String> = (0..6).map(|i| format!("ana{i}")).collect();
+    let registros = nomes.iter().map(|nome| app.registrar(nome, "ana@example.com", SENHA_VALIDA));
+    let mut status: Vec<StatusCode> = futures::future::join_all(registros).await.into_iter().map(|resposta| resposta.status).collect();
+    status.sort();
+    assert_eq!(status, [StatusCode::OK, StatusCode::CONFLICT, StatusCode::CONFLICT, StatusCode::CONFLICT, StatusCode::CONFLICT, StatusCode::CONFLICT]);
+}
+
+#[tokio::test]
+async fn email_duplicado_e_violacao_de_unicidade() {
+    let app = AppTeste::nova().await;
+    app.repositorios.usuarios.criar("ana", "ana@example.com", "hash").await.unwrap();
+
+    let erro = app.repositorios.usuarios.criar("outra", "ana@example.com", "hash").await.unwrap_err();
+    assert!(repositorios::violacao_unicidade(&erro), "{erro:?}");
+}
+
+#[tokio::test]
+async fn email_com_outra_caixa_e_violacao_de_unicidade() {
+    let app = AppTeste::nova().await;
+    app.repositorios.usuarios.criar("ana", "Ana@Example.com", "hash").await.unwrap();
+
+    let erro = app.repositorios.usuarios.criar("outra", "ana@example.com", "hash").await.unwrap_err();
+    assert!(repositorios::violacao_unicidade(&erro), "{erro:?}");
+
+    let resposta = app.registrar("outra", "ana@example.com", SENHA_VALIDA).await;
+    assert_eq!(resposta.status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn registro_invalido_nao_cria_usuario() {
+    let app = AppTeste::nova().await;
+
+    let resposta = app.registrar("a b", "nao-e-email", "123").await;
+    assert_eq!(resposta.status, StatusCode::UNPROCESSABLE_ENTITY);
+    for campo in ["username", "email", "password"] {
+        assert!(resposta.corpo["fields"][campo].is_array(), "sem erro para {campo}: {}", resposta.corpo);
+    }
+
+    let usuarios = app.get("/users", None).await;
+    assert_eq!(usuarios.corpo.as_array().unwrap().len(), 0);
+}
+
+#[tokio::test]
+async fn login_com_senha_errada_falha() {
+    let app = AppTeste::nova().await;
+    app.registrar("ana", "ana@example.com", SENHA_VALIDA).await;
+
+    let resposta = app.login("ana@example.com", "Senha#Errada2024").await;
+    assert_eq!(resposta.status, StatusCode::UNAUTHORIZED);
+    assert!(resposta.corpo.get("token").is_none());
+}
+
+#[tokio::test]
+async fn login_com_email_desconhecido_falha() {
+    let app = AppTeste::nova().await;
+
+    let resposta = app.login("ninguem@example.com", SENHA_VALIDA).await;
+    assert_eq!(resposta.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn conta_desativada_nao_faz_login() {
+    let app = AppTeste::nova().await;
+    let registro = app.registrar("ana", "ana@example.com", SENHA_VALIDA).await;
+    let id = registro.corpo["id"].as_i64().unwrap() as i32;
+
+    app.repositorios.usuarios.definir_desativado(id, true).await.unwrap();
+    let resposta = app.login("ana@example.com", SENHA_VALIDA).await;
+    assert_eq!(resposta.status, StatusCode::FORBIDDEN);
+
+    app.repositorios.usuarios.definir_desativado(id, false).await.unwrap();
+    let resposta = app.login("ana@example.com", SENHA_VALIDA).await;
+    assert_eq!(resposta.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn rotas_protegidas_exigem_token() {
+    let app = AppTeste::nova().await;
+
+    let protegidas = rotas::todas_as_rotas()
+        .into_iter()
+        .filter(|(_, rota)| rota.acesso.exige_token());
+
+    for (caminho, rota) in protegidas {
+        let caminho = caminho.replace("{id}", "1");
+        let corpo = (rota.metodo == Method::POST || rota.metodo == Method::PUT)
+            .then(|| serde_json::json!({ "title": "Título", "content": "Conteúdo" }));
+
+        let resposta = app.requisitar(rota.metodo.clone(), &caminho, None, corpo.clone()).await;
+        assert_eq!(resposta.status, StatusCode::UNAUTHORIZED, "{} {caminho} sem token", rota.metodo);
+
+        let resposta = app.requisitar(rota.metodo.clone(), &caminho, Some("nao.e.um.jwt"), corpo).await;
+        assert_eq!(resposta.status, StatusCode::UNAUTHORIZED, "{} {caminho} com token inválido", rota.metodo);
+    }
+}
+
+#[tokio::test]
+async fn rotas_legadas_tambem_exigem_token() {
+    let app = AppTeste::nova().await;
+
+    let resposta = app.requisitar(Method::GET, "/api/profile", None, None).await;
+    assert_eq!(resposta.status, StatusCode::UNAUTHORIZED);
+    assert!(resposta.cabecalhos.contains_key("deprecation"));
+}
+
+#[tokio::test]
+async fn sessao_revogada_invalida_o_token() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+    let outro_token = app.token("ana@example.com", SENHA_VALIDA).await;
+
+    let sessoes = app.get("/profile/sessions", Some(&token)).await;
+    assert_eq!(sessoes.status, StatusCode::OK);
+    let sessoes = sessoes.corpo.as_array().unwrap().clone();
+    assert_eq!(sessoes.len(), 2);
+
+    let atual = sessoes.iter().find(|sessao| sessao["current"] == true).unwrap();
+    let revogacao = app
+        .delete(&format!("/profile/sessions/{}", atual["id"].as_str().unwrap()), Some(&token))
+        .await;
+    assert_eq!(revogacao.status, StatusCode::NO_CONTENT);
+
+    assert_eq!(app.get("/profile", Some(&token)).await.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(app.get("/profile", Some(&outro_token)).await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn sessoes_listam_dispositivo_e_ip() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+    let user_agent = "<img src=x onerror=alert(1)>";
+    let login = Request::builder()
+        .method(Method::POST)
+        .uri(format!("{PREFIXO}/auth/login"))
+        .header("content-type", "application/json")
+        .header("user-agent", user_agent)
+        .body(Body::from(json!({ "email": "ana@example.com", "password": SENHA_VALIDA }).to_string()))
+        .unwrap();
+    assert_eq!(app.enviar(login).await.status, StatusCode::OK);
+
+    let sessoes = app.get("/profile/sessions", Some(&token)).await.corpo;
+    let sessoes = sessoes.as_array().unwrap();
+    assert_eq!(sessoes.len(), 2);
+    assert_eq!(sessoes.iter().filter(|sessao| sessao["current"] == true).count(), 1);
+
+    // O user agent volta como veio; escapá-lo é papel de quem exibe
+    let nova = sessoes.iter().find(|sessao| sessao["current"] == false).unwrap();
+    assert_eq!(nova["device"], user_agent);
+    assert_eq!(nova["ip"], "127.0.0.1");
+}
+
+#[tokio::test]
+async fn revogar_outra_sessao_derruba_so_o_token_dela() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+    let outro_token = app.token("ana@example.com", SENHA_VALIDA).await;
+
+    let sessoes = app.get("/profile/sessions", Some(&token)).await.corpo;
+    let outra = sessoes.as_array().unwrap().iter().find(|sessao| sessao["current"] == false).unwrap().clone();
+    let revogacao = app
+        .delete(&format!("/profile/sessions/{}", outra["id"].as_str().unwrap()), Some(&token))
+        .await;
+    assert_eq!(revogacao.status, StatusCode::NO_CONTENT);
+
+    assert_eq!(app.get("/profile", Some(&outro_token)).await.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(app.get("/profile", Some(&token)).await.status, StatusCode::OK);
+    let restantes = app.get("/profile/sessions", Some(&token)).await.corpo;
+    assert_eq!(restantes.as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn sessao_de_outro_usuario_nao_e_revogada() {
+    let app = AppTeste::nova().await;
+    let token_ana = app.usuario("ana").await;
+    let token_bia = app.usuario("bia").await;
+
+    let sessoes = app.get("/profile/sessions", Some(&token_bia)).await.corpo;
+    let id_sessao = sessoes[0]["id"].as_str().unwrap().to_string();
+    let revogacao = app.delete(&format!("/profile/sessions/{id_sessao}"), Some(&token_ana)).await;
+    assert_eq!(revogacao.status, StatusCode::NOT_FOUND);
+
+    assert_eq!(app.get("/profile", Some(&token_bia)).await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn rotas_publicas_respondem_sem_token() {
+    let app = AppTeste::nova().await;
+
+    for caminho in ["/posts", "/users"] {
+        let resposta = app.get(caminho, None).await;
+        assert_eq!(resposta.status, StatusCode::OK, "{PREFIXO}{caminho}");
+    }
+    assert_eq!(app.requisitar(Method::GET, "/home", None, None).await.status, StatusCode::OK);
+    assert_eq!(app.requisitar(Method::GET, "/openapi.json", None, None).await.status, StatusCode::OK);
+    assert_eq!(app.requisitar(Method::GET, "/health/live", None, None).await.status, StatusCode::OK);
+}
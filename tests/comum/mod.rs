@@ -0,0 +1,248 @@
+//! Infraestrutura dos testes de integração: cada teste monta a aplicação
+//! completa (o mesmo `Router` do binário) sobre um banco descartável.
+//!
+//! Sem `TEST_DATABASE_URL`, o banco é um SQLite em memória por teste. Com
+//! `TEST_DATABASE_URL=postgresql://...`, cada teste ganha um schema próprio
+//! nesse banco, apagado ao final, inclusive quando o teste falha.
+
+#![allow(dead_code)]
+
+use axum::{
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{HeaderMap, Method, Request, StatusCode},
+    Router,
+};
+use http_body_util::BodyExt;
+use serde_json::Value;
+use sqlx::{
+    postgres::PgPoolOptions,
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    Connection, Executor, PgConnection,
+};
+use std::{net::SocketAddr, str::FromStr};
+use tower::ServiceExt;
+
+use hello_rust::{
+    configuracao::Configuracao,
+    db::Banco,
+    repositorios::Repositorios,
+    seed,
+    ConstrutorAplicacao,
+};
+
+pub const PREFIXO: &str = "/api/v1";
+
+pub const SEGREDO_JWT: &str = "segredo-dos-testes-de-integracao";
+
+pub struct AppTeste {
+    roteador: Router,
+    pub banco: Banco,
+    pub repositorios: Repositorios,
+    esquema: Option<EsquemaTemporario>,
+}
+
+pub struct Resposta {
+    pub status: StatusCode,
+    pub cabecalhos: HeaderMap,
+    pub corpo: Value,
+}
+
+struct EsquemaTemporario {
+    url: String,
+    nome: String,
+}
+
+impl AppTeste {
+    /// Banco migrado e vazio.
+    pub async fn nova() -> Self {
+        Self::com_construtor(|construtor| construtor).await
+    }
+
+    /// Como `nova`, ajustando o `ConstrutorAplicacao` antes de montar o `Router`.
+    pub async fn com_construtor(ajustar: impl FnOnce(ConstrutorAplicacao) -> ConstrutorAplicacao) -> Self {
+        let (banco, esquema) = match std::env::var("TEST_DATABASE_URL") {
+            Ok(url) if !url.is_empty() => {
+                let (banco, esquema) = postgres_em_esquema(url).await;
+                (banco, Some(esquema))
+            }
+            _ => (sqlite_em_memoria(), None),
+        };
+
+        banco.executar_migracoes().await.expect("migrações do banco de teste");
+
+        let repositorios = banco.repositorios();
+        let construtor = ConstrutorAplicacao::new(banco.clone()).configuracao(Configuracao {
+            segredo_jwt: SEGREDO_JWT.to_string(),
+            ..Default::default()
+        });
+        let roteador = ajustar(construtor)
+            .construir()
+            .layer(MockConnectInfo(SocketAddr::from(([127, 0, 0, 1], 40000))));
+
+        AppTeste { roteador, banco, repositorios, esquema }
+    }
+
+    /// Banco com a fixture `demo` (3 usuários e 6 posts).
+    pub async fn com_seed() -> Self {
+        let app = Self::nova().await;
+        seed::aplicar(&app.repositorios, seed::Fixture::demo()).await.expect("seed do banco de teste");
+        app
+    }
+
+    pub fn roteador(&self) -> Router {
+        self.roteador.clone()
+    }
+
+    /// A mesma aplicação de teste, servindo outro `Router` (por exemplo, um anfitrião que a embute).
+    pub fn com_roteador(mut self, roteador: Router) -> Self {
+        self.roteador = roteador;
+        self
+    }
+
+    pub async fn requisitar(&self, metodo: Method, caminho: &str, token: Option<&str>, corpo: Option<Value>) -> Resposta {
+        let mut requisicao = Request::builder().method(metodo).uri(caminho);
+        if let Some(token) = token {
+            requisicao = requisicao.header("authorization", format!("Bearer {token}"));
+        }
+        let requisicao = match corpo {
+            Some(corpo) => requisicao
+                .header("content-type", "application/json")
+                .body(Body::from(corpo.to_string())),
+            None => requisicao.body(Body::empty()),
+        }
+        .unwrap();
+
+        self.enviar(requisicao).await
+    }
+
+    pub async fn enviar(&self, requisicao: Request<Body>) -> Resposta {
+        let resposta = self.roteador.clone().oneshot(requisicao).await.unwrap();
+        let status = resposta.status();
+        let cabecalhos = resposta.headers().clone();
+        let bytes = resposta.into_body().collect().await.unwrap().to_bytes();
+        let corpo = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+
+        Resposta { status, cabecalhos, corpo }
+    }
+
+    pub async fn get(&self, caminho: &str, token: Option<&str>) -> Resposta {
+        self.requisitar(Method::GET, &format!("{PREFIXO}{caminho}"), token, None).await
+    }
+
+    pub async fn post(&self, caminho: &str, token: Option<&str>, corpo: Value) -> Resposta {
+        self.requisitar(Method::POST, &format!("{PREFIXO}{caminho}"), token, Some(corpo)).await
+    }
+
+    pub async fn put(&self, caminho: &str, token: Option<&str>, corpo: Value) -> Resposta {
+        self.requisitar(Method::PUT, &format!("{PREFIXO}{caminho}"), token, Some(corpo)).await
+    }
+
+    pub async fn delete(&self, caminho: &str, token: Option<&str>) -> Resposta {
+        self.requisitar(Method::DELETE, &format!("{PREFIXO}{caminho}"), token, None).await
+    }
+
+    pub async fn registrar(&self, nome_usuario: &str, email: &str, senha: &str) -> Resposta {
+        self.post(
+            "/auth/register",
+            None,
+            serde_json::json!({ "username": nome_usuario, "email": email, "password": senha }),
+        )
+        .await
+    }
+
+    pub async fn login(&self, email: &str, senha: &str) -> Resposta {
+        self.post("/auth/login", None, serde_json::json!({ "email": email, "password": senha })).await
+    }
+
+    /// Login que precisa dar certo; devolve o token.
+    pub async fn token(&self, email: &str, senha: &str) -> String {
+        let resposta = self.login(email, senha).await;
+        assert_eq!(resposta.status, StatusCode::OK, "login de {email}: {}", resposta.corpo);
+        resposta.corpo["token"].as_str().unwrap().to_string()
+    }
+
+    /// Cria um usuário e devolve o token de uma sessão dele.
+    pub async fn usuario(&self, nome_usuario: &str) -> String {
+        let email = format!("{nome_usuario}@example.com");
+        let resposta = self.registrar(nome_usuario, &email, SENHA_VALIDA).await;
+        assert_eq!(resposta.status, StatusCode::OK, "registro de {nome_usuario}: {}", resposta.corpo);
+        self.token(&email, SENHA_VALIDA).await
+    }
+
+    pub async fn criar_postagem(&self, token: &str, titulo: &str) -> i64 {
+        let resposta = self
+            .post("/posts", Some(token), serde_json::json!({ "title": titulo, "content": "Conteúdo de teste" }))
+            .await;
+        assert_eq!(resposta.status, StatusCode::OK, "criação de post: {}", resposta.corpo);
+        resposta.corpo["id"].as_i64().unwrap()
+    }
+}
+
+/// Atende à política de senhas (tamanho, entropia e lista de vazadas).
+pub const SENHA_VALIDA: &str = "Integracao#Teste2024";
+
+fn sqlite_em_memoria() -> Banco {
+    let opcoes = SqliteConnectOptions::from_str("sqlite::memory:")
+        .unwrap()
+        .foreign_keys(true);
+
+    // Uma conexão só: cada conexão a `:memory:` abre um banco diferente
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect_lazy_with(opcoes);
+
+    Banco::Sqlite(pool)
+}
+
+async fn postgres_em_esquema(url: String) -> (Banco, EsquemaTemporario) {
+    let nome = format!("teste_{}", uuid::Uuid::new_v4().simple());
+
+    let mut conexao = PgConnection::connect(&url).await.expect("conexão com TEST_DATABASE_URL");
+    conexao
+        .execute(format!("CREATE SCHEMA {nome}").as_str())
+        .await
+        .expect("criação do schema de teste");
+    conexao.close().await.ok();
+
+    // Todas as conexões do pool enxergam só o schema do teste
+    let caminho_busca = format!("SET search_path TO {nome}");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .after_connect(move |conexao, _| {
+            let caminho_busca = caminho_busca.clone();
+            Box::pin(async move {
+                conexao.execute(caminho_busca.as_str()).await?;
+                Ok(())
+            })
+        })
+        .connect_lazy(&url)
+        .expect("URL de TEST_DATABASE_URL");
+
+    (Banco::Postgres(pool), EsquemaTemporario { url, nome })
+}
+
+impl Drop for EsquemaTemporario {
+    // Drop não pode ser assíncrono: o schema é apagado num runtime à parte
+    fn drop(&mut self) {
+        let url = self.url.clone();
+        let comando = format!("DROP SCHEMA IF EXISTS {} CASCADE", self.nome);
+
+        let resultado = std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+            runtime.block_on(async {
+                let mut conexao = PgConnection::connect(&url).await?;
+                conexao.execute(comando.as_str()).await?;
+                conexao.close().await
+            })?;
+            Ok::<_, Box<dyn std::error::Error + Send + Sync>>(())
+        })
+        .join();
+
+        if !matches!(resultado, Ok(Ok(()))) {
+            eprintln!("aviso: não foi possível apagar o schema de teste {}", self.nome);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+mod comum;
+
+use axum::{
+    http::{HeaderValue, Method, StatusCode},
+    routing::get,
+    Router,
+};
+use comum::{AppTeste, SENHA_VALIDA};
+use hello_rust::{configuracao::Configuracao, saude::Prontidao};
+use std::time::Duration;
+use tower_http::set_header::SetResponseHeaderLayer;
+
+#[tokio::test]
+async fn aplicacao_sob_prefixo() {
+    let app = AppTeste::com_construtor(|construtor| construtor.prefixo("/blog")).await;
+
+    assert_eq!(app.requisitar(Method::GET, "/blog/api/v1/posts", None, None).await.status, StatusCode::OK);
+    assert_eq!(app.requisitar(Method::GET, "/blog/health/live", None, None).await.status, StatusCode::OK);
+    assert_eq!(app.requisitar(Method::GET, "/api/v1/posts", None, None).await.status, StatusCode::NOT_FOUND);
+
+    let especificacao = app.requisitar(Method::GET, "/blog/openapi.json", None, None).await;
+    assert_eq!(especificacao.corpo["servers"][0]["url"], "/blog");
+
+    // Sem prefixo a especificação não declara servers
+    let app = AppTeste::nova().await;
+    let especificacao = app.requisitar(Method::GET, "/openapi.json", None, None).await;
+    assert!(especificacao.corpo.get("servers").is_none());
+}
+
+#[tokio::test]
+async fn prefixo_sem_barra_e_normalizado() {
+    let app = AppTeste::com_construtor(|construtor| construtor.prefixo("blog/")).await;
+
+    assert_eq!(app.requisitar(Method::GET, "/blog/api/v1/posts", None, None).await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn sem_rotas_operacionais() {
+    let app = AppTeste::com_construtor(|construtor| construtor.sem_rotas_operacionais()).await;
+
+    assert_eq!(app.get("/posts", None).await.status, StatusCode::OK);
+    for caminho in ["/health/live", "/metrics", "/docs", "/home"] {
+        let resposta = app.requisitar(Method::GET, caminho, None, None).await;
+        assert_eq!(resposta.status, StatusCode::NOT_FOUND, "{caminho}");
+    }
+}
+
+#[tokio::test]
+async fn embutida_com_middleware_e_rotas_do_anfitriao() {
+    let app = AppTeste::com_construtor(|construtor| construtor.prefixo("/blog").sem_rotas_operacionais()).await;
+    let anfitriao = Router::new()
+        .route("/status", get(|| async { "ok" }))
+        .merge(app.roteador())
+        .layer(SetResponseHeaderLayer::overriding(
+            axum::http::HeaderName::from_static("x-anfitriao"),
+            HeaderValue::from_static("sim"),
+        ));
+    let app = app.com_roteador(anfitriao);
+
+    let resposta = app.requisitar(Method::GET, "/blog/api/v1/posts", None, None).await;
+    assert_eq!(resposta.status, StatusCode::OK);
+    assert_eq!(resposta.cabecalhos["x-anfitriao"], "sim");
+    assert_eq!(app.requisitar(Method::GET, "/status", None, None).await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn token_so_vale_para_o_segredo_que_o_assinou() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+
+    let outra = AppTeste::com_construtor(|construtor| {
+        construtor
+            .configuracao(Configuracao { segredo_jwt: "outro-segredo".to_string(), ..Default::default() })
+            .repositorios(app.repositorios.clone())
+    })
+    .await;
+
+    assert_eq!(app.get("/profile", Some(&token)).await.status, StatusCode::OK);
+    assert_eq!(outra.get("/profile", Some(&token)).await.status, StatusCode::UNAUTHORIZED);
+
+    // Os mesmos dados, vistos pela outra instância com um token dela
+    let token_da_outra = outra.token("ana@example.com", SENHA_VALIDA).await;
+    assert_eq!(outra.get("/profile", Some(&token_da_outra)).await.status, StatusCode::OK);
+}
+
+/// `/health/ready` depois de as conexões da requisição anterior voltarem ao
+/// pool, o que o sqlx faz em segundo plano; senão o pool do teste, de uma ou
+/// duas conexões, aparece saturado.
+async fn prontidao(app: &AppTeste, caminho: &str) -> comum::Resposta {
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    app.requisitar(Method::GET, caminho, None, None).await
+}
+
+#[tokio::test]
+async fn embutida_fica_pronta_sem_marcar_nada() {
+    let app = AppTeste::com_construtor(|construtor| construtor.prefixo("/blog")).await;
+
+    let pronta = prontidao(&app, "/blog/health/ready").await;
+    assert_eq!(pronta.status, StatusCode::OK, "{}", pronta.corpo);
+    assert_eq!(pronta.corpo["checks"]["startup"]["status"], "ok");
+    assert_eq!(app.requisitar(Method::GET, "/blog/health/startup", None, None).await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn prontidao_informada_acompanha_a_inicializacao_e_o_encerramento() {
+    let inicializacao = Prontidao::iniciando();
+    let app = AppTeste::com_construtor(|construtor| construtor.prontidao(inicializacao.clone())).await;
+    let outra = AppTeste::nova().await;
+
+    let iniciando = prontidao(&app, "/health/ready").await;
+    assert_eq!(iniciando.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(iniciando.corpo["checks"]["startup"]["status"], "starting");
+    assert_eq!(app.requisitar(Method::GET, "/health/startup", None, None).await.status, StatusCode::SERVICE_UNAVAILABLE);
+
+    inicializacao.marcar_inicializada();
+    let pronta = prontidao(&app, "/health/ready").await;
+    assert_eq!(pronta.status, StatusCode::OK, "{}", pronta.corpo);
+    assert_eq!(app.requisitar(Method::GET, "/health/startup", None, None).await.status, StatusCode::OK);
+
+    inicializacao.marcar_encerrando();
+    let encerrando = prontidao(&app, "/health/ready").await;
+    assert_eq!(encerrando.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(encerrando.corpo["status"], "shutting_down");
+
+    // Outra aplicação no mesmo processo não é afetada
+    let pronta = prontidao(&outra, "/health/ready").await;
+    assert_eq!(pronta.status, StatusCode::OK, "{}", pronta.corpo);
+}
@@ -0,0 +1,316 @@
+mod comum;
+
+use axum::http::StatusCode;
+use chrono::{DateTime, Duration, Utc};
+use comum::AppTeste;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+
+use hello_rust::{
+    fila::{self, ErroTarefa, Fila, NovaTarefa, TipoTarefa},
+    repositorios::Repositorios,
+};
+
+/// Publica um post: o efeito da tarefa aparece nos repositórios.
+#[derive(Serialize, Deserialize)]
+struct Publicar {
+    id_usuario: i32,
+    titulo: String,
+}
+
+impl TipoTarefa for Publicar {
+    const TIPO: &'static str = "test.publish";
+
+    fn executar(self, repositorios: Repositorios) -> BoxFuture<'static, Result<(), ErroTarefa>> {
+        Box::pin(async move {
+            repositorios.postagens.criar(self.id_usuario, &self.titulo, "Texto").await?;
+            Ok(())
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Falhar {
+    definitivo: bool,
+}
+
+impl TipoTarefa for Falhar {
+    const TIPO: &'static str = "test.fail";
+    const MAXIMO_TENTATIVAS: i32 = 3;
+
+    fn executar(self, _: Repositorios) -> BoxFuture<'static, Result<(), ErroTarefa>> {
+        Box::pin(async move {
+            if self.definitivo {
+                Err(ErroTarefa::Definitivo("não adianta tentar de novo".to_string()))
+            } else {
+                Err(ErroTarefa::Temporario("falhou de novo".to_string()))
+            }
+        })
+    }
+}
+
+/// Só espera: fica em execução pelo tempo pedido.
+#[derive(Serialize, Deserialize)]
+struct Demorar {
+    milissegundos: u64,
+}
+
+impl TipoTarefa for Demorar {
+    const TIPO: &'static str = "test.sleep";
+
+    fn executar(self, _: Repositorios) -> BoxFuture<'static, Result<(), ErroTarefa>> {
+        Box::pin(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(self.milissegundos)).await;
+            Ok(())
+        })
+    }
+}
+
+/// Usuário administrador; devolve o token e o id.
+async fn admin(app: &AppTeste, nome_usuario: &str) -> (String, i32) {
+    let token = app.usuario(nome_usuario).await;
+    let id = app.repositorios.usuarios.buscar_por_nome_usuario(nome_usuario).await.unwrap().unwrap().id;
+    app.repositorios.usuarios.definir_admin(id, true).await.unwrap();
+    (token, id)
+}
+
+fn fila(app: &AppTeste) -> Fila {
+    Fila::new(app.repositorios.clone()).registrar::<Publicar>().registrar::<Falhar>()
+}
+
+async fn tarefa(app: &AppTeste, token: &str, id: i64) -> Value {
+    let resposta = app.get(&format!("/admin/jobs/{id}"), Some(token)).await;
+    assert_eq!(resposta.status, StatusCode::OK, "{}", resposta.corpo);
+    resposta.corpo
+}
+
+fn data(texto: &str) -> DateTime<Utc> {
+    texto.parse().unwrap()
+}
+
+#[tokio::test]
+async fn tarefas_enfileiradas_sao_executadas_pelos_tipos_registrados() {
+    let app = AppTeste::nova().await;
+    let (token, id_usuario) = admin(&app, "ana").await;
+
+    let publicar = |titulo: &str| Publicar { id_usuario, titulo: titulo.to_string() };
+    let agora = fila::enfileirar(&app.repositorios, &publicar("Agora")).await.unwrap();
+    let depois = fila::enfileirar_em(&app.repositorios, &publicar("Depois"), Utc::now() + Duration::hours(1))
+        .await
+        .unwrap();
+    assert_eq!(agora.status, "pending");
+    assert_eq!(agora.maximo_tentativas, fila::MAXIMO_TENTATIVAS_PADRAO);
+
+    // Uma fila que não conhece o tipo não reserva a tarefa
+    let so_falhas = Fila::new(app.repositorios.clone()).registrar::<Falhar>();
+    assert_eq!(so_falhas.processar(Utc::now(), 10).await.unwrap(), 0);
+
+    assert_eq!(fila(&app).processar(Utc::now(), 10).await.unwrap(), 1);
+    let postagens = app.repositorios.postagens.listar_do_usuario(id_usuario).await.unwrap();
+    assert_eq!(postagens.len(), 1);
+    assert_eq!(postagens[0].titulo, "Agora");
+
+    let concluida = tarefa(&app, &token, agora.id).await;
+    assert_eq!(concluida["kind"], "test.publish");
+    assert_eq!(concluida["payload"]["titulo"], "Agora");
+    assert_eq!(concluida["status"], "succeeded");
+    assert_eq!(concluida["attempts"], 1);
+    assert!(concluida["finished_at"].is_string());
+    assert!(concluida["run_at"].is_null());
+
+    let pendente = tarefa(&app, &token, depois.id).await;
+    assert_eq!(pendente["status"], "pending");
+    assert!(pendente["run_at"].is_string());
+
+    assert_eq!(fila(&app).processar(Utc::now() + Duration::hours(2), 10).await.unwrap(), 1);
+    assert_eq!(app.repositorios.postagens.listar_do_usuario(id_usuario).await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn falhas_sao_tentadas_de_novo_ate_a_tarefa_morrer() {
+    let app = AppTeste::nova().await;
+    let (token, _) = admin(&app, "ana").await;
+    let id = fila::enfileirar(&app.repositorios, &Falhar { definitivo: false }).await.unwrap().id;
+
+    let agora = Utc::now();
+    assert_eq!(fila(&app).processar(agora, 10).await.unwrap(), 1);
+    let reagendada = tarefa(&app, &token, id).await;
+    assert_eq!(reagendada["status"], "pending");
+    assert_eq!(reagendada["attempts"], 1);
+    assert_eq!(reagendada["max_attempts"], 3);
+    assert_eq!(reagendada["last_error"], "falhou de novo");
+
+    // A próxima tentativa espera o backoff
+    assert_eq!(fila(&app).processar(agora + Duration::seconds(5), 10).await.unwrap(), 0);
+    assert_eq!(fila(&app).processar(agora + fila::espera(1), 10).await.unwrap(), 1);
+    assert_eq!(fila(&app).processar(agora + Duration::hours(1), 10).await.unwrap(), 1);
+    assert_eq!(fila(&app).processar(agora + Duration::hours(3), 10).await.unwrap(), 0);
+
+    let morta = tarefa(&app, &token, id).await;
+    assert_eq!(morta["status"], "dead");
+    assert_eq!(morta["attempts"], 3);
+    assert!(morta["finished_at"].is_string());
+
+    // Erro definitivo e payload que não é do tipo não são tentados de novo
+    let definitiva = fila::enfileirar(&app.repositorios, &Falhar { definitivo: true }).await.unwrap().id;
+    let invalida = NovaTarefa {
+        payload: json!("não é um Falhar"),
+        ..NovaTarefa::new(&Falhar { definitivo: false }, Utc::now())
+    };
+    let invalida = app.repositorios.tarefas.enfileirar(&invalida).await.unwrap().unwrap().id;
+    assert_eq!(fila(&app).processar(Utc::now(), 10).await.unwrap(), 2);
+    assert_eq!(tarefa(&app, &token, definitiva).await["status"], "dead");
+    let invalida = tarefa(&app, &token, invalida).await;
+    assert_eq!(invalida["status"], "dead");
+    assert_eq!(invalida["attempts"], 1);
+    assert!(invalida["last_error"].as_str().unwrap().starts_with("payload inválido"), "{invalida}");
+
+    let mortas = app.get("/admin/jobs?status=dead&kind=test.fail", Some(&token)).await.corpo;
+    let ids: Vec<&Value> = mortas.as_array().unwrap().iter().map(|tarefa| &tarefa["id"]).collect();
+    assert_eq!(ids, [&json!(invalida["id"]), &json!(definitiva), &json!(id)]);
+    assert!(app.get("/admin/jobs?status=running", Some(&token)).await.corpo.as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn administrador_devolve_tarefas_mortas_a_fila() {
+    let app = AppTeste::nova().await;
+    let (token, id_usuario) = admin(&app, "ana").await;
+    let comum = app.usuario("bruno").await;
+
+    let id = fila::enfileirar(&app.repositorios, &Falhar { definitivo: true }).await.unwrap().id;
+    fila(&app).processar(Utc::now(), 10).await.unwrap();
+    let caminho = format!("/admin/jobs/{id}/retry");
+
+    assert_eq!(app.get("/admin/jobs", Some(&comum)).await.status, StatusCode::FORBIDDEN);
+    assert_eq!(app.post(&caminho, Some(&comum), json!({})).await.status, StatusCode::FORBIDDEN);
+
+    let resposta = app.post(&caminho, Some(&token), json!({})).await;
+    assert_eq!(resposta.status, StatusCode::ACCEPTED, "{}", resposta.corpo);
+    assert_eq!(resposta.corpo["status"], "pending");
+    assert_eq!(resposta.corpo["attempts"], 0);
+    assert!(resposta.corpo["finished_at"].is_null());
+    // O último erro fica, para comparar com o da nova tentativa
+    assert_eq!(resposta.corpo["last_error"], "não adianta tentar de novo");
+
+    assert_eq!(fila(&app).processar(Utc::now(), 10).await.unwrap(), 1);
+    assert_eq!(tarefa(&app, &token, id).await["attempts"], 1);
+
+    let eventos = app.get("/admin/audit?action=admin.job_retried", Some(&token)).await.corpo;
+    assert_eq!(eventos[0]["actor_id"], id_usuario);
+    assert_eq!(eventos[0]["target_type"], "job");
+    assert_eq!(eventos[0]["target_id"], id.to_string());
+
+    // Concluídas não voltam à fila
+    let publicar = Publicar { id_usuario, titulo: "Feito".to_string() };
+    let concluida = fila::enfileirar(&app.repositorios, &publicar).await.unwrap().id;
+    fila(&app).processar(Utc::now(), 10).await.unwrap();
+    let resposta = app.post(&format!("/admin/jobs/{concluida}/retry"), Some(&token), json!({})).await;
+    assert_eq!(resposta.status, StatusCode::CONFLICT);
+    assert_eq!(app.post("/admin/jobs/999999/retry", Some(&token), json!({})).await.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.get("/admin/jobs/999999", Some(&token)).await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn tarefa_com_reserva_vencida_e_retomada() {
+    let app = AppTeste::nova().await;
+    let (token, id_usuario) = admin(&app, "ana").await;
+    let publicar = Publicar { id_usuario, titulo: "Retomado".to_string() };
+    let id = fila::enfileirar(&app.repositorios, &publicar).await.unwrap().id;
+
+    // Uma instância reserva a tarefa e cai antes de terminar
+    let agora = Utc::now();
+    let reservadas = app
+        .repositorios
+        .tarefas
+        .reservar(&[Publicar::TIPO], agora, agora + Duration::minutes(10), 10)
+        .await
+        .unwrap();
+    assert_eq!(reservadas.len(), 1);
+    let em_execucao = tarefa(&app, &token, id).await;
+    assert_eq!(em_execucao["status"], "running");
+    assert!(em_execucao["locked_until"].is_string());
+
+    assert_eq!(fila(&app).processar(agora + Duration::minutes(5), 10).await.unwrap(), 0);
+    assert_eq!(fila(&app).processar(agora + Duration::minutes(11), 10).await.unwrap(), 1);
+
+    let retomada = tarefa(&app, &token, id).await;
+    assert_eq!(retomada["status"], "succeeded");
+    assert_eq!(retomada["attempts"], 2);
+    assert!(retomada["locked_until"].is_null());
+
+    // O resultado tardio da primeira instância é descartado
+    let tardio = fila::ResultadoExecucao {
+        tentativa: 1,
+        concluida_em: Utc::now(),
+        erro: Some("perdeu a reserva".to_string()),
+        proxima_tentativa: None,
+    };
+    assert!(!app.repositorios.tarefas.concluir(id, &tardio).await.unwrap());
+    assert_eq!(tarefa(&app, &token, id).await["status"], "succeeded");
+}
+
+#[tokio::test]
+async fn agendadas_sao_enfileiradas_uma_vez_por_ocorrencia() {
+    let app = AppTeste::nova().await;
+    let (token, id_usuario) = admin(&app, "ana").await;
+    let agendada = || {
+        Fila::new(app.repositorios.clone()).agendar("*/10 * * * *", Publicar { id_usuario, titulo: "Agendado".into() })
+    };
+    let (instancia_a, instancia_b) = (agendada(), agendada());
+
+    // Duas ocorrências na janela (10:10 e 10:20): só a última é enfileirada
+    let (desde, ate) = (data("2026-10-18T10:05:00Z"), data("2026-10-18T10:25:00Z"));
+    assert_eq!(instancia_a.enfileirar_agendadas(desde, ate).await.unwrap(), 1);
+    // Outra instância verificando a mesma agenda não duplica a tarefa
+    assert_eq!(instancia_b.enfileirar_agendadas(desde, ate).await.unwrap(), 0);
+    assert_eq!(instancia_b.enfileirar_agendadas(ate, data("2026-10-18T10:29:00Z")).await.unwrap(), 0);
+    assert_eq!(instancia_b.enfileirar_agendadas(ate, data("2026-10-18T10:30:00Z")).await.unwrap(), 1);
+
+    let tarefas = app.get("/admin/jobs?kind=test.publish", Some(&token)).await.corpo;
+    let chaves: Vec<&str> = tarefas.as_array().unwrap().iter().map(|tarefa| tarefa["unique_key"].as_str().unwrap()).collect();
+    assert_eq!(chaves, ["test.publish@2026-10-18T10:30:00+00:00", "test.publish@2026-10-18T10:20:00+00:00"]);
+    assert_eq!(tarefas[1]["run_at"], "2026-10-18T10:20:00Z");
+
+    assert_eq!(instancia_a.processar(Utc::now(), 10).await.unwrap(), 2);
+    assert_eq!(app.repositorios.postagens.listar_do_usuario(id_usuario).await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn encerramento_espera_as_tarefas_e_devolve_as_que_nao_terminam() {
+    let app = AppTeste::nova().await;
+    let (token, id_usuario) = admin(&app, "ana").await;
+    let rapida = fila::enfileirar(&app.repositorios, &Demorar { milissegundos: 300 }).await.unwrap().id;
+    let lenta = fila::enfileirar(&app.repositorios, &Demorar { milissegundos: 60_000 }).await.unwrap().id;
+    let seguinte = fila::enfileirar(&app.repositorios, &Publicar { id_usuario, titulo: "Depois".into() })
+        .await
+        .unwrap()
+        .id;
+
+    let parar = CancellationToken::new();
+    let execucao = tokio::spawn(fila(&app).registrar::<Demorar>().executar(2, parar.clone(), std::time::Duration::from_secs(1)));
+    for _ in 0..100 {
+        let (a, b) = (tarefa(&app, &token, rapida).await, tarefa(&app, &token, lenta).await);
+        if a["status"] == "running" && b["status"] == "running" {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+
+    parar.cancel();
+    let inicio = std::time::Instant::now();
+    tokio::time::timeout(std::time::Duration::from_secs(10), execucao).await.unwrap().unwrap();
+    assert!(inicio.elapsed() < std::time::Duration::from_secs(5));
+
+    // A que coube no prazo terminou; a outra voltou à fila sem contar a tentativa
+    assert_eq!(tarefa(&app, &token, rapida).await["status"], "succeeded");
+    let devolvida = tarefa(&app, &token, lenta).await;
+    assert_eq!(devolvida["status"], "pending");
+    assert_eq!(devolvida["attempts"], 0);
+    assert!(devolvida["locked_until"].is_null());
+    // Com o encerramento pedido, a vaga aberta pela rápida não foi preenchida
+    let nao_reservada = tarefa(&app, &token, seguinte).await;
+    assert_eq!(nao_reservada["status"], "pending");
+    assert_eq!(nao_reservada["attempts"], 0);
+}
@@ -0,0 +1,235 @@
+mod comum;
+
+use axum::http::{header::CONTENT_TYPE, Method, StatusCode};
+use comum::AppTeste;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id},
+    Subscriber,
+};
+use tracing_subscriber::{layer, prelude::*, Layer};
+
+async fn graphql(app: &AppTeste, token: Option<&str>, consulta: &str, variaveis: Value) -> Value {
+    let resposta = app.post("/graphql", token, json!({ "query": consulta, "variables": variaveis })).await;
+    assert_eq!(resposta.status, StatusCode::OK, "{}", resposta.corpo);
+    resposta.corpo
+}
+
+/// `data` de uma resposta sem erros.
+async fn dados(app: &AppTeste, token: Option<&str>, consulta: &str, variaveis: Value) -> Value {
+    let resposta = graphql(app, token, consulta, variaveis).await;
+    assert!(resposta.get("errors").is_none(), "{resposta}");
+    resposta["data"].clone()
+}
+
+fn codigo_erro(resposta: &Value) -> &str {
+    resposta["errors"][0]["extensions"]["code"].as_str().unwrap_or_else(|| panic!("sem código de erro: {resposta}"))
+}
+
+fn ids(conexao: &Value) -> Vec<i64> {
+    conexao["edges"].as_array().unwrap().iter().map(|edge| edge["node"]["id"].as_i64().unwrap()).collect()
+}
+
+/// Guarda a operação (ex.: `SELECT posts`) de cada span `db.query` aberto na thread do teste.
+#[derive(Clone, Default)]
+struct Consultas(Arc<Mutex<Vec<String>>>);
+
+impl<S: Subscriber> Layer<S> for Consultas {
+    fn on_new_span(&self, atributos: &Attributes<'_>, _: &Id, _: layer::Context<'_, S>) {
+        if atributos.metadata().name() == "db.query" {
+            let mut operacao = Operacao::default();
+            atributos.record(&mut operacao);
+            self.0.lock().unwrap().push(operacao.0);
+        }
+    }
+}
+
+#[derive(Default)]
+struct Operacao(String);
+
+impl Visit for Operacao {
+    fn record_str(&mut self, campo: &Field, valor: &str) {
+        if campo.name() == "db.operation" {
+            self.0 = valor.to_string();
+        }
+    }
+
+    fn record_debug(&mut self, _: &Field, _: &dyn std::fmt::Debug) {}
+}
+
+#[tokio::test]
+async fn posts_vem_com_autor_e_contagem_em_paginas() {
+    let app = AppTeste::com_seed().await;
+    let consulta = r#"
+        query Pagina($after: String) {
+            posts(first: 4, after: $after) {
+                edges { cursor node { id title authorId author { username postCount } } }
+                pageInfo { hasNextPage hasPreviousPage endCursor }
+            }
+        }
+    "#;
+
+    let primeira = dados(&app, None, consulta, json!({})).await["posts"].clone();
+    let ids_primeira = ids(&primeira);
+    assert_eq!(ids_primeira.len(), 4);
+    assert!(ids_primeira.is_sorted_by(|a, b| a > b), "{ids_primeira:?}");
+    assert_eq!(primeira["pageInfo"]["hasNextPage"], true);
+    assert_eq!(primeira["pageInfo"]["hasPreviousPage"], false);
+    assert_eq!(primeira["pageInfo"]["endCursor"], ids_primeira[3].to_string());
+
+    // O autor é o mesmo que o REST informa em user_id
+    for edge in primeira["edges"].as_array().unwrap() {
+        let postagem = app.get(&format!("/posts/{}", edge["node"]["id"]), None).await.corpo;
+        let autor = app.repositorios.usuarios.buscar_por_id(postagem["user_id"].as_i64().unwrap() as i32).await.unwrap().unwrap();
+        assert_eq!(edge["cursor"], edge["node"]["id"].to_string());
+        assert_eq!(edge["node"]["title"], postagem["title"]);
+        assert_eq!(edge["node"]["authorId"], postagem["user_id"]);
+        assert_eq!(edge["node"]["author"]["username"], autor.nome_usuario);
+        assert_eq!(edge["node"]["author"]["postCount"], 2);
+    }
+
+    let segunda = dados(&app, None, consulta, json!({ "after": primeira["pageInfo"]["endCursor"] })).await["posts"].clone();
+    assert_eq!(ids(&segunda).len(), 2);
+    assert!(ids(&segunda).iter().all(|id| id < &ids_primeira[3]));
+    assert_eq!(segunda["pageInfo"]["hasNextPage"], false);
+    assert_eq!(segunda["pageInfo"]["hasPreviousPage"], true);
+
+    let invalido = graphql(&app, None, "{ posts(after: \"abc\") { edges { cursor } } }", json!({})).await;
+    assert_eq!(codigo_erro(&invalido), "BAD_USER_INPUT");
+    let grande = graphql(&app, None, "{ posts(first: 101) { edges { cursor } } }", json!({})).await;
+    assert_eq!(codigo_erro(&grande), "BAD_USER_INPUT");
+}
+
+#[tokio::test]
+async fn usuarios_trazem_os_proprios_posts() {
+    let app = AppTeste::com_seed().await;
+    let token = app.token("joao@example.com", "Joao#Rust2024").await;
+
+    let usuarios = dados(
+        &app,
+        None,
+        r#"{
+            users(first: 2) {
+                edges { node { id username posts(first: 1) { edges { node { authorId } } pageInfo { hasNextPage } } } }
+                pageInfo { hasNextPage endCursor }
+            }
+        }"#,
+        json!({}),
+    )
+    .await["users"]
+    .clone();
+
+    // Em ordem de cadastro, como na fixture
+    let nomes: Vec<&str> = usuarios["edges"].as_array().unwrap().iter().map(|edge| edge["node"]["username"].as_str().unwrap()).collect();
+    assert_eq!(nomes, ["admin", "joao"]);
+    assert_eq!(usuarios["pageInfo"]["hasNextPage"], true);
+    for edge in usuarios["edges"].as_array().unwrap() {
+        let postagens = &edge["node"]["posts"];
+        assert_eq!(postagens["edges"].as_array().unwrap().len(), 1);
+        assert_eq!(postagens["edges"][0]["node"]["authorId"], edge["node"]["id"]);
+        assert_eq!(postagens["pageInfo"]["hasNextPage"], true);
+    }
+
+    let resto = dados(&app, None, "query($after: String) { users(after: $after) { edges { node { username } } } }", json!({ "after": usuarios["pageInfo"]["endCursor"] })).await;
+    assert_eq!(resto["users"]["edges"][0]["node"]["username"], "maria");
+
+    assert_eq!(dados(&app, None, "{ me { username } }", json!({})).await["me"], Value::Null);
+    assert_eq!(dados(&app, Some(&token), "{ me { username email } }", json!({})).await["me"], json!({ "username": "joao", "email": "joao@example.com" }));
+    assert_eq!(dados(&app, None, "{ user(id: 999) { username } post(id: 999) { title } }", json!({})).await, json!({ "user": null, "post": null }));
+}
+
+#[tokio::test]
+async fn relacoes_sao_carregadas_em_lote() {
+    let app = AppTeste::com_seed().await;
+    let consultas = Consultas::default();
+    let _assinante = tracing::subscriber::set_default(tracing_subscriber::registry().with(consultas.clone()));
+
+    let resposta = dados(
+        &app,
+        None,
+        "{ posts(first: 6) { edges { node { author { username postCount posts(first: 2) { edges { node { id } } } } } } } }",
+        json!({}),
+    )
+    .await;
+    assert_eq!(resposta["posts"]["edges"].as_array().unwrap().len(), 6);
+
+    // Uma consulta por campo (lista, autores, contagens, posts dos autores), não uma por post
+    let mut operacoes = consultas.0.lock().unwrap().clone();
+    operacoes.sort();
+    assert_eq!(operacoes, ["SELECT posts", "SELECT posts", "SELECT posts", "SELECT users"]);
+}
+
+#[tokio::test]
+async fn mutacoes_exigem_token_e_espelham_o_rest() {
+    let app = AppTeste::nova().await;
+    let ana = app.usuario("ana").await;
+    let bruno = app.usuario("bruno").await;
+
+    let criar = "mutation($input: PostInput!) { createPost(input: $input) { id title content author { username } } }";
+    let anonima = graphql(&app, None, criar, json!({ "input": { "title": "Sem token", "content": "Texto" } })).await;
+    assert_eq!(codigo_erro(&anonima), "UNAUTHENTICATED");
+    assert_eq!(anonima["data"], Value::Null);
+    let resposta = app.post("/graphql", Some("token-invalido"), json!({ "query": "{ me { id } }" })).await;
+    assert_eq!(resposta.status, StatusCode::UNAUTHORIZED);
+
+    // As mesmas regras de validação do REST, com as mensagens por campo
+    let invalida = graphql(&app, Some(&ana), criar, json!({ "input": { "title": "   ", "content": "Texto" } })).await;
+    assert_eq!(codigo_erro(&invalida), "BAD_USER_INPUT");
+    assert!(invalida["errors"][0]["extensions"]["fields"]["title"].is_array(), "{invalida}");
+
+    let criada = dados(&app, Some(&ana), criar, json!({ "input": { "title": "  Pelo GraphQL ", "content": "Texto" } })).await["createPost"].clone();
+    assert_eq!(criada["title"], "Pelo GraphQL");
+    assert_eq!(criada["author"]["username"], "ana");
+    let id = criada["id"].as_i64().unwrap();
+    assert_eq!(app.get(&format!("/posts/{id}"), None).await.corpo["title"], "Pelo GraphQL");
+
+    let atualizar = "mutation($id: Int!) { updatePost(id: $id, input: { title: \"Editado\", content: \"Novo\" }) { title content } }";
+    let de_outro = graphql(&app, Some(&bruno), atualizar, json!({ "id": id })).await;
+    assert_eq!(codigo_erro(&de_outro), "NOT_FOUND");
+    let atualizada = dados(&app, Some(&ana), atualizar, json!({ "id": id })).await;
+    assert_eq!(atualizada["updatePost"], json!({ "title": "Editado", "content": "Novo" }));
+    // A lista pública em cache foi invalidada, como numa edição pelo REST
+    assert_eq!(app.get("/posts", None).await.corpo[0]["title"], "Editado");
+
+    let deletar = "mutation($id: Int!) { deletePost(id: $id) }";
+    assert_eq!(codigo_erro(&graphql(&app, Some(&bruno), deletar, json!({ "id": id })).await), "NOT_FOUND");
+    assert_eq!(dados(&app, Some(&ana), deletar, json!({ "id": id })).await["deletePost"], id);
+    assert_eq!(app.get(&format!("/posts/{id}"), None).await.status, StatusCode::NOT_FOUND);
+
+    // Auditadas como as escritas pelo REST
+    let id_ana = app.get("/profile", Some(&ana)).await.corpo["id"].as_i64().unwrap();
+    app.repositorios.usuarios.definir_admin(id_ana as i32, true).await.unwrap();
+    let eventos = app.get(&format!("/admin/audit?actor_id={id_ana}"), Some(&ana)).await.corpo;
+    let acoes: Vec<&str> = eventos.as_array().unwrap().iter().map(|evento| evento["action"].as_str().unwrap()).collect();
+    assert_eq!(acoes[..3], ["post.deleted", "post.updated", "post.created"]);
+    assert_eq!(eventos[1]["diff"]["title"], json!(["Pelo GraphQL", "Editado"]));
+}
+
+#[tokio::test]
+async fn consultas_fundas_ou_caras_sao_recusadas() {
+    let app = AppTeste::com_seed().await;
+
+    let funda = "{ posts(first: 1) { edges { node { author { posts(first: 1) { edges { node { author { posts(first: 1) { edges { node { id } } } } } } } } } } } }";
+    let resposta = graphql(&app, None, funda, json!({})).await;
+    assert_eq!(resposta["errors"][0]["message"], "Query is nested too deep.");
+    assert!(resposta.get("data").is_none_or(Value::is_null));
+
+    let cara = "{ users(first: 100) { edges { node { posts(first: 100) { edges { node { id } } } } } } }";
+    let resposta = graphql(&app, None, cara, json!({})).await;
+    assert_eq!(resposta["errors"][0]["message"], "Query is too complex.");
+
+    // A introspecção das ferramentas passa do limite de profundidade das consultas, mas não busca dados
+    let introspeccao = "{ __schema { types { name fields { name type { kind ofType { kind ofType { kind ofType { kind ofType { name } } } } } } } } }";
+    let tipos = dados(&app, None, introspeccao, json!({})).await;
+    assert!(tipos["__schema"]["types"].as_array().unwrap().iter().any(|tipo| tipo["name"] == "PostConnection"));
+}
+
+#[tokio::test]
+async fn graphiql_e_servido_no_mesmo_caminho() {
+    let app = AppTeste::nova().await;
+    let resposta = app.requisitar(Method::GET, "/api/v1/graphql", None, None).await;
+    assert_eq!(resposta.status, StatusCode::OK);
+    assert!(resposta.cabecalhos[CONTENT_TYPE].to_str().unwrap().starts_with("text/html"));
+}
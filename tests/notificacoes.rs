@@ -0,0 +1,174 @@
+mod comum;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use comum::{AppTeste, PREFIXO, SENHA_VALIDA};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::{self, client::IntoClientRequest, Message};
+
+/// Login de outro navegador (user agent diferente do das sessões anteriores).
+async fn login_de(app: &AppTeste, email: &str, user_agent: &str) -> comum::Resposta {
+    let requisicao = Request::post(format!("{PREFIXO}/auth/login"))
+        .header("content-type", "application/json")
+        .header("user-agent", user_agent)
+        .body(Body::from(json!({ "email": email, "password": SENHA_VALIDA }).to_string()))
+        .unwrap();
+    app.enviar(requisicao).await
+}
+
+async fn notificacoes(app: &AppTeste, token: &str, consulta: &str) -> Vec<Value> {
+    let resposta = app.get(&format!("/notifications?{consulta}"), Some(token)).await;
+    assert_eq!(resposta.status, StatusCode::OK, "{}", resposta.corpo);
+    resposta.corpo.as_array().unwrap().clone()
+}
+
+/// Serve a aplicação numa porta local: o WebSocket precisa de uma conexão de verdade.
+async fn servir(app: &AppTeste) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endereco = listener.local_addr().unwrap();
+    let roteador = app.roteador();
+    tokio::spawn(async move { axum::serve(listener, roteador).await.unwrap() });
+    format!("ws://{endereco}{PREFIXO}/notifications/ws")
+}
+
+type Socket = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+async fn conectar(url: &str, token: &str) -> Result<Socket, tungstenite::Error> {
+    let mut requisicao = url.into_client_request().unwrap();
+    requisicao
+        .headers_mut()
+        .insert("sec-websocket-protocol", format!("bearer, {token}").parse().unwrap());
+    let (socket, resposta) = tokio_tungstenite::connect_async(requisicao).await?;
+    assert_eq!(resposta.headers()["sec-websocket-protocol"], "bearer");
+    Ok(socket)
+}
+
+async fn proxima_mensagem(socket: &mut Socket) -> Value {
+    loop {
+        let mensagem = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .expect("nenhuma mensagem em 5 segundos")
+            .expect("conexão encerrada")
+            .unwrap();
+        if let Message::Text(texto) = mensagem {
+            return serde_json::from_str(&texto).unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn login_de_dispositivo_novo_gera_notificacao() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+
+    // O primeiro login e os do mesmo navegador não avisam
+    app.token("ana@example.com", SENHA_VALIDA).await;
+    assert!(notificacoes(&app, &token, "").await.is_empty());
+
+    assert_eq!(login_de(&app, "ana@example.com", "Navegador Novo/1.0").await.status, StatusCode::OK);
+    login_de(&app, "ana@example.com", "Navegador Novo/1.0").await;
+
+    let recebidas = notificacoes(&app, &token, "").await;
+    assert_eq!(recebidas.len(), 1);
+    assert_eq!(recebidas[0]["kind"], "auth.new_login");
+    assert_eq!(recebidas[0]["data"]["user_agent"], "Navegador Novo/1.0");
+    assert_eq!(recebidas[0]["data"]["ip"], "127.0.0.1");
+    assert!(recebidas[0]["read_at"].is_null());
+}
+
+#[tokio::test]
+async fn notificacoes_sao_marcadas_como_lidas() {
+    let app = AppTeste::nova().await;
+    let ana = app.usuario("ana").await;
+    let bruno = app.usuario("bruno").await;
+    let id_ana = app.get("/profile", Some(&ana)).await.corpo["id"].as_i64().unwrap() as i32;
+    for indice in 0..3 {
+        app.repositorios
+            .notificacoes
+            .criar(id_ana, "post.removed", &json!({ "post_id": indice }))
+            .await
+            .unwrap();
+    }
+
+    let todas = notificacoes(&app, &ana, "").await;
+    let ids: Vec<i64> = todas.iter().map(|notificacao| notificacao["id"].as_i64().unwrap()).collect();
+    assert_eq!(todas[0]["data"]["post_id"], 2);
+
+    // De outro usuário é como se não existisse
+    let caminho = format!("/notifications/{}/read", ids[0]);
+    assert_eq!(app.post(&caminho, Some(&bruno), json!({})).await.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.post(&caminho, Some(&ana), json!({})).await.status, StatusCode::NO_CONTENT);
+    assert_eq!(app.post(&caminho, Some(&ana), json!({})).await.status, StatusCode::NO_CONTENT);
+
+    let nao_lidas = notificacoes(&app, &ana, "unread=true").await;
+    assert_eq!(nao_lidas.len(), 2);
+    assert!(nao_lidas.iter().all(|notificacao| notificacao["id"] != ids[0]));
+
+    let pagina = notificacoes(&app, &ana, &format!("limit=1&before_id={}", ids[0])).await;
+    assert_eq!(pagina.len(), 1);
+    assert_eq!(pagina[0]["id"], ids[1]);
+
+    let resposta = app.post("/notifications/read-all", Some(&ana), json!({})).await;
+    assert_eq!(resposta.corpo, json!({ "updated": 2 }));
+    assert!(notificacoes(&app, &ana, "unread=true").await.is_empty());
+}
+
+#[tokio::test]
+async fn websocket_entrega_as_notificacoes_do_usuario() {
+    let app = AppTeste::nova().await;
+    let ana = app.usuario("ana").await;
+    let bruno = app.usuario("bruno").await;
+    let url = servir(&app).await;
+
+    let mut da_ana = conectar(&url, &ana).await.unwrap();
+    assert_eq!(proxima_mensagem(&mut da_ana).await, json!({ "type": "hello", "unread": 0 }));
+    let mut do_bruno = conectar(&url, &bruno).await.unwrap();
+    proxima_mensagem(&mut do_bruno).await;
+
+    login_de(&app, "ana@example.com", "Navegador Novo/1.0").await;
+
+    let mensagem = proxima_mensagem(&mut da_ana).await;
+    assert_eq!(mensagem["type"], "notification");
+    assert_eq!(mensagem["notification"]["kind"], "auth.new_login");
+    assert_eq!(mensagem["notification"]["data"]["user_agent"], "Navegador Novo/1.0");
+    assert!(tokio::time::timeout(Duration::from_millis(300), do_bruno.next()).await.is_err());
+
+    // Reconectando, o hello traz a contagem do que falta ler
+    drop(da_ana);
+    let mut da_ana = conectar(&url, &ana).await.unwrap();
+    assert_eq!(proxima_mensagem(&mut da_ana).await, json!({ "type": "hello", "unread": 1 }));
+}
+
+#[tokio::test]
+async fn websocket_entrega_notificacoes_maiores_que_o_limite_do_notify() {
+    let app = AppTeste::nova().await;
+    let ana = app.usuario("ana").await;
+    let url = servir(&app).await;
+    let mut socket = conectar(&url, &ana).await.unwrap();
+    proxima_mensagem(&mut socket).await;
+
+    // O payload do NOTIFY vai até 8000 bytes; a notificação inteira não caberia
+    let user_agent = format!("Navegador/{}", "x".repeat(9000));
+    login_de(&app, "ana@example.com", &user_agent).await;
+
+    let mensagem = proxima_mensagem(&mut socket).await;
+    assert_eq!(mensagem["notification"]["data"]["user_agent"], user_agent.as_str());
+}
+
+#[tokio::test]
+async fn websocket_exige_token_valido() {
+    let app = AppTeste::nova().await;
+    let url = servir(&app).await;
+
+    for token in ["", "nao-e-um-jwt"] {
+        let erro = conectar(&url, token).await.unwrap_err();
+        let tungstenite::Error::Http(resposta) = erro else {
+            panic!("esperava uma resposta HTTP: {erro}");
+        };
+        assert_eq!(resposta.status(), StatusCode::UNAUTHORIZED);
+    }
+}
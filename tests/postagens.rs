@@ -0,0 +1,161 @@
+mod comum;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use comum::{AppTeste, PREFIXO};
+use serde_json::json;
+
+#[tokio::test]
+async fn dono_cria_le_atualiza_e_deleta() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+
+    let id = app.criar_postagem(&token, "Primeiro post").await;
+
+    let lida = app.get(&format!("/posts/{id}"), None).await;
+    assert_eq!(lida.status, StatusCode::OK);
+    assert_eq!(lida.corpo["title"], "Primeiro post");
+
+    let atualizada = app
+        .put(&format!("/posts/{id}"), Some(&token), json!({ "title": "Editado", "content": "Novo conteúdo" }))
+        .await;
+    assert_eq!(atualizada.status, StatusCode::OK);
+    assert_eq!(atualizada.corpo["title"], "Editado");
+
+    // A leitura pública não pode continuar servindo a versão em cache
+    assert_eq!(app.get(&format!("/posts/{id}"), None).await.corpo["title"], "Editado");
+
+    let minhas = app.get("/posts/my", Some(&token)).await;
+    assert_eq!(minhas.corpo.as_array().unwrap().len(), 1);
+
+    assert_eq!(app.delete(&format!("/posts/{id}"), Some(&token)).await.status, StatusCode::NO_CONTENT);
+    assert_eq!(app.get(&format!("/posts/{id}"), None).await.status, StatusCode::NOT_FOUND);
+    assert!(app.get("/posts", None).await.corpo.as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn outro_usuario_nao_atualiza_post_alheio() {
+    let app = AppTeste::nova().await;
+    let dona = app.usuario("ana").await;
+    let intruso = app.usuario("bruno").await;
+    let id = app.criar_postagem(&dona, "Post da Ana").await;
+
+    let resposta = app
+        .put(&format!("/posts/{id}"), Some(&intruso), json!({ "title": "Invadido", "content": "Invadido" }))
+        .await;
+    assert_eq!(resposta.status, StatusCode::NOT_FOUND);
+
+    // Com If-Match o post também não é revelado a quem não é dono
+    let requisicao = Request::put(format!("{PREFIXO}/posts/{id}"))
+        .header("authorization", format!("Bearer {intruso}"))
+        .header("content-type", "application/json")
+        .header("if-match", "*")
+        .body(Body::from(json!({ "title": "Invadido", "content": "Invadido" }).to_string()))
+        .unwrap();
+    assert_eq!(app.enviar(requisicao).await.status, StatusCode::NOT_FOUND);
+
+    assert_eq!(app.get(&format!("/posts/{id}"), None).await.corpo["title"], "Post da Ana");
+}
+
+#[tokio::test]
+async fn outro_usuario_nao_deleta_post_alheio() {
+    let app = AppTeste::nova().await;
+    let dona = app.usuario("ana").await;
+    let intruso = app.usuario("bruno").await;
+    let id = app.criar_postagem(&dona, "Post da Ana").await;
+
+    assert_eq!(app.delete(&format!("/posts/{id}"), Some(&intruso)).await.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.get(&format!("/posts/{id}"), None).await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn post_inexistente() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+
+    assert_eq!(app.get("/posts/999", None).await.status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        app.put("/posts/999", Some(&token), json!({ "title": "X", "content": "Y" })).await.status,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(app.delete("/posts/999", Some(&token)).await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn post_invalido_e_rejeitado() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+
+    let resposta = app.post("/posts", Some(&token), json!({ "title": "", "content": "" })).await;
+    assert_eq!(resposta.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(resposta.corpo["fields"]["title"].is_array());
+}
+
+#[tokio::test]
+async fn edicao_com_versao_desatualizada_falha() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+    let id = app.criar_postagem(&token, "Original").await;
+
+    let etag = app.get(&format!("/posts/{id}"), None).await.cabecalhos["etag"].to_str().unwrap().to_string();
+
+    let edicao = |titulo: &str| {
+        Request::put(format!("{PREFIXO}/posts/{id}"))
+            .header("authorization", format!("Bearer {token}"))
+            .header("content-type", "application/json")
+            .header("if-match", &etag)
+            .body(Body::from(json!({ "title": titulo, "content": "Conteúdo" }).to_string()))
+            .unwrap()
+    };
+
+    assert_eq!(app.enviar(edicao("Primeira edição")).await.status, StatusCode::OK);
+    assert_eq!(app.enviar(edicao("Segunda edição")).await.status, StatusCode::PRECONDITION_FAILED);
+}
+
+#[tokio::test]
+async fn etag_muda_com_a_codificacao() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+    let id = app.criar_postagem(&token, "Comprimido").await;
+
+    let leitura = |codificacao: Option<&str>, if_none_match: Option<&str>| {
+        let mut requisicao = Request::get(format!("{PREFIXO}/posts/{id}"));
+        if let Some(codificacao) = codificacao {
+            requisicao = requisicao.header("accept-encoding", codificacao);
+        }
+        if let Some(etag) = if_none_match {
+            requisicao = requisicao.header("if-none-match", etag);
+        }
+        requisicao.body(Body::empty()).unwrap()
+    };
+
+    let identidade = app.enviar(leitura(None, None)).await;
+    let etag = identidade.cabecalhos["etag"].to_str().unwrap().to_string();
+    assert!(identidade.cabecalhos.get("content-encoding").is_none());
+
+    // A representação gzip tem outros bytes, então outro validador forte
+    let gzip = app.enviar(leitura(Some("gzip"), None)).await;
+    assert_eq!(gzip.cabecalhos["content-encoding"], "gzip");
+    let etag_gzip = gzip.cabecalhos["etag"].to_str().unwrap().to_string();
+    assert_eq!(etag_gzip, format!("{}-gzip\"", etag.trim_end_matches('"')));
+
+    // O 304 repete o validador que o cliente guardou
+    let revalidacao = app.enviar(leitura(Some("gzip"), Some(&etag_gzip))).await;
+    assert_eq!(revalidacao.status, StatusCode::NOT_MODIFIED);
+    assert_eq!(revalidacao.cabecalhos["etag"], etag_gzip.as_str());
+
+    // Qualquer codificação do mesmo JSON protege a edição
+    let edicao = Request::put(format!("{PREFIXO}/posts/{id}"))
+        .header("authorization", format!("Bearer {token}"))
+        .header("content-type", "application/json")
+        .header("if-match", &etag_gzip)
+        .body(Body::from(json!({ "title": "Editado", "content": "Conteúdo" }).to_string()))
+        .unwrap();
+    assert_eq!(app.enviar(edicao).await.status, StatusCode::OK);
+
+    let desatualizada = app.enviar(leitura(Some("gzip"), Some(&etag_gzip))).await;
+    assert_eq!(desatualizada.status, StatusCode::OK);
+    assert_ne!(desatualizada.cabecalhos["etag"], etag_gzip.as_str());
+}
@@ -0,0 +1,235 @@
+mod comum;
+
+use axum::http::{Method, StatusCode};
+use comum::{AppTeste, PREFIXO, SENHA_VALIDA};
+use http_body_util::BodyExt;
+use serde_json::{json, Value};
+use std::io::{Cursor, Read};
+use tower::ServiceExt;
+
+use hello_rust::{
+    auditoria::{acoes, FiltroEventos, NovoEvento},
+    privacidade,
+};
+
+async fn pedir_exclusao(app: &AppTeste, token: &str, senha: &str) -> comum::Resposta {
+    app.requisitar(
+        Method::DELETE,
+        &format!("{PREFIXO}/profile"),
+        Some(token),
+        Some(json!({ "password": senha })),
+    )
+    .await
+}
+
+fn ler(arquivo: &mut zip::ZipArchive<Cursor<Vec<u8>>>, nome: &str) -> String {
+    let mut conteudo = String::new();
+    arquivo.by_name(nome).unwrap().read_to_string(&mut conteudo).unwrap();
+    conteudo
+}
+
+#[tokio::test]
+async fn exportacao_traz_json_e_markdown() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+    app.criar_postagem(&token, "Primeiro post").await;
+    app.criar_postagem(&token, "Segundo: o retorno").await;
+    app.login("ana@example.com", "senha-errada").await;
+    let id_ana = app.get("/profile", Some(&token)).await.corpo["id"].as_i64().unwrap();
+    app.repositorios
+        .notificacoes
+        .criar(id_ana as i32, "auth.new_login", &json!({ "ip": "203.0.113.7" }))
+        .await
+        .unwrap();
+    let webhook = app
+        .post("/webhooks", Some(&token), json!({ "url": "https://hooks.example.com/ana", "events": ["post.created"] }))
+        .await;
+    assert_eq!(webhook.status, StatusCode::CREATED);
+    // Dados de outra conta não entram
+    let outro = app.usuario("bruno").await;
+    app.criar_postagem(&outro, "Do bruno").await;
+
+    let requisicao = axum::http::Request::get(format!("{PREFIXO}/profile/export"))
+        .header("authorization", format!("Bearer {token}"))
+        .body(axum::body::Body::empty())
+        .unwrap();
+    let resposta = app.roteador().oneshot(requisicao).await.unwrap();
+    assert_eq!(resposta.status(), StatusCode::OK);
+    assert_eq!(resposta.headers()["content-type"], "application/zip");
+    let bytes = resposta.into_body().collect().await.unwrap().to_bytes().to_vec();
+
+    let mut arquivo = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+    let perfil: Value = serde_json::from_str(&ler(&mut arquivo, "profile.json")).unwrap();
+    assert_eq!(perfil["username"], "ana");
+    assert_eq!(perfil["email"], "ana@example.com");
+
+    let postagens: Value = serde_json::from_str(&ler(&mut arquivo, "posts.json")).unwrap();
+    assert_eq!(postagens.as_array().unwrap().len(), 2);
+    let sessoes: Value = serde_json::from_str(&ler(&mut arquivo, "sessions.json")).unwrap();
+    assert_eq!(sessoes.as_array().unwrap().len(), 1);
+
+    let notificacoes: Value = serde_json::from_str(&ler(&mut arquivo, "notifications.json")).unwrap();
+    assert_eq!(notificacoes.as_array().unwrap().len(), 1);
+    assert_eq!(notificacoes[0]["data"]["ip"], "203.0.113.7");
+
+    let webhooks: Value = serde_json::from_str(&ler(&mut arquivo, "webhooks.json")).unwrap();
+    assert_eq!(webhooks.as_array().unwrap().len(), 1);
+    assert_eq!(webhooks[0]["url"], "https://hooks.example.com/ana");
+    assert!(webhooks[0].get("secret").is_none(), "{webhooks}");
+
+    let eventos: Value = serde_json::from_str(&ler(&mut arquivo, "audit.json")).unwrap();
+    let eventos = eventos.as_array().unwrap();
+    let acoes: Vec<&str> = eventos.iter().map(|evento| evento["action"].as_str().unwrap()).collect();
+    for acao in ["account.registered", "auth.login_succeeded", "auth.login_failed", "post.created", "webhook.created"] {
+        assert!(acoes.contains(&acao), "{acao}: {acoes:?}");
+    }
+    // A falha de login não tem autor, mas tem a conta como alvo
+    let alvo = json!(id_ana.to_string());
+    assert!(eventos
+        .iter()
+        .filter(|evento| evento["actor_id"].is_null())
+        .all(|evento| evento["target_id"] == alvo));
+    assert!(eventos.iter().all(|evento| evento["actor_id"].is_null() || evento["actor_id"] == id_ana));
+
+    assert!(ler(&mut arquivo, "profile.md").starts_with("# Dados de ana"));
+    let markdown: Vec<String> = arquivo
+        .file_names()
+        .filter(|nome| nome.starts_with("posts/"))
+        .map(str::to_string)
+        .collect();
+    assert_eq!(markdown.len(), 2);
+    assert!(markdown.iter().any(|nome| nome.ends_with("-segundo-o-retorno.md")), "{markdown:?}");
+}
+
+#[tokio::test]
+async fn exclusao_exige_a_senha() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+
+    let resposta = pedir_exclusao(&app, &token, "senha-errada").await;
+    assert_eq!(resposta.status, StatusCode::FORBIDDEN);
+
+    let perfil = app.get("/profile", Some(&token)).await.corpo;
+    assert!(perfil.get("deletion_scheduled_at").is_none());
+}
+
+#[tokio::test]
+async fn exclusao_agendada_pode_ser_cancelada() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+
+    let resposta = pedir_exclusao(&app, &token, SENHA_VALIDA).await;
+    assert_eq!(resposta.status, StatusCode::ACCEPTED);
+    let agendada = resposta.corpo["deletion_scheduled_at"].clone();
+    let data: chrono::DateTime<chrono::Utc> = serde_json::from_value(agendada.clone()).unwrap();
+    assert!(data > chrono::Utc::now() + chrono::Duration::days(29));
+
+    // Pedir de novo não adia a data
+    assert_eq!(pedir_exclusao(&app, &token, SENHA_VALIDA).await.corpo["deletion_scheduled_at"], agendada);
+    assert_eq!(app.get("/profile", Some(&token)).await.corpo["deletion_scheduled_at"], agendada);
+
+    assert_eq!(app.delete("/profile/deletion", Some(&token)).await.status, StatusCode::NO_CONTENT);
+    assert_eq!(app.delete("/profile/deletion", Some(&token)).await.status, StatusCode::NOT_FOUND);
+
+    // Cancelada, a conta não é anonimizada mesmo depois da data
+    let anonimizadas = privacidade::processar_exclusoes(&app.repositorios, data + chrono::Duration::days(1))
+        .await
+        .unwrap();
+    assert!(anonimizadas.is_empty());
+    assert_eq!(app.get("/profile", Some(&token)).await.corpo["username"], "ana");
+}
+
+#[tokio::test]
+async fn conta_e_anonimizada_ao_fim_da_carencia() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+    let id_postagem = app.criar_postagem(&token, "Fica publicado").await;
+    let id_usuario = app.get("/profile", Some(&token)).await.corpo["id"].as_i64().unwrap();
+    app.repositorios
+        .notificacoes
+        .criar(id_usuario as i32, "auth.new_login", &json!({ "ip": "203.0.113.7" }))
+        .await
+        .unwrap();
+    let webhook = app
+        .post("/webhooks", Some(&token), json!({ "url": "https://hooks.example.com/ana", "events": ["post.created"] }))
+        .await;
+    assert_eq!(webhook.status, StatusCode::CREATED);
+
+    pedir_exclusao(&app, &token, SENHA_VALIDA).await;
+
+    // Antes da data nada acontece
+    let agora = chrono::Utc::now();
+    assert!(privacidade::processar_exclusoes(&app.repositorios, agora).await.unwrap().is_empty());
+
+    let depois = agora + privacidade::carencia() + chrono::Duration::minutes(1);
+    let anonimizadas = privacidade::processar_exclusoes(&app.repositorios, depois).await.unwrap();
+    assert_eq!(anonimizadas, [id_usuario as i32]);
+
+    // Sessões, notificações e webhooks apagados, credenciais inúteis
+    let notificacoes = app.repositorios.notificacoes.listar(id_usuario as i32, false, None, 10).await.unwrap();
+    assert!(notificacoes.is_empty());
+    assert!(app.repositorios.webhooks.listar_do_usuario(id_usuario as i32).await.unwrap().is_empty());
+    assert_eq!(app.get("/profile", Some(&token)).await.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(app.login("ana@example.com", SENHA_VALIDA).await.status, StatusCode::UNAUTHORIZED);
+
+    // O post continua, com o autor anonimizado
+    let postagem = app.get(&format!("/posts/{id_postagem}"), None).await;
+    assert_eq!(postagem.status, StatusCode::OK);
+    assert_eq!(postagem.corpo["user_id"], id_usuario);
+    let usuarios = app.get("/users", None).await.corpo;
+    assert_eq!(usuarios[0]["username"], format!("usuario_removido_{id_usuario}"));
+
+    // O email e o nome de usuário ficam livres
+    assert_eq!(app.registrar("ana", "ana@example.com", SENHA_VALIDA).await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn conta_anonimizada_nao_faz_login() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+    let id_usuario = app.get("/profile", Some(&token)).await.corpo["id"].as_i64().unwrap();
+    pedir_exclusao(&app, &token, SENHA_VALIDA).await;
+    let depois = chrono::Utc::now() + privacidade::carencia() + chrono::Duration::minutes(1);
+    privacidade::processar_exclusoes(&app.repositorios, depois).await.unwrap();
+
+    // O email anonimizado existe, mas a senha '!' não é um hash: é recusado como desconhecido
+    let email = format!("removido-{id_usuario}@anonimo.invalid");
+    for senha in [SENHA_VALIDA, "!"] {
+        assert_eq!(app.login(&email, senha).await.status, StatusCode::UNAUTHORIZED);
+    }
+}
+
+#[tokio::test]
+async fn anonimizacao_apaga_os_dados_pessoais_da_auditoria() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+    app.usuario("bruno").await;
+    app.login("ana@example.com", "senha-errada").await;
+    let id_ana = app.repositorios.usuarios.buscar_por_nome_usuario("ana").await.unwrap().unwrap().id;
+    // Falha de login gravada antes do hash, com o email em claro
+    let antiga = NovoEvento::new(acoes::LOGIN_FALHA).diff(json!({ "email": "ana@example.com", "reason": "unknown_email" }));
+    app.repositorios.auditoria.registrar(&antiga).await.unwrap();
+
+    pedir_exclusao(&app, &token, SENHA_VALIDA).await;
+    let depois = chrono::Utc::now() + privacidade::carencia() + chrono::Duration::minutes(1);
+    assert_eq!(privacidade::processar_exclusoes(&app.repositorios, depois).await.unwrap(), [id_ana]);
+
+    let eventos = app.repositorios.auditoria.listar(&FiltroEventos::default(), 100).await.unwrap();
+    let (da_ana, outros): (Vec<_>, Vec<_>) = eventos.iter().partition(|evento| {
+        evento.id_ator == Some(id_ana)
+            || evento.id_alvo == Some(id_ana.to_string())
+            || evento.acao == acoes::LOGIN_FALHA && evento.id_alvo.is_none()
+    });
+    assert!(da_ana.len() >= 4, "{da_ana:?}");
+    for evento in da_ana {
+        assert!(evento.ip.is_none() && evento.user_agent.is_none(), "{evento:?}");
+        for campo in ["email", "email_hash", "username"] {
+            assert!(evento.diff.0.get(campo).is_none(), "{evento:?}");
+        }
+    }
+    // O resto do evento fica como estava, e os de outras contas não mudam
+    let falha = eventos.iter().find(|evento| evento.acao == acoes::LOGIN_FALHA && evento.id_alvo.is_some()).unwrap();
+    assert_eq!(falha.diff.0, json!({ "reason": "wrong_password" }));
+    assert!(!outros.is_empty());
+    assert!(outros.iter().all(|evento| evento.ip.as_deref() == Some("127.0.0.1")), "{outros:?}");
+}
@@ -0,0 +1,138 @@
+mod comum;
+
+use axum::http::StatusCode;
+use comum::{AppTeste, SENHA_VALIDA};
+use hello_rust::seed::{self, Fixture, Gerador, Idioma};
+
+const CREDENCIAIS: [(&str, &str, &str); 3] = [
+    ("admin", "admin@example.com", "Admin#Blog2024"),
+    ("joao", "joao@example.com", "Joao#Rust2024"),
+    ("maria", "maria@example.com", "Maria#Postgres2024"),
+];
+
+#[tokio::test]
+async fn seed_cria_usuarios_e_posts() {
+    let app = AppTeste::com_seed().await;
+
+    let usuarios = app.get("/users", None).await.corpo;
+    let mut nomes: Vec<&str> = usuarios
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|usuario| usuario["username"].as_str().unwrap())
+        .collect();
+    nomes.sort();
+    assert_eq!(nomes, ["admin", "joao", "maria"]);
+
+    let postagens = app.get("/posts", None).await.corpo;
+    assert_eq!(postagens.as_array().unwrap().len(), 6);
+}
+
+#[tokio::test]
+async fn credenciais_do_seed_funcionam() {
+    let app = AppTeste::com_seed().await;
+
+    for (nome_usuario, email, senha) in CREDENCIAIS {
+        let token = app.token(email, senha).await;
+
+        let perfil = app.get("/profile", Some(&token)).await;
+        assert_eq!(perfil.corpo["username"], nome_usuario);
+
+        // Cada usuário do seed tem dois posts
+        let minhas = app.get("/posts/my", Some(&token)).await;
+        assert_eq!(minhas.corpo.as_array().unwrap().len(), 2, "posts de {nome_usuario}");
+    }
+}
+
+#[tokio::test]
+async fn seed_nao_duplica_dados() {
+    let app = AppTeste::com_seed().await;
+
+    let resumo = seed::aplicar(&app.repositorios, Fixture::demo()).await.unwrap();
+    assert_eq!((resumo.usuarios_criados, resumo.usuarios_existentes), (0, 3));
+    assert_eq!((resumo.postagens_criadas, resumo.postagens_existentes), (0, 6));
+
+    assert_eq!(app.get("/users", None).await.corpo.as_array().unwrap().len(), 3);
+    assert_eq!(app.get("/posts", None).await.corpo.as_array().unwrap().len(), 6);
+}
+
+#[tokio::test]
+async fn fixture_atualiza_conteudo_alterado() {
+    let app = AppTeste::com_seed().await;
+
+    let mut fixture = Fixture::demo();
+    fixture.postagens.truncate(1);
+    fixture.postagens[0].conteudo = "Conteúdo revisado".to_string();
+
+    let resumo = seed::aplicar(&app.repositorios, fixture).await.unwrap();
+    assert_eq!(resumo.postagens_atualizadas, 1);
+
+    let postagens = app.get("/posts", None).await.corpo;
+    let postagens = postagens.as_array().unwrap();
+    assert_eq!(postagens.len(), 6);
+    assert!(postagens.iter().any(|postagem| postagem["content"] == "Conteúdo revisado"));
+}
+
+#[tokio::test]
+async fn fixture_json_do_diretorio() {
+    let app = AppTeste::com_seed().await;
+
+    // Os autores da fixture podem ser usuários já cadastrados
+    let mut fixture = Fixture::carregar("fixtures/autores.json").unwrap();
+    fixture.postagens[0].autor = "joao".to_string();
+    let resumo = seed::aplicar(&app.repositorios, fixture).await.unwrap();
+    assert_eq!((resumo.usuarios_criados, resumo.postagens_criadas), (2, 3));
+
+    let joao = app.token("joao@example.com", "Joao#Rust2024").await;
+    assert_eq!(app.get("/posts/my", Some(&joao)).await.corpo.as_array().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn fixture_invalida_nao_altera_o_banco() {
+    let app = AppTeste::nova().await;
+
+    let mut fixture = Fixture::demo();
+    fixture.usuarios[1].email = "nao-e-email".to_string();
+    fixture.postagens[0].autor = "ninguem".to_string();
+
+    let erro = seed::aplicar(&app.repositorios, fixture).await.unwrap_err();
+    assert!(erro.to_string().contains("users[1].email"), "{erro}");
+    assert_eq!(app.get("/users", None).await.corpo.as_array().unwrap().len(), 0);
+}
+
+#[tokio::test]
+async fn geracao_em_massa_e_deterministica() {
+    let gerar = |semente| Gerador::new(semente, Idioma::Pt).gerar(20, 60, SENHA_VALIDA);
+
+    let (primeira, segunda) = (gerar(7), gerar(7));
+    let titulos = |fixture: &Fixture| fixture.postagens.iter().map(|postagem| postagem.titulo.clone()).collect::<Vec<_>>();
+    assert_eq!(titulos(&primeira), titulos(&segunda));
+    assert_ne!(titulos(&primeira), titulos(&gerar(8)));
+
+    let app = AppTeste::nova().await;
+    let resumo = seed::aplicar(&app.repositorios, primeira).await.unwrap();
+    assert_eq!((resumo.usuarios_criados, resumo.postagens_criadas), (20, 60));
+
+    // Reaplicar a mesma semente não cria nada
+    let resumo = seed::aplicar(&app.repositorios, segunda).await.unwrap();
+    assert_eq!((resumo.usuarios_criados, resumo.postagens_criadas), (0, 0));
+
+    let usuarios = app.get("/users", None).await.corpo;
+    let email = usuarios[0]["email"].as_str().unwrap();
+    app.token(email, SENHA_VALIDA).await;
+}
+
+#[tokio::test]
+async fn usuario_do_seed_nao_edita_post_de_outro() {
+    let app = AppTeste::com_seed().await;
+    let joao = app.token("joao@example.com", "Joao#Rust2024").await;
+    let maria = app.token("maria@example.com", "Maria#Postgres2024").await;
+
+    let id = app.get("/posts/my", Some(&maria)).await.corpo[0]["id"].as_i64().unwrap();
+
+    let resposta = app
+        .put(&format!("/posts/{id}"), Some(&joao), serde_json::json!({ "title": "X", "content": "Y" }))
+        .await;
+    assert_eq!(resposta.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.delete(&format!("/posts/{id}"), Some(&joao)).await.status, StatusCode::NOT_FOUND);
+}
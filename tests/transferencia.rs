@@ -0,0 +1,200 @@
+mod comum;
+
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode},
+};
+use comum::{AppTeste, PREFIXO, SENHA_VALIDA};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+
+use hello_rust::{
+    configuracao::Configuracao,
+    transferencia::{self, Cabecalho, ErroTransferencia},
+};
+
+async fn exportar(app: &AppTeste) -> Vec<u8> {
+    let cabecalho = Cabecalho {
+        format: transferencia::FORMATO.to_string(),
+        format_version: transferencia::VERSAO_FORMATO,
+        schema_version: 4,
+        source: "teste".to_string(),
+        exported_at: chrono::Utc::now(),
+    };
+    let mut arquivo = Vec::new();
+    transferencia::exportar(&app.repositorios, cabecalho, &mut arquivo).await.unwrap();
+    arquivo
+}
+
+async fn titulos(app: &AppTeste) -> Vec<String> {
+    let postagens = app.get("/posts", None).await.corpo;
+    let mut titulos: Vec<String> = postagens
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|postagem| postagem["title"].as_str().unwrap().to_string())
+        .collect();
+    titulos.sort();
+    titulos
+}
+
+#[tokio::test]
+async fn importacao_em_banco_vazio_preserva_ids() {
+    let origem = AppTeste::com_seed().await;
+    let arquivo = exportar(&origem).await;
+
+    let destino = AppTeste::nova().await;
+    let resumo = transferencia::importar(&destino.repositorios, arquivo.as_slice()).await.unwrap();
+    assert_eq!((resumo.users.imported, resumo.posts.imported), (3, 6));
+    assert_eq!(resumo.remapped_ids, 0);
+
+    let original = origem.repositorios.usuarios.buscar_por_email("maria@example.com").await.unwrap().unwrap();
+    let importado = destino.repositorios.usuarios.buscar_por_email("maria@example.com").await.unwrap().unwrap();
+    assert_eq!(importado.id, original.id);
+    assert_eq!(importado.criado_em.timestamp_micros(), original.criado_em.timestamp_micros());
+    assert_eq!(titulos(&destino).await, titulos(&origem).await);
+
+    // As senhas vêm junto: as mesmas credenciais funcionam no destino
+    destino.token("maria@example.com", "Maria#Postgres2024").await;
+}
+
+#[tokio::test]
+async fn importacao_em_banco_com_dados_remapeia_ids() {
+    let origem = AppTeste::com_seed().await;
+    let arquivo = exportar(&origem).await;
+
+    // O id 1 já pertence a outra conta, com posts próprios
+    let destino = AppTeste::nova().await;
+    let token = destino.usuario("bruno").await;
+    destino.criar_postagem(&token, "Post do destino").await;
+
+    let resumo = transferencia::importar(&destino.repositorios, arquivo.as_slice()).await.unwrap();
+    assert_eq!((resumo.users.imported, resumo.posts.imported), (3, 6));
+    assert!(resumo.remapped_ids > 0);
+
+    // Os posts continuam com os seus autores
+    let maria = destino.token("maria@example.com", "Maria#Postgres2024").await;
+    let minhas = destino.get("/posts/my", Some(&maria)).await.corpo;
+    assert_eq!(minhas.as_array().unwrap().len(), 2);
+    assert_eq!(titulos(&destino).await.len(), 7);
+}
+
+#[tokio::test]
+async fn importar_de_novo_nao_duplica() {
+    let origem = AppTeste::com_seed().await;
+    let arquivo = exportar(&origem).await;
+
+    let resumo = transferencia::importar(&origem.repositorios, arquivo.as_slice()).await.unwrap();
+    assert_eq!((resumo.users.imported, resumo.users.existing), (0, 3));
+    assert_eq!((resumo.posts.imported, resumo.posts.existing), (0, 6));
+    assert_eq!(titulos(&origem).await.len(), 6);
+}
+
+#[tokio::test]
+async fn arquivo_alterado_e_recusado() {
+    let origem = AppTeste::com_seed().await;
+    let arquivo = String::from_utf8(exportar(&origem).await).unwrap();
+    let alterado = arquivo.replacen("Dicas de Rust", "Dicas de Go", 1);
+    assert_ne!(alterado, arquivo);
+
+    let destino = AppTeste::nova().await;
+    let erro = transferencia::importar(&destino.repositorios, alterado.as_bytes()).await.unwrap_err();
+    assert!(matches!(erro, ErroTransferencia::Integridade(_)), "{erro}");
+
+    // Sem o manifesto, o arquivo está incompleto
+    let truncado: String = arquivo
+        .lines()
+        .filter(|linha| !linha.contains("\"manifest\""))
+        .map(|linha| format!("{linha}\n"))
+        .collect();
+    let erro = transferencia::importar(&destino.repositorios, truncado.as_bytes()).await.unwrap_err();
+    assert!(matches!(erro, ErroTransferencia::Integridade(_)), "{erro}");
+
+    // Nada foi gravado
+    assert!(titulos(&destino).await.is_empty());
+}
+
+#[tokio::test]
+async fn nome_de_usuario_em_uso_e_conflito() {
+    let origem = AppTeste::com_seed().await;
+    let arquivo = exportar(&origem).await;
+
+    let destino = AppTeste::nova().await;
+    destino.registrar("maria", "outra.maria@example.com", SENHA_VALIDA).await;
+
+    let erro = transferencia::importar(&destino.repositorios, arquivo.as_slice()).await.unwrap_err();
+    assert!(matches!(erro, ErroTransferencia::Conflito(_)), "{erro}");
+}
+
+#[tokio::test]
+async fn api_de_exportacao_e_importacao() {
+    let origem = AppTeste::com_seed().await;
+
+    // Só administradores
+    let joao = origem.token("joao@example.com", "Joao#Rust2024").await;
+    assert_eq!(origem.get("/admin/export", Some(&joao)).await.status, StatusCode::FORBIDDEN);
+    assert_eq!(origem.get("/admin/export", None).await.status, StatusCode::UNAUTHORIZED);
+
+    let admin = origem.token("admin@example.com", "Admin#Blog2024").await;
+    let resposta = origem
+        .roteador()
+        .oneshot(
+            Request::get(format!("{PREFIXO}/admin/export"))
+                .header("authorization", format!("Bearer {admin}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resposta.status(), StatusCode::OK);
+    assert_eq!(resposta.headers()["content-type"], "application/x-ndjson");
+    let arquivo = resposta.into_body().collect().await.unwrap().to_bytes();
+
+    let destino = AppTeste::nova().await;
+    let token = destino.usuario("chefe").await;
+    let id = destino.repositorios.usuarios.buscar_por_nome_usuario("chefe").await.unwrap().unwrap().id;
+    destino.repositorios.usuarios.definir_admin(id, true).await.unwrap();
+
+    let importar = |corpo: Body| {
+        Request::builder()
+            .method(Method::POST)
+            .uri(format!("{PREFIXO}/admin/import"))
+            .header("authorization", format!("Bearer {token}"))
+            .header("content-type", "application/x-ndjson")
+            .body(corpo)
+            .unwrap()
+    };
+
+    let resposta = destino.enviar(importar(Body::from(arquivo.clone()))).await;
+    assert_eq!(resposta.status, StatusCode::OK);
+    assert_eq!(resposta.corpo["users"]["imported"], 3);
+    assert_eq!(resposta.corpo["posts"]["imported"], 6);
+    assert_eq!(titulos(&destino).await, titulos(&origem).await);
+
+    let resposta = destino.enviar(importar(Body::from("não é json\n"))).await;
+    assert_eq!(resposta.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn arquivo_acima_do_limite_configurado_e_recusado() {
+    let app = AppTeste::com_construtor(|construtor| {
+        construtor.configuracao(Configuracao { limite_importacao: 16, ..Default::default() })
+    })
+    .await;
+    let token = app.usuario("chefe").await;
+    let id = app.repositorios.usuarios.buscar_por_nome_usuario("chefe").await.unwrap().unwrap().id;
+    app.repositorios.usuarios.definir_admin(id, true).await.unwrap();
+
+    let resposta = app
+        .enviar(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("{PREFIXO}/admin/import"))
+                .header("authorization", format!("Bearer {token}"))
+                .header("content-type", "application/x-ndjson")
+                .body(Body::from("x".repeat(17)))
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(resposta.status, StatusCode::PAYLOAD_TOO_LARGE);
+}
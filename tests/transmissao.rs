@@ -0,0 +1,141 @@
+mod comum;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use comum::{AppTeste, PREFIXO};
+use http_body_util::BodyExt;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tower::ServiceExt;
+
+/// Um feed aberto, lido evento a evento.
+struct Feed {
+    corpo: Body,
+    pendente: String,
+}
+
+#[derive(Debug)]
+struct EventoRecebido {
+    id: Option<u64>,
+    nome: String,
+    dados: Value,
+}
+
+impl Feed {
+    async fn abrir(app: &AppTeste, ultimo_id: Option<u64>) -> Self {
+        let mut requisicao = Request::get(format!("{PREFIXO}/posts/stream"));
+        if let Some(ultimo_id) = ultimo_id {
+            requisicao = requisicao.header("last-event-id", ultimo_id.to_string());
+        }
+        let resposta = app.roteador().oneshot(requisicao.body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(resposta.status(), StatusCode::OK);
+        assert_eq!(resposta.headers()["content-type"], "text/event-stream");
+
+        Feed { corpo: resposta.into_body(), pendente: String::new() }
+    }
+
+    async fn proximo(&mut self) -> EventoRecebido {
+        loop {
+            if let Some(fim) = self.pendente.find("\n\n") {
+                let bloco: String = self.pendente.drain(..fim + 2).collect();
+                // Comentários (o heartbeat) não são eventos
+                if bloco.lines().all(|linha| linha.starts_with(':')) {
+                    continue;
+                }
+
+                let campo = |nome: &str| {
+                    bloco
+                        .lines()
+                        .find_map(|linha| linha.strip_prefix(nome)?.strip_prefix(':'))
+                        .map(|valor| valor.trim_start().to_string())
+                };
+                return EventoRecebido {
+                    id: campo("id").map(|id| id.parse().unwrap()),
+                    nome: campo("event").unwrap(),
+                    dados: serde_json::from_str(&campo("data").unwrap()).unwrap(),
+                };
+            }
+
+            let quadro = tokio::time::timeout(Duration::from_secs(5), self.corpo.frame())
+                .await
+                .expect("nenhum evento em 5 segundos")
+                .expect("feed encerrado")
+                .unwrap();
+            if let Ok(dados) = quadro.into_data() {
+                self.pendente.push_str(std::str::from_utf8(&dados).unwrap());
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn feed_recebe_criacao_edicao_e_exclusao() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+    let mut feed = Feed::abrir(&app, None).await;
+
+    let id = app.criar_postagem(&token, "Ao vivo").await;
+    app.put(&format!("/posts/{id}"), Some(&token), json!({ "title": "Ao vivo (editado)", "content": "Novo" }))
+        .await;
+    app.delete(&format!("/posts/{id}"), Some(&token)).await;
+
+    let criado = feed.proximo().await;
+    assert_eq!(criado.nome, "post.created");
+    assert_eq!(criado.dados["id"], id);
+    assert_eq!(criado.dados["title"], "Ao vivo");
+
+    let editado = feed.proximo().await;
+    assert_eq!(editado.nome, "post.updated");
+    assert_eq!(editado.dados["content"], "Novo");
+    assert!(editado.id > criado.id);
+
+    let apagado = feed.proximo().await;
+    assert_eq!(apagado.nome, "post.deleted");
+    assert_eq!(apagado.dados, json!({ "id": id }));
+}
+
+#[tokio::test]
+async fn reconexao_com_last_event_id_reenvia_o_que_faltou() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+    let mut feed = Feed::abrir(&app, None).await;
+
+    app.criar_postagem(&token, "Primeiro").await;
+    let primeiro = feed.proximo().await;
+    drop(feed);
+
+    // Publicados enquanto o cliente estava desconectado
+    app.criar_postagem(&token, "Segundo").await;
+    app.criar_postagem(&token, "Terceiro").await;
+
+    let mut feed = Feed::abrir(&app, primeiro.id).await;
+    assert_eq!(feed.proximo().await.dados["title"], "Segundo");
+    assert_eq!(feed.proximo().await.dados["title"], "Terceiro");
+
+    // Depois do histórico, os eventos novos seguem sem repetição
+    app.criar_postagem(&token, "Quarto").await;
+    assert_eq!(feed.proximo().await.dados["title"], "Quarto");
+
+    // Um id além de qualquer evento (u64::MAX) não estoura: só vêm os novos
+    let mut feed = Feed::abrir(&app, Some(u64::MAX)).await;
+    app.criar_postagem(&token, "Quinto").await;
+    assert_eq!(feed.proximo().await.dados["title"], "Quinto");
+}
+
+#[tokio::test]
+async fn alteracoes_rejeitadas_nao_geram_eventos() {
+    let app = AppTeste::nova().await;
+    let ana = app.usuario("ana").await;
+    let bruno = app.usuario("bruno").await;
+    let id = app.criar_postagem(&ana, "Da Ana").await;
+    let mut feed = Feed::abrir(&app, None).await;
+
+    assert_eq!(app.delete(&format!("/posts/{id}"), Some(&bruno)).await.status, StatusCode::NOT_FOUND);
+    app.criar_postagem(&bruno, "Do Bruno").await;
+
+    let evento = feed.proximo().await;
+    assert_eq!(evento.nome, "post.created");
+    assert_eq!(evento.dados["title"], "Do Bruno");
+}
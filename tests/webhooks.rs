@@ -0,0 +1,358 @@
+mod comum;
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Router,
+};
+use chrono::{Duration, Utc};
+use comum::AppTeste;
+use hello_rust::{encerramento::EmAndamento, webhooks};
+use serde_json::{json, Value};
+use std::sync::{
+    atomic::{AtomicU16, Ordering},
+    Arc, Mutex,
+};
+
+struct Recebida {
+    cabecalhos: HeaderMap,
+    corpo: String,
+}
+
+/// Endpoint de webhook de verdade numa porta local, que guarda o que recebe
+/// e responde com o status configurado.
+#[derive(Clone)]
+struct Recebedor {
+    url: String,
+    recebidas: Arc<Mutex<Vec<Recebida>>>,
+    status: Arc<AtomicU16>,
+}
+
+impl Recebedor {
+    async fn novo() -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let recebedor = Recebedor {
+            url: format!("http://{}/hook", listener.local_addr().unwrap()),
+            recebidas: Arc::new(Mutex::new(Vec::new())),
+            status: Arc::new(AtomicU16::new(200)),
+        };
+
+        let roteador = Router::new().route("/hook", post(receber)).with_state(recebedor.clone());
+        tokio::spawn(async move { axum::serve(listener, roteador).await.unwrap() });
+        recebedor
+    }
+
+    fn responder(&self, status: u16) {
+        self.status.store(status, Ordering::SeqCst);
+    }
+
+    fn total(&self) -> usize {
+        self.recebidas.lock().unwrap().len()
+    }
+}
+
+async fn receber(State(recebedor): State<Recebedor>, cabecalhos: HeaderMap, corpo: String) -> StatusCode {
+    recebedor.recebidas.lock().unwrap().push(Recebida { cabecalhos, corpo });
+    StatusCode::from_u16(recebedor.status.load(Ordering::SeqCst)).unwrap()
+}
+
+/// Usuário administrador: só eles cadastram destinos locais, como o do `Recebedor`.
+async fn admin(app: &AppTeste, nome_usuario: &str) -> String {
+    let token = app.usuario(nome_usuario).await;
+    let id = app.get("/profile", Some(&token)).await.corpo["id"].as_i64().unwrap();
+    app.repositorios.usuarios.definir_admin(id as i32, true).await.unwrap();
+    token
+}
+
+async fn cadastrar(app: &AppTeste, token: &str, url: &str, eventos: Value) -> Value {
+    let resposta = app.post("/webhooks", Some(token), json!({ "url": url, "events": eventos })).await;
+    assert_eq!(resposta.status, StatusCode::CREATED, "{}", resposta.corpo);
+    resposta.corpo
+}
+
+async fn entregas(app: &AppTeste, token: &str, id_webhook: i64) -> Vec<Value> {
+    let resposta = app.get(&format!("/webhooks/{id_webhook}/deliveries"), Some(token)).await;
+    assert_eq!(resposta.status, StatusCode::OK, "{}", resposta.corpo);
+    resposta.corpo.as_array().unwrap().clone()
+}
+
+/// Reserva as entregas vencidas em `agora` e espera os envios terminarem.
+async fn processar(app: &AppTeste, agora: chrono::DateTime<Utc>) -> usize {
+    let mut em_envio = EmAndamento::default();
+    let total = webhooks::processar_entregas(&app.repositorios, &webhooks::Clientes::new(), agora, &mut em_envio)
+        .await
+        .unwrap();
+    while em_envio.proxima().await.is_some() {}
+    total
+}
+
+#[tokio::test]
+async fn eventos_assinados_sao_entregues_com_assinatura() {
+    let app = AppTeste::nova().await;
+    let token = admin(&app, "ana").await;
+    let recebedor = Recebedor::novo().await;
+
+    let webhook = cadastrar(&app, &token, &recebedor.url, json!(["post.created", "post.deleted"])).await;
+    let segredo = webhook["secret"].as_str().unwrap().to_string();
+    assert!(segredo.starts_with("whsec_"));
+    let id_webhook = webhook["id"].as_i64().unwrap();
+
+    // O segredo só aparece na criação
+    let listados = app.get("/webhooks", Some(&token)).await.corpo;
+    assert_eq!(listados[0]["events"], json!(["post.created", "post.deleted"]));
+    assert!(listados[0].get("secret").is_none());
+
+    let id_postagem = app.criar_postagem(&token, "Olá, webhooks").await;
+    // post.updated não foi assinado
+    app.put(&format!("/posts/{id_postagem}"), Some(&token), json!({ "title": "Editado", "content": "Texto" }))
+        .await;
+
+    let pendentes = entregas(&app, &token, id_webhook).await;
+    assert_eq!(pendentes.len(), 1);
+    assert_eq!(pendentes[0]["status"], "pending");
+
+    assert_eq!(processar(&app, Utc::now()).await, 1);
+    assert_eq!(recebedor.total(), 1);
+    {
+        let recebidas = recebedor.recebidas.lock().unwrap();
+        let Recebida { cabecalhos, corpo } = &recebidas[0];
+        let timestamp: i64 = cabecalhos["x-webhook-timestamp"].to_str().unwrap().parse().unwrap();
+        assert!((Utc::now().timestamp() - timestamp).abs() < 60);
+        assert_eq!(
+            cabecalhos["x-webhook-signature"].to_str().unwrap(),
+            webhooks::assinar(&segredo, timestamp, corpo.as_bytes())
+        );
+        assert_eq!(cabecalhos["x-webhook-event"], "post.created");
+
+        let corpo: Value = serde_json::from_str(corpo).unwrap();
+        assert_eq!(cabecalhos["x-webhook-id"].to_str().unwrap(), corpo["id"]);
+        assert_eq!(corpo["event"], "post.created");
+        assert_eq!(corpo["data"]["id"], id_postagem);
+        assert_eq!(corpo["data"]["title"], "Olá, webhooks");
+    }
+
+    let id_entrega = pendentes[0]["id"].as_i64().unwrap();
+    let entrega = app
+        .get(&format!("/webhooks/{id_webhook}/deliveries/{id_entrega}"), Some(&token))
+        .await
+        .corpo;
+    assert_eq!(entrega["status"], "succeeded");
+    assert_eq!(entrega["last_status_code"], 200);
+    assert!(entrega["delivered_at"].is_string());
+    assert_eq!(entrega["payload"]["data"]["id"], id_postagem);
+    assert_eq!(entrega["attempt_log"].as_array().unwrap().len(), 1);
+
+    // Nada mais a enviar
+    assert_eq!(processar(&app, Utc::now()).await, 0);
+}
+
+#[tokio::test]
+async fn falhas_sao_tentadas_de_novo_e_podem_ser_reenviadas() {
+    let app = AppTeste::nova().await;
+    let token = admin(&app, "ana").await;
+    let recebedor = Recebedor::novo().await;
+    let id_webhook = cadastrar(&app, &token, &recebedor.url, json!(["post.created"])).await["id"]
+        .as_i64()
+        .unwrap();
+    app.criar_postagem(&token, "Primeiro").await;
+
+    recebedor.responder(500);
+    let agora = Utc::now();
+    processar(&app, agora).await;
+
+    let entrega = entregas(&app, &token, id_webhook).await.remove(0);
+    assert_eq!(entrega["status"], "pending");
+    assert_eq!(entrega["attempts"], 1);
+    assert_eq!(entrega["last_status_code"], 500);
+    assert_eq!(entrega["last_error"], "HTTP 500 Internal Server Error");
+
+    // A próxima tentativa espera o backoff
+    assert_eq!(processar(&app, agora + Duration::seconds(5)).await, 0);
+    recebedor.responder(204);
+    assert_eq!(processar(&app, agora + webhooks::espera(1) + Duration::seconds(1)).await, 1);
+
+    let caminho = format!("/webhooks/{id_webhook}/deliveries/{}", entrega["id"]);
+    let entrega = app.get(&caminho, Some(&token)).await.corpo;
+    assert_eq!(entrega["status"], "succeeded");
+    assert_eq!(entrega["attempts"], 2);
+    let historico: Vec<&Value> = entrega["attempt_log"].as_array().unwrap().iter().collect();
+    assert_eq!(historico.len(), 2);
+    assert_eq!(historico[0]["status_code"], 500);
+    assert_eq!(historico[1]["status_code"], 204);
+
+    // O reenvio é uma entrega nova, com o mesmo evento
+    let reenvio = app.post(&format!("{caminho}/redeliver"), Some(&token), json!({})).await;
+    assert_eq!(reenvio.status, StatusCode::ACCEPTED);
+    assert_ne!(reenvio.corpo["id"], entrega["id"]);
+    assert_eq!(reenvio.corpo["event_id"], entrega["event_id"]);
+    assert_eq!(reenvio.corpo["status"], "pending");
+
+    processar(&app, Utc::now()).await;
+    let recebidas = recebedor.recebidas.lock().unwrap();
+    assert_eq!(recebidas.len(), 3);
+    assert_eq!(recebidas[2].cabecalhos["x-webhook-id"], recebidas[0].cabecalhos["x-webhook-id"]);
+}
+
+#[tokio::test]
+async fn entrega_falha_ao_esgotar_as_tentativas() {
+    let app = AppTeste::nova().await;
+    let token = admin(&app, "ana").await;
+    let recebedor = Recebedor::novo().await;
+    recebedor.responder(410);
+    let id_webhook = cadastrar(&app, &token, &recebedor.url, json!(["post.created"])).await["id"]
+        .as_i64()
+        .unwrap();
+    app.criar_postagem(&token, "Ninguém recebe").await;
+
+    let mut agora = Utc::now();
+    for _ in 0..webhooks::MAXIMO_TENTATIVAS {
+        assert_eq!(processar(&app, agora).await, 1);
+        agora += Duration::hours(2);
+    }
+    assert_eq!(processar(&app, agora).await, 0);
+
+    let entrega = entregas(&app, &token, id_webhook).await.remove(0);
+    assert_eq!(entrega["status"], "failed");
+    assert_eq!(entrega["attempts"], webhooks::MAXIMO_TENTATIVAS);
+    assert!(entrega["next_attempt_at"].is_null());
+    assert_eq!(recebedor.total(), webhooks::MAXIMO_TENTATIVAS as usize);
+}
+
+#[tokio::test]
+async fn cadastro_respeita_dono_e_permissoes() {
+    let app = AppTeste::nova().await;
+    let ana = app.usuario("ana").await;
+    let bruno = app.usuario("bruno").await;
+
+    for (corpo, campo) in [
+        (json!({ "url": "http://localhost:9000/hook", "events": ["post.created"] }), "url"),
+        (json!({ "url": "http://10.0.0.5/hook", "events": ["post.created"] }), "url"),
+        (json!({ "url": "ftp://hooks.example.com", "events": ["post.created"] }), "url"),
+        (json!({ "url": "https://hooks.example.com", "events": [] }), "events"),
+        (json!({ "url": "https://hooks.example.com", "events": ["post.liked"] }), "events"),
+        // Traz o email de quem se cadastra
+        (json!({ "url": "https://hooks.example.com", "events": ["user.registered"] }), "events"),
+    ] {
+        let resposta = app.post("/webhooks", Some(&ana), corpo.clone()).await;
+        assert_eq!(resposta.status, StatusCode::UNPROCESSABLE_ENTITY, "{corpo}");
+        assert!(resposta.corpo["fields"].get(campo).is_some(), "{corpo}: {}", resposta.corpo);
+    }
+
+    let webhook = cadastrar(&app, &ana, "https://hooks.example.com/blog", json!(["post.created"])).await;
+    let id_webhook = webhook["id"].as_i64().unwrap();
+
+    // De outro usuário é como se não existisse
+    assert!(app.get("/webhooks", Some(&bruno)).await.corpo.as_array().unwrap().is_empty());
+    let caminho = format!("/webhooks/{id_webhook}");
+    assert_eq!(app.get(&format!("{caminho}/deliveries"), Some(&bruno)).await.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.delete(&caminho, Some(&bruno)).await.status, StatusCode::NOT_FOUND);
+
+    // Só administradores recebem user.registered
+    let admin = admin(&app, "carla").await;
+    let do_admin = cadastrar(&app, &admin, "https://hooks.example.com/admin", json!(["user.registered"])).await;
+    app.usuario("davi").await;
+    let registros = entregas(&app, &admin, do_admin["id"].as_i64().unwrap()).await;
+    assert_eq!(registros.len(), 1);
+    let entrega = app
+        .get(&format!("/webhooks/{}/deliveries/{}", do_admin["id"], registros[0]["id"]), Some(&admin))
+        .await
+        .corpo;
+    assert_eq!(entrega["payload"]["data"]["username"], "davi");
+
+    assert_eq!(app.delete(&caminho, Some(&ana)).await.status, StatusCode::NO_CONTENT);
+    assert!(app.get("/webhooks", Some(&ana)).await.corpo.as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn encerramento_devolve_as_entregas_que_nao_terminam() {
+    let app = AppTeste::nova().await;
+    let token = admin(&app, "ana").await;
+    let recebedor = Recebedor::novo().await;
+    // Aceita a conexão e nunca responde
+    let mudo = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url_muda = format!("http://{}/hook", mudo.local_addr().unwrap());
+    tokio::spawn(async move {
+        let mut conexoes = Vec::new();
+        while let Ok((conexao, _)) = mudo.accept().await {
+            conexoes.push(conexao);
+        }
+    });
+
+    let id_rapido = cadastrar(&app, &token, &recebedor.url, json!(["post.created"])).await["id"].as_i64().unwrap();
+    let id_mudo = cadastrar(&app, &token, &url_muda, json!(["post.created"])).await["id"].as_i64().unwrap();
+    app.criar_postagem(&token, "Durante o encerramento").await;
+
+    let parar = tokio_util::sync::CancellationToken::new();
+    let execucao = tokio::spawn(webhooks::executar_entregas(
+        app.repositorios.clone(),
+        parar.clone(),
+        std::time::Duration::from_millis(500),
+    ));
+    for _ in 0..100 {
+        if recebedor.total() == 1 && entregas(&app, &token, id_mudo).await[0]["attempts"] == 1 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+
+    parar.cancel();
+    tokio::time::timeout(std::time::Duration::from_secs(5), execucao).await.unwrap().unwrap();
+
+    assert_eq!(entregas(&app, &token, id_rapido).await[0]["status"], "succeeded");
+    // A entrega presa volta a vencer na hora, sem tentativa no histórico
+    let presa = entregas(&app, &token, id_mudo).await.remove(0);
+    assert_eq!(presa["status"], "pending");
+    assert_eq!(presa["attempts"], 0);
+    let proxima: chrono::DateTime<Utc> = serde_json::from_value(presa["next_attempt_at"].clone()).unwrap();
+    assert!(proxima <= Utc::now());
+    let detalhe = app.get(&format!("/webhooks/{id_mudo}/deliveries/{}", presa["id"]), Some(&token)).await.corpo;
+    assert!(detalhe["attempt_log"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn webhooks_de_usuarios_comuns_nao_alcancam_a_rede_interna() {
+    let app = AppTeste::nova().await;
+    let token = app.usuario("ana").await;
+    let id_ana = app.get("/profile", Some(&token)).await.corpo["id"].as_i64().unwrap() as i32;
+    let recebedor = Recebedor::novo().await;
+    let porta = recebedor.url.trim_start_matches("http://127.0.0.1:").trim_end_matches("/hook").to_string();
+
+    // Como se o DNS de um destino aceito no cadastro passasse a apontar para
+    // a rede interna: o webhook é gravado direto, sem a validação da API
+    let eventos = ["post.created".to_string()];
+    let por_nome = app
+        .repositorios
+        .webhooks
+        .criar(id_ana, &format!("http://localhost:{porta}/hook"), &eventos, "whsec_teste")
+        .await
+        .unwrap();
+    let por_ip = app.repositorios.webhooks.criar(id_ana, &recebedor.url, &eventos, "whsec_teste").await.unwrap();
+    app.criar_postagem(&token, "Não sai daqui").await;
+
+    assert_eq!(processar(&app, Utc::now()).await, 2);
+    assert_eq!(recebedor.total(), 0);
+
+    let erro = |id_webhook: i32| {
+        let (app, token) = (&app, &token);
+        async move {
+            let entrega = entregas(app, token, id_webhook as i64).await.remove(0);
+            assert_eq!(entrega["status"], "pending");
+            assert!(entrega["last_status_code"].is_null());
+            entrega["last_error"].as_str().unwrap().to_string()
+        }
+    };
+    let erro_nome = erro(por_nome.id).await;
+    assert!(erro_nome.contains("localhost não resolve para nenhum IP público"), "{erro_nome}");
+    assert_eq!(erro(por_ip.id).await, "IP local ou de rede privada, reservado a administradores");
+
+    // O mesmo destino funciona para administradores, sem seguir redirecionamentos
+    app.repositorios.usuarios.definir_admin(id_ana, true).await.unwrap();
+    recebedor.responder(302);
+    app.criar_postagem(&token, "Agora sai").await;
+    processar(&app, Utc::now()).await;
+    assert_eq!(recebedor.total(), 2);
+    let redirecionada = entregas(&app, &token, por_ip.id as i64).await.remove(0);
+    assert_eq!(redirecionada["last_status_code"], 302);
+    assert_eq!(redirecionada["status"], "pending");
+}